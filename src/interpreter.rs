@@ -1 +1,5 @@
+pub mod builtins;
+pub mod format;
+#[allow(clippy::module_inception)]
 pub mod interpreter;
+pub mod modules;
@@ -0,0 +1,862 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::BufRead;
+use std::io::Write;
+use std::rc::Rc;
+
+use crate::interpreter::interpreter::add_values;
+use crate::interpreter::interpreter::bool_value;
+use crate::interpreter::interpreter::invoke;
+use crate::interpreter::interpreter::iter;
+use crate::interpreter::interpreter::keyword_pairs;
+use crate::interpreter::interpreter::len;
+use crate::interpreter::interpreter::next;
+use crate::interpreter::interpreter::range_of;
+use crate::interpreter::interpreter::relational;
+use crate::interpreter::interpreter::special_method;
+use crate::interpreter::interpreter::Environment;
+use crate::interpreter::interpreter::Exception;
+use crate::ir::ast::repr;
+use crate::ir::ast::Expression;
+use crate::ir::ast::FileRef;
+use crate::ir::ast::IteratorRef;
+use crate::ir::ast::IteratorState;
+use crate::ir::ast::Name;
+use crate::ir::ast::RelationalOp;
+use crate::ir::ast::Type;
+use crate::ir::builtins::builtin_function_type;
+use crate::ir::builtins::file_method;
+use crate::ir::builtins::POSITIONAL_ONLY;
+use crate::ir::builtins::PRINT_KEYWORDS;
+
+pub mod files;
+mod json;
+mod math;
+pub(super) mod random;
+
+/// Where `print` writes and `input` reads. Embedders provide their own
+/// to redirect a program's input and output.
+pub trait Console {
+    fn write(&mut self, text: &str);
+    /// The next line of input without its line break, or None at the
+    /// end of the input.
+    fn read_line(&mut self) -> Option<String>;
+}
+
+/// The standard output and standard input of the process.
+pub struct StdConsole;
+
+impl Console for StdConsole {
+    fn write(&mut self, text: &str) {
+        let mut stdout = std::io::stdout();
+        let _ = stdout.write_all(text.as_bytes());
+        let _ = stdout.flush();
+    }
+
+    fn read_line(&mut self) -> Option<String> {
+        let mut line = String::new();
+        match std::io::stdin().lock().read_line(&mut line) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => Some(line.trim_end_matches(['\n', '\r']).to_string()),
+        }
+    }
+}
+
+/// A console that records the output and reads from the given lines.
+#[derive(Debug, Default)]
+pub struct BufferConsole {
+    pub output: String,
+    pub input: VecDeque<String>,
+}
+
+impl BufferConsole {
+    pub fn new(input: &[&str]) -> BufferConsole {
+        BufferConsole {
+            output: String::new(),
+            input: input.iter().map(|line| line.to_string()).collect(),
+        }
+    }
+}
+
+impl Console for BufferConsole {
+    fn write(&mut self, text: &str) {
+        self.output.push_str(text);
+    }
+
+    fn read_line(&mut self) -> Option<String> {
+        self.input.pop_front()
+    }
+}
+
+/* the console of an environment, shared by its copies */
+#[derive(Clone)]
+pub struct ConsoleRef(pub Rc<RefCell<dyn Console>>);
+
+impl Default for ConsoleRef {
+    fn default() -> Self {
+        ConsoleRef(Rc::new(RefCell::new(StdConsole)))
+    }
+}
+
+impl fmt::Debug for ConsoleRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ConsoleRef")
+    }
+}
+
+impl PartialEq for ConsoleRef {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/* the standard module of the given name, as if imported from a program
+ * that defines its functions and constants */
+pub(super) fn standard_module(name: &str) -> Option<Environment> {
+    let mut module = Environment::new();
+    for (n, t) in crate::ir::builtins::standard_module(name)? {
+        let value = match t {
+            Type::TFunction(..) => Expression::BuiltinFunction(format!("{}.{}", name, n)),
+            _ => match name {
+                "math" => math::constant(&n),
+                _ => Expression::CNone,
+            },
+        };
+        module.insert(n, value);
+    }
+    Some(module)
+}
+
+/* calls a built-in function, given by its qualified name, with its
+ * evaluated arguments */
+pub(super) fn call_function(
+    name: &str,
+    args: Vec<Expression>,
+    env: &Environment,
+) -> Result<Expression, Exception> {
+    let signature = match builtin_function_type(name) {
+        Some(Type::TFunction(_, signature)) => signature,
+        _ => {
+            return Err(Exception::new(
+                "NameError",
+                &format!("Variable {} not found", name),
+            ))
+        }
+    };
+    let keywords = args
+        .iter()
+        .any(|arg| matches!(arg, Expression::KeywordArgument(..)));
+    if keywords && POSITIONAL_ONLY.contains(&name) {
+        return Err(Exception::new(
+            "TypeError",
+            &format!("{}() takes no keyword arguments.", name),
+        ));
+    }
+    let binding = signature
+        .bind(name, keyword_pairs(args))
+        .map_err(|message| Exception::new("TypeError", &message))?;
+    if let Some((module, function)) = name.split_once('.') {
+        return match module {
+            "math" => math::call(function, binding.args, env),
+            "random" => random::call(function, binding.args, env),
+            "json" => json::call(function, binding.args, env),
+            _ => Err(Exception::new(
+                "AttributeError",
+                &format!("module '{}' has no attribute '{}'.", module, function),
+            )),
+        };
+    }
+    let mut args = binding.args.into_iter();
+    let mut arg = || args.next().flatten();
+    match name {
+        "print" => print(binding.varargs, binding.kwargs, env),
+        "input" => input(arg(), env),
+        "len" => len(arg().unwrap_or(Expression::CNone), env),
+        "range" => range(arg(), arg(), arg()),
+        "abs" => abs(arg().unwrap_or(Expression::CNone), env),
+        "min" => extremum("min", binding.varargs, false, env),
+        "max" => extremum("max", binding.varargs, true, env),
+        "sum" => sum(arg().unwrap_or(Expression::CNone), arg(), env),
+        "int" => int(arg().unwrap_or(Expression::CInt(0)), env),
+        "float" => float(arg().unwrap_or(Expression::CReal(0.0)), env),
+        "str" => match arg() {
+            Some(value) => Ok(Expression::CString(to_str(&value, env)?)),
+            None => Ok(Expression::CString(String::new())),
+        },
+        "map" => map(arg(), binding.varargs, env),
+        "filter" => filter(arg(), arg(), env),
+        "reduce" => reduce(arg(), arg(), arg(), env),
+        "any" | "all" => {
+            let wanted = name == "any";
+            for value in collect(arg().unwrap_or(Expression::CNone), env)? {
+                if truthy(&value, env)? == wanted {
+                    return Ok(bool_value(wanted));
+                }
+            }
+            Ok(bool_value(!wanted))
+        }
+        "zip" => Ok(iterator(IteratorState::Zip(iterators(
+            binding.varargs,
+            env,
+        )?))),
+        "enumerate" => enumerate(arg(), arg(), env),
+        "reversed" => reversed(arg().unwrap_or(Expression::CNone), env),
+        "sorted" => sorted(arg(), arg(), arg(), env),
+        "bool" => Ok(bool_value(truthy(
+            &arg().unwrap_or(Expression::CFalse),
+            env,
+        )?)),
+        "type" => Ok(Expression::ClassValue(type_name(
+            &arg().unwrap_or(Expression::CNone),
+            env,
+        ))),
+        "open" => files::open(arg(), arg(), env),
+        _ => Err(Exception::new(
+            "NameError",
+            &format!("Variable {} not found", name),
+        )),
+    }
+}
+
+/* calls a method of a file with its evaluated arguments */
+pub(super) fn call_file_method(
+    file: &FileRef,
+    method: &str,
+    args: Vec<Expression>,
+    env: &Environment,
+) -> Result<Expression, Exception> {
+    let signature = match file_method(method) {
+        Some(Type::TFunction(_, signature)) => signature,
+        _ => {
+            return Err(Exception::new(
+                "AttributeError",
+                &format!("'TextIOWrapper' object has no attribute '{}'.", method),
+            ))
+        }
+    };
+    let binding = signature
+        .bind(method, keyword_pairs(args))
+        .map_err(|message| Exception::new("TypeError", &message))?;
+    files::call(file, method, binding.args, env)
+}
+
+fn print(
+    values: Vec<Expression>,
+    keywords: Vec<(Name, Expression)>,
+    env: &Environment,
+) -> Result<Expression, Exception> {
+    let (mut sep, mut end) = (String::from(" "), String::from("\n"));
+    for (keyword, value) in keywords {
+        if !PRINT_KEYWORDS.contains(&keyword.as_str()) {
+            return Err(Exception::new(
+                "TypeError",
+                &format!("print() got an unexpected keyword argument '{}'.", keyword),
+            ));
+        }
+        let value = match value {
+            Expression::CString(s) => s,
+            Expression::CNone if keyword == "sep" => String::from(" "),
+            Expression::CNone => String::from("\n"),
+            _ => {
+                return Err(Exception::new(
+                    "TypeError",
+                    &format!("{} must be None or a string.", keyword),
+                ))
+            }
+        };
+        match keyword.as_str() {
+            "sep" => sep = value,
+            _ => end = value,
+        }
+    }
+    let mut text = vec![];
+    for value in values.iter() {
+        text.push(to_str(value, env)?);
+    }
+    env.console()
+        .0
+        .borrow_mut()
+        .write(&(text.join(&sep) + &end));
+    Ok(Expression::CNone)
+}
+
+/* a value as 'str' and 'print' give it: an object with '__str__' is
+ * shown by it */
+pub(super) fn to_str(value: &Expression, env: &Environment) -> Result<String, Exception> {
+    match special_method(value, "__str__", vec![], env)? {
+        Some(Expression::CString(s)) => Ok(s),
+        Some(_) => Err(Exception::new(
+            "TypeError",
+            "'__str__' must return a string.",
+        )),
+        None => Ok(value.to_string()),
+    }
+}
+
+/* the line read lacks its line break; there is none to read at the end
+ * of the input */
+fn input(prompt: Option<Expression>, env: &Environment) -> Result<Expression, Exception> {
+    let console = env.console();
+    let mut console = console.0.borrow_mut();
+    if let Some(prompt) = prompt {
+        console.write(&prompt.to_string());
+    }
+    match console.read_line() {
+        Some(line) => Ok(Expression::CString(line)),
+        None => Err(Exception::new("EOFError", "EOF when reading a line.")),
+    }
+}
+
+/* the integers from 'start' up to, but excluding, 'stop', produced
+ * as they are needed */
+fn range(
+    a: Option<Expression>,
+    b: Option<Expression>,
+    step: Option<Expression>,
+) -> Result<Expression, Exception> {
+    let integer = |value: Expression| match value {
+        Expression::CInt(i) => Ok(i as i64),
+        _ => Err(Exception::new(
+            "TypeError",
+            &format!("range() arguments must be integers, not {}.", repr(&value)),
+        )),
+    };
+    let (start, stop) = match (a, b) {
+        (Some(stop), None) => (0, integer(stop)?),
+        (Some(start), Some(stop)) => (integer(start)?, integer(stop)?),
+        _ => {
+            return Err(Exception::new(
+                "TypeError",
+                "range() missing required argument 'stop'.",
+            ))
+        }
+    };
+    let step = step.map_or(Ok(1), integer)?;
+    if step == 0 {
+        return Err(Exception::new(
+            "ValueError",
+            "range() arg 3 must not be zero.",
+        ));
+    }
+    Ok(iterator(IteratorState::Range {
+        next: start,
+        stop,
+        step,
+    }))
+}
+
+fn abs(value: Expression, env: &Environment) -> Result<Expression, Exception> {
+    match value {
+        Expression::CInt(i) => i
+            .checked_abs()
+            .map(Expression::CInt)
+            .ok_or_else(|| Exception::new("OverflowError", "integer overflow in 'abs'.")),
+        Expression::CReal(r) => Ok(Expression::CReal(r.abs())),
+        _ => match special_method(&value, "__abs__", vec![], env)? {
+            Some(result) => Ok(result),
+            None => Err(Exception::new(
+                "TypeError",
+                &format!("bad operand type for abs(): '{}'.", type_name(&value, env)),
+            )),
+        },
+    }
+}
+
+/* 'min' and 'max' of a single iterable argument, or of all the
+ * arguments; of equal values, the first is the result */
+fn extremum(
+    name: &str,
+    values: Vec<Expression>,
+    greatest: bool,
+    env: &Environment,
+) -> Result<Expression, Exception> {
+    let values = match values.len() {
+        0 => {
+            return Err(Exception::new(
+                "TypeError",
+                &format!("{}() expected at least 1 argument, got 0.", name),
+            ))
+        }
+        1 => collect(values.into_iter().next().unwrap(), env)?,
+        _ => values,
+    };
+    let mut values = values.into_iter();
+    let mut result = values.next().ok_or_else(|| {
+        Exception::new(
+            "ValueError",
+            &format!("{}() arg is an empty sequence.", name),
+        )
+    })?;
+    for value in values {
+        let (smaller, larger) = match greatest {
+            true => (&result, &value),
+            false => (&value, &result),
+        };
+        if less_than(smaller, larger, env)? {
+            result = value;
+        }
+    }
+    Ok(result)
+}
+
+fn sum(
+    iterable: Expression,
+    start: Option<Expression>,
+    env: &Environment,
+) -> Result<Expression, Exception> {
+    let numeric = |value: &Expression| match value {
+        Expression::CInt(_) | Expression::CReal(_) => Ok(()),
+        _ => Err(Exception::new(
+            "TypeError",
+            &format!(
+                "unsupported operand type for sum(): '{}'.",
+                type_name(value, env)
+            ),
+        )),
+    };
+    let mut total = start.unwrap_or(Expression::CInt(0));
+    numeric(&total)?;
+    for value in collect(iterable, env)? {
+        numeric(&value)?;
+        total = add_values(total, value, env)?;
+    }
+    Ok(total)
+}
+
+fn int(value: Expression, env: &Environment) -> Result<Expression, Exception> {
+    match value {
+        Expression::CInt(_) => Ok(value),
+        Expression::CTrue => Ok(Expression::CInt(1)),
+        Expression::CFalse => Ok(Expression::CInt(0)),
+        Expression::CReal(r) if r.is_nan() => Err(Exception::new(
+            "ValueError",
+            "cannot convert float nan to integer.",
+        )),
+        Expression::CReal(r) if r.trunc() < i32::MIN as f64 || r.trunc() > i32::MAX as f64 => {
+            Err(Exception::new(
+                "OverflowError",
+                &format!("cannot convert float {} to integer.", value),
+            ))
+        }
+        Expression::CReal(r) => Ok(Expression::CInt(r.trunc() as i32)),
+        Expression::CString(ref s) => match s.trim().parse() {
+            Ok(i) => Ok(Expression::CInt(i)),
+            Err(_) => Err(Exception::new(
+                "ValueError",
+                &format!("invalid literal for int() with base 10: {}.", repr(&value)),
+            )),
+        },
+        _ => Err(Exception::new(
+            "TypeError",
+            &format!(
+                "int() argument must be a string or a number, not '{}'.",
+                type_name(&value, env)
+            ),
+        )),
+    }
+}
+
+fn float(value: Expression, env: &Environment) -> Result<Expression, Exception> {
+    match value {
+        Expression::CReal(_) => Ok(value),
+        Expression::CInt(i) => Ok(Expression::CReal(i as f64)),
+        Expression::CTrue => Ok(Expression::CReal(1.0)),
+        Expression::CFalse => Ok(Expression::CReal(0.0)),
+        Expression::CString(ref s) => match s.trim().parse() {
+            Ok(r) => Ok(Expression::CReal(r)),
+            Err(_) => Err(Exception::new(
+                "ValueError",
+                &format!("could not convert string to float: {}.", repr(&value)),
+            )),
+        },
+        _ => Err(Exception::new(
+            "TypeError",
+            &format!(
+                "float() argument must be a string or a number, not '{}'.",
+                type_name(&value, env)
+            ),
+        )),
+    }
+}
+
+fn iterator(state: IteratorState) -> Expression {
+    Expression::Iterator(IteratorRef::new(state))
+}
+
+/* an iterator over each of the iterables */
+fn iterators(iterables: Vec<Expression>, env: &Environment) -> Result<Vec<Expression>, Exception> {
+    iterables
+        .into_iter()
+        .map(|iterable| iter(iterable, env))
+        .collect()
+}
+
+/* the next value of each of the iterators, or None once any of them is
+ * exhausted (or if there are none) */
+fn next_of_each(
+    iterators: &[Expression],
+    env: &Environment,
+) -> Result<Option<Vec<Expression>>, Exception> {
+    if iterators.is_empty() {
+        return Ok(None);
+    }
+    let mut values = vec![];
+    for iterator in iterators {
+        match next(iterator, env)? {
+            Some(value) => values.push(value),
+            None => return Ok(None),
+        }
+    }
+    Ok(Some(values))
+}
+
+/* the next value of the iterator 'map', 'filter', 'zip' or 'enumerate'
+ * gives, taken from those it transforms only now; the state is copied
+ * out, so that those may be advanced meanwhile. Other iterators advance
+ * as usual. */
+pub(super) fn advance(
+    iterator: &IteratorRef,
+    env: &Environment,
+) -> Result<Option<Expression>, Exception> {
+    let state = iterator.0.borrow().clone();
+    match state {
+        IteratorState::Map(function, iterators) => match next_of_each(&iterators, env)? {
+            Some(args) => Ok(Some(apply("map", &function, args, env)?)),
+            None => Ok(None),
+        },
+        IteratorState::Filter(function, values) => {
+            while let Some(value) = next(&values, env)? {
+                let test = match function {
+                    Expression::CNone => value.clone(),
+                    _ => apply("filter", &function, vec![value.clone()], env)?,
+                };
+                if truthy(&test, env)? {
+                    return Ok(Some(value));
+                }
+            }
+            Ok(None)
+        }
+        IteratorState::Zip(iterators) => Ok(next_of_each(&iterators, env)?.map(Expression::Tuple)),
+        IteratorState::Enumerate(values, index) => {
+            let Some(value) = next(&values, env)? else {
+                return Ok(None);
+            };
+            let i = i32::try_from(index)
+                .map_err(|_| Exception::new("OverflowError", "integer overflow in 'enumerate'."))?;
+            *iterator.0.borrow_mut() = IteratorState::Enumerate(values, index + 1);
+            Ok(Some(Expression::Tuple(vec![Expression::CInt(i), value])))
+        }
+        _ => next(&Expression::Iterator(iterator.clone()), env),
+    }
+}
+
+/* the values of the function applied to a value of each iterable, as
+ * they are needed */
+fn map(
+    function: Option<Expression>,
+    iterables: Vec<Expression>,
+    env: &Environment,
+) -> Result<Expression, Exception> {
+    let function = function.unwrap_or(Expression::CNone);
+    if iterables.is_empty() {
+        return Err(Exception::new(
+            "TypeError",
+            "map() must have at least two arguments.",
+        ));
+    }
+    Ok(iterator(IteratorState::Map(
+        function,
+        iterators(iterables, env)?,
+    )))
+}
+
+/* the values for which the function gives a true value; without a
+ * function, the values that are true. They are tested as they are
+ * needed. */
+fn filter(
+    function: Option<Expression>,
+    iterable: Option<Expression>,
+    env: &Environment,
+) -> Result<Expression, Exception> {
+    Ok(iterator(IteratorState::Filter(
+        function.unwrap_or(Expression::CNone),
+        iter(iterable.unwrap_or(Expression::CNone), env)?,
+    )))
+}
+
+/* combines the values from left to right, starting from the initial
+ * value if there is one */
+fn reduce(
+    function: Option<Expression>,
+    iterable: Option<Expression>,
+    initial: Option<Expression>,
+    env: &Environment,
+) -> Result<Expression, Exception> {
+    let function = function.unwrap_or(Expression::CNone);
+    let mut values = collect(iterable.unwrap_or(Expression::CNone), env)?.into_iter();
+    let mut result = match initial.or_else(|| values.next()) {
+        Some(value) => value,
+        None => {
+            return Err(Exception::new(
+                "TypeError",
+                "reduce() of empty iterable with no initial value.",
+            ))
+        }
+    };
+    for value in values {
+        result = apply("reduce", &function, vec![result, value], env)?;
+    }
+    Ok(result)
+}
+
+fn enumerate(
+    iterable: Option<Expression>,
+    start: Option<Expression>,
+    env: &Environment,
+) -> Result<Expression, Exception> {
+    let start = match start {
+        None => 0,
+        Some(Expression::CInt(start)) => start,
+        Some(value) => {
+            return Err(Exception::new(
+                "TypeError",
+                &format!(
+                    "'{}' object cannot be interpreted as an integer.",
+                    type_name(&value, env)
+                ),
+            ))
+        }
+    };
+    Ok(iterator(IteratorState::Enumerate(
+        iter(iterable.unwrap_or(Expression::CNone), env)?,
+        start as i64,
+    )))
+}
+
+/* only sequences, whose values come in a fixed order, can be reversed */
+fn reversed(sequence: Expression, env: &Environment) -> Result<Expression, Exception> {
+    let reversible = match &sequence {
+        Expression::ListValue(_)
+        | Expression::Tuple(_)
+        | Expression::CString(_)
+        | Expression::DictValue(_) => true,
+        other => range_of(other).is_some(),
+    };
+    if !reversible {
+        return Err(Exception::new(
+            "TypeError",
+            &format!("'{}' object is not reversible.", type_name(&sequence, env)),
+        ));
+    }
+    let mut values = collect(sequence, env)?;
+    values.reverse();
+    Ok(Expression::ListValue(values))
+}
+
+/* the values in ascending order of their keys (or, with 'reverse', in
+ * descending order); values with equal keys keep their order */
+fn sorted(
+    iterable: Option<Expression>,
+    key: Option<Expression>,
+    reverse: Option<Expression>,
+    env: &Environment,
+) -> Result<Expression, Exception> {
+    let reverse = match reverse {
+        None | Some(Expression::CFalse) => false,
+        Some(Expression::CTrue) => true,
+        Some(value) => {
+            return Err(Exception::new(
+                "TypeError",
+                &format!(
+                    "'reverse' must be a boolean, not '{}'.",
+                    type_name(&value, env)
+                ),
+            ))
+        }
+    };
+    let mut items = vec![];
+    for value in collect(iterable.unwrap_or(Expression::CNone), env)? {
+        let sort_key = match &key {
+            None | Some(Expression::CNone) => value.clone(),
+            Some(function) => apply("sorted", function, vec![value.clone()], env)?,
+        };
+        items.push((sort_key, value));
+    }
+    let items = merge_sort(items, reverse, env)?;
+    Ok(Expression::ListValue(
+        items.into_iter().map(|(_, value)| value).collect(),
+    ))
+}
+
+/* a merge sort, which is stable and, unlike the sorts of the standard
+ * library, lets comparisons fail */
+fn merge_sort(
+    mut items: Vec<(Expression, Expression)>,
+    reverse: bool,
+    env: &Environment,
+) -> Result<Vec<(Expression, Expression)>, Exception> {
+    if items.len() <= 1 {
+        return Ok(items);
+    }
+    let right = items.split_off(items.len() / 2);
+    let left = merge_sort(items, reverse, env)?;
+    let right = merge_sort(right, reverse, env)?;
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    let mut left = left.into_iter().peekable();
+    let mut right = right.into_iter().peekable();
+    while let (Some((l, _)), Some((r, _))) = (left.peek(), right.peek()) {
+        /* an item on the right goes first only if it comes strictly
+         * before the one on the left */
+        let right_first = match reverse {
+            false => less_than(r, l, env)?,
+            true => less_than(l, r, env)?,
+        };
+        merged.extend(match right_first {
+            true => right.next(),
+            false => left.next(),
+        });
+    }
+    merged.extend(left);
+    merged.extend(right);
+    Ok(merged)
+}
+
+/* calls the function given to a built-in, such as the one 'map' applies
+ * to each value, checking first that it takes as many arguments as the
+ * built-in passes */
+fn apply(
+    builtin: &str,
+    function: &Expression,
+    args: Vec<Expression>,
+    env: &Environment,
+) -> Result<Expression, Exception> {
+    let (name, signature) = match function {
+        Expression::FunctionValue(f, _) => (f.name.clone(), f.signature()),
+        Expression::BuiltinFunction(name) => match builtin_function_type(name) {
+            Some(Type::TFunction(_, signature)) => (name.clone(), signature),
+            _ => {
+                return Err(Exception::new(
+                    "NameError",
+                    &format!("Variable {} not found", name),
+                ))
+            }
+        },
+        _ => {
+            return Err(Exception::new(
+                "TypeError",
+                &format!("'{}' object is not callable.", type_name(function, env)),
+            ))
+        }
+    };
+    if !signature.accepts(args.len()) {
+        return Err(Exception::new(
+            "TypeError",
+            &format!(
+                "{}() expects a function of {} argument(s), but '{}' takes {}.",
+                builtin,
+                args.len(),
+                name,
+                signature.arity()
+            ),
+        ));
+    }
+    match function {
+        Expression::FunctionValue(f, closure) => {
+            invoke(*f.clone(), closure.clone(), args, env, vec![])
+        }
+        _ => call_function(&name, args, env),
+    }
+}
+
+/* as in Python: false, None, Nothing, zero and empty collections are
+ * false; an object is true unless its '__bool__' or its '__len__'
+ * says otherwise */
+pub(super) fn truthy(value: &Expression, env: &Environment) -> Result<bool, Exception> {
+    Ok(match value {
+        Expression::CFalse | Expression::CNone | Expression::CNothing => false,
+        Expression::CInt(i) => *i != 0,
+        Expression::CReal(r) => *r != 0.0,
+        Expression::CString(s) => !s.is_empty(),
+        Expression::ListValue(elements)
+        | Expression::Tuple(elements)
+        | Expression::SetValue(elements) => !elements.is_empty(),
+        Expression::DictValue(entries) => !entries.is_empty(),
+        Expression::Object(_) => match special_method(value, "__bool__", vec![], env)? {
+            Some(Expression::CTrue) => true,
+            Some(Expression::CFalse) => false,
+            Some(_) => {
+                return Err(Exception::new(
+                    "TypeError",
+                    "'__bool__' must return a boolean.",
+                ))
+            }
+            None => match special_method(value, "__len__", vec![], env)? {
+                Some(Expression::CInt(n)) => n != 0,
+                Some(_) => {
+                    return Err(Exception::new(
+                        "TypeError",
+                        "'__len__' must return an integer.",
+                    ))
+                }
+                None => true,
+            },
+        },
+        _ => true,
+    })
+}
+
+/* the name of the type of a value, as 'type' gives it */
+pub(super) fn type_name(value: &Expression, env: &Environment) -> String {
+    match value {
+        Expression::CInt(_) => String::from("int"),
+        Expression::CReal(_) => String::from("float"),
+        Expression::CString(_) => String::from("str"),
+        Expression::CTrue | Expression::CFalse => String::from("bool"),
+        Expression::CNone => String::from("NoneType"),
+        Expression::CJust(_) | Expression::CNothing => String::from("Maybe"),
+        Expression::COk(_) | Expression::CErr(_) => String::from("Result"),
+        Expression::ListValue(_) => String::from("list"),
+        Expression::Tuple(_) => String::from("tuple"),
+        Expression::SetValue(_) => String::from("set"),
+        Expression::DictValue(_) => String::from("dict"),
+        Expression::FunctionValue(..) => String::from("function"),
+        Expression::BuiltinFunction(_) => String::from("builtin_function_or_method"),
+        Expression::Module(..) => String::from("module"),
+        Expression::ClassValue(_) => String::from("type"),
+        Expression::File(_) => String::from("TextIOWrapper"),
+        Expression::Object(object) => object.class(),
+        Expression::Record(name, _) | Expression::Exception(name, _) => name.clone(),
+        Expression::ADTConstructor(constructor, _) => env
+            .type_of_constructor(constructor)
+            .unwrap_or_else(|| constructor.clone()),
+        Expression::Iterator(iterator) => match &*iterator.0.borrow() {
+            IteratorState::Sequence(..) => String::from("iterator"),
+            IteratorState::Range { .. } => String::from("range"),
+            IteratorState::Map(..) => String::from("map"),
+            IteratorState::Filter(..) => String::from("filter"),
+            IteratorState::Zip(..) => String::from("zip"),
+            IteratorState::Enumerate(..) => String::from("enumerate"),
+            IteratorState::Generator { .. } => String::from("generator"),
+        },
+        _ => String::from("object"),
+    }
+}
+
+/* the values an iterable produces */
+pub(super) fn collect(
+    iterable: Expression,
+    env: &Environment,
+) -> Result<Vec<Expression>, Exception> {
+    let iterator = iter(iterable, env)?;
+    let mut values = vec![];
+    while let Some(value) = next(&iterator, env)? {
+        values.push(value);
+    }
+    Ok(values)
+}
+
+/* '<', with the special methods of objects */
+fn less_than(v1: &Expression, v2: &Expression, env: &Environment) -> Result<bool, Exception> {
+    relational(&RelationalOp::LT, v1, v2, env)
+}
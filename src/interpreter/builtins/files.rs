@@ -0,0 +1,479 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use super::type_name;
+use crate::interpreter::interpreter::Environment;
+use crate::interpreter::interpreter::Exception;
+use crate::ir::ast::repr;
+use crate::ir::ast::Expression;
+use crate::ir::ast::FileRef;
+use crate::ir::ast::FileState;
+
+/// The files `open` reads and writes. Embedders provide their own to
+/// keep a program's files in memory or within a directory.
+pub trait FileSystem {
+    /// The text of the file at the path.
+    fn read(&self, path: &str) -> Result<String, FileError>;
+    /// Replaces the text of the file at the path, creating it if needed.
+    fn write(&mut self, path: &str, text: &str) -> Result<(), FileError>;
+    /// Adds the text at the end of the file at the path, creating it if
+    /// needed.
+    fn append(&mut self, path: &str, text: &str) -> Result<(), FileError>;
+}
+
+/// Why a file could not be read or written. Programs get it as a
+/// `FileNotFoundError`, a `PermissionError` or an `OSError`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileError {
+    NotFound,
+    PermissionDenied,
+    Other(String),
+}
+
+impl From<io::Error> for FileError {
+    fn from(error: io::Error) -> FileError {
+        match error.kind() {
+            io::ErrorKind::NotFound => FileError::NotFound,
+            io::ErrorKind::PermissionDenied => FileError::PermissionDenied,
+            _ => FileError::Other(error.to_string()),
+        }
+    }
+}
+
+/// The files of the machine. One made by `within` only gives access to
+/// the files under a directory, against which relative paths are
+/// resolved.
+#[derive(Debug, Clone, Default)]
+pub struct DiskFileSystem {
+    root: Option<PathBuf>,
+}
+
+impl DiskFileSystem {
+    pub fn new() -> DiskFileSystem {
+        DiskFileSystem::default()
+    }
+
+    pub fn within(directory: impl AsRef<Path>) -> io::Result<DiskFileSystem> {
+        Ok(DiskFileSystem {
+            root: Some(fs::canonicalize(directory)?),
+        })
+    }
+
+    /* the path under the allowed directory once '..' and symbolic links
+     * are followed, so that neither leads out of it; the file is then
+     * opened by this path */
+    fn resolve(&self, path: &str) -> Result<PathBuf, FileError> {
+        let root = match &self.root {
+            Some(root) => root,
+            None => return Ok(PathBuf::from(path)),
+        };
+        let mut lexical = PathBuf::new();
+        for component in root.join(path).components() {
+            match component {
+                Component::CurDir => (),
+                Component::ParentDir => {
+                    lexical.pop();
+                }
+                _ => lexical.push(component),
+            }
+        }
+        /* only the part that exists may go through links; a dangling link
+         * counts as existing, so that it is not created through */
+        let existing = lexical
+            .ancestors()
+            .find(|a| fs::symlink_metadata(a).is_ok())
+            .ok_or(FileError::NotFound)?;
+        let mut real = fs::canonicalize(existing)?;
+        for component in lexical
+            .strip_prefix(existing)
+            .unwrap_or(Path::new(""))
+            .components()
+        {
+            real.push(component);
+        }
+        match real.starts_with(root) {
+            true => Ok(real),
+            false => Err(FileError::PermissionDenied),
+        }
+    }
+}
+
+impl FileSystem for DiskFileSystem {
+    fn read(&self, path: &str) -> Result<String, FileError> {
+        Ok(fs::read_to_string(self.resolve(path)?)?)
+    }
+
+    fn write(&mut self, path: &str, text: &str) -> Result<(), FileError> {
+        Ok(fs::write(self.resolve(path)?, text)?)
+    }
+
+    fn append(&mut self, path: &str, text: &str) -> Result<(), FileError> {
+        let mut file = fs::OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(self.resolve(path)?)?;
+        Ok(file.write_all(text.as_bytes())?)
+    }
+}
+
+/// Files kept in memory, by path, out of reach of the rest of the
+/// machine.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryFileSystem {
+    pub files: HashMap<String, String>,
+}
+
+impl MemoryFileSystem {
+    pub fn new(files: &[(&str, &str)]) -> MemoryFileSystem {
+        MemoryFileSystem {
+            files: files
+                .iter()
+                .map(|(path, text)| (path.to_string(), text.to_string()))
+                .collect(),
+        }
+    }
+}
+
+impl FileSystem for MemoryFileSystem {
+    fn read(&self, path: &str) -> Result<String, FileError> {
+        self.files.get(path).cloned().ok_or(FileError::NotFound)
+    }
+
+    fn write(&mut self, path: &str, text: &str) -> Result<(), FileError> {
+        self.files.insert(path.to_string(), text.to_string());
+        Ok(())
+    }
+
+    fn append(&mut self, path: &str, text: &str) -> Result<(), FileError> {
+        self.files
+            .entry(path.to_string())
+            .or_default()
+            .push_str(text);
+        Ok(())
+    }
+}
+
+/* the file system of an environment, shared by its copies; an empty
+ * one in memory, unless the embedder gives another */
+#[derive(Clone)]
+pub struct FileSystemRef(pub Rc<RefCell<dyn FileSystem>>);
+
+impl Default for FileSystemRef {
+    fn default() -> Self {
+        FileSystemRef(Rc::new(RefCell::new(MemoryFileSystem::default())))
+    }
+}
+
+impl fmt::Debug for FileSystemRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "FileSystemRef")
+    }
+}
+
+impl PartialEq for FileSystemRef {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/* 'open(file, mode)': a file opened for reading is read at once; one
+ * opened for writing is created, and emptied with mode 'w' */
+pub(super) fn open(
+    path: Option<Expression>,
+    mode: Option<Expression>,
+    env: &Environment,
+) -> Result<Expression, Exception> {
+    let path = text("open", path, env)?;
+    let mode = match mode {
+        Some(mode) => text("open", Some(mode), env)?,
+        None => String::from("r"),
+    };
+    let files = env.file_system();
+    let mut files = files.0.borrow_mut();
+    let opened = match mode.as_str() {
+        "r" => files.read(&path),
+        "w" => files.write(&path, "").map(|_| String::new()),
+        "a" => files.append(&path, "").map(|_| String::new()),
+        _ => {
+            return Err(Exception::new(
+                "ValueError",
+                &format!("invalid mode: {}.", repr(&Expression::CString(mode))),
+            ))
+        }
+    };
+    let text = opened.map_err(|error| file_error(error, &path))?;
+    Ok(Expression::File(FileRef::new(FileState {
+        path,
+        mode,
+        text,
+        position: 0,
+        closed: false,
+    })))
+}
+
+/* closing a file again does nothing */
+fn close(file: &FileRef) {
+    file.0.borrow_mut().closed = true;
+}
+
+/* calls a method of a file with the arguments matched to its parameters;
+ * what is written goes to the file system right away */
+pub(super) fn call(
+    file: &FileRef,
+    method: &str,
+    args: Vec<Option<Expression>>,
+    env: &Environment,
+) -> Result<Expression, Exception> {
+    if method == "close" {
+        close(file);
+        return Ok(Expression::CNone);
+    }
+    let mut state = file.0.borrow_mut();
+    if state.closed {
+        return Err(Exception::new(
+            "ValueError",
+            "I/O operation on closed file.",
+        ));
+    }
+    match method {
+        "read" | "readlines" => {
+            if state.mode != "r" {
+                return Err(Exception::new("UnsupportedOperation", "not readable."));
+            }
+            let rest = state.text[state.position..].to_string();
+            state.position = state.text.len();
+            Ok(match method {
+                "read" => Expression::CString(rest),
+                _ => Expression::ListValue(
+                    rest.split_inclusive('\n')
+                        .map(|line| Expression::CString(line.to_string()))
+                        .collect(),
+                ),
+            })
+        }
+        "write" => {
+            if state.mode == "r" {
+                return Err(Exception::new("UnsupportedOperation", "not writable."));
+            }
+            let text = text("write", args.into_iter().next().flatten(), env)?;
+            env.file_system()
+                .0
+                .borrow_mut()
+                .append(&state.path, &text)
+                .map_err(|error| file_error(error, &state.path))?;
+            Ok(Expression::CInt(text.chars().count() as i32))
+        }
+        _ => Err(Exception::new(
+            "AttributeError",
+            &format!("'TextIOWrapper' object has no attribute '{}'.", method),
+        )),
+    }
+}
+
+fn text(function: &str, value: Option<Expression>, env: &Environment) -> Result<String, Exception> {
+    match value {
+        Some(Expression::CString(s)) => Ok(s),
+        value => Err(Exception::new(
+            "TypeError",
+            &format!(
+                "{}() argument must be str, not '{}'.",
+                function,
+                type_name(&value.unwrap_or(Expression::CNone), env)
+            ),
+        )),
+    }
+}
+
+fn file_error(error: FileError, path: &str) -> Exception {
+    let path = repr(&Expression::CString(path.to_string()));
+    match error {
+        FileError::NotFound => Exception::new(
+            "FileNotFoundError",
+            &format!("No such file or directory: {}.", path),
+        ),
+        FileError::PermissionDenied => {
+            Exception::new("PermissionError", &format!("Permission denied: {}.", path))
+        }
+        FileError::Other(message) => Exception::new("OSError", &format!("{}: {}.", message, path)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn environment(files: MemoryFileSystem) -> (Environment, Rc<RefCell<MemoryFileSystem>>) {
+        let files = Rc::new(RefCell::new(files));
+        let mut env = Environment::new();
+        env.set_file_system(files.clone());
+        (env, files)
+    }
+
+    fn string(s: &str) -> Expression {
+        Expression::CString(s.to_string())
+    }
+
+    fn opened(path: &str, mode: &str, env: &Environment) -> FileRef {
+        match open(Some(string(path)), Some(string(mode)), env) {
+            Ok(Expression::File(file)) => file,
+            result => panic!("cannot open {}: {:?}", path, result),
+        }
+    }
+
+    #[test]
+    fn read_and_write_memory_files() {
+        let (env, files) = environment(MemoryFileSystem::new(&[("notes.txt", "a\nb\nc")]));
+
+        let file = opened("notes.txt", "r", &env);
+        assert_eq!(
+            call(&file, "readlines", vec![], &env),
+            Ok(Expression::ListValue(vec![
+                string("a\n"),
+                string("b\n"),
+                string("c")
+            ]))
+        );
+        /* everything has been read */
+        assert_eq!(call(&file, "read", vec![], &env), Ok(string("")));
+
+        let file = opened("out.txt", "w", &env);
+        assert_eq!(
+            call(&file, "write", vec![Some(string("héllo\n"))], &env),
+            Ok(Expression::CInt(6))
+        );
+        let file = opened("out.txt", "a", &env);
+        call(&file, "write", vec![Some(string("again"))], &env).unwrap();
+        assert_eq!(files.borrow().files["out.txt"], "héllo\nagain");
+        assert_eq!(
+            call(&opened("out.txt", "r", &env), "read", vec![], &env),
+            Ok(string("héllo\nagain"))
+        );
+
+        /* 'w' empties the file */
+        opened("out.txt", "w", &env);
+        assert_eq!(files.borrow().files["out.txt"], "");
+        assert_eq!(
+            Expression::File(file).to_string(),
+            "<file 'out.txt' mode='a'>"
+        );
+    }
+
+    #[test]
+    fn file_errors() {
+        let (env, _) = environment(MemoryFileSystem::new(&[("a.txt", "a")]));
+        assert_eq!(
+            open(Some(string("b.txt")), None, &env),
+            Err(Exception::new(
+                "FileNotFoundError",
+                "No such file or directory: 'b.txt'."
+            ))
+        );
+        assert_eq!(
+            open(Some(string("a.txt")), Some(string("x")), &env),
+            Err(Exception::new("ValueError", "invalid mode: 'x'."))
+        );
+        assert_eq!(
+            open(Some(Expression::CInt(1)), None, &env),
+            Err(Exception::new(
+                "TypeError",
+                "open() argument must be str, not 'int'."
+            ))
+        );
+
+        let file = opened("a.txt", "r", &env);
+        assert_eq!(
+            call(&file, "write", vec![Some(string("b"))], &env),
+            Err(Exception::new("UnsupportedOperation", "not writable."))
+        );
+        assert_eq!(
+            call(&opened("a.txt", "a", &env), "read", vec![], &env),
+            Err(Exception::new("UnsupportedOperation", "not readable."))
+        );
+        call(&file, "close", vec![], &env).unwrap();
+        assert_eq!(call(&file, "close", vec![], &env), Ok(Expression::CNone));
+        assert_eq!(
+            call(&file, "read", vec![], &env),
+            Err(Exception::new(
+                "ValueError",
+                "I/O operation on closed file."
+            ))
+        );
+    }
+
+    #[test]
+    fn disk_access_is_restricted_to_a_directory() {
+        let root = std::env::temp_dir().join(format!("rpy-files-{}", std::process::id()));
+        let allowed = root.join("allowed");
+        fs::create_dir_all(allowed.join("data")).unwrap();
+        fs::write(root.join("secret.txt"), "secret").unwrap();
+
+        let mut files = DiskFileSystem::within(&allowed).unwrap();
+        files.write("data/out.txt", "one").unwrap();
+        files.append("data/../data/out.txt", " two").unwrap();
+        assert_eq!(files.read("data/out.txt"), Ok(String::from("one two")));
+        assert_eq!(
+            fs::read_to_string(allowed.join("data").join("out.txt")).unwrap(),
+            "one two"
+        );
+        assert_eq!(files.read("missing.txt"), Err(FileError::NotFound));
+
+        let outside = root.join("secret.txt");
+        for path in [
+            "../secret.txt",
+            "data/../../secret.txt",
+            outside.to_str().unwrap(),
+        ] {
+            assert_eq!(
+                files.read(path),
+                Err(FileError::PermissionDenied),
+                "{}",
+                path
+            );
+            assert_eq!(
+                files.write(path, "leak"),
+                Err(FileError::PermissionDenied),
+                "{}",
+                path
+            );
+        }
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(&root, allowed.join("link")).unwrap();
+            assert_eq!(
+                files.read("link/secret.txt"),
+                Err(FileError::PermissionDenied)
+            );
+            assert_eq!(
+                files.write("link/new.txt", "leak"),
+                Err(FileError::PermissionDenied)
+            );
+        }
+        assert_eq!(fs::read_to_string(&outside).unwrap(), "secret");
+        assert!(!root.join("new.txt").exists());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn environments_have_no_disk_access_by_default() {
+        let path = std::env::temp_dir().join(format!("rpy-default-{}.txt", std::process::id()));
+        fs::write(&path, "secret").unwrap();
+        let result = open(
+            Some(string(&path.to_string_lossy())),
+            None,
+            &Environment::new(),
+        );
+        fs::remove_file(&path).unwrap();
+        match result {
+            Err(exception) => assert_eq!(exception.kind, "FileNotFoundError"),
+            result => panic!("{} is open: {:?}", path.display(), result),
+        }
+    }
+}
@@ -0,0 +1,741 @@
+use super::type_name;
+use crate::interpreter::interpreter::Environment;
+use crate::interpreter::interpreter::Exception;
+use crate::ir::ast::format_real;
+use crate::ir::ast::Expression;
+
+/* calls a function of 'json' with the arguments matched to its
+ * parameters */
+pub(super) fn call(
+    function: &str,
+    args: Vec<Option<Expression>>,
+    env: &Environment,
+) -> Result<Expression, Exception> {
+    let mut args = args.into_iter();
+    let mut arg = || args.next().flatten();
+    match function {
+        "loads" => match arg() {
+            Some(Expression::CString(text)) => loads(&text),
+            value => Err(Exception::new(
+                "TypeError",
+                &format!(
+                    "the JSON object must be str, not '{}'.",
+                    type_name(&value.unwrap_or(Expression::CNone), env)
+                ),
+            )),
+        },
+        "dumps" => {
+            let value = arg().unwrap_or(Expression::CNone);
+            let indent = match arg() {
+                None | Some(Expression::CNone) => None,
+                Some(Expression::CInt(n)) => Some(n.max(0) as usize),
+                Some(value) => {
+                    return Err(Exception::new(
+                        "TypeError",
+                        &format!(
+                            "indent must be None or an integer, not '{}'.",
+                            type_name(&value, env)
+                        ),
+                    ))
+                }
+            };
+            let sort_keys = arg() == Some(Expression::CTrue);
+            let mut writer = Writer {
+                indent,
+                sort_keys,
+                text: String::new(),
+            };
+            writer.write(&value, 0, env)?;
+            Ok(Expression::CString(writer.text))
+        }
+        _ => Err(Exception::new(
+            "AttributeError",
+            &format!("module 'json' has no attribute '{}'.", function),
+        )),
+    }
+}
+
+/* how deeply arrays and objects may be nested in the texts 'loads'
+ * decodes and the values 'dumps' encodes, both of which recurse on the
+ * native stack */
+const MAX_DEPTH: usize = 500;
+
+fn too_deep(doing: &str) -> Exception {
+    Exception::new(
+        "RecursionError",
+        &format!("maximum recursion depth exceeded while {} JSON.", doing),
+    )
+}
+
+/* Decoding */
+
+/* the value of a JSON text: objects become dictionaries (a repeated
+ * key keeps its first position and its last value), arrays lists, and
+ * numbers integers unless they have a fraction or an exponent. An
+ * integer too large for an 'int' becomes the nearest real instead, as
+ * it would lose its digits beyond the precision of a real. */
+fn loads(text: &str) -> Result<Expression, Exception> {
+    let mut parser = Parser {
+        chars: text.chars().collect(),
+        position: 0,
+        depth: 0,
+    };
+    parser.skip_whitespace();
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.position < parser.chars.len() {
+        return Err(parser.error("Extra data", parser.position));
+    }
+    Ok(value)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    /* the index of the next character */
+    position: usize,
+    /* the number of arrays and objects the next character is in */
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+            self.position += 1;
+        }
+    }
+
+    /* the text at the current position starts with the given word */
+    fn looking_at(&self, word: &str) -> bool {
+        let end = self.position + word.chars().count();
+        end <= self.chars.len()
+            && self.chars[self.position..end]
+                .iter()
+                .copied()
+                .eq(word.chars())
+    }
+
+    fn value(&mut self) -> Result<Expression, Exception> {
+        let constants = [
+            ("null", Expression::CNone),
+            ("true", Expression::CTrue),
+            ("false", Expression::CFalse),
+            ("NaN", Expression::CReal(f64::NAN)),
+            ("Infinity", Expression::CReal(f64::INFINITY)),
+            ("-Infinity", Expression::CReal(f64::NEG_INFINITY)),
+        ];
+        for (word, value) in constants {
+            if self.looking_at(word) {
+                self.position += word.len();
+                return Ok(value);
+            }
+        }
+        match self.peek() {
+            Some('{' | '[') if self.depth == MAX_DEPTH => Err(too_deep("decoding")),
+            Some(c @ ('{' | '[')) => {
+                self.depth += 1;
+                let value = match c {
+                    '{' => self.object(),
+                    _ => self.array(),
+                };
+                self.depth -= 1;
+                value
+            }
+            Some('"') => self.string().map(Expression::CString),
+            Some('-' | '0'..='9') => self.number(),
+            _ => Err(self.error("Expecting value", self.position)),
+        }
+    }
+
+    fn object(&mut self) -> Result<Expression, Exception> {
+        self.position += 1;
+        let mut entries: Vec<(Expression, Expression)> = vec![];
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.position += 1;
+            return Ok(Expression::DictValue(entries));
+        }
+        loop {
+            if self.peek() != Some('"') {
+                return Err(self.error(
+                    "Expecting property name enclosed in double quotes",
+                    self.position,
+                ));
+            }
+            let key = Expression::CString(self.string()?);
+            self.skip_whitespace();
+            if self.peek() != Some(':') {
+                return Err(self.error("Expecting ':' delimiter", self.position));
+            }
+            self.position += 1;
+            self.skip_whitespace();
+            let value = self.value()?;
+            match entries.iter_mut().find(|(k, _)| *k == key) {
+                Some(entry) => entry.1 = value,
+                None => entries.push((key, value)),
+            }
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.position += 1,
+                Some('}') => {
+                    self.position += 1;
+                    return Ok(Expression::DictValue(entries));
+                }
+                _ => return Err(self.error("Expecting ',' delimiter", self.position)),
+            }
+            self.skip_whitespace();
+        }
+    }
+
+    fn array(&mut self) -> Result<Expression, Exception> {
+        self.position += 1;
+        let mut elements = vec![];
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.position += 1;
+            return Ok(Expression::ListValue(elements));
+        }
+        loop {
+            self.skip_whitespace();
+            elements.push(self.value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.position += 1,
+                Some(']') => {
+                    self.position += 1;
+                    return Ok(Expression::ListValue(elements));
+                }
+                _ => return Err(self.error("Expecting ',' delimiter", self.position)),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, Exception> {
+        let start = self.position;
+        self.position += 1;
+        let mut s = String::new();
+        loop {
+            match self.peek() {
+                None => return Err(self.error("Unterminated string starting at", start)),
+                Some('"') => {
+                    self.position += 1;
+                    return Ok(s);
+                }
+                Some('\\') => {
+                    let escape = self.position;
+                    self.position += 1;
+                    let c = match self.peek() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => {
+                            s.push(self.unicode_escape(escape)?);
+                            continue;
+                        }
+                        _ => return Err(self.error("Invalid \\escape", escape)),
+                    };
+                    self.position += 1;
+                    s.push(c);
+                }
+                Some(c) if (c as u32) < 0x20 => {
+                    return Err(self.error("Invalid control character at", self.position))
+                }
+                Some(c) => {
+                    self.position += 1;
+                    s.push(c);
+                }
+            }
+        }
+    }
+
+    /* '\uXXXX', where a high surrogate followed by an escaped low one
+     * stands for a single character beyond the basic plane */
+    fn unicode_escape(&mut self, escape: usize) -> Result<char, Exception> {
+        let high = self.hex4(escape)?;
+        if (0xd800..0xdc00).contains(&high) && self.looking_at("\\u") {
+            let position = self.position;
+            self.position += 1;
+            let low = self.hex4(position)?;
+            if (0xdc00..0xe000).contains(&low) {
+                let code = 0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00);
+                return Ok(char::from_u32(code).unwrap_or('\u{fffd}'));
+            }
+            self.position = position;
+        }
+        /* a lone surrogate is no character */
+        Ok(char::from_u32(high).unwrap_or('\u{fffd}'))
+    }
+
+    /* the four hexadecimal digits of an escape '\uXXXX' at the current
+     * position */
+    fn hex4(&mut self, escape: usize) -> Result<u32, Exception> {
+        let digits: String = self.chars.iter().skip(self.position + 1).take(4).collect();
+        match u32::from_str_radix(&digits, 16) {
+            Ok(code) if digits.len() == 4 && digits.chars().all(|c| c.is_ascii_hexdigit()) => {
+                self.position += 5;
+                Ok(code)
+            }
+            _ => Err(self.error("Invalid \\uXXXX escape", escape)),
+        }
+    }
+
+    /* JSON's grammar: an optional minus, an integer part without
+     * leading zeros, then an optional fraction and exponent */
+    fn number(&mut self) -> Result<Expression, Exception> {
+        let start = self.position;
+        let digits = |parser: &mut Parser| {
+            let from = parser.position;
+            while matches!(parser.peek(), Some('0'..='9')) {
+                parser.position += 1;
+            }
+            parser.position > from
+        };
+        if self.peek() == Some('-') {
+            self.position += 1;
+        }
+        if self.peek() == Some('0') {
+            self.position += 1;
+        } else if !digits(self) {
+            return Err(self.error("Expecting value", start));
+        }
+        let mut real = false;
+        if self.peek() == Some('.') && matches!(self.chars.get(self.position + 1), Some('0'..='9'))
+        {
+            self.position += 1;
+            digits(self);
+            real = true;
+        }
+        if matches!(self.peek(), Some('e' | 'E')) {
+            let mark = self.position;
+            self.position += 1;
+            if matches!(self.peek(), Some('+' | '-')) {
+                self.position += 1;
+            }
+            match digits(self) {
+                true => real = true,
+                /* not an exponent after all: what follows is extra */
+                false => self.position = mark,
+            }
+        }
+        let text: String = self.chars[start..self.position].iter().collect();
+        match text.parse() {
+            Ok(i) if !real => Ok(Expression::CInt(i)),
+            _ => Ok(Expression::CReal(text.parse().unwrap_or(f64::NAN))),
+        }
+    }
+
+    /* positions are given as Python gives them: a line and a column,
+     * counted from 1, and the index of the character, from 0 */
+    fn error(&self, message: &str, position: usize) -> Exception {
+        let before = &self.chars[..position.min(self.chars.len())];
+        let line = before.iter().filter(|c| **c == '\n').count() + 1;
+        let column = match before.iter().rposition(|c| *c == '\n') {
+            Some(newline) => position - newline,
+            None => position + 1,
+        };
+        Exception::new(
+            "JSONDecodeError",
+            &format!(
+                "{}: line {} column {} (char {}).",
+                message, line, column, position
+            ),
+        )
+    }
+}
+
+/* Encoding */
+
+struct Writer {
+    /* the number of spaces per level of nesting, or None to write
+     * everything on one line */
+    indent: Option<usize>,
+    sort_keys: bool,
+    text: String,
+}
+
+impl Writer {
+    fn write(
+        &mut self,
+        value: &Expression,
+        level: usize,
+        env: &Environment,
+    ) -> Result<(), Exception> {
+        match value {
+            Expression::CNone => self.text.push_str("null"),
+            Expression::CTrue => self.text.push_str("true"),
+            Expression::CFalse => self.text.push_str("false"),
+            Expression::CInt(i) => self.text.push_str(&i.to_string()),
+            Expression::CReal(r) => self.text.push_str(&real(*r)),
+            Expression::CString(s) => self.text.push_str(&quote(s)),
+            Expression::ListValue(_) | Expression::Tuple(_) | Expression::DictValue(_)
+                if level == MAX_DEPTH =>
+            {
+                return Err(too_deep("encoding"))
+            }
+            Expression::ListValue(elements) | Expression::Tuple(elements) => {
+                self.text.push('[');
+                for (i, element) in elements.iter().enumerate() {
+                    self.separate(i, level + 1);
+                    self.write(element, level + 1, env)?;
+                }
+                self.close(elements.is_empty(), level);
+                self.text.push(']');
+            }
+            Expression::DictValue(entries) => {
+                let mut keyed = vec![];
+                for (key, value) in entries {
+                    keyed.push((object_key(key, env)?, value));
+                }
+                if self.sort_keys {
+                    keyed.sort_by(|(a, _), (b, _)| a.cmp(b));
+                }
+                self.text.push('{');
+                for (i, (key, value)) in keyed.iter().enumerate() {
+                    self.separate(i, level + 1);
+                    self.text.push_str(&quote(key));
+                    self.text.push_str(": ");
+                    self.write(value, level + 1, env)?;
+                }
+                self.close(entries.is_empty(), level);
+                self.text.push('}');
+            }
+            _ => {
+                return Err(Exception::new(
+                    "TypeError",
+                    &format!(
+                        "Object of type {} is not JSON serializable.",
+                        type_name(value, env)
+                    ),
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    /* what comes before the i-th element of an array or object */
+    fn separate(&mut self, i: usize, level: usize) {
+        if i > 0 {
+            self.text.push(',');
+        }
+        match self.indent {
+            Some(indent) => {
+                self.text.push('\n');
+                self.text.push_str(&" ".repeat(indent * level));
+            }
+            None if i > 0 => self.text.push(' '),
+            None => (),
+        }
+    }
+
+    fn close(&mut self, empty: bool, level: usize) {
+        if let (Some(indent), false) = (self.indent, empty) {
+            self.text.push('\n');
+            self.text.push_str(&" ".repeat(indent * level));
+        }
+    }
+}
+
+/* as in Python, non-finite reals are written as JavaScript names them */
+fn real(r: f64) -> String {
+    match r {
+        r if r.is_nan() => String::from("NaN"),
+        r if r.is_infinite() && r > 0.0 => String::from("Infinity"),
+        r if r.is_infinite() => String::from("-Infinity"),
+        r => format_real(r),
+    }
+}
+
+/* keys of objects are strings; other simple keys are converted */
+fn object_key(key: &Expression, env: &Environment) -> Result<String, Exception> {
+    match key {
+        Expression::CString(s) => Ok(s.clone()),
+        Expression::CInt(i) => Ok(i.to_string()),
+        Expression::CReal(r) => Ok(real(*r)),
+        Expression::CTrue => Ok(String::from("true")),
+        Expression::CFalse => Ok(String::from("false")),
+        Expression::CNone => Ok(String::from("null")),
+        _ => Err(Exception::new(
+            "TypeError",
+            &format!(
+                "keys must be str, int, float, bool or None, not {}.",
+                type_name(key, env)
+            ),
+        )),
+    }
+}
+
+/* characters outside ASCII are escaped, as Python does by default */
+fn quote(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            '\u{8}' => quoted.push_str("\\b"),
+            '\u{c}' => quoted.push_str("\\f"),
+            c if (c as u32) < 0x20 || !c.is_ascii() => {
+                let mut units = [0u16; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    quoted.push_str(&format!("\\u{:04x}", unit));
+                }
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Exception;
+    use super::*;
+    use Expression::*;
+
+    fn string(s: &str) -> Expression {
+        CString(String::from(s))
+    }
+
+    fn dumps(
+        value: Expression,
+        indent: Option<i32>,
+        sort_keys: bool,
+    ) -> Result<Expression, Exception> {
+        let args = vec![
+            Some(value),
+            indent.map(CInt),
+            Some(if sort_keys { CTrue } else { CFalse }),
+        ];
+        call("dumps", args, &Environment::new())
+    }
+
+    #[test]
+    fn loads_values() {
+        let text = r#" {"name": "r-python", "version": [0, 1.5, -2e3],
+                        "tags": {"a": true, "b": false, "c": null},
+                        "escaped": "tab\t quote\" \u00e9 \ud83d\ude00", "name": "again"} "#;
+        assert_eq!(
+            loads(text),
+            Ok(DictValue(vec![
+                (string("name"), string("again")),
+                (
+                    string("version"),
+                    ListValue(vec![CInt(0), CReal(1.5), CReal(-2000.0)])
+                ),
+                (
+                    string("tags"),
+                    DictValue(vec![
+                        (string("a"), CTrue),
+                        (string("b"), CFalse),
+                        (string("c"), CNone),
+                    ])
+                ),
+                (string("escaped"), string("tab\t quote\" é 😀")),
+            ]))
+        );
+        assert_eq!(loads("[]"), Ok(ListValue(vec![])));
+        assert_eq!(loads("-0"), Ok(CInt(0)));
+        assert_eq!(loads("Infinity"), Ok(CReal(f64::INFINITY)));
+    }
+
+    fn decode_error(message: &str) -> Result<Expression, Exception> {
+        Err(Exception::new("JSONDecodeError", message))
+    }
+
+    #[test]
+    fn loads_reports_missing_values() {
+        assert_eq!(
+            loads(""),
+            decode_error("Expecting value: line 1 column 1 (char 0).")
+        );
+        assert_eq!(
+            loads("[1,]"),
+            decode_error("Expecting value: line 1 column 4 (char 3).")
+        );
+        assert_eq!(
+            loads("-"),
+            decode_error("Expecting value: line 1 column 1 (char 0).")
+        );
+    }
+
+    #[test]
+    fn loads_reports_missing_delimiters() {
+        assert_eq!(
+            loads("[1, 2"),
+            decode_error("Expecting ',' delimiter: line 1 column 6 (char 5).")
+        );
+        assert_eq!(
+            loads("{\n  \"a\" 1}"),
+            decode_error("Expecting ':' delimiter: line 2 column 7 (char 8).")
+        );
+        assert_eq!(
+            loads("{\"a\": 1,\n 'b': 2}"),
+            decode_error(
+                "Expecting property name enclosed in double quotes: line 2 column 2 (char 10)."
+            )
+        );
+    }
+
+    #[test]
+    fn loads_reports_malformed_strings() {
+        assert_eq!(
+            loads("\"abc"),
+            decode_error("Unterminated string starting at: line 1 column 1 (char 0).")
+        );
+        assert_eq!(
+            loads("\"a\\x\""),
+            decode_error("Invalid \\escape: line 1 column 3 (char 2).")
+        );
+        assert_eq!(
+            loads("\"\\u12g4\""),
+            decode_error("Invalid \\uXXXX escape: line 1 column 2 (char 1).")
+        );
+        assert_eq!(
+            loads("\"a\nb\""),
+            decode_error("Invalid control character at: line 1 column 3 (char 2).")
+        );
+    }
+
+    #[test]
+    fn loads_reports_extra_data() {
+        assert_eq!(
+            loads("[1] x"),
+            decode_error("Extra data: line 1 column 5 (char 4).")
+        );
+        assert_eq!(
+            loads("01"),
+            decode_error("Extra data: line 1 column 2 (char 1).")
+        );
+    }
+
+    #[test]
+    fn loads_large_integers_as_reals() {
+        assert_eq!(loads("3000000000"), Ok(CReal(3e9)));
+        assert_eq!(loads("[-2147483648]"), Ok(ListValue(vec![CInt(i32::MIN)])));
+    }
+
+    #[test]
+    fn loads_limits_nesting() {
+        let nested = |depth: usize| "[".repeat(depth) + &"]".repeat(depth);
+        assert!(loads(&nested(MAX_DEPTH)).is_ok());
+        assert_eq!(
+            loads(&nested(MAX_DEPTH + 1)),
+            Err(Exception::new(
+                "RecursionError",
+                "maximum recursion depth exceeded while decoding JSON."
+            ))
+        );
+        /* an unterminated text is rejected as soon as it is too deep */
+        assert_eq!(
+            loads(&"[".repeat(200_000)),
+            Err(Exception::new(
+                "RecursionError",
+                "maximum recursion depth exceeded while decoding JSON."
+            ))
+        );
+    }
+
+    fn sample() -> Expression {
+        DictValue(vec![
+            (string("b"), ListValue(vec![CInt(1), CReal(2.0), CNone])),
+            (
+                string("a"),
+                DictValue(vec![(CInt(1), CTrue), (CNone, ListValue(vec![]))]),
+            ),
+            (string("é\n"), Tuple(vec![string("\"q\"")])),
+        ])
+    }
+
+    #[test]
+    fn dumps_values() {
+        assert_eq!(
+            dumps(sample(), None, false),
+            Ok(string(
+                r#"{"b": [1, 2.0, null], "a": {"1": true, "null": []}, "\u00e9\n": ["\"q\""]}"#
+            ))
+        );
+    }
+
+    #[test]
+    fn dumps_large_and_tiny_reals_with_an_exponent() {
+        let reals = ListValue(vec![CReal(1e16), CReal(1e-7)]);
+        assert_eq!(
+            dumps(reals.clone(), None, false),
+            Ok(string("[1e+16, 1e-07]"))
+        );
+        assert_eq!(loads("[1e+16, 1e-07]"), Ok(reals));
+    }
+
+    #[test]
+    fn dumps_indented_with_sorted_keys() {
+        assert_eq!(
+            dumps(sample(), Some(2), true),
+            Ok(string(
+                "{\n  \"a\": {\n    \"1\": true,\n    \"null\": []\n  },\n  \"b\": [\n    1,\n    2.0,\n    null\n  ],\n  \"\\u00e9\\n\": [\n    \"\\\"q\\\"\"\n  ]\n}"
+            ))
+        );
+    }
+
+    #[test]
+    fn loads_reads_back_what_dumps_writes() {
+        /* keys are read back as strings */
+        let written = match dumps(
+            ListValue(vec![sample(), CReal(f64::NEG_INFINITY)]),
+            None,
+            false,
+        ) {
+            Ok(CString(text)) => text,
+            result => panic!("unexpected result {:?}", result),
+        };
+        assert_eq!(
+            loads(&written).map(|v| v.to_string()),
+            Ok(String::from(
+                "[{'b': [1, 2.0, None], 'a': {'1': True, 'null': []}, 'é\\n': ['\"q\"']}, -inf]"
+            ))
+        );
+    }
+
+    #[test]
+    fn dumps_rejects_values_json_lacks() {
+        assert_eq!(
+            dumps(SetValue(vec![CInt(1)]), None, false),
+            Err(Exception::new(
+                "TypeError",
+                "Object of type set is not JSON serializable."
+            ))
+        );
+        assert_eq!(
+            dumps(DictValue(vec![(Tuple(vec![]), CInt(1))]), None, false),
+            Err(Exception::new(
+                "TypeError",
+                "keys must be str, int, float, bool or None, not tuple."
+            ))
+        );
+    }
+
+    #[test]
+    fn dumps_limits_nesting() {
+        let mut value = ListValue(vec![]);
+        for _ in 0..MAX_DEPTH {
+            value = ListValue(vec![value]);
+        }
+        assert_eq!(
+            dumps(value, None, false),
+            Err(Exception::new(
+                "RecursionError",
+                "maximum recursion depth exceeded while encoding JSON."
+            ))
+        );
+    }
+}
@@ -0,0 +1,340 @@
+use std::f64::consts;
+
+use super::type_name;
+use crate::interpreter::interpreter::Environment;
+use crate::interpreter::interpreter::Exception;
+use crate::ir::ast::Expression;
+
+pub(super) fn constant(name: &str) -> Expression {
+    Expression::CReal(match name {
+        "pi" => consts::PI,
+        "e" => consts::E,
+        "inf" => f64::INFINITY,
+        _ => f64::NAN,
+    })
+}
+
+/* calls a function of 'math' with the arguments matched to its
+ * parameters (None for those left to their default) */
+pub(super) fn call(
+    function: &str,
+    args: Vec<Option<Expression>>,
+    env: &Environment,
+) -> Result<Expression, Exception> {
+    let mut args = args.into_iter();
+    let mut arg = || args.next().flatten();
+    match function {
+        "floor" | "ceil" | "round" => match arg() {
+            Some(Expression::CInt(i)) => Ok(Expression::CInt(i)),
+            x => {
+                let x = real(x, env)?;
+                let rounded = match function {
+                    "floor" => x.floor(),
+                    "ceil" => x.ceil(),
+                    _ => x.round_ties_even(),
+                };
+                to_integer(rounded)
+            }
+        },
+        "gcd" => {
+            let (a, b) = (integer(arg(), env)?, integer(arg(), env)?);
+            let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+            while b != 0 {
+                (a, b) = (b, a % b);
+            }
+            i32::try_from(a)
+                .map(Expression::CInt)
+                .map_err(|_| Exception::new("OverflowError", "integer overflow in 'gcd'."))
+        }
+        "isclose" => {
+            let (a, b) = (real(arg(), env)?, real(arg(), env)?);
+            let rel_tol = arg().map_or(Ok(1e-09), |tol| real(Some(tol), env))?;
+            let abs_tol = arg().map_or(Ok(0.0), |tol| real(Some(tol), env))?;
+            if rel_tol < 0.0 || abs_tol < 0.0 {
+                return Err(Exception::new(
+                    "ValueError",
+                    "tolerances must be non-negative.",
+                ));
+            }
+            /* infinities are close only to themselves */
+            let close = a == b
+                || (a.is_finite()
+                    && b.is_finite()
+                    && (a - b).abs() <= (rel_tol * a.abs().max(b.abs())).max(abs_tol));
+            Ok(match close {
+                true => Expression::CTrue,
+                false => Expression::CFalse,
+            })
+        }
+        "log" => {
+            let x = real(arg(), env)?;
+            let base = arg().map(|base| real(Some(base), env)).transpose()?;
+            if x <= 0.0 || base.is_some_and(|base| base <= 0.0 || base == 1.0) {
+                return Err(domain_error());
+            }
+            let result = match base {
+                Some(base) => x.ln() / base.ln(),
+                None => x.ln(),
+            };
+            checked(result, &[x, base.unwrap_or(consts::E)])
+        }
+        "pow" => {
+            let (x, y) = (real(arg(), env)?, real(arg(), env)?);
+            if x == 0.0 && y < 0.0 {
+                return Err(domain_error());
+            }
+            checked(x.powf(y), &[x, y])
+        }
+        "atan2" => {
+            let (y, x) = (real(arg(), env)?, real(arg(), env)?);
+            checked(y.atan2(x), &[y, x])
+        }
+        _ => {
+            let x = real(arg(), env)?;
+            let result = match function {
+                "sqrt" => x.sqrt(),
+                "exp" => x.exp(),
+                "sin" => x.sin(),
+                "cos" => x.cos(),
+                "tan" => x.tan(),
+                "asin" => x.asin(),
+                "acos" => x.acos(),
+                "atan" => x.atan(),
+                _ => {
+                    return Err(Exception::new(
+                        "AttributeError",
+                        &format!("module 'math' has no attribute '{}'.", function),
+                    ))
+                }
+            };
+            checked(result, &[x])
+        }
+    }
+}
+
+/* as in Python, a result that is not a number for arguments that are
+ * is outside the domain of the function, and an infinite result for
+ * finite arguments is out of range */
+fn checked(result: f64, args: &[f64]) -> Result<Expression, Exception> {
+    if result.is_nan() && !args.iter().any(|x| x.is_nan()) {
+        return Err(domain_error());
+    }
+    if result.is_infinite() && args.iter().all(|x| x.is_finite()) {
+        return Err(Exception::new("OverflowError", "math range error."));
+    }
+    Ok(Expression::CReal(result))
+}
+
+fn domain_error() -> Exception {
+    Exception::new("ValueError", "math domain error.")
+}
+
+fn real(value: Option<Expression>, env: &Environment) -> Result<f64, Exception> {
+    match value {
+        Some(Expression::CInt(i)) => Ok(i as f64),
+        Some(Expression::CReal(r)) => Ok(r),
+        value => Err(Exception::new(
+            "TypeError",
+            &format!(
+                "must be a real number, not '{}'.",
+                type_name(&value.unwrap_or(Expression::CNone), env)
+            ),
+        )),
+    }
+}
+
+fn integer(value: Option<Expression>, env: &Environment) -> Result<i32, Exception> {
+    match value {
+        Some(Expression::CInt(i)) => Ok(i),
+        value => Err(Exception::new(
+            "TypeError",
+            &format!(
+                "'{}' object cannot be interpreted as an integer.",
+                type_name(&value.unwrap_or(Expression::CNone), env)
+            ),
+        )),
+    }
+}
+
+fn to_integer(x: f64) -> Result<Expression, Exception> {
+    if x.is_nan() {
+        return Err(Exception::new(
+            "ValueError",
+            "cannot convert float nan to integer.",
+        ));
+    }
+    if x < i32::MIN as f64 || x > i32::MAX as f64 {
+        return Err(Exception::new(
+            "OverflowError",
+            &format!("cannot convert float {} to integer.", Expression::CReal(x)),
+        ));
+    }
+    Ok(Expression::CInt(x as i32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::relative_eq;
+
+    fn call_math(function: &str, args: Vec<Expression>) -> Result<Expression, Exception> {
+        call(
+            function,
+            args.into_iter().map(Some).collect(),
+            &Environment::new(),
+        )
+    }
+
+    fn real_result(function: &str, args: Vec<Expression>) -> f64 {
+        match call_math(function, args) {
+            Ok(Expression::CReal(r)) => r,
+            result => panic!("{}: expecting a real, got {:?}", function, result),
+        }
+    }
+
+    fn assert_real(function: &str, args: Vec<Expression>, expected: f64) {
+        let result = real_result(function, args);
+        assert!(
+            relative_eq!(result, expected, epsilon = 1e-12),
+            "{}: {} != {}",
+            function,
+            result,
+            expected
+        );
+    }
+
+    #[test]
+    fn math_powers_and_logarithms() {
+        use Expression::{CInt, CReal};
+        assert_real("sqrt", vec![CInt(2)], consts::SQRT_2);
+        assert_real("pow", vec![CInt(2), CReal(0.5)], consts::SQRT_2);
+        assert_real("pow", vec![CReal(-2.0), CInt(3)], -8.0);
+        assert_real("exp", vec![CInt(1)], consts::E);
+        assert_real("log", vec![CReal(consts::E)], 1.0);
+        assert_real("log", vec![CInt(1000), CInt(10)], 3.0);
+    }
+
+    #[test]
+    fn math_trigonometry() {
+        use Expression::{CInt, CReal};
+        assert_real("sin", vec![CReal(consts::FRAC_PI_6)], 0.5);
+        assert_real("cos", vec![CReal(consts::PI)], -1.0);
+        assert_real("tan", vec![CReal(consts::FRAC_PI_4)], 1.0);
+        assert_real("asin", vec![CInt(1)], consts::FRAC_PI_2);
+        assert_real("acos", vec![CReal(0.5)], consts::FRAC_PI_3);
+        assert_real("atan", vec![CInt(1)], consts::FRAC_PI_4);
+        assert_real("atan2", vec![CInt(1), CInt(-1)], 3.0 * consts::FRAC_PI_4);
+    }
+
+    #[test]
+    fn math_rounding() {
+        use Expression::{CInt, CReal};
+        assert_eq!(call_math("floor", vec![CReal(-2.5)]), Ok(CInt(-3)));
+        assert_eq!(call_math("ceil", vec![CReal(2.1)]), Ok(CInt(3)));
+        /* halves round to the even neighbour */
+        assert_eq!(call_math("round", vec![CReal(2.5)]), Ok(CInt(2)));
+        assert_eq!(call_math("round", vec![CReal(3.5)]), Ok(CInt(4)));
+        assert_eq!(call_math("round", vec![CInt(7)]), Ok(CInt(7)));
+    }
+
+    #[test]
+    fn math_gcd() {
+        use Expression::CInt;
+        assert_eq!(call_math("gcd", vec![CInt(-12), CInt(18)]), Ok(CInt(6)));
+        assert_eq!(call_math("gcd", vec![CInt(0), CInt(0)]), Ok(CInt(0)));
+    }
+
+    #[test]
+    fn math_isclose() {
+        use Expression::CReal;
+        assert_eq!(
+            call_math("isclose", vec![CReal(0.1 + 0.2), CReal(0.3)]),
+            Ok(Expression::CTrue)
+        );
+        assert_eq!(
+            call_math("isclose", vec![CReal(1.0), CReal(1.1)]),
+            Ok(Expression::CFalse)
+        );
+        assert_eq!(
+            call(
+                "isclose",
+                vec![Some(CReal(1.0)), Some(CReal(1.1)), None, Some(CReal(0.2))],
+                &Environment::new()
+            ),
+            Ok(Expression::CTrue)
+        );
+    }
+
+    #[test]
+    fn math_infinity_and_nan() {
+        assert!(relative_eq!(
+            real_result("sqrt", vec![constant("inf")]),
+            f64::INFINITY
+        ));
+        assert!(real_result("cos", vec![constant("nan")]).is_nan());
+    }
+
+    #[test]
+    fn math_domain_errors() {
+        use Expression::{CInt, CReal};
+        let domain = Err(Exception::new("ValueError", "math domain error."));
+        assert_eq!(call_math("sqrt", vec![CInt(-1)]), domain);
+        assert_eq!(call_math("log", vec![CInt(0)]), domain);
+        assert_eq!(call_math("log", vec![CInt(8), CInt(1)]), domain);
+        assert_eq!(call_math("asin", vec![CReal(1.5)]), domain);
+        assert_eq!(call_math("pow", vec![CInt(0), CInt(-1)]), domain);
+        assert_eq!(call_math("pow", vec![CInt(-8), CReal(0.5)]), domain);
+    }
+
+    #[test]
+    fn math_range_errors() {
+        use Expression::{CInt, CReal};
+        let range = Err(Exception::new("OverflowError", "math range error."));
+        assert_eq!(call_math("exp", vec![CInt(1000)]), range);
+        assert_eq!(call_math("pow", vec![CReal(10.0), CInt(400)]), range);
+    }
+
+    #[test]
+    fn math_integer_conversion_errors() {
+        assert_eq!(
+            call_math("floor", vec![constant("inf")]),
+            Err(Exception::new(
+                "OverflowError",
+                "cannot convert float inf to integer."
+            ))
+        );
+        assert_eq!(
+            call_math("round", vec![constant("nan")]),
+            Err(Exception::new(
+                "ValueError",
+                "cannot convert float nan to integer."
+            ))
+        );
+    }
+
+    #[test]
+    fn math_argument_errors() {
+        use Expression::{CInt, CReal};
+        assert_eq!(
+            call_math("gcd", vec![CReal(1.0), CInt(2)]),
+            Err(Exception::new(
+                "TypeError",
+                "'float' object cannot be interpreted as an integer.",
+            ))
+        );
+        assert_eq!(
+            call_math("sqrt", vec![Expression::CString(String::from("4"))]),
+            Err(Exception::new(
+                "TypeError",
+                "must be a real number, not 'str'."
+            ))
+        );
+        assert_eq!(
+            call_math("isclose", vec![CInt(1), CInt(1), CReal(-0.1)]),
+            Err(Exception::new(
+                "ValueError",
+                "tolerances must be non-negative."
+            ))
+        );
+    }
+}
@@ -0,0 +1,329 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use super::collect;
+use super::type_name;
+use crate::interpreter::interpreter::Environment;
+use crate::interpreter::interpreter::Exception;
+use crate::ir::ast::Expression;
+
+/* the seed of the generator of a new environment, so that programs that
+ * do not call 'seed' are reproducible too */
+const DEFAULT_SEED: u64 = 0;
+
+/// The pseudo-random number generator of the `random` module,
+/// xoshiro256**, whose state is filled by SplitMix64 from the seed. It is
+/// implemented here so that a seed gives the same numbers on every
+/// machine and with every version of the dependencies.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Random {
+    state: [u64; 4],
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Random {
+        let mut x = seed;
+        let mut splitmix = || {
+            x = x.wrapping_add(0x9e3779b97f4a7c15);
+            let mut z = x;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+            z ^ (z >> 31)
+        };
+        Random {
+            state: [splitmix(), splitmix(), splitmix(), splitmix()],
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        result
+    }
+
+    /// A real in [0, 1), with 53 random bits.
+    pub fn next_real(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// An integer in [0, n), without bias: the numbers beyond the last
+    /// multiple of n are drawn again.
+    pub fn below(&mut self, n: u64) -> u64 {
+        let zone = u64::MAX - u64::MAX % n;
+        loop {
+            let x = self.next_u64();
+            if x < zone {
+                return x % n;
+            }
+        }
+    }
+}
+
+/* the generator of an environment, shared by its copies */
+#[derive(Clone)]
+pub struct RandomRef(pub Rc<RefCell<Random>>);
+
+impl Default for RandomRef {
+    fn default() -> Self {
+        RandomRef(Rc::new(RefCell::new(Random::new(DEFAULT_SEED))))
+    }
+}
+
+impl fmt::Debug for RandomRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "RandomRef")
+    }
+}
+
+impl PartialEq for RandomRef {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/* calls a function of 'random' with the arguments matched to its
+ * parameters */
+pub(super) fn call(
+    function: &str,
+    args: Vec<Option<Expression>>,
+    env: &Environment,
+) -> Result<Expression, Exception> {
+    /* borrowed for each draw only, as the arguments may be generators
+     * that draw numbers too */
+    let generator = env.random();
+    let random = || generator.0.borrow_mut();
+    let mut args = args.into_iter();
+    let mut arg = || args.next().flatten().unwrap_or(Expression::CNone);
+    match function {
+        "seed" => {
+            /* negative seeds are as good as any */
+            *random() = Random::new(integer(arg(), env)? as i64 as u64);
+            Ok(Expression::CNone)
+        }
+        "random" => Ok(Expression::CReal(random().next_real())),
+        "randint" => {
+            let (a, b) = (integer(arg(), env)?, integer(arg(), env)?);
+            if a > b {
+                return Err(Exception::new(
+                    "ValueError",
+                    &format!("empty range for randint({}, {}).", a, b),
+                ));
+            }
+            let offset = random().below((b as i64 - a as i64 + 1) as u64);
+            Ok(Expression::CInt((a as i64 + offset as i64) as i32))
+        }
+        "choice" => {
+            let values = collect(arg(), env)?;
+            if values.is_empty() {
+                return Err(Exception::new(
+                    "IndexError",
+                    "cannot choose from an empty sequence.",
+                ));
+            }
+            let i = random().below(values.len() as u64) as usize;
+            Ok(values[i].clone())
+        }
+        "shuffle" => {
+            let mut values = collect(arg(), env)?;
+            for i in (1..values.len()).rev() {
+                let j = random().below(i as u64 + 1) as usize;
+                values.swap(i, j);
+            }
+            Ok(Expression::ListValue(values))
+        }
+        "sample" => {
+            let mut values = collect(arg(), env)?;
+            let k = integer(arg(), env)?;
+            if k < 0 || k as usize > values.len() {
+                return Err(Exception::new(
+                    "ValueError",
+                    "sample larger than population or is negative.",
+                ));
+            }
+            /* the first k steps of a shuffle */
+            for i in 0..k as usize {
+                let j = i + random().below((values.len() - i) as u64) as usize;
+                values.swap(i, j);
+            }
+            values.truncate(k as usize);
+            Ok(Expression::ListValue(values))
+        }
+        _ => Err(Exception::new(
+            "AttributeError",
+            &format!("module 'random' has no attribute '{}'.", function),
+        )),
+    }
+}
+
+fn integer(value: Expression, env: &Environment) -> Result<i32, Exception> {
+    match value {
+        Expression::CInt(i) => Ok(i),
+        _ => Err(Exception::new(
+            "TypeError",
+            &format!(
+                "'{}' object cannot be interpreted as an integer.",
+                type_name(&value, env)
+            ),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call_random(
+        function: &str,
+        args: Vec<Expression>,
+        env: &Environment,
+    ) -> Result<Expression, Exception> {
+        call(function, args.into_iter().map(Some).collect(), env)
+    }
+
+    #[test]
+    fn generator_is_deterministic() {
+        /* as given by the reference implementations of SplitMix64 and
+         * xoshiro256** */
+        let mut random = Random::new(42);
+        let first: Vec<u64> = (0..3).map(|_| random.next_u64()).collect();
+        assert_eq!(
+            first,
+            [
+                1546998764402558742,
+                6990951692964543102,
+                12544586762248559009
+            ]
+        );
+        assert_eq!(Random::new(42), Random::new(42));
+        assert_ne!(Random::new(42), Random::new(43));
+
+        let mut random = Random::new(7);
+        for _ in 0..1000 {
+            let x = random.next_real();
+            assert!((0.0..1.0).contains(&x));
+            assert!(random.below(6) < 6);
+        }
+    }
+
+    fn ints(values: &[i32]) -> Expression {
+        Expression::ListValue(values.iter().map(|i| Expression::CInt(*i)).collect())
+    }
+
+    /* a draw of each function after seeding the generator */
+    fn draw(env: &Environment) -> Vec<Expression> {
+        use Expression::CInt;
+        call_random("seed", vec![CInt(2024)], env).unwrap();
+        vec![
+            call_random("random", vec![], env).unwrap(),
+            call_random("randint", vec![CInt(1), CInt(6)], env).unwrap(),
+            call_random("choice", vec![ints(&[10, 20, 30])], env).unwrap(),
+            call_random("shuffle", vec![ints(&[1, 2, 3, 4, 5])], env).unwrap(),
+            call_random("sample", vec![ints(&[1, 2, 3, 4, 5]), CInt(3)], env).unwrap(),
+        ]
+    }
+
+    #[test]
+    fn random_draws_depend_only_on_the_seed() {
+        let env = Environment::new();
+        let first = draw(&env);
+        assert_eq!(first, draw(&env));
+        assert_eq!(first, draw(&Environment::new()));
+    }
+
+    #[test]
+    fn random_draws_are_within_their_bounds() {
+        use Expression::CInt;
+        match &draw(&Environment::new())[..] {
+            [Expression::CReal(x), CInt(die), CInt(choice), Expression::ListValue(shuffled), Expression::ListValue(sample)] =>
+            {
+                assert!((0.0..1.0).contains(x));
+                assert!((1..=6).contains(die));
+                assert!([10, 20, 30].contains(choice));
+                let mut sorted = shuffled.clone();
+                sorted.sort_by_key(|v| v.to_string());
+                assert_eq!(Expression::ListValue(sorted), ints(&[1, 2, 3, 4, 5]));
+                assert_eq!(sample.len(), 3);
+                assert!(sample
+                    .iter()
+                    .enumerate()
+                    .all(|(i, v)| !sample[..i].contains(v)));
+            }
+            draws => panic!("unexpected draws {:?}", draws),
+        }
+    }
+
+    #[test]
+    fn randint_covers_its_range() {
+        use Expression::CInt;
+        let env = Environment::new();
+        let mut seen = [false; 3];
+        for _ in 0..100 {
+            match call_random("randint", vec![CInt(-1), CInt(1)], &env) {
+                Ok(CInt(i)) => seen[(i + 1) as usize] = true,
+                result => panic!("unexpected result {:?}", result),
+            }
+        }
+        assert_eq!(seen, [true; 3]);
+        assert_eq!(
+            call_random("randint", vec![CInt(i32::MIN), CInt(i32::MIN)], &env),
+            Ok(CInt(i32::MIN))
+        );
+    }
+
+    #[test]
+    fn randint_with_an_empty_range() {
+        use Expression::CInt;
+        assert_eq!(
+            call_random("randint", vec![CInt(2), CInt(1)], &Environment::new()),
+            Err(Exception::new(
+                "ValueError",
+                "empty range for randint(2, 1)."
+            ))
+        );
+    }
+
+    #[test]
+    fn choice_from_an_empty_sequence() {
+        assert_eq!(
+            call_random("choice", vec![ints(&[])], &Environment::new()),
+            Err(Exception::new(
+                "IndexError",
+                "cannot choose from an empty sequence."
+            ))
+        );
+    }
+
+    #[test]
+    fn sample_larger_than_the_population() {
+        assert_eq!(
+            call_random(
+                "sample",
+                vec![ints(&[1]), Expression::CInt(2)],
+                &Environment::new()
+            ),
+            Err(Exception::new(
+                "ValueError",
+                "sample larger than population or is negative."
+            ))
+        );
+    }
+
+    #[test]
+    fn seed_must_be_an_integer() {
+        assert_eq!(
+            call_random("seed", vec![Expression::CReal(1.5)], &Environment::new()),
+            Err(Exception::new(
+                "TypeError",
+                "'float' object cannot be interpreted as an integer."
+            ))
+        );
+    }
+}
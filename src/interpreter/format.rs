@@ -0,0 +1,299 @@
+use crate::ir::ast::format_real;
+use crate::ir::ast::Expression;
+use crate::ir::format::Spec;
+
+type ErrorMessage = String;
+
+/// Formats an evaluated value according to `spec`.
+pub fn format_value(value: &Expression, spec: &Spec) -> Result<String, ErrorMessage> {
+    let (prefix, body, numeric) = match (value, spec.kind) {
+        (Expression::CInt(n), None | Some('d')) => {
+            let digits = n.unsigned_abs().to_string();
+            let digits = if spec.grouping {
+                group_thousands(&digits)
+            } else {
+                digits
+            };
+            (sign(*n < 0, spec), digits, true)
+        }
+        (Expression::CInt(n), Some(kind @ ('b' | 'o' | 'x' | 'X'))) => {
+            let v = n.unsigned_abs();
+            let (digits, base) = match kind {
+                'b' => (format!("{:b}", v), "0b"),
+                'o' => (format!("{:o}", v), "0o"),
+                'x' => (format!("{:x}", v), "0x"),
+                _ => (format!("{:X}", v), "0X"),
+            };
+            let mut prefix = sign(*n < 0, spec);
+            if spec.alternate {
+                prefix.push_str(base);
+            }
+            (prefix, digits, true)
+        }
+        (Expression::CInt(n), Some(kind @ ('f' | 'F' | 'e' | 'E' | 'g' | 'G' | '%'))) => {
+            let v = *n as f64;
+            (
+                sign(v < 0.0, spec),
+                format_real_as(v.abs(), kind, spec),
+                true,
+            )
+        }
+        (Expression::CReal(v), Some(kind @ ('f' | 'F' | 'e' | 'E' | 'g' | 'G' | '%'))) => (
+            sign(v.is_sign_negative() && !v.is_nan(), spec),
+            format_real_as(v.abs(), kind, spec),
+            true,
+        ),
+        (Expression::CReal(v), None) => {
+            let body = match spec.precision {
+                Some(precision) if v.is_finite() => general(v.abs(), precision, false, spec),
+                _ => format_real(v.abs()),
+            };
+            (sign(v.is_sign_negative() && !v.is_nan(), spec), body, true)
+        }
+        (Expression::CString(s), None | Some('s')) => {
+            let body = match spec.precision {
+                Some(p) => s.chars().take(p).collect(),
+                None => s.clone(),
+            };
+            (String::new(), body, false)
+        }
+        (_, None) => (String::new(), value.to_string(), false),
+        (_, Some(kind)) => {
+            return Err(format!(
+                "format type '{}' is not defined for the value {}.",
+                kind, value
+            ))
+        }
+    };
+    Ok(pad(prefix, body, numeric, spec))
+}
+
+fn sign(negative: bool, spec: &Spec) -> String {
+    match (negative, spec.sign) {
+        (true, _) => String::from("-"),
+        (false, '+') => String::from("+"),
+        (false, ' ') => String::from(" "),
+        _ => String::new(),
+    }
+}
+
+/* formats a non-negative real with one of the types 'f', 'e', 'g' or '%' */
+fn format_real_as(v: f64, kind: char, spec: &Spec) -> String {
+    if !v.is_finite() {
+        let body = format_real(v);
+        return if kind.is_uppercase() {
+            body.to_uppercase()
+        } else {
+            body
+        };
+    }
+    let precision = spec.precision.unwrap_or(6);
+    let body = match kind {
+        'e' | 'E' => exponential(v, precision),
+        'g' | 'G' => general(v, precision, true, spec),
+        '%' => format!("{}%", fixed(v * 100.0, precision, spec.grouping)),
+        _ => fixed(v, precision, spec.grouping),
+    };
+    if kind.is_uppercase() {
+        body.to_uppercase()
+    } else {
+        body
+    }
+}
+
+fn exponential(v: f64, precision: usize) -> String {
+    let (mantissa, exponent) = scientific(v, precision);
+    let sign = if exponent < 0 { '-' } else { '+' };
+    format!("{}e{}{:02}", mantissa, sign, exponent.abs())
+}
+
+/* the mantissa, with 'precision' digits after the point, and the exponent */
+fn scientific(v: f64, precision: usize) -> (String, i32) {
+    let formatted = format!("{:.*e}", precision, v);
+    let (mantissa, exponent) = formatted.split_once('e').unwrap_or((&formatted, "0"));
+    (mantissa.to_string(), exponent.parse().unwrap_or(0))
+}
+
+/* 'precision' significant digits, in fixed notation unless the exponent
+ * is below -4 or reaches the precision, without trailing zeros unless '#'
+ * is given; with no type, fixed notation keeps a digit after the point
+ * and exponent notation starts one digit earlier, as in Python */
+fn general(v: f64, precision: usize, typed: bool, spec: &Spec) -> String {
+    let precision = precision.max(1);
+    let (_, exponent) = scientific(v, precision - 1);
+    let limit = if typed { precision } else { precision - 1 } as i32;
+    let (body, is_fixed) = if (-4..limit).contains(&exponent) {
+        let decimals = (precision as i32 - 1 - exponent) as usize;
+        (fixed(v, decimals, spec.grouping), true)
+    } else {
+        (exponential(v, precision - 1), false)
+    };
+    if spec.alternate {
+        return body;
+    }
+    let (mantissa, exponent) = match body.split_once('e') {
+        Some((mantissa, exponent)) => (mantissa, format!("e{}", exponent)),
+        None => (body.as_str(), String::new()),
+    };
+    let mut mantissa = match mantissa.contains('.') {
+        true => mantissa.trim_end_matches('0').trim_end_matches('.'),
+        false => mantissa,
+    }
+    .to_string();
+    if is_fixed && !typed && !mantissa.contains('.') {
+        mantissa.push_str(".0");
+    }
+    mantissa + &exponent
+}
+
+fn fixed(v: f64, precision: usize, grouping: bool) -> String {
+    let formatted = format!("{:.*}", precision, v);
+    if !grouping {
+        return formatted;
+    }
+    match formatted.split_once('.') {
+        Some((integer, fraction)) => format!("{}.{}", group_thousands(integer), fraction),
+        None => group_thousands(&formatted),
+    }
+}
+
+fn group_thousands(digits: &str) -> String {
+    let mut grouped = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    grouped
+}
+
+/* numbers are right-aligned and strings left-aligned by default, but
+ * numbers padded with zeros put them after the sign; '=' places the
+ * padding between the sign (and base prefix) and the digits */
+fn pad(prefix: String, body: String, numeric: bool, spec: &Spec) -> String {
+    let len = prefix.chars().count() + body.chars().count();
+    let padding = spec.width.saturating_sub(len);
+    let fill = |n: usize| spec.fill.to_string().repeat(n);
+    let align = spec.align.unwrap_or(match (numeric, spec.fill) {
+        (true, '0') => '=',
+        (true, _) => '>',
+        (false, _) => '<',
+    });
+    match align {
+        '<' => format!("{}{}{}", prefix, body, fill(padding)),
+        '^' => format!(
+            "{}{}{}{}",
+            fill(padding / 2),
+            prefix,
+            body,
+            fill(padding - padding / 2)
+        ),
+        '=' => format!("{}{}{}", prefix, fill(padding), body),
+        _ => format!("{}{}{}", fill(padding), prefix, body),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::ast::Expression::*;
+    use crate::ir::format::parse_spec;
+
+    fn fmt(value: Expression, spec: &str) -> Result<String, ErrorMessage> {
+        format_value(&value, &parse_spec(spec, 0)?)
+    }
+
+    #[test]
+    fn format_width_and_alignment() {
+        assert_eq!(fmt(CInt(42), "5"), Ok(String::from("   42")));
+        assert_eq!(fmt(CInt(42), "<5"), Ok(String::from("42   ")));
+        assert_eq!(fmt(CInt(42), "*^6"), Ok(String::from("**42**")));
+        assert_eq!(fmt(CInt(-42), "=6"), Ok(String::from("-   42")));
+        assert_eq!(fmt(CInt(-42), "06"), Ok(String::from("-00042")));
+        assert_eq!(
+            fmt(CString(String::from("ab")), "4"),
+            Ok(String::from("ab  "))
+        );
+        assert_eq!(
+            fmt(CString(String::from("ação")), ">5"),
+            Ok(String::from(" ação"))
+        );
+    }
+
+    #[test]
+    fn format_numeric_bases() {
+        assert_eq!(fmt(CInt(10), "b"), Ok(String::from("1010")));
+        assert_eq!(fmt(CInt(8), "#o"), Ok(String::from("0o10")));
+        assert_eq!(fmt(CInt(255), "x"), Ok(String::from("ff")));
+        assert_eq!(fmt(CInt(-255), "#X"), Ok(String::from("-0XFF")));
+        assert_eq!(fmt(CInt(255), "#010x"), Ok(String::from("0x000000ff")));
+    }
+
+    #[test]
+    fn format_reals() {
+        assert_eq!(fmt(CReal(1.23456), ".2f"), Ok(String::from("1.23")));
+        assert_eq!(fmt(CReal(2.0), ">8.2f"), Ok(String::from("    2.00")));
+        assert_eq!(
+            fmt(CReal(1234567.891), ",.1f"),
+            Ok(String::from("1,234,567.9"))
+        );
+        assert_eq!(fmt(CReal(1234.5), ".2e"), Ok(String::from("1.23e+03")));
+        assert_eq!(fmt(CReal(0.00012), "E"), Ok(String::from("1.200000E-04")));
+        assert_eq!(fmt(CReal(0.256), ".1%"), Ok(String::from("25.6%")));
+        assert_eq!(fmt(CReal(1.5), "+"), Ok(String::from("+1.5")));
+        assert_eq!(fmt(CInt(7), ".1f"), Ok(String::from("7.0")));
+        assert_eq!(
+            fmt(CReal(f64::NEG_INFINITY), ".2f"),
+            Ok(String::from("-inf"))
+        );
+        assert_eq!(fmt(CReal(f64::NAN), "F"), Ok(String::from("NAN")));
+    }
+
+    #[test]
+    fn format_reals_in_general_format() {
+        assert_eq!(fmt(CReal(1.23456), ".3"), Ok(String::from("1.23")));
+        assert_eq!(fmt(CReal(3.0), ".3"), Ok(String::from("3.0")));
+        assert_eq!(fmt(CReal(100.0), ".3"), Ok(String::from("1e+02")));
+        assert_eq!(fmt(CReal(1.23456), ".3g"), Ok(String::from("1.23")));
+        assert_eq!(fmt(CReal(3.0), "g"), Ok(String::from("3")));
+        assert_eq!(fmt(CReal(100.0), ".3g"), Ok(String::from("100")));
+        assert_eq!(fmt(CReal(1234.5), ".3g"), Ok(String::from("1.23e+03")));
+        assert_eq!(fmt(CReal(0.00001234), "g"), Ok(String::from("1.234e-05")));
+        assert_eq!(fmt(CReal(0.0001234), "G"), Ok(String::from("0.0001234")));
+        assert_eq!(fmt(CReal(1.5e20), "G"), Ok(String::from("1.5E+20")));
+        assert_eq!(fmt(CReal(2.0), "#.3g"), Ok(String::from("2.00")));
+        assert_eq!(fmt(CReal(9.99), ".2g"), Ok(String::from("10")));
+        assert_eq!(fmt(CInt(42), "g"), Ok(String::from("42")));
+        assert_eq!(fmt(CReal(-2.5), "08.3"), Ok(String::from("-00002.5")));
+        assert_eq!(fmt(CReal(f64::INFINITY), ".3"), Ok(String::from("inf")));
+    }
+
+    #[test]
+    fn format_strings_padded_with_zeros() {
+        assert_eq!(
+            fmt(CString(String::from("ab")), "05"),
+            Ok(String::from("ab000"))
+        );
+        assert_eq!(
+            fmt(CString(String::from("ab")), ">05"),
+            Ok(String::from("   ab"))
+        );
+    }
+
+    #[test]
+    fn format_strings_and_grouping() {
+        assert_eq!(
+            fmt(CString(String::from("abcdef")), ".3"),
+            Ok(String::from("abc"))
+        );
+        assert_eq!(fmt(CInt(-1234567), ","), Ok(String::from("-1,234,567")));
+        assert_eq!(fmt(CTrue, ""), Ok(String::from("True")));
+        assert_eq!(
+            fmt(CReal(1.5), "d"),
+            Err(String::from(
+                "format type 'd' is not defined for the value 1.5."
+            ))
+        );
+    }
+}
@@ -1,48 +1,791 @@
+use std::cell::RefCell;
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
 
+use crate::interpreter::builtins;
+use crate::interpreter::builtins::files::FileSystem;
+use crate::interpreter::builtins::files::FileSystemRef;
+use crate::interpreter::builtins::random::RandomRef;
+use crate::interpreter::builtins::Console;
+use crate::interpreter::builtins::ConsoleRef;
+use crate::interpreter::format;
+use crate::ir::ast::repr;
+use crate::ir::ast::slice_positions;
+use crate::ir::ast::ArithmeticOp;
+use crate::ir::ast::Class;
+use crate::ir::ast::Clause;
+use crate::ir::ast::Closure;
+use crate::ir::ast::ExceptHandler;
 use crate::ir::ast::Expression;
+use crate::ir::ast::FStringPart;
+use crate::ir::ast::Frame;
+use crate::ir::ast::Function;
+use crate::ir::ast::IteratorRef;
+use crate::ir::ast::IteratorState;
+use crate::ir::ast::MatchCase;
 use crate::ir::ast::Name;
+use crate::ir::ast::ObjectRef;
+use crate::ir::ast::Pattern;
+use crate::ir::ast::RelationalOp;
+use crate::ir::ast::Scope;
+use crate::ir::ast::ScopeRef;
 use crate::ir::ast::Statement;
+use crate::ir::ast::Type;
+use crate::ir::ast::ValueConstructor;
+use crate::ir::ast::WeakScopeRef;
+use crate::ir::builtins::builtin_signature;
+use crate::ir::format::parse_spec;
+use crate::ir::format::Spec;
 
-type ErrorMessage = String;
+/// A raised exception. Runtime errors of the interpreter itself
+/// (unbound variables, division by zero, ...) are exceptions too, so
+/// programs can catch them with 'try'/'except'.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Exception {
+    pub kind: Name,
+    pub message: String,
+    /* set when '?' unwinds to the enclosing function, which returns
+     * this value instead of raising */
+    propagated: Option<Box<Expression>>,
+}
+
+impl fmt::Display for Exception {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.kind, self.message)
+    }
+}
+
+impl Exception {
+    pub fn new(kind: &str, message: &str) -> Exception {
+        Exception {
+            kind: kind.to_string(),
+            message: message.to_string(),
+            propagated: None,
+        }
+    }
+
+    /* outside a function, there is nothing to return to, so the
+     * propagation surfaces as this error */
+    fn propagation(value: Expression) -> Exception {
+        Exception {
+            propagated: Some(Box::new(value)),
+            ..Exception::new("SyntaxError", "'?' used outside a function.")
+        }
+    }
+
+    /* the exception as a value bound by 'except ... as name' */
+    fn value(&self) -> Expression {
+        Expression::Exception(
+            self.kind.clone(),
+            Box::new(Expression::CString(self.message.clone())),
+        )
+    }
+
+    fn from_value(value: Expression) -> Exception {
+        match value {
+            Expression::Exception(kind, message) => Exception::new(&kind, &message.to_string()),
+            _ => Exception::new(
+                "TypeError",
+                "exceptions must be raised from exception values.",
+            ),
+        }
+    }
+
+    /* 'except Exception' catches everything; a few built-in kinds are
+     * grouped under a common base, as in Python */
+    fn is_caught_by(&self, handler: &str) -> bool {
+        let base = match self.kind.as_str() {
+            "ZeroDivisionError" | "OverflowError" => Some("ArithmeticError"),
+            "IndexError" | "KeyError" => Some("LookupError"),
+            "JSONDecodeError" => Some("ValueError"),
+            "FileNotFoundError" | "PermissionError" | "UnsupportedOperation" => Some("OSError"),
+            _ => None,
+        };
+        handler == "Exception" || handler == self.kind || base == Some(handler)
+    }
+}
+
+/// Policy for real divisions whose divisor is zero. Integer division
+/// by zero is always a runtime error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RealDivision {
+    /// follow IEEE 754: `1.0 / 0.0` is `inf` and `0.0 / 0.0` is `nan`.
+    #[default]
+    Ieee,
+    /// raise a runtime error, as integer division does.
+    Strict,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Options {
+    pub real_division: RealDivision,
+    /// how many function calls may be nested before a call raises
+    /// `RecursionError`. Each call takes a lot more of the native stack
+    /// than in CPython, so the default is lower than its 1000.
+    pub recursion_limit: usize,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            real_division: RealDivision::default(),
+            recursion_limit: 100,
+        }
+    }
+}
+
+/// Variables live in the module scope, shared by every function call
+/// (so functions can update module variables even while evaluating an
+/// expression), and in a chain of local scopes for the function calls
+/// and the blocks that declare variables, innermost first.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Environment {
+    pub options: Options,
+    globals: ScopeRef,
+    /* the innermost local scope; None at module level */
+    locals: Option<ScopeRef>,
+    /* the modules available to 'import', by name */
+    modules: Rc<HashMap<Name, Environment>>,
+    /* where 'print' writes and 'input' reads */
+    console: ConsoleRef,
+    /* the generator of the 'random' module */
+    random: RandomRef,
+    /* the files 'open' reads and writes */
+    files: FileSystemRef,
+    /* the number of function calls being evaluated */
+    depth: usize,
+}
+
+impl Environment {
+    pub fn new() -> Environment {
+        Environment::default()
+    }
+
+    pub fn with_options(options: Options) -> Environment {
+        Environment {
+            options,
+            ..Environment::default()
+        }
+    }
+
+    /// Redirects the input and output of the program.
+    pub fn set_console(&mut self, console: Rc<RefCell<dyn Console>>) {
+        self.console = ConsoleRef(console);
+    }
+
+    pub(super) fn console(&self) -> ConsoleRef {
+        self.console.clone()
+    }
+
+    pub(super) fn random(&self) -> RandomRef {
+        self.random.clone()
+    }
+
+    /// Gives the program its files, such as files in memory or only
+    /// those of a directory. A program has none but those it writes
+    /// itself, in memory, unless it is given access to the disk, with
+    /// `DiskFileSystem`.
+    pub fn set_file_system(&mut self, files: Rc<RefCell<dyn FileSystem>>) {
+        self.files = FileSystemRef(files);
+    }
+
+    pub(super) fn file_system(&self) -> FileSystemRef {
+        self.files.clone()
+    }
+
+    pub fn get(&self, name: &str) -> Option<Expression> {
+        self.find(name).ok()
+    }
+
+    pub fn insert(&mut self, name: Name, value: Expression) {
+        self.owner(&name)
+            .0
+            .borrow_mut()
+            .bindings
+            .insert(name, value);
+    }
+
+    fn is_val(&self, name: &str) -> bool {
+        self.owner(name).0.borrow().vals.contains(name)
+    }
+
+    /* the value of a variable, from the innermost scope that owns it: a
+     * local variable that is not bound yet does not refer to a variable
+     * of an enclosing scope, as in Python */
+    fn find(&self, name: &str) -> Result<Expression, Exception> {
+        let mut current = self.locals.clone();
+        while let Some(s) = current {
+            let scope = s.0.borrow();
+            if scope.globals.contains(name) {
+                break;
+            }
+            if let Some(value) = scope.bindings.get(name) {
+                return Ok(value.clone());
+            }
+            if scope.locals.contains(name) {
+                return Err(Exception::new(
+                    "UnboundLocalError",
+                    &format!("local variable '{}' referenced before assignment.", name),
+                ));
+            }
+            current = scope.parent.clone();
+        }
+        match self.globals.0.borrow().bindings.get(name) {
+            Some(value) => Ok(value.clone()),
+            None => Err(Exception::new(
+                "NameError",
+                &format!("Variable {} not found", name),
+            )),
+        }
+    }
+
+    /* the scope in which an assignment binds the name: the innermost
+     * block that declares it or function that does not declare it
+     * 'nonlocal', unless declared 'global'; the module scope otherwise */
+    fn owner(&self, name: &str) -> ScopeRef {
+        let mut current = self.locals.clone();
+        while let Some(s) = current {
+            let scope = s.0.borrow();
+            if scope.globals.contains(name) {
+                break;
+            }
+            if scope.locals.contains(name) || (scope.function && !scope.nonlocals.contains(name)) {
+                drop(scope);
+                return s;
+            }
+            current = scope.parent.clone();
+        }
+        self.globals.clone()
+    }
+
+    /* a block that declares variables gets a scope of its own */
+    fn enter_block(&mut self, names: Vec<Name>) {
+        self.locals = Some(ScopeRef::new(Scope {
+            locals: names.into_iter().collect(),
+            parent: self.locals.take(),
+            ..Scope::default()
+        }));
+    }
 
-type Environment = HashMap<Name, Expression>;
+    /* the classes of a module are known to the modules that import it,
+     * by their qualified names, so that its objects can be used there */
+    fn import_declarations(&mut self, module: &Environment) {
+        let definitions = module.globals.0.borrow().definitions.clone();
+        self.globals.0.borrow_mut().definitions.extend(definitions);
+    }
+
+    fn leave_block(&mut self) {
+        self.locals = self.locals.take().and_then(|s| s.0.borrow().parent.clone());
+    }
+
+    /* the type the module being executed knows by the name */
+    fn get_type(&self, name: &str) -> Option<Type> {
+        self.globals.0.borrow().types.get(name).cloned()
+    }
+
+    fn declare_type(&mut self, name: Name, t: Type) {
+        self.globals.0.borrow_mut().types.insert(name, t);
+    }
+
+    /* a class by its qualified name */
+    fn get_class(&self, name: &str) -> Option<Rc<Class>> {
+        let globals = self.globals.0.borrow();
+        globals
+            .definitions
+            .get(name)
+            .map(|(class, _)| class.clone())
+    }
+
+    /* the module scope the methods of a class see */
+    fn class_scope(&self, name: &str) -> Option<WeakScopeRef> {
+        let globals = self.globals.0.borrow();
+        globals
+            .definitions
+            .get(name)
+            .map(|(_, scope)| scope.clone())
+    }
+
+    fn define_class(&mut self, name: Name, class: Class) {
+        let mut globals = self.globals.0.borrow_mut();
+        globals.classes.insert(name, class.name.clone());
+        let scope = self.globals.downgrade();
+        globals
+            .definitions
+            .insert(class.name.clone(), (Rc::new(class), scope));
+    }
+
+    /* the qualified name of the class the module being executed knows
+     * by the name */
+    fn resolve_class(&self, name: &str) -> Option<Name> {
+        self.globals.0.borrow().classes.get(name).cloned()
+    }
+
+    /* the name a class defined by the module being executed is known by
+     * elsewhere */
+    fn qualify(&self, name: &str) -> Name {
+        match self.globals.0.borrow().module.as_str() {
+            "" => name.to_string(),
+            module => format!("{}.{}", module, name),
+        }
+    }
+
+    /* an environment for the program of the named module, with the
+     * options, the console, the random generator and the files of this
+     * one */
+    pub(super) fn for_module(&self, name: &str) -> Environment {
+        let env = Environment {
+            options: self.options,
+            console: self.console.clone(),
+            random: self.random.clone(),
+            files: self.files.clone(),
+            ..Environment::default()
+        };
+        env.globals.0.borrow_mut().module = name.to_string();
+        env
+    }
+
+    /* a copy with a module scope of its own, to which the functions and
+     * classes of the module are rebound; objects and iterators are
+     * shared, as they are references */
+    fn detach(&self) -> Environment {
+        let globals = ScopeRef::new(self.globals.0.borrow().clone());
+        let (from, to) = (self.globals.downgrade(), globals.downgrade());
+        let mut scope = globals.0.borrow_mut();
+        for value in scope.bindings.values_mut() {
+            rebind(value, &self.globals, &globals);
+        }
+        for (_, defined_in) in scope.definitions.values_mut() {
+            if *defined_in == from {
+                *defined_in = to.clone();
+            }
+        }
+        drop(scope);
+        Environment {
+            globals,
+            ..self.clone()
+        }
+    }
+
+    /* makes a module, already executed, available to 'import' */
+    pub fn add_module(&mut self, name: Name, module: Environment) {
+        Rc::make_mut(&mut self.modules).insert(name, module);
+    }
+
+    fn closure(&self) -> Closure {
+        Closure {
+            globals: self.globals.downgrade(),
+            locals: self.locals.clone(),
+        }
+    }
+
+    fn get_constructor(&self, name: &str) -> Option<ValueConstructor> {
+        let globals = self.globals.0.borrow();
+        globals.types.values().find_map(|t| match t {
+            Type::TAlgebraicData(_, constructors) => {
+                constructors.iter().find(|c| c.name == name).cloned()
+            }
+            _ => None,
+        })
+    }
+
+    /* the algebraic data type a value constructor belongs to */
+    pub(super) fn type_of_constructor(&self, name: &str) -> Option<Name> {
+        let globals = self.globals.0.borrow();
+        globals.types.iter().find_map(|(type_name, t)| match t {
+            Type::TAlgebraicData(_, constructors)
+                if constructors.iter().any(|c| c.name == name) =>
+            {
+                Some(type_name.clone())
+            }
+            _ => None,
+        })
+    }
+}
+
+/* makes the functions a value holds that close over one module scope
+ * close over another */
+fn rebind(value: &mut Expression, from: &ScopeRef, to: &ScopeRef) {
+    match value {
+        Expression::FunctionValue(_, closure) if closure.globals == from.downgrade() => {
+            closure.globals = to.downgrade()
+        }
+        Expression::ListValue(values)
+        | Expression::Tuple(values)
+        | Expression::SetValue(values)
+        | Expression::ADTConstructor(_, values) => {
+            values.iter_mut().for_each(|v| rebind(v, from, to))
+        }
+        Expression::DictValue(entries) => entries.iter_mut().for_each(|(_, v)| rebind(v, from, to)),
+        Expression::Record(_, fields) => fields.iter_mut().for_each(|(_, v)| rebind(v, from, to)),
+        Expression::CJust(v) | Expression::COk(v) | Expression::CErr(v) => rebind(v, from, to),
+        _ => (),
+    }
+}
+
+impl<const N: usize> From<[(Name, Expression); N]> for Environment {
+    fn from(bindings: [(Name, Expression); N]) -> Environment {
+        Environment {
+            globals: ScopeRef::new(Scope {
+                bindings: HashMap::from(bindings),
+                ..Scope::default()
+            }),
+            ..Environment::default()
+        }
+    }
+}
 
-pub fn eval(exp: Expression, env: &Environment) -> Result<Expression, ErrorMessage> {
+pub fn eval(exp: Expression, env: &Environment) -> Result<Expression, Exception> {
     match exp {
         Expression::Add(lhs, rhs) => add(*lhs, *rhs, env),
         Expression::Sub(lhs, rhs) => sub(*lhs, *rhs, env),
         Expression::Mul(lhs, rhs) => mul(*lhs, *rhs, env),
         Expression::Div(lhs, rhs) => div(*lhs, *rhs, env),
+        Expression::Mod(lhs, rhs) => modulo(*lhs, *rhs, env),
+        Expression::Conditional(cond, then, otherwise) => match eval(*cond, env)? {
+            Expression::CTrue => eval(*then, env),
+            Expression::CFalse => eval(*otherwise, env),
+            _ => Err(Exception::new("TypeError", "expecting a boolean value.")),
+        },
         Expression::And(lhs, rhs) => and(*lhs, *rhs, env),
         Expression::Or(lhs, rhs) => or(*lhs, *rhs, env),
         Expression::Not(lhs) => not(*lhs, env),
         Expression::EQ(lhs, rhs) => eq(*lhs, *rhs, env),
+        Expression::NEQ(lhs, rhs) => neq(*lhs, *rhs, env),
         Expression::GT(lhs, rhs) => gt(*lhs, *rhs, env),
         Expression::LT(lhs, rhs) => lt(*lhs, *rhs, env),
         Expression::GTE(lhs, rhs) => gte(*lhs, *rhs, env),
         Expression::LTE(lhs, rhs) => lte(*lhs, *rhs, env),
+        Expression::Comparison(first, links) => comparison(*first, links, env),
         Expression::Var(name) => lookup(name, env),
+        Expression::Index(exp, idx) => index(*exp, *idx, env),
+        Expression::Slice(exp, start, stop, step) => slice(*exp, start, stop, step, env),
+        Expression::Len(exp) => len(*exp, env),
+        Expression::In(element, container) => contains(*element, *container, env),
+        Expression::MethodCall(receiver, method, args) => method_call(*receiver, method, args, env),
+        Expression::FString(parts) => fstring(parts, env),
+        Expression::ListValue(elements) => Ok(Expression::ListValue(eval_all(elements, env)?)),
+        Expression::Tuple(elements) => Ok(Expression::Tuple(eval_all(elements, env)?)),
+        Expression::SetValue(elements) => Ok(Expression::SetValue(distinct(
+            eval_all(elements, env)?,
+            env,
+        )?)),
+        Expression::DictValue(entries) => {
+            let entries = entries
+                .into_iter()
+                .map(|(key, value)| Ok((eval(key, env)?, eval(value, env)?)))
+                .collect::<Result<Vec<(Expression, Expression)>, Exception>>()?;
+            Ok(Expression::DictValue(dict_entries(entries, env)?))
+        }
+        Expression::ListComprehension(element, clauses) => {
+            let mut values = vec![];
+            comprehend(
+                &element,
+                &clauses,
+                &mut comprehension_scope(&clauses, env),
+                &mut values,
+            )?;
+            Ok(Expression::ListValue(values))
+        }
+        Expression::SetComprehension(element, clauses) => {
+            let mut values = vec![];
+            comprehend(
+                &element,
+                &clauses,
+                &mut comprehension_scope(&clauses, env),
+                &mut values,
+            )?;
+            Ok(Expression::SetValue(distinct(values, env)?))
+        }
+        Expression::DictComprehension(key, value, clauses) => {
+            let entry = Expression::Tuple(vec![*key, *value]);
+            let mut values = vec![];
+            comprehend(
+                &entry,
+                &clauses,
+                &mut comprehension_scope(&clauses, env),
+                &mut values,
+            )?;
+            let entries = values
+                .into_iter()
+                .map(|entry| match entry {
+                    Expression::Tuple(pair) => match <[Expression; 2]>::try_from(pair) {
+                        Ok([key, value]) => Ok((key, value)),
+                        Err(_) => Err(malformed_entry()),
+                    },
+                    _ => Err(malformed_entry()),
+                })
+                .collect::<Result<Vec<(Expression, Expression)>, Exception>>()?;
+            Ok(Expression::DictValue(dict_entries(entries, env)?))
+        }
+        Expression::Exception(kind, message) => {
+            Ok(Expression::Exception(kind, Box::new(eval(*message, env)?)))
+        }
+        Expression::Record(name, fields) => record(name, fields, env),
+        Expression::FieldAccess(exp, field) => field_access(*exp, field, env),
+        Expression::ADTConstructor(name, args) => adt_constructor(name, args, env),
+        Expression::CJust(exp) => Ok(Expression::CJust(Box::new(eval(*exp, env)?))),
+        Expression::COk(exp) => Ok(Expression::COk(Box::new(eval(*exp, env)?))),
+        Expression::CErr(exp) => Ok(Expression::CErr(Box::new(eval(*exp, env)?))),
+        Expression::Unwrap(exp) => unwrap(*exp, env),
+        Expression::UnwrapOr(exp, default) => unwrap_or(*exp, *default, env),
+        Expression::IsError(exp) => is_error(*exp, env),
+        Expression::IsNothing(exp) => is_nothing(*exp, env),
+        Expression::Propagate(exp) => propagate(*exp, env),
+        Expression::FuncCall(name, args) => func_call(name, args, env),
+        Expression::IsInstance(exp, class) => is_instance(*exp, class, env),
+        Expression::Super => Err(Exception::new(
+            "TypeError",
+            "'super()' can only be used to call a method.",
+        )),
+        Expression::KeywordArgument(..) => Err(Exception::new(
+            "SyntaxError",
+            "keyword arguments are only allowed in calls.",
+        )),
         _ if is_constant(exp.clone()) => Ok(exp),
-        _ => Err(String::from("Not implemented yet.")),
+        _ => Err(Exception::new(
+            "NotImplementedError",
+            "Not implemented yet.",
+        )),
     }
 }
 
 fn is_constant(exp: Expression) -> bool {
-    match exp {
-        Expression::CTrue => true,
-        Expression::CFalse => true,
-        Expression::CInt(_) => true,
-        Expression::CReal(_) => true,
-        Expression::CString(_) => true,
-        _ => false,
+    matches!(
+        exp,
+        Expression::CTrue
+            | Expression::CFalse
+            | Expression::CInt(_)
+            | Expression::CReal(_)
+            | Expression::CString(_)
+            | Expression::CNone
+            | Expression::CNothing
+            | Expression::FunctionValue(..)
+            | Expression::BuiltinFunction(_)
+            | Expression::Object(_)
+            | Expression::Iterator(_)
+            | Expression::File(_)
+            | Expression::Module(..)
+            | Expression::ClassValue(_)
+    )
+}
+
+fn eval_all(exps: Vec<Expression>, env: &Environment) -> Result<Vec<Expression>, Exception> {
+    exps.into_iter().map(|e| eval(e, env)).collect()
+}
+
+/* arguments of a call; keyword arguments keep their names */
+fn eval_args(args: Vec<Expression>, env: &Environment) -> Result<Vec<Expression>, Exception> {
+    args.into_iter()
+        .map(|arg| match arg {
+            Expression::KeywordArgument(name, value) => Ok(Expression::KeywordArgument(
+                name,
+                Box::new(eval(*value, env)?),
+            )),
+            _ => eval(arg, env),
+        })
+        .collect()
+}
+
+/* elements of a set: the first of equal values is kept */
+fn distinct(values: Vec<Expression>, env: &Environment) -> Result<Vec<Expression>, Exception> {
+    let mut buckets: HashMap<String, Vec<usize>> = HashMap::new();
+    let mut elements = vec![];
+    for value in values {
+        let bucket = buckets.entry(hash_key(&value, env)?).or_default();
+        if position_in(bucket, &value, |i| &elements[i], env)?.is_none() {
+            bucket.push(elements.len());
+            elements.push(value);
+        }
     }
+    Ok(elements)
 }
 
-fn lookup(name: String, env: &Environment) -> Result<Expression, ErrorMessage> {
-    match env.get(&name) {
-        Some(value) => Ok(value.clone()),
-        None => Err(format!("Variable {} not found", name)),
+/* entries of a dictionary: a repeated key keeps its first position but
+ * takes the last value */
+fn dict_entries(
+    entries: Vec<(Expression, Expression)>,
+    env: &Environment,
+) -> Result<Vec<(Expression, Expression)>, Exception> {
+    let mut buckets: HashMap<String, Vec<usize>> = HashMap::new();
+    let mut dict: Vec<(Expression, Expression)> = vec![];
+    for (key, value) in entries {
+        let bucket = buckets.entry(hash_key(&key, env)?).or_default();
+        match position_in(bucket, &key, |i| &dict[i].0, env)? {
+            Some(position) => dict[position].1 = value,
+            None => {
+                bucket.push(dict.len());
+                dict.push((key, value));
+            }
+        }
+    }
+    Ok(dict)
+}
+
+/* the position of the key among the keys of a set or a dictionary */
+fn find_key<'a>(
+    key: &Expression,
+    keys: impl Iterator<Item = &'a Expression>,
+    env: &Environment,
+) -> Result<Option<usize>, Exception> {
+    let hash = hash_key(key, env)?;
+    for (position, k) in keys.enumerate() {
+        if hash_key(k, env)? == hash && same_key(k, key, env)? {
+            return Ok(Some(position));
+        }
+    }
+    Ok(None)
+}
+
+/* the position of the key among the keys of the same hash */
+fn position_in<'a>(
+    bucket: &[usize],
+    key: &Expression,
+    keys: impl Fn(usize) -> &'a Expression,
+    env: &Environment,
+) -> Result<Option<usize>, Exception> {
+    for &position in bucket {
+        if same_key(keys(position), key, env)? {
+            return Ok(Some(position));
+        }
+    }
+    Ok(None)
+}
+
+/* keys of the same hash are equal, unless they are objects, whose hash
+ * comes from '__hash__', and which are compared by '__eq__' */
+fn same_key(k1: &Expression, k2: &Expression, env: &Environment) -> Result<bool, Exception> {
+    match (k1, k2) {
+        (Expression::Object(_), _) | (_, Expression::Object(_)) => {
+            let equal = overloaded_relational(&RelationalOp::EQ, k1, k2, env)?;
+            Ok(equal.unwrap_or(k1 == k2))
+        }
+        (Expression::Tuple(t1), Expression::Tuple(t2)) => {
+            for (e1, e2) in t1.iter().zip(t2) {
+                if !same_key(e1, e2, env)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        }
+        _ => Ok(true),
+    }
+}
+
+/* the key a value is hashed by in a set or a dictionary: values that
+ * are equal have the same key, so 1 and 1.0 collide as in Python, while
+ * lists, sets and dictionaries are unhashable. Objects, iterators and
+ * files are hashed by identity, as they are compared, unless the class
+ * of an object defines '__hash__'; one that defines only '__eq__' is
+ * unhashable. */
+fn hash_key(value: &Expression, env: &Environment) -> Result<String, Exception> {
+    let all = |values: &[Expression]| -> Result<String, Exception> {
+        let keys = values.iter().map(|v| hash_key(v, env));
+        Ok(keys.collect::<Result<Vec<String>, Exception>>()?.join(","))
+    };
+    Ok(match value {
+        Expression::ListValue(_) | Expression::SetValue(_) | Expression::DictValue(_) => {
+            let kind = match value {
+                Expression::ListValue(_) => "list",
+                Expression::SetValue(_) => "set",
+                _ => "dict",
+            };
+            return Err(Exception::new(
+                "TypeError",
+                &format!("unhashable type: '{kind}'."),
+            ));
+        }
+        Expression::CInt(_) | Expression::CReal(_) => {
+            /* adding 0.0 turns -0.0 into 0.0, which it equals */
+            format!("n{:?}", as_real(value).unwrap_or_default() + 0.0)
+        }
+        Expression::CString(s) => format!("s{}:{s}", s.len()),
+        Expression::Tuple(elements) => format!("({})", all(elements)?),
+        Expression::CJust(v) => format!("Just({})", hash_key(v, env)?),
+        Expression::COk(v) => format!("Ok({})", hash_key(v, env)?),
+        Expression::CErr(v) => format!("Err({})", hash_key(v, env)?),
+        Expression::ADTConstructor(name, args) => format!("{name}({})", all(args)?),
+        Expression::Record(name, fields) => {
+            let fields = fields
+                .iter()
+                .map(|(field, v)| Ok(format!("{field}={}", hash_key(v, env)?)))
+                .collect::<Result<Vec<String>, Exception>>()?;
+            format!("{name}{{{}}}", fields.join(","))
+        }
+        Expression::Object(object) => match special_method(value, "__hash__", vec![], env)? {
+            Some(Expression::CInt(hash)) => format!("h{hash}"),
+            Some(_) => {
+                return Err(Exception::new(
+                    "TypeError",
+                    "'__hash__' must return an integer.",
+                ))
+            }
+            None if find_method(&object.class(), "__eq__", env).is_some() => {
+                return Err(Exception::new(
+                    "TypeError",
+                    &format!("unhashable type: '{}'.", object.class()),
+                ))
+            }
+            None => format!("o{:p}", Rc::as_ptr(&object.0)),
+        },
+        Expression::Iterator(iterator) => format!("i{:p}", Rc::as_ptr(&iterator.0)),
+        Expression::File(file) => format!("f{:p}", Rc::as_ptr(&file.0)),
+        _ => format!("{value:?}"),
+    })
+}
+
+fn malformed_entry() -> Exception {
+    Exception::new(
+        "TypeError",
+        "a dictionary comprehension must produce key-value pairs.",
+    )
+}
+
+/* a comprehension runs in a scope of its own, which owns the variables
+ * of its 'for' clauses */
+fn comprehension_scope(clauses: &[Clause], env: &Environment) -> Environment {
+    let names = clauses.iter().filter_map(|clause| match clause {
+        Clause::For(name, _) => Some(name.clone()),
+        Clause::If(_) => None,
+    });
+    let mut local = env.clone();
+    local.enter_block(names.collect());
+    local
+}
+
+/* the values of 'element' for each binding produced by the clauses */
+fn comprehend(
+    element: &Expression,
+    clauses: &[Clause],
+    env: &mut Environment,
+    values: &mut Vec<Expression>,
+) -> Result<(), Exception> {
+    match clauses.split_first() {
+        None => values.push(eval(element.clone(), env)?),
+        Some((Clause::For(name, iterable), rest)) => {
+            let iterator = iter(eval(iterable.clone(), env)?, env)?;
+            while let Some(value) = next(&iterator, env)? {
+                env.insert(name.clone(), value);
+                comprehend(element, rest, env, values)?;
+            }
+        }
+        Some((Clause::If(cond), rest)) => match eval(cond.clone(), env)? {
+            Expression::CTrue => comprehend(element, rest, env, values)?,
+            Expression::CFalse => (),
+            _ => return Err(Exception::new("TypeError", "expecting a boolean value.")),
+        },
+    }
+    Ok(())
+}
+
+/* a built-in function is a value too, unless the program binds its name */
+fn lookup(name: String, env: &Environment) -> Result<Expression, Exception> {
+    match env.find(&name) {
+        Err(exception) if exception.kind == "NameError" && builtin_signature(&name).is_some() => {
+            Ok(Expression::BuiltinFunction(name))
+        }
+        result => result,
     }
 }
 
@@ -53,12 +796,24 @@ fn eval_binary_arith_op<F>(
     env: &Environment,
     op: F,
     error_msg: &str,
-) -> Result<Expression, ErrorMessage>
+) -> Result<Expression, Exception>
 where
     F: Fn(f64, f64) -> f64,
 {
     let v1 = eval(lhs, env)?;
     let v2 = eval(rhs, env)?;
+    numeric_op(v1, v2, op, error_msg)
+}
+
+fn numeric_op<F>(
+    v1: Expression,
+    v2: Expression,
+    op: F,
+    error_msg: &str,
+) -> Result<Expression, Exception>
+where
+    F: Fn(f64, f64) -> f64,
+{
     match (v1, v2) {
         (Expression::CInt(v1), Expression::CInt(v2)) => {
             Ok(Expression::CInt(op(v1 as f64, v2 as f64) as i32))
@@ -66,509 +821,6349 @@ where
         (Expression::CInt(v1), Expression::CReal(v2)) => Ok(Expression::CReal(op(v1 as f64, v2))),
         (Expression::CReal(v1), Expression::CInt(v2)) => Ok(Expression::CReal(op(v1, v2 as f64))),
         (Expression::CReal(v1), Expression::CReal(v2)) => Ok(Expression::CReal(op(v1, v2))),
-        _ => Err(error_msg.to_string()),
+        _ => Err(Exception::new("TypeError", error_msg)),
     }
 }
 
-fn add(lhs: Expression, rhs: Expression, env: &Environment) -> Result<Expression, ErrorMessage> {
-    eval_binary_arith_op(
-        lhs,
-        rhs,
-        env,
-        |a, b| a + b,
-        "addition '(+)' is only defined for numbers (integers and real).",
-    )
+fn add(lhs: Expression, rhs: Expression, env: &Environment) -> Result<Expression, Exception> {
+    let v1 = eval(lhs, env)?;
+    let v2 = eval(rhs, env)?;
+    add_values(v1, v2, env)
 }
 
-fn sub(lhs: Expression, rhs: Expression, env: &Environment) -> Result<Expression, ErrorMessage> {
-    eval_binary_arith_op(
-        lhs,
-        rhs,
-        env,
-        |a, b| a - b,
-        "subtraction '(-)' is only defined for numbers (integers and real).",
-    )
+/* '+' on the values of its operands */
+pub(super) fn add_values(
+    v1: Expression,
+    v2: Expression,
+    env: &Environment,
+) -> Result<Expression, Exception> {
+    if let Some(result) = special_method(&v1, "__add__", vec![v2.clone()], env)? {
+        return Ok(result);
+    }
+    match (v1, v2) {
+        (Expression::CString(s1), Expression::CString(s2)) => Ok(Expression::CString(s1 + &s2)),
+        (v1, v2) => numeric_op(
+            v1,
+            v2,
+            |a, b| a + b,
+            "addition '(+)' is only defined for numbers (integers and real) and strings.",
+        ),
+    }
 }
 
-fn mul(lhs: Expression, rhs: Expression, env: &Environment) -> Result<Expression, ErrorMessage> {
+fn sub(lhs: Expression, rhs: Expression, env: &Environment) -> Result<Expression, Exception> {
     eval_binary_arith_op(
         lhs,
         rhs,
         env,
-        |a, b| a * b,
-        "multiplication '(*)' is only defined for numbers (integers and real).",
+        |a, b| a - b,
+        "subtraction '(-)' is only defined for numbers (integers and real).",
     )
 }
 
-fn div(lhs: Expression, rhs: Expression, env: &Environment) -> Result<Expression, ErrorMessage> {
-    eval_binary_arith_op(
-        lhs,
-        rhs,
-        env,
-        |a, b| a / b,
-        "division '(/)' is only defined for numbers (integers and real).",
-    )
+fn mul(lhs: Expression, rhs: Expression, env: &Environment) -> Result<Expression, Exception> {
+    let v1 = eval(lhs, env)?;
+    let v2 = eval(rhs, env)?;
+    match (v1, v2) {
+        (Expression::CString(s), Expression::CInt(n))
+        | (Expression::CInt(n), Expression::CString(s)) => {
+            Ok(Expression::CString(s.repeat(n.max(0) as usize)))
+        }
+        (v1, v2) => numeric_op(
+            v1,
+            v2,
+            |a, b| a * b,
+            "multiplication '(*)' is only defined for numbers (integers and real), or a string and an integer.",
+        ),
+    }
 }
 
-/* Boolean Expressions */
-fn eval_binary_boolean_op<F>(
-    lhs: Expression,
-    rhs: Expression,
-    env: &Environment,
-    op: F,
-    error_msg: &str,
-) -> Result<Expression, ErrorMessage>
-where
-    F: Fn(bool, bool) -> Expression,
-{
+fn div(lhs: Expression, rhs: Expression, env: &Environment) -> Result<Expression, Exception> {
     let v1 = eval(lhs, env)?;
     let v2 = eval(rhs, env)?;
     match (v1, v2) {
-        (Expression::CTrue, Expression::CTrue) => Ok(op(true, true)),
-        (Expression::CTrue, Expression::CFalse) => Ok(op(true, false)),
-        (Expression::CFalse, Expression::CTrue) => Ok(op(false, true)),
-        (Expression::CFalse, Expression::CFalse) => Ok(op(false, false)),
-        _ => Err(error_msg.to_string()),
+        (Expression::CInt(_), Expression::CInt(0)) => {
+            Err(Exception::new("ZeroDivisionError", "division by zero."))
+        }
+        (Expression::CInt(v1), Expression::CInt(v2)) => v1
+            .checked_div(v2)
+            .map(Expression::CInt)
+            .ok_or(Exception::new(
+                "OverflowError",
+                "integer overflow in division '(/)'.",
+            )),
+        (Expression::CInt(v1), Expression::CReal(v2)) => real_div(v1 as f64, v2, env),
+        (Expression::CReal(v1), Expression::CInt(v2)) => real_div(v1, v2 as f64, env),
+        (Expression::CReal(v1), Expression::CReal(v2)) => real_div(v1, v2, env),
+        _ => Err(Exception::new(
+            "TypeError",
+            "division '(/)' is only defined for numbers (integers and real).",
+        )),
     }
 }
 
-fn and(lhs: Expression, rhs: Expression, env: &Environment) -> Result<Expression, ErrorMessage> {
-    eval_binary_boolean_op(
-        lhs,
-        rhs,
-        env,
+fn modulo(lhs: Expression, rhs: Expression, env: &Environment) -> Result<Expression, Exception> {
+    let v1 = eval(lhs, env)?;
+    let v2 = eval(rhs, env)?;
+    let by_zero = match (&v1, &v2) {
+        (Expression::CInt(_), Expression::CInt(0)) => true,
+        (Expression::CInt(_) | Expression::CReal(_), Expression::CReal(r)) => {
+            *r == 0.0 && env.options.real_division == RealDivision::Strict
+        }
+        (Expression::CReal(_), Expression::CInt(0)) => {
+            env.options.real_division == RealDivision::Strict
+        }
+        _ => false,
+    };
+    if by_zero {
+        return Err(Exception::new("ZeroDivisionError", "modulo by zero."));
+    }
+    numeric_op(
+        v1,
+        v2,
         |a, b| {
-            if a && b {
-                Expression::CTrue
+            let r = a % b;
+            if r != 0.0 && (r < 0.0) != (b < 0.0) {
+                r + b
             } else {
-                Expression::CFalse
+                r
             }
         },
-        "'and' is only defined for booleans.",
+        "modulo '(%)' is only defined for numbers (integers and real).",
     )
 }
 
-fn or(lhs: Expression, rhs: Expression, env: &Environment) -> Result<Expression, ErrorMessage> {
-    eval_binary_boolean_op(
-        lhs,
-        rhs,
-        env,
-        |a, b| {
-            if a || b {
-                Expression::CTrue
-            } else {
-                Expression::CFalse
-            }
-        },
-        "'or' is only defined for booleans.",
-    )
+fn real_div(v1: f64, v2: f64, env: &Environment) -> Result<Expression, Exception> {
+    if v2 == 0.0 && env.options.real_division == RealDivision::Strict {
+        return Err(Exception::new("ZeroDivisionError", "division by zero."));
+    }
+    Ok(Expression::CReal(v1 / v2))
 }
 
-fn not(lhs: Expression, env: &Environment) -> Result<Expression, ErrorMessage> {
-    let v = eval(lhs, env)?;
+/* Sequence Operations
+ *
+ * strings are indexed by characters (Unicode scalar values), not by
+ * bytes, and negative indices count from the end as in Python. */
+fn index(exp: Expression, idx: Expression, env: &Environment) -> Result<Expression, Exception> {
+    let v = eval(exp, env)?;
+    let idx = eval(idx, env)?;
+    if let Some(result) = special_method(&v, "__getitem__", vec![idx.clone()], env)? {
+        return Ok(result);
+    }
+    if let Expression::DictValue(entries) = v {
+        return match find_key(&idx, entries.iter().map(|(key, _)| key), env)? {
+            Some(position) => Ok(entries[position].1.clone()),
+            None => Err(Exception::new("KeyError", &repr(&idx))),
+        };
+    }
+    let i = match idx {
+        Expression::CInt(i) => i,
+        _ => return Err(Exception::new("TypeError", "indices must be integers.")),
+    };
+    if let Some((start, count, step)) = range_of(&v) {
+        let pos = normalize_index(i, count as usize).ok_or(Exception::new(
+            "IndexError",
+            "range object index out of range.",
+        ))?;
+        return Ok(Expression::CInt((start + pos as i64 * step) as i32));
+    }
     match v {
-        Expression::CTrue => Ok(Expression::CFalse),
-        Expression::CFalse => Ok(Expression::CTrue),
-        _ => Err(String::from("'not' is only defined for booleans.")),
+        Expression::CString(s) => {
+            let chars: Vec<char> = s.chars().collect();
+            let pos = normalize_index(i, chars.len())
+                .ok_or(Exception::new("IndexError", "string index out of range."))?;
+            Ok(Expression::CString(chars[pos].to_string()))
+        }
+        Expression::ListValue(elements) | Expression::Tuple(elements) => {
+            let pos = normalize_index(i, elements.len())
+                .ok_or(Exception::new("IndexError", "index out of range."))?;
+            Ok(elements[pos].clone())
+        }
+        _ => Err(Exception::new(
+            "TypeError",
+            "indexing is only defined for strings, lists, tuples, dictionaries and ranges.",
+        )),
     }
 }
 
-/* Relational Operations */
-fn eval_binary_rel_op<F>(
-    lhs: Expression,
-    rhs: Expression,
+fn normalize_index(i: i32, len: usize) -> Option<usize> {
+    let i = if i < 0 {
+        i as i64 + len as i64
+    } else {
+        i as i64
+    };
+    if (0..len as i64).contains(&i) {
+        Some(i as usize)
+    } else {
+        None
+    }
+}
+
+fn slice(
+    exp: Expression,
+    start: Option<Box<Expression>>,
+    stop: Option<Box<Expression>>,
+    step: Option<Box<Expression>>,
     env: &Environment,
-    op: F,
-    error_msg: &str,
-) -> Result<Expression, ErrorMessage>
-where
-    F: Fn(f64, f64) -> Expression,
-{
-    let v1 = eval(lhs, env)?;
-    let v2 = eval(rhs, env)?;
-    match (v1, v2) {
-        (Expression::CInt(v1), Expression::CInt(v2)) => Ok(op(v1 as f64, v2 as f64)),
-        (Expression::CInt(v1), Expression::CReal(v2)) => Ok(op(v1 as f64, v2)),
-        (Expression::CReal(v1), Expression::CInt(v2)) => Ok(op(v1, v2 as f64)),
-        (Expression::CReal(v1), Expression::CReal(v2)) => Ok(op(v1, v2)),
-        _ => Err(error_msg.to_string()),
+) -> Result<Expression, Exception> {
+    let v = eval(exp, env)?;
+    let bound = |b: Option<Box<Expression>>| match b {
+        None => Ok(None),
+        Some(e) => match eval(*e, env)? {
+            Expression::CInt(i) => Ok(Some(i as i64)),
+            _ => Err(Exception::new(
+                "TypeError",
+                "slice indices must be integers.",
+            )),
+        },
+    };
+    let (start, stop, step) = (bound(start)?, bound(stop)?, bound(step)?.unwrap_or(1));
+    if step == 0 {
+        return Err(Exception::new("ValueError", "slice step cannot be zero."));
+    }
+    match v {
+        Expression::CString(s) => {
+            let chars: Vec<char> = s.chars().collect();
+            let positions = slice_positions(chars.len(), start, stop, step);
+            Ok(Expression::CString(
+                positions.iter().map(|&p| chars[p]).collect(),
+            ))
+        }
+        Expression::ListValue(elements) => {
+            let positions = slice_positions(elements.len(), start, stop, step);
+            Ok(Expression::ListValue(
+                positions.iter().map(|&p| elements[p].clone()).collect(),
+            ))
+        }
+        Expression::Tuple(elements) => {
+            let positions = slice_positions(elements.len(), start, stop, step);
+            Ok(Expression::Tuple(
+                positions.iter().map(|&p| elements[p].clone()).collect(),
+            ))
+        }
+        _ => Err(Exception::new(
+            "TypeError",
+            "slicing is only defined for strings, lists and tuples.",
+        )),
     }
 }
 
-fn eq(lhs: Expression, rhs: Expression, env: &Environment) -> Result<Expression, ErrorMessage> {
-    eval_binary_rel_op(
-        lhs,
-        rhs,
-        env,
-        |a, b| {
-            if a == b {
-                Expression::CTrue
-            } else {
-                Expression::CFalse
-            }
+pub(super) fn len(exp: Expression, env: &Environment) -> Result<Expression, Exception> {
+    let v = eval(exp, env)?;
+    match special_method(&v, "__len__", vec![], env)? {
+        Some(Expression::CInt(n)) => return Ok(Expression::CInt(n)),
+        Some(_) => {
+            return Err(Exception::new(
+                "TypeError",
+                "'__len__' must return an integer.",
+            ))
+        }
+        None => (),
+    }
+    if let Some((_, count, _)) = range_of(&v) {
+        return i32::try_from(count)
+            .map(Expression::CInt)
+            .map_err(|_| Exception::new("OverflowError", "range has too many elements."));
+    }
+    match v {
+        Expression::CString(s) => Ok(Expression::CInt(s.chars().count() as i32)),
+        Expression::ListValue(elements)
+        | Expression::Tuple(elements)
+        | Expression::SetValue(elements) => Ok(Expression::CInt(elements.len() as i32)),
+        Expression::DictValue(entries) => Ok(Expression::CInt(entries.len() as i32)),
+        _ => Err(Exception::new(
+            "TypeError",
+            "'len' is only defined for strings, lists, tuples, sets, dictionaries, ranges and objects with '__len__'.",
+        )),
+    }
+}
+
+fn contains(
+    element: Expression,
+    container: Expression,
+    env: &Environment,
+) -> Result<Expression, Exception> {
+    let element = eval(element, env)?;
+    match (element, eval(container, env)?) {
+        (Expression::CString(sub), Expression::CString(s)) => Ok(bool_value(s.contains(&sub))),
+        (_, Expression::CString(_)) => Err(Exception::new(
+            "TypeError",
+            "'in <string>' requires a string as left operand.",
+        )),
+        (element, Expression::ListValue(elements) | Expression::Tuple(elements)) => Ok(bool_value(
+            elements.iter().any(|e| values_equal(&element, e)),
+        )),
+        (element, Expression::SetValue(elements)) => Ok(bool_value(
+            find_key(&element, elements.iter(), env)?.is_some(),
+        )),
+        (element, Expression::DictValue(entries)) => Ok(bool_value(
+            find_key(&element, entries.iter().map(|(key, _)| key), env)?.is_some(),
+        )),
+        (element, container) => match range_of(&container) {
+            /* a number is in a range if it is one of its integers */
+            Some((start, count, step)) => Ok(bool_value(match as_real(&element) {
+                Some(v) if v.fract() == 0.0 => {
+                    let offset = v as i64 - start;
+                    offset % step == 0 && (0..count).contains(&(offset / step))
+                }
+                _ => false,
+            })),
+            None => Err(Exception::new(
+                "TypeError",
+                "'in' is only defined for strings, lists, tuples, sets, dictionaries and ranges.",
+            )),
         },
-        "(==) is only defined for numbers (integers and real).",
-    )
+    }
 }
 
-fn gt(lhs: Expression, rhs: Expression, env: &Environment) -> Result<Expression, ErrorMessage> {
-    eval_binary_rel_op(
-        lhs,
-        rhs,
-        env,
-        |a, b| {
-            if a > b {
-                Expression::CTrue
-            } else {
-                Expression::CFalse
-            }
+/* the first integer a range has yet to give, how many it has, and the
+ * step between them; None for other values */
+pub(super) fn range_of(value: &Expression) -> Option<(i64, i64, i64)> {
+    let Expression::Iterator(iterator) = value else {
+        return None;
+    };
+    match &*iterator.0.borrow() {
+        IteratorState::Range { next, stop, step } => {
+            let count = match *step > 0 {
+                true => (stop - next + step - 1) / step,
+                false => (next - stop - step - 1) / -step,
+            };
+            Some((*next, count.max(0), *step))
+        }
+        _ => None,
+    }
+}
+
+fn method_call(
+    receiver: Expression,
+    method: Name,
+    args: Vec<Expression>,
+    env: &Environment,
+) -> Result<Expression, Exception> {
+    if receiver == Expression::Super {
+        return super_call(method, args, env);
+    }
+    let receiver = eval(receiver, env)?;
+    let args = eval_args(args, env)?;
+    match receiver {
+        Expression::CString(s) => string_method(s, &method, args),
+        Expression::Object(object) => match find_method(&object.class(), &method, env) {
+            Some((owner, function)) => call_method(owner, function, object, args, env),
+            None => Err(no_such_attribute(&object, &method)),
         },
-        "(>) is only defined for numbers (integers and real).",
-    )
+        Expression::Module(name, globals) => module_call(&name, &globals, method, args, env),
+        Expression::File(file) => builtins::call_file_method(&file, &method, args, env),
+        _ => Err(Exception::new(
+            "AttributeError",
+            &format!("'{}' is not a method of this value.", method),
+        )),
+    }
 }
 
-fn lt(lhs: Expression, rhs: Expression, env: &Environment) -> Result<Expression, ErrorMessage> {
-    eval_binary_rel_op(
-        lhs,
-        rhs,
-        env,
-        |a, b| {
-            if a < b {
-                Expression::CTrue
-            } else {
-                Expression::CFalse
+/* 'module.f(args)' calls a function of the module, and 'module.C(args)'
+ * instantiates a class it defines */
+fn module_call(
+    module: &str,
+    globals: &ScopeRef,
+    name: Name,
+    args: Vec<Expression>,
+    env: &Environment,
+) -> Result<Expression, Exception> {
+    let value = globals.0.borrow().bindings.get(&name).cloned();
+    match value {
+        Some(Expression::FunctionValue(function, closure)) => {
+            invoke(*function, closure, args, env, vec![])
+        }
+        Some(Expression::BuiltinFunction(name)) => builtins::call_function(&name, args, env),
+        Some(_) => Err(Exception::new(
+            "TypeError",
+            &format!("'{}.{}' is not a function.", module, name),
+        )),
+        None => {
+            let class = globals.0.borrow().classes.get(&name).cloned();
+            match class {
+                Some(class) => instantiate(&class, args, env),
+                None => Err(no_module_attribute(module, &name)),
             }
-        },
-        "(<) is only defined for numbers (integers and real).",
+        }
+    }
+}
+
+fn no_module_attribute(module: &str, attribute: &str) -> Exception {
+    Exception::new(
+        "AttributeError",
+        &format!("module '{}' has no attribute '{}'.", module, attribute),
     )
 }
 
-fn gte(lhs: Expression, rhs: Expression, env: &Environment) -> Result<Expression, ErrorMessage> {
-    eval_binary_rel_op(
-        lhs,
-        rhs,
-        env,
-        |a, b| {
-            if a >= b {
-                Expression::CTrue
-            } else {
-                Expression::CFalse
+fn fstring(parts: Vec<FStringPart>, env: &Environment) -> Result<Expression, Exception> {
+    let mut result = String::new();
+    for part in parts {
+        match part {
+            FStringPart::Literal(text) => result.push_str(&text),
+            FStringPart::Field(exp, spec) => {
+                let value = eval(*exp, env)?;
+                let spec = match spec {
+                    Some((text, offset)) => parse_spec(&text, offset)
+                        .map_err(|msg| Exception::new("ValueError", &msg))?,
+                    None => Spec::default(),
+                };
+                let formatted = format::format_value(&value, &spec)
+                    .map_err(|msg| Exception::new("ValueError", &msg))?;
+                result.push_str(&formatted);
+            }
+        }
+    }
+    Ok(Expression::CString(result))
+}
+
+/* String Methods */
+fn string_method(s: String, method: &str, args: Vec<Expression>) -> Result<Expression, Exception> {
+    let string_args = args.iter().map(|a| match a {
+        Expression::CString(a) => Ok(a.as_str()),
+        _ => Err(Exception::new(
+            "TypeError",
+            &format!("'{}' expects string arguments.", method),
+        )),
+    });
+    match (method, args.as_slice()) {
+        ("upper", []) => Ok(Expression::CString(s.to_uppercase())),
+        ("lower", []) => Ok(Expression::CString(s.to_lowercase())),
+        ("strip", []) => Ok(Expression::CString(s.trim().to_string())),
+        ("strip", [_]) => {
+            let chars: Vec<char> = string_args
+                .collect::<Result<String, _>>()?
+                .chars()
+                .collect();
+            Ok(Expression::CString(
+                s.trim_matches(chars.as_slice()).to_string(),
+            ))
+        }
+        ("split", []) => Ok(string_list(s.split_whitespace())),
+        ("split", [_]) => {
+            let sep = string_args.collect::<Result<String, _>>()?;
+            if sep.is_empty() {
+                return Err(Exception::new(
+                    "ValueError",
+                    "'split' separator cannot be empty.",
+                ));
+            }
+            Ok(string_list(s.split(sep.as_str())))
+        }
+        ("join", [Expression::ListValue(elements)]) => {
+            let parts = elements
+                .iter()
+                .map(|e| match e {
+                    Expression::CString(e) => Ok(e.as_str()),
+                    _ => Err(Exception::new(
+                        "TypeError",
+                        "'join' expects a list of strings.",
+                    )),
+                })
+                .collect::<Result<Vec<&str>, Exception>>()?;
+            Ok(Expression::CString(parts.join(&s)))
+        }
+        ("join", [_]) => Err(Exception::new(
+            "TypeError",
+            "'join' expects a list of strings.",
+        )),
+        ("replace", [_, _]) => {
+            let args = string_args.collect::<Result<Vec<&str>, _>>()?;
+            Ok(Expression::CString(s.replace(args[0], args[1])))
+        }
+        ("startswith", [_]) => {
+            let prefix = string_args.collect::<Result<String, _>>()?;
+            Ok(bool_value(s.starts_with(prefix.as_str())))
+        }
+        ("endswith", [_]) => {
+            let suffix = string_args.collect::<Result<String, _>>()?;
+            Ok(bool_value(s.ends_with(suffix.as_str())))
+        }
+        ("find", [_]) => {
+            let sub = string_args.collect::<Result<String, _>>()?;
+            Ok(Expression::CInt(match s.find(sub.as_str()) {
+                Some(byte_pos) => s[..byte_pos].chars().count() as i32,
+                None => -1,
+            }))
+        }
+        (
+            "upper" | "lower" | "strip" | "split" | "join" | "replace" | "startswith" | "endswith"
+            | "find",
+            _,
+        ) => Err(Exception::new(
+            "TypeError",
+            &format!("wrong number of arguments to string method '{}'.", method),
+        )),
+        _ => Err(Exception::new(
+            "AttributeError",
+            &format!("'{}' is not a string method.", method),
+        )),
+    }
+}
+
+fn string_list<'a>(parts: impl Iterator<Item = &'a str>) -> Expression {
+    Expression::ListValue(parts.map(|p| Expression::CString(p.to_string())).collect())
+}
+
+/* Records */
+
+/* builds a record value, with its fields in declaration order */
+fn record(
+    name: Name,
+    fields: Vec<(Name, Expression)>,
+    env: &Environment,
+) -> Result<Expression, Exception> {
+    let declared = match env.get_type(&name) {
+        Some(Type::TRecord(_, declared)) => declared,
+        _ => {
+            return Err(Exception::new(
+                "NameError",
+                &format!("record type '{}' is not declared.", name),
+            ))
+        }
+    };
+    let mut values = vec![];
+    for (field, _) in declared.iter() {
+        let mut given = fields.iter().filter(|(f, _)| f == field);
+        match (given.next(), given.next()) {
+            (Some((_, exp)), None) => values.push((field.clone(), eval(exp.clone(), env)?)),
+            (None, _) => {
+                return Err(Exception::new(
+                    "TypeError",
+                    &format!("missing field '{}' of record '{}'.", field, name),
+                ))
             }
+            (Some(_), Some(_)) => {
+                return Err(Exception::new(
+                    "TypeError",
+                    &format!("field '{}' of record '{}' is given twice.", field, name),
+                ))
+            }
+        }
+    }
+    if let Some((field, _)) = fields
+        .iter()
+        .find(|(f, _)| !declared.iter().any(|(d, _)| d == f))
+    {
+        return Err(no_such_field(&name, field));
+    }
+    Ok(Expression::Record(name, values))
+}
+
+fn field_access(exp: Expression, field: Name, env: &Environment) -> Result<Expression, Exception> {
+    match eval(exp, env)? {
+        Expression::Record(name, fields) => match fields.into_iter().find(|(f, _)| *f == field) {
+            Some((_, value)) => Ok(value),
+            None => Err(no_such_field(&name, &field)),
         },
-        "(>=) is only defined for numbers (integers and real).",
+        Expression::Object(object) => {
+            let value = object.0.borrow().fields.get(&field).cloned();
+            value.ok_or(no_such_attribute(&object, &field))
+        }
+        Expression::Module(name, globals) => {
+            let value = globals.0.borrow().bindings.get(&field).cloned();
+            value.ok_or(no_module_attribute(&name, &field))
+        }
+        _ => Err(Exception::new(
+            "TypeError",
+            &format!(
+                "cannot access field '{}' of a value that is not a record.",
+                field
+            ),
+        )),
+    }
+}
+
+fn no_such_field(record: &str, field: &str) -> Exception {
+    Exception::new(
+        "AttributeError",
+        &format!("record '{}' has no field '{}'.", record, field),
     )
 }
 
-fn lte(lhs: Expression, rhs: Expression, env: &Environment) -> Result<Expression, ErrorMessage> {
-    eval_binary_rel_op(
-        lhs,
-        rhs,
-        env,
-        |a, b| {
-            if a <= b {
-                Expression::CTrue
-            } else {
-                Expression::CFalse
+/* 'target.field = value': records are values, so the update rebuilds
+ * every record along the path and rebinds the variable at its root */
+fn field_assignment(
+    target: Expression,
+    field: Name,
+    value: Expression,
+    env: &mut Environment,
+) -> Result<(), Exception> {
+    let value = eval(value, env)?;
+    store(Expression::FieldAccess(Box::new(target), field), value, env)
+}
+
+fn store(target: Expression, value: Expression, env: &mut Environment) -> Result<(), Exception> {
+    match target {
+        Expression::Var(name) if env.is_val(&name) => Err(Exception::new(
+            "TypeError",
+            &format!("cannot modify '{}', declared with 'val'.", name),
+        )),
+        Expression::Var(name) => {
+            env.insert(name, value);
+            Ok(())
+        }
+        Expression::FieldAccess(inner, field) => {
+            let updated = match eval(*inner.clone(), env)? {
+                /* objects are shared, so they are updated in place */
+                Expression::Object(object) => {
+                    object.0.borrow_mut().fields.insert(field, value);
+                    return Ok(());
+                }
+                Expression::Record(name, mut fields) => {
+                    match fields.iter_mut().find(|(f, _)| *f == field) {
+                        Some((_, old)) => *old = value,
+                        None => return Err(no_such_field(&name, &field)),
+                    }
+                    Expression::Record(name, fields)
+                }
+                _ => {
+                    return Err(Exception::new(
+                        "TypeError",
+                        &format!(
+                            "cannot assign field '{}' of a value that is not a record.",
+                            field
+                        ),
+                    ))
+                }
+            };
+            store(*inner, updated, env)
+        }
+        Expression::Index(inner, idx) => {
+            let updated = match eval(*inner.clone(), env)? {
+                Expression::ListValue(mut elements) => {
+                    let i = match eval(*idx, env)? {
+                        Expression::CInt(i) => i,
+                        _ => return Err(Exception::new("TypeError", "indices must be integers.")),
+                    };
+                    let pos = normalize_index(i, elements.len()).ok_or(Exception::new(
+                        "IndexError",
+                        "list assignment index out of range.",
+                    ))?;
+                    elements[pos] = value;
+                    Expression::ListValue(elements)
+                }
+                Expression::DictValue(mut entries) => {
+                    entries.push((eval(*idx, env)?, value));
+                    Expression::DictValue(dict_entries(entries, env)?)
+                }
+                _ => {
+                    return Err(Exception::new(
+                        "TypeError",
+                        "item assignment is only defined for lists and dictionaries.",
+                    ))
+                }
+            };
+            store(*inner, updated, env)
+        }
+        _ => Err(Exception::new(
+            "TypeError",
+            "cannot assign to this expression.",
+        )),
+    }
+}
+
+/* 'target op= value': the indices in the target are evaluated once, so
+ * that the element read is the one that is updated */
+fn augmented_assignment(
+    target: Expression,
+    op: ArithmeticOp,
+    exp: Expression,
+    env: &mut Environment,
+) -> Result<(), Exception> {
+    let location = locate(target, env)?;
+    let value = eval(op.apply(location.clone(), exp), env)?;
+    store(location, value, env)
+}
+
+/* the target of an assignment with its indices replaced by their values */
+fn locate(target: Expression, env: &Environment) -> Result<Expression, Exception> {
+    match target {
+        Expression::Var(_) => Ok(target),
+        Expression::FieldAccess(inner, field) => Ok(Expression::FieldAccess(
+            Box::new(locate(*inner, env)?),
+            field,
+        )),
+        Expression::Index(inner, idx) => {
+            let inner = locate(*inner, env)?;
+            Ok(Expression::Index(
+                Box::new(inner),
+                Box::new(eval(*idx, env)?),
+            ))
+        }
+        _ => Err(Exception::new(
+            "TypeError",
+            "cannot assign to this expression.",
+        )),
+    }
+}
+
+/* Algebraic Data Types */
+
+fn adt_constructor(
+    name: Name,
+    args: Vec<Expression>,
+    env: &Environment,
+) -> Result<Expression, Exception> {
+    let arity = match env.get_constructor(&name) {
+        Some(constructor) => constructor.types.len(),
+        None => {
+            return Err(Exception::new(
+                "NameError",
+                &format!("constructor '{}' is not defined.", name),
+            ))
+        }
+    };
+    if args.len() != arity {
+        return Err(Exception::new(
+            "TypeError",
+            &format!(
+                "constructor '{}' expects {} argument(s), but {} were given.",
+                name,
+                arity,
+                args.len()
+            ),
+        ));
+    }
+    Ok(Expression::ADTConstructor(name, eval_all(args, env)?))
+}
+
+/* Maybe and Result */
+
+fn unwrap(exp: Expression, env: &Environment) -> Result<Expression, Exception> {
+    match eval(exp, env)? {
+        Expression::CJust(v) | Expression::COk(v) => Ok(*v),
+        v @ (Expression::CNothing | Expression::CErr(_)) => Err(Exception::new(
+            "ValueError",
+            &format!("called 'unwrap' on {}.", v),
+        )),
+        _ => Err(not_maybe_or_result("unwrap")),
+    }
+}
+
+/* the default is only evaluated when there is no value to unwrap */
+fn unwrap_or(
+    exp: Expression,
+    default: Expression,
+    env: &Environment,
+) -> Result<Expression, Exception> {
+    match eval(exp, env)? {
+        Expression::CJust(v) | Expression::COk(v) => Ok(*v),
+        Expression::CNothing | Expression::CErr(_) => eval(default, env),
+        _ => Err(not_maybe_or_result("unwrap_or")),
+    }
+}
+
+fn is_error(exp: Expression, env: &Environment) -> Result<Expression, Exception> {
+    match eval(exp, env)? {
+        Expression::COk(_) => Ok(Expression::CFalse),
+        Expression::CErr(_) => Ok(Expression::CTrue),
+        _ => Err(Exception::new(
+            "TypeError",
+            "'is_error' expects a Result value.",
+        )),
+    }
+}
+
+fn is_nothing(exp: Expression, env: &Environment) -> Result<Expression, Exception> {
+    match eval(exp, env)? {
+        Expression::CJust(_) => Ok(Expression::CFalse),
+        Expression::CNothing => Ok(Expression::CTrue),
+        _ => Err(Exception::new(
+            "TypeError",
+            "'is_nothing' expects a Maybe value.",
+        )),
+    }
+}
+
+fn propagate(exp: Expression, env: &Environment) -> Result<Expression, Exception> {
+    match eval(exp, env)? {
+        Expression::CJust(v) | Expression::COk(v) => Ok(*v),
+        v @ (Expression::CNothing | Expression::CErr(_)) => Err(Exception::propagation(v)),
+        _ => Err(not_maybe_or_result("?")),
+    }
+}
+
+fn not_maybe_or_result(operation: &str) -> Exception {
+    Exception::new(
+        "TypeError",
+        &format!("'{}' expects a Maybe or Result value.", operation),
+    )
+}
+
+/* Functions */
+
+/* calling a class creates an instance of it */
+fn func_call(
+    name: Name,
+    args: Vec<Expression>,
+    env: &Environment,
+) -> Result<Expression, Exception> {
+    if env.get(&name).is_none() {
+        if let Some(class) = env.resolve_class(&name) {
+            return instantiate(&class, eval_args(args, env)?, env);
+        }
+        if builtin_signature(&name).is_some() {
+            return builtins::call_function(&name, eval_args(args, env)?, env);
+        }
+    }
+    match lookup(name.clone(), env)? {
+        Expression::FunctionValue(function, closure) => {
+            invoke(*function, closure, eval_args(args, env)?, env, vec![])
+        }
+        Expression::BuiltinFunction(name) => {
+            builtins::call_function(&name, eval_args(args, env)?, env)
+        }
+        _ => Err(Exception::new(
+            "TypeError",
+            &format!("'{}' is not a function.", name),
+        )),
+    }
+}
+
+/* the arguments of a call, with the names of the keyword arguments */
+pub(super) fn keyword_pairs(args: Vec<Expression>) -> Vec<(Option<Name>, Expression)> {
+    args.into_iter()
+        .map(|arg| match arg {
+            Expression::KeywordArgument(name, value) => (Some(name), *value),
+            _ => (None, arg),
+        })
+        .collect()
+}
+
+/* the body runs in a new scope, enclosed by the scope the function was
+ * defined in and seeing the variables of its module; 'context' holds
+ * bindings made for the call besides the parameters */
+pub(super) fn invoke(
+    function: Function,
+    closure: Closure,
+    args: Vec<Expression>,
+    env: &Environment,
+    context: Vec<(Name, Expression)>,
+) -> Result<Expression, Exception> {
+    let binding = function
+        .signature()
+        .bind(&function.name, keyword_pairs(args))
+        .map_err(|message| Exception::new("TypeError", &message))?;
+    /* the default values belong to the last parameters */
+    let mut defaults = function.defaults.iter().rev();
+    let mut bindings = context;
+    for ((param, _), arg) in function.params.iter().zip(binding.args).rev() {
+        let default = defaults.next().cloned();
+        bindings.push((param.clone(), arg.or(default).unwrap_or(Expression::CNone)));
+    }
+    if let Some((varargs, _)) = &function.varargs {
+        bindings.push((varargs.clone(), Expression::ListValue(binding.varargs)));
+    }
+    if let Some((kwargs, _)) = &function.kwargs {
+        let entries = binding.kwargs.into_iter();
+        bindings.push((
+            kwargs.clone(),
+            Expression::DictValue(entries.map(|(k, v)| (Expression::CString(k), v)).collect()),
+        ));
+    }
+    let names = function.scope();
+    if let Some(name) = names
+        .nonlocals
+        .iter()
+        .find(|name| !encloses(&closure.locals, name))
+    {
+        return Err(Exception::new(
+            "SyntaxError",
+            &format!("no binding for nonlocal '{}' found.", name),
+        ));
+    }
+    let globals = closure.globals.upgrade().ok_or(Exception::new(
+        "RuntimeError",
+        &format!("the module of {}() no longer exists.", function.name),
+    ))?;
+    let scope = ScopeRef::new(Scope {
+        bindings: bindings.into_iter().collect(),
+        locals: names.locals,
+        function: true,
+        globals: names.globals,
+        nonlocals: names.nonlocals,
+        parent: closure.locals,
+        ..Scope::default()
+    });
+    if function.body.yields() {
+        return Ok(Expression::Iterator(IteratorRef::new(
+            IteratorState::Generator {
+                globals,
+                scope,
+                frames: vec![Frame::Run(*function.body)],
+                running: false,
+            },
+        )));
+    }
+    if env.depth >= env.options.recursion_limit {
+        return Err(recursion_error());
+    }
+    let mut local = Environment {
+        globals,
+        locals: Some(scope),
+        depth: env.depth + 1,
+        ..env.clone()
+    };
+    match exec(*function.body, &mut local) {
+        Ok(ControlFlow::Return(value)) => Ok(value),
+        Ok(ControlFlow::Continue) => Ok(Expression::CNone),
+        Err(Exception {
+            propagated: Some(value),
+            ..
+        }) => Ok(*value),
+        Err(exception) => Err(exception),
+    }
+}
+
+fn recursion_error() -> Exception {
+    Exception::new("RecursionError", "maximum recursion depth exceeded.")
+}
+
+/* whether a local scope of the chain owns the name */
+fn encloses(scope: &Option<ScopeRef>, name: &str) -> bool {
+    let mut current = scope.clone();
+    while let Some(s) = current {
+        if s.0.borrow().locals.contains(name) {
+            return true;
+        }
+        current = s.0.borrow().parent.clone();
+    }
+    false
+}
+
+/* Classes */
+
+fn instantiate(
+    class: &str,
+    args: Vec<Expression>,
+    env: &Environment,
+) -> Result<Expression, Exception> {
+    let object = ObjectRef::new(class);
+    match find_method(class, "__init__", env) {
+        Some((owner, init)) => {
+            call_method(owner, init, object.clone(), args, env)?;
+        }
+        None if args.is_empty() => (),
+        None => {
+            return Err(Exception::new(
+                "TypeError",
+                &format!("{}() takes no arguments.", class),
+            ))
+        }
+    }
+    Ok(Expression::Object(object))
+}
+
+/* the method and the class that defines it, looked up from 'class'
+ * up its chain of base classes */
+fn find_method(class: &str, method: &str, env: &Environment) -> Option<(Name, Function)> {
+    let mut current = env.get_class(class);
+    while let Some(c) = current {
+        if let Some(function) = c.methods.iter().find(|m| m.name == method) {
+            return Some((c.name.clone(), function.clone()));
+        }
+        current = c.base.as_ref().and_then(|base| env.get_class(base));
+    }
+    None
+}
+
+/* as in Python, the method body sees the class that defines it as
+ * '__class__', which is where 'super()' starts looking for methods */
+fn call_method(
+    owner: Name,
+    method: Function,
+    object: ObjectRef,
+    args: Vec<Expression>,
+    env: &Environment,
+) -> Result<Expression, Exception> {
+    let mut args = args;
+    args.insert(0, Expression::Object(object));
+    let closure = Closure {
+        globals: env.class_scope(&owner).unwrap_or(env.globals.downgrade()),
+        locals: None,
+    };
+    let context = vec![(String::from("__class__"), Expression::CString(owner))];
+    invoke(method, closure, args, env, context)
+}
+
+fn super_call(
+    method: Name,
+    args: Vec<Expression>,
+    env: &Environment,
+) -> Result<Expression, Exception> {
+    let (owner, object) = match (env.get("__class__"), env.get("self")) {
+        (Some(Expression::CString(owner)), Some(Expression::Object(object))) => (owner, object),
+        _ => {
+            return Err(Exception::new(
+                "RuntimeError",
+                "'super()' used outside a method.",
+            ))
+        }
+    };
+    let base = env.get_class(&owner).and_then(|c| c.base.clone());
+    match base.and_then(|base| find_method(&base, &method, env)) {
+        Some((owner, function)) => call_method(owner, function, object, eval_args(args, env)?, env),
+        None => Err(Exception::new(
+            "AttributeError",
+            &format!("'super' object has no attribute '{}'.", method),
+        )),
+    }
+}
+
+fn is_instance(exp: Expression, class: Name, env: &Environment) -> Result<Expression, Exception> {
+    let class = env.resolve_class(&class).ok_or(undefined_class(&class))?;
+    let mut current = match eval(exp, env)? {
+        Expression::Object(object) => Some(object.class()),
+        _ => None,
+    };
+    while let Some(c) = current {
+        if c == class {
+            return Ok(Expression::CTrue);
+        }
+        current = env.get_class(&c).and_then(|c| c.base.clone());
+    }
+    Ok(Expression::CFalse)
+}
+
+/* Iterators and Generators */
+
+/* an iterator over the value, which must be a string, list, tuple,
+ * iterator, or an object with '__iter__' */
+pub(super) fn iter(value: Expression, env: &Environment) -> Result<Expression, Exception> {
+    let elements = match value {
+        Expression::CString(s) => s
+            .chars()
+            .map(|c| Expression::CString(c.to_string()))
+            .collect(),
+        Expression::ListValue(elements)
+        | Expression::Tuple(elements)
+        | Expression::SetValue(elements) => elements,
+        Expression::DictValue(entries) => entries.into_iter().map(|(key, _)| key).collect(),
+        Expression::Iterator(_) => return Ok(value),
+        _ => {
+            return match special_method(&value, "__iter__", vec![], env)? {
+                Some(iterator) => Ok(iterator),
+                None => Err(Exception::new(
+                    "TypeError",
+                    &format!("{} is not iterable.", repr(&value)),
+                )),
             }
+        }
+    };
+    Ok(Expression::Iterator(IteratorRef::new(
+        IteratorState::Sequence(elements, 0),
+    )))
+}
+
+/* the next value of an iterator, or None when it is exhausted. Objects
+ * signal exhaustion by raising 'StopIteration' from '__next__'. */
+pub(super) fn next(
+    iterator: &Expression,
+    env: &Environment,
+) -> Result<Option<Expression>, Exception> {
+    match iterator {
+        Expression::Iterator(iterator) => advance(iterator, env),
+        _ => match special_method(iterator, "__next__", vec![], env) {
+            Ok(Some(value)) => Ok(Some(value)),
+            Ok(None) => Err(Exception::new(
+                "TypeError",
+                &format!("{} is not an iterator.", repr(iterator)),
+            )),
+            Err(exception) if exception.kind == "StopIteration" => Ok(None),
+            Err(exception) => Err(exception),
         },
-        "(<=) is only defined for numbers (integers and real).",
-    )
+    }
 }
 
-pub fn execute(stmt: Statement, env: Environment) -> Result<Environment, ErrorMessage> {
-    match stmt {
-        Statement::Assignment(name, exp) => {
-            let value = eval(*exp, &env)?;
-            let mut new_env = env;
-            new_env.insert(*name.clone(), value);
-            Ok(new_env.clone())
+/* a generator is resumed with its state taken out of the iterator, so
+ * that it may advance other iterators (but not itself) meanwhile; the
+ * iterators of built-ins such as 'map' advance those they take their
+ * values from */
+fn advance(iterator: &IteratorRef, env: &Environment) -> Result<Option<Expression>, Exception> {
+    let generator = match &mut *iterator.0.borrow_mut() {
+        IteratorState::Sequence(elements, position) => {
+            let value = elements.get(*position).cloned();
+            *position += value.is_some() as usize;
+            return Ok(value);
         }
-        Statement::IfThenElse(cond, stmt_then, stmt_else) => {
-            let value = eval(*cond, &env)?;
-            match value {
-                Expression::CTrue => execute(*stmt_then, env),
-                Expression::CFalse => execute(*stmt_else, env),
-                _ => Err(String::from("expecting a boolean value.")),
+        IteratorState::Range { next, stop, step } => {
+            if (*step > 0 && *next >= *stop) || (*step < 0 && *next <= *stop) {
+                return Ok(None);
             }
+            let value = Expression::CInt(*next as i32);
+            *next += *step;
+            return Ok(Some(value));
         }
-        Statement::While(cond, stmt) => {
-            let mut value = eval(*cond.clone(), &env)?;
-            let mut new_env = env;
-            while value == Expression::CTrue {
-                new_env = execute(*stmt.clone(), new_env.clone())?;
-                value = eval(*cond.clone(), &new_env.clone())?;
+        IteratorState::Generator { running: true, .. } => {
+            return Err(Exception::new("ValueError", "generator already executing."))
+        }
+        IteratorState::Generator { .. } if env.depth >= env.options.recursion_limit => {
+            return Err(recursion_error());
+        }
+        IteratorState::Generator {
+            globals,
+            scope,
+            frames,
+            running,
+        } => {
+            *running = true;
+            Some((
+                globals.clone(),
+                std::mem::take(scope),
+                std::mem::take(frames),
+            ))
+        }
+        IteratorState::Map(..)
+        | IteratorState::Filter(..)
+        | IteratorState::Zip(..)
+        | IteratorState::Enumerate(..) => None,
+    };
+    let Some((globals, scope, mut frames)) = generator else {
+        return builtins::advance(iterator, env);
+    };
+    /* resuming a generator counts as a call */
+    let mut local = Environment {
+        globals: globals.clone(),
+        locals: Some(scope),
+        depth: env.depth + 1,
+        ..env.clone()
+    };
+    let result = resume(&mut frames, &mut local);
+    if !matches!(result, Ok(Some(_))) {
+        frames.clear();
+    }
+    *iterator.0.borrow_mut() = IteratorState::Generator {
+        globals,
+        scope: local.locals.unwrap_or_default(),
+        frames,
+        running: false,
+    };
+    result
+}
+
+/* what a frame of a generator does besides going on with the next */
+enum Step {
+    Next,
+    Yield(Expression),
+    Return,
+}
+
+/* runs the frames of a generator until it yields a value, returns or
+ * runs out of work. Compound statements are unfolded into frames, so
+ * that the generator can be suspended inside them; the others are
+ * executed as usual. */
+fn resume(frames: &mut Vec<Frame>, env: &mut Environment) -> Result<Option<Expression>, Exception> {
+    while let Some(frame) = frames.pop() {
+        let exception = match step(frame, frames, env) {
+            Ok(Step::Next) => continue,
+            Ok(Step::Yield(value)) => return Ok(Some(value)),
+            Ok(Step::Return) => None,
+            /* '?' returns from the generator */
+            Err(exception) if exception.propagated.is_some() => None,
+            Err(exception) => Some(exception),
+        };
+        if !unwind(exception, frames, env)? {
+            return Ok(None);
+        }
+    }
+    Ok(None)
+}
+
+fn step(frame: Frame, frames: &mut Vec<Frame>, env: &mut Environment) -> Result<Step, Exception> {
+    match frame {
+        Frame::Loop(name, iterator, body) => {
+            if let Some(value) = next(&iterator, env)? {
+                assign(name.clone(), value, env)?;
+                frames.push(Frame::Loop(name, iterator, body.clone()));
+                push_block(body, frames, env);
+            }
+        }
+        Frame::Leave => env.leave_block(),
+        Frame::Try(_, finally) => {
+            if let Some(finally) = finally {
+                push_block(*finally, frames, env);
+            }
+        }
+        Frame::Exit(manager) => {
+            exit(&manager, None, env)?;
+        }
+        Frame::Resume(None) => return Ok(Step::Return),
+        Frame::Resume(Some(exception)) => return Err(Exception::from_value(exception)),
+        Frame::Run(Statement::Yield(exp)) => return Ok(Step::Yield(eval(*exp, env)?)),
+        Frame::Run(Statement::Sequence(s1, s2)) => {
+            frames.push(Frame::Run(*s2));
+            frames.push(Frame::Run(*s1));
+        }
+        Frame::Run(Statement::IfThenElse(cond, stmt_then, stmt_else)) => match eval(*cond, env)? {
+            Expression::CTrue => push_block(*stmt_then, frames, env),
+            Expression::CFalse => push_block(*stmt_else, frames, env),
+            _ => return Err(Exception::new("TypeError", "expecting a boolean value.")),
+        },
+        Frame::Run(Statement::While(cond, body)) => {
+            if loop_condition(*cond.clone(), env)? {
+                frames.push(Frame::Run(Statement::While(cond, body.clone())));
+                push_block(*body, frames, env);
+            }
+        }
+        Frame::Run(Statement::For(name, iterable, body)) => {
+            let iterator = iter(eval(*iterable, env)?, env)?;
+            frames.push(Frame::Loop(name, iterator, *body));
+        }
+        Frame::Run(Statement::Match(exp, cases)) => {
+            let body = select_case(*exp, cases, env)?;
+            push_block(body, frames, env);
+        }
+        Frame::Run(Statement::Try(body, handlers, finally)) => {
+            frames.push(Frame::Try(handlers, finally));
+            push_block(*body, frames, env);
+        }
+        Frame::Run(Statement::With(manager, name, body)) => {
+            let manager = eval(*manager, env)?;
+            let value = enter(&manager, env)?;
+            if let Some(name) = name {
+                assign(name, value, env)?;
+            }
+            frames.push(Frame::Exit(manager));
+            push_block(*body, frames, env);
+        }
+        Frame::Run(stmt) => {
+            if let ControlFlow::Return(_) = exec(stmt, env)? {
+                return Ok(Step::Return);
             }
-            Ok(new_env)
         }
-        Statement::Sequence(s1, s2) => execute(*s1, env).and_then(|new_env| execute(*s2, new_env)),
-        _ => Err(String::from("not implemented yet")),
     }
+    Ok(Step::Next)
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::ir::ast::Expression::*;
-    use crate::ir::ast::Statement::*;
-    use approx::relative_eq;
+/* leaves the frames of a generator that raises the exception, or that
+ * returns if there is none, exiting the 'with' statements on the way, up
+ * to a 'try' that handles the exception or has a 'finally' block to run
+ * first; whether the generator goes on */
+fn unwind(
+    mut exception: Option<Exception>,
+    frames: &mut Vec<Frame>,
+    env: &mut Environment,
+) -> Result<bool, Exception> {
+    while let Some(frame) = frames.pop() {
+        match frame {
+            Frame::Leave => env.leave_block(),
+            Frame::Try(handlers, finally) => {
+                let handler = exception
+                    .as_ref()
+                    .and_then(|e| handlers.into_iter().find(|h| catches(h, e)));
+                if let (Some(handler), Some(exception)) = (handler, &exception) {
+                    if let Some(name) = handler.name {
+                        env.insert(name, exception.value());
+                    }
+                    frames.push(Frame::Try(vec![], finally));
+                    push_block(*handler.body, frames, env);
+                    return Ok(true);
+                }
+                if let Some(finally) = finally {
+                    frames.push(Frame::Resume(exception.map(|e| e.value())));
+                    push_block(*finally, frames, env);
+                    return Ok(true);
+                }
+            }
+            /* as in 'with', the pending exception wins over one on exit,
+             * and the generator goes on after the statement if '__exit__'
+             * suppresses it */
+            Frame::Exit(manager) => match exit(&manager, raised(exception.as_ref()), env) {
+                Ok(true) => return Ok(true),
+                Err(error) if exception.is_none() => exception = Some(error),
+                _ => (),
+            },
+            _ => (),
+        }
+    }
+    match exception {
+        Some(exception) => Err(exception),
+        None => Ok(false),
+    }
+}
+
+fn push_block(block: Statement, frames: &mut Vec<Frame>, env: &mut Environment) {
+    let names = block.declarations();
+    if !names.is_empty() {
+        frames.push(Frame::Leave);
+        env.enter_block(names);
+    }
+    frames.push(Frame::Run(block));
+}
+
+/* Special Methods */
+
+/* an operator applied to an object calls the special method of its
+ * class that implements it, if there is one */
+pub(super) fn special_method(
+    receiver: &Expression,
+    method: &str,
+    args: Vec<Expression>,
+    env: &Environment,
+) -> Result<Option<Expression>, Exception> {
+    match receiver {
+        Expression::Object(object) => match find_method(&object.class(), method, env) {
+            Some((owner, function)) => {
+                call_method(owner, function, object.clone(), args, env).map(Some)
+            }
+            None => Ok(None),
+        },
+        _ => Ok(None),
+    }
+}
+
+/* '==' and '!=' call '__eq__' on the left operand; an ordering calls
+ * its method on the left operand ('__lt__', '__le__', '__gt__' or
+ * '__ge__') or, as in Python, when the left operand does not define it,
+ * the reflected one on the right operand: 'a > b' calls 'b.__lt__(a)' */
+fn overloaded_relational(
+    op: &RelationalOp,
+    v1: &Expression,
+    v2: &Expression,
+    env: &Environment,
+) -> Result<Option<bool>, Exception> {
+    let (method, result) = match op {
+        RelationalOp::EQ | RelationalOp::NEQ => (
+            "__eq__",
+            special_method(v1, "__eq__", vec![v2.clone()], env)?,
+        ),
+        _ => match special_method(v1, op.special_method(), vec![v2.clone()], env)? {
+            Some(result) => (op.special_method(), Some(result)),
+            None => {
+                let reflected = op.reflected().special_method();
+                (
+                    reflected,
+                    special_method(v2, reflected, vec![v1.clone()], env)?,
+                )
+            }
+        },
+    };
+    match result {
+        None => Ok(None),
+        Some(Expression::CTrue) => Ok(Some(*op != RelationalOp::NEQ)),
+        Some(Expression::CFalse) => Ok(Some(*op == RelationalOp::NEQ)),
+        Some(_) => Err(Exception::new(
+            "TypeError",
+            &format!("'{}' must return a boolean.", method),
+        )),
+    }
+}
+
+fn undefined_class(class: &str) -> Exception {
+    Exception::new("NameError", &format!("class '{}' is not defined.", class))
+}
+
+fn no_such_attribute(object: &ObjectRef, attribute: &str) -> Exception {
+    Exception::new(
+        "AttributeError",
+        &format!(
+            "'{}' object has no attribute '{}'.",
+            object.class(),
+            attribute
+        ),
+    )
+}
+
+/* Boolean Expressions */
+
+/* 'and' and 'or' short-circuit: the right operand is evaluated only
+ * when the left one does not already decide the result, i.e., when it
+ * is not the 'decisive' value (False for 'and', True for 'or'). */
+fn eval_binary_boolean_op(
+    lhs: Expression,
+    rhs: Expression,
+    env: &Environment,
+    decisive: Expression,
+    error_msg: &str,
+) -> Result<Expression, Exception> {
+    match eval(lhs, env)? {
+        v1 if v1 == decisive => Ok(v1),
+        Expression::CTrue | Expression::CFalse => match eval(rhs, env)? {
+            v2 @ (Expression::CTrue | Expression::CFalse) => Ok(v2),
+            _ => Err(Exception::new("TypeError", error_msg)),
+        },
+        _ => Err(Exception::new("TypeError", error_msg)),
+    }
+}
+
+fn and(lhs: Expression, rhs: Expression, env: &Environment) -> Result<Expression, Exception> {
+    eval_binary_boolean_op(
+        lhs,
+        rhs,
+        env,
+        Expression::CFalse,
+        "'and' is only defined for booleans.",
+    )
+}
+
+fn or(lhs: Expression, rhs: Expression, env: &Environment) -> Result<Expression, Exception> {
+    eval_binary_boolean_op(
+        lhs,
+        rhs,
+        env,
+        Expression::CTrue,
+        "'or' is only defined for booleans.",
+    )
+}
+
+fn not(lhs: Expression, env: &Environment) -> Result<Expression, Exception> {
+    let v = eval(lhs, env)?;
+    match v {
+        Expression::CTrue => Ok(Expression::CFalse),
+        Expression::CFalse => Ok(Expression::CTrue),
+        _ => Err(Exception::new(
+            "TypeError",
+            "'not' is only defined for booleans.",
+        )),
+    }
+}
+
+/* Relational Operations */
+fn eval_binary_rel_op(
+    lhs: Expression,
+    rhs: Expression,
+    env: &Environment,
+    op: RelationalOp,
+) -> Result<Expression, Exception> {
+    let v1 = eval(lhs, env)?;
+    let v2 = eval(rhs, env)?;
+    Ok(bool_value(relational(&op, &v1, &v2, env)?))
+}
+
+pub(super) fn relational(
+    op: &RelationalOp,
+    v1: &Expression,
+    v2: &Expression,
+    env: &Environment,
+) -> Result<bool, Exception> {
+    if let Some(holds) = overloaded_relational(op, v1, v2, env)? {
+        return Ok(holds);
+    }
+    let ordering = |error_msg: &str| compare_values(v1, v2, error_msg);
+    Ok(match op {
+        RelationalOp::EQ => values_equal(v1, v2),
+        RelationalOp::NEQ => !values_equal(v1, v2),
+        RelationalOp::GT => {
+            ordering("(>) is only defined for numbers, booleans, strings, lists and tuples.")?
+                == Some(Ordering::Greater)
+        }
+        RelationalOp::LT => {
+            ordering("(<) is only defined for numbers, booleans, strings, lists and tuples.")?
+                == Some(Ordering::Less)
+        }
+        RelationalOp::GTE => matches!(
+            ordering("(>=) is only defined for numbers, booleans, strings, lists and tuples.")?,
+            Some(Ordering::Greater | Ordering::Equal)
+        ),
+        RelationalOp::LTE => matches!(
+            ordering("(<=) is only defined for numbers, booleans, strings, lists and tuples.")?,
+            Some(Ordering::Less | Ordering::Equal)
+        ),
+    })
+}
+
+/* a chain 'a op1 b op2 c' means 'a op1 b and b op2 c', except that
+ * 'b' is evaluated only once. Evaluation stops at the first false link. */
+fn comparison(
+    first: Expression,
+    links: Vec<(RelationalOp, Expression)>,
+    env: &Environment,
+) -> Result<Expression, Exception> {
+    Ok(bool_value(failing_link(first, links, env)?.is_none()))
+}
+
+/* the first link of a comparison chain that does not hold, with the
+ * values of both of its operands */
+fn failing_link(
+    first: Expression,
+    links: Vec<(RelationalOp, Expression)>,
+    env: &Environment,
+) -> Result<Option<(Expression, RelationalOp, Expression)>, Exception> {
+    let mut left = eval(first, env)?;
+    for (op, exp) in links {
+        let right = eval(exp, env)?;
+        if !relational(&op, &left, &right, env)? {
+            return Ok(Some((left, op, right)));
+        }
+        left = right;
+    }
+    Ok(None)
+}
+
+/* orders numbers numerically, booleans with False first, and strings,
+ * lists and tuples lexicographically. Comparisons involving 'nan' are unordered
+ * (Ok(None)), so every ordering test over them is false. */
+fn compare_values(
+    v1: &Expression,
+    v2: &Expression,
+    error_msg: &str,
+) -> Result<Option<Ordering>, Exception> {
+    match (v1, v2) {
+        (Expression::CString(s1), Expression::CString(s2)) => Ok(Some(s1.cmp(s2))),
+        (
+            b1 @ (Expression::CTrue | Expression::CFalse),
+            b2 @ (Expression::CTrue | Expression::CFalse),
+        ) => Ok(Some(
+            (*b1 == Expression::CTrue).cmp(&(*b2 == Expression::CTrue)),
+        )),
+        (Expression::ListValue(l1), Expression::ListValue(l2))
+        | (Expression::Tuple(l1), Expression::Tuple(l2)) => {
+            for (e1, e2) in l1.iter().zip(l2.iter()) {
+                if !values_equal(e1, e2) {
+                    return compare_values(e1, e2, error_msg);
+                }
+            }
+            Ok(Some(l1.len().cmp(&l2.len())))
+        }
+        _ => match (as_real(v1), as_real(v2)) {
+            (Some(r1), Some(r2)) => Ok(r1.partial_cmp(&r2)),
+            _ => Err(Exception::new("TypeError", error_msg)),
+        },
+    }
+}
+
+/* structural equality: numbers compare by value regardless of being
+ * integers or reals, and values of different types are never equal. */
+fn values_equal(v1: &Expression, v2: &Expression) -> bool {
+    match (v1, v2) {
+        (Expression::ListValue(l1), Expression::ListValue(l2))
+        | (Expression::Tuple(l1), Expression::Tuple(l2)) => {
+            l1.len() == l2.len()
+                && l1
+                    .iter()
+                    .zip(l2.iter())
+                    .all(|(e1, e2)| values_equal(e1, e2))
+        }
+        (Expression::SetValue(s1), Expression::SetValue(s2)) => {
+            s1.len() == s2.len() && s1.iter().all(|e1| s2.iter().any(|e2| values_equal(e1, e2)))
+        }
+        (Expression::DictValue(d1), Expression::DictValue(d2)) => {
+            d1.len() == d2.len()
+                && d1.iter().all(|(k1, v1)| {
+                    d2.iter()
+                        .any(|(k2, v2)| values_equal(k1, k2) && values_equal(v1, v2))
+                })
+        }
+        (Expression::CJust(v1), Expression::CJust(v2))
+        | (Expression::COk(v1), Expression::COk(v2))
+        | (Expression::CErr(v1), Expression::CErr(v2)) => values_equal(v1, v2),
+        (Expression::ADTConstructor(n1, a1), Expression::ADTConstructor(n2, a2)) => {
+            n1 == n2
+                && a1.len() == a2.len()
+                && a1
+                    .iter()
+                    .zip(a2.iter())
+                    .all(|(e1, e2)| values_equal(e1, e2))
+        }
+        (Expression::Record(n1, f1), Expression::Record(n2, f2)) => {
+            n1 == n2
+                && f1.len() == f2.len()
+                && f1
+                    .iter()
+                    .zip(f2.iter())
+                    .all(|((k1, e1), (k2, e2))| k1 == k2 && values_equal(e1, e2))
+        }
+        _ => match (as_real(v1), as_real(v2)) {
+            (Some(r1), Some(r2)) => r1 == r2,
+            _ => v1 == v2,
+        },
+    }
+}
+
+fn as_real(v: &Expression) -> Option<f64> {
+    match v {
+        Expression::CInt(v) => Some(*v as f64),
+        Expression::CReal(v) => Some(*v),
+        _ => None,
+    }
+}
+
+pub(super) fn bool_value(b: bool) -> Expression {
+    if b {
+        Expression::CTrue
+    } else {
+        Expression::CFalse
+    }
+}
+
+fn eq(lhs: Expression, rhs: Expression, env: &Environment) -> Result<Expression, Exception> {
+    eval_binary_rel_op(lhs, rhs, env, RelationalOp::EQ)
+}
+
+fn neq(lhs: Expression, rhs: Expression, env: &Environment) -> Result<Expression, Exception> {
+    eval_binary_rel_op(lhs, rhs, env, RelationalOp::NEQ)
+}
+
+fn gt(lhs: Expression, rhs: Expression, env: &Environment) -> Result<Expression, Exception> {
+    eval_binary_rel_op(lhs, rhs, env, RelationalOp::GT)
+}
+
+fn lt(lhs: Expression, rhs: Expression, env: &Environment) -> Result<Expression, Exception> {
+    eval_binary_rel_op(lhs, rhs, env, RelationalOp::LT)
+}
+
+fn gte(lhs: Expression, rhs: Expression, env: &Environment) -> Result<Expression, Exception> {
+    eval_binary_rel_op(lhs, rhs, env, RelationalOp::GTE)
+}
+
+fn lte(lhs: Expression, rhs: Expression, env: &Environment) -> Result<Expression, Exception> {
+    eval_binary_rel_op(lhs, rhs, env, RelationalOp::LTE)
+}
+
+/// Executes a program in a copy of the environment, whose module
+/// variables are its own: the environment given is left as it was, even
+/// if the program raises an exception.
+pub fn execute(stmt: Statement, env: Environment) -> Result<Environment, Exception> {
+    let mut env = env.detach();
+    match exec(stmt, &mut env) {
+        Ok(ControlFlow::Continue) => Ok(env),
+        Ok(ControlFlow::Return(_)) => {
+            Err(Exception::new("SyntaxError", "'return' outside function."))
+        }
+        Err(exception) => Err(Exception {
+            propagated: None,
+            ..exception
+        }),
+    }
+}
+
+/* whether execution continues with the next statement or the enclosing
+ * function returns */
+#[derive(Debug, PartialEq)]
+enum ControlFlow {
+    Continue,
+    Return(Expression),
+}
+
+/* statements update the environment in place, so that the bindings
+ * made before an exception is raised survive when a handler catches it */
+fn exec(stmt: Statement, env: &mut Environment) -> Result<ControlFlow, Exception> {
+    match stmt {
+        Statement::VarDeclaration(name) => {
+            env.owner(&name).0.borrow_mut().vals.remove(name.as_str());
+            Ok(ControlFlow::Continue)
+        }
+        Statement::ValDeclaration(name) => {
+            env.owner(&name).0.borrow_mut().vals.insert(*name);
+            Ok(ControlFlow::Continue)
+        }
+        /* both take effect when the function is called */
+        Statement::Global(_) => Ok(ControlFlow::Continue),
+        Statement::Nonlocal(_) if env.locals.is_none() => Err(Exception::new(
+            "SyntaxError",
+            "nonlocal declaration not allowed at module level.",
+        )),
+        Statement::Nonlocal(_) => Ok(ControlFlow::Continue),
+        Statement::Import(name) => import(name, env),
+        Statement::FromImport(name, names) => from_import(name, names, env),
+        Statement::Assignment(name, exp) => {
+            let value = eval(*exp, env)?;
+            assign(*name, value, env)?;
+            Ok(ControlFlow::Continue)
+        }
+        Statement::RecordDeclaration(name, fields) => {
+            env.declare_type(name.clone(), Type::TRecord(name, fields));
+            Ok(ControlFlow::Continue)
+        }
+        Statement::TypeDeclaration(name, constructors) => {
+            env.declare_type(name.clone(), Type::TAlgebraicData(name, constructors));
+            Ok(ControlFlow::Continue)
+        }
+        Statement::Match(exp, cases) => match_cases(*exp, cases, env),
+        Statement::FieldAssignment(target, field, exp) => {
+            field_assignment(*target, field, *exp, env)?;
+            Ok(ControlFlow::Continue)
+        }
+        Statement::AugmentedAssignment(target, op, exp) => {
+            augmented_assignment(*target, op, *exp, env)?;
+            Ok(ControlFlow::Continue)
+        }
+        Statement::ClassDef(class) => class_def(class, env),
+        /* default values are evaluated once, when the function is defined */
+        Statement::FuncDef(mut function) => {
+            function.defaults = eval_all(std::mem::take(&mut function.defaults), env)?;
+            let name = function.name.clone();
+            let closure = env.closure();
+            assign(name, Expression::FunctionValue(function, closure), env)?;
+            Ok(ControlFlow::Continue)
+        }
+        Statement::Return(exp) => Ok(ControlFlow::Return(eval(*exp, env)?)),
+        Statement::IfThenElse(cond, stmt_then, stmt_else) => {
+            let value = eval(*cond, env)?;
+            match value {
+                Expression::CTrue => exec_block(*stmt_then, env),
+                Expression::CFalse => exec_block(*stmt_else, env),
+                _ => Err(Exception::new("TypeError", "expecting a boolean value.")),
+            }
+        }
+        Statement::While(cond, stmt) => {
+            while loop_condition(*cond.clone(), env)? {
+                if let ControlFlow::Return(value) = exec_block(*stmt.clone(), env)? {
+                    return Ok(ControlFlow::Return(value));
+                }
+            }
+            Ok(ControlFlow::Continue)
+        }
+        Statement::For(name, iterable, body) => {
+            let iterator = iter(eval(*iterable, env)?, env)?;
+            while let Some(value) = next(&iterator, env)? {
+                assign(name.clone(), value, env)?;
+                if let ControlFlow::Return(value) = exec_block(*body.clone(), env)? {
+                    return Ok(ControlFlow::Return(value));
+                }
+            }
+            Ok(ControlFlow::Continue)
+        }
+        Statement::Yield(_) => Err(Exception::new("SyntaxError", "'yield' outside function.")),
+        Statement::Sequence(s1, s2) => sequence(*s1, *s2, env),
+        Statement::Raise(exp) => Err(Exception::from_value(eval(*exp, env)?)),
+        Statement::Try(body, handlers, finally) => try_except(*body, handlers, finally, env),
+        Statement::With(manager, name, body) => with(*manager, name, *body, env),
+        Statement::Assert(cond, message) => {
+            assert(*cond, *message, env)?;
+            Ok(ControlFlow::Continue)
+        }
+    }
+}
+
+/* the right spine of a sequence is walked in a loop, so that a long
+ * program does not take a native stack frame per statement */
+fn sequence(
+    first: Statement,
+    rest: Statement,
+    env: &mut Environment,
+) -> Result<ControlFlow, Exception> {
+    let mut current = Statement::Sequence(Box::new(first), Box::new(rest));
+    while let Statement::Sequence(s1, s2) = current {
+        if let ControlFlow::Return(value) = exec(*s1, env)? {
+            return Ok(ControlFlow::Return(value));
+        }
+        current = *s2;
+    }
+    exec(current, env)
+}
+
+/* the classes and types a module declares are available to the
+ * modules that import it, so that its values can be used there */
+fn import(name: Name, env: &mut Environment) -> Result<ControlFlow, Exception> {
+    let module = imported(&name, env)?;
+    env.import_declarations(&module);
+    assign(name.clone(), Expression::Module(name, module.globals), env)?;
+    Ok(ControlFlow::Continue)
+}
+
+/* a name may stand for a variable, a class and a type of the module at
+ * once; each is imported */
+fn from_import(
+    name: Name,
+    names: Vec<Name>,
+    env: &mut Environment,
+) -> Result<ControlFlow, Exception> {
+    let module = imported(&name, env)?;
+    env.import_declarations(&module);
+    for n in names {
+        let exported = module.globals.0.borrow();
+        let value = exported.bindings.get(&n).cloned();
+        let class = exported.classes.get(&n).cloned();
+        let t = exported.types.get(&n).cloned();
+        drop(exported);
+        if value.is_none() && class.is_none() && t.is_none() {
+            return Err(Exception::new(
+                "ImportError",
+                &format!("cannot import name '{}' from '{}'.", n, name),
+            ));
+        }
+        if let Some(class) = class {
+            env.globals.0.borrow_mut().classes.insert(n.clone(), class);
+        }
+        if let Some(t) = t {
+            env.declare_type(n.clone(), t);
+        }
+        if let Some(value) = value {
+            assign(n, value, env)?;
+        }
+    }
+    Ok(ControlFlow::Continue)
+}
+
+/* the class is registered by its qualified name, as is its base, and is
+ * known by its plain name in the module that defines it */
+fn class_def(mut class: Class, env: &mut Environment) -> Result<ControlFlow, Exception> {
+    if let Some(base) = &class.base {
+        class.base = Some(env.resolve_class(base).ok_or(undefined_class(base))?);
+    }
+    for method in class.methods.iter_mut() {
+        method.defaults = eval_all(std::mem::take(&mut method.defaults), env)?;
+    }
+    let qualified = env.qualify(&class.name);
+    let name = std::mem::replace(&mut class.name, qualified);
+    env.define_class(name, class);
+    Ok(ControlFlow::Continue)
+}
+
+fn loop_condition(cond: Expression, env: &Environment) -> Result<bool, Exception> {
+    match eval(cond, env)? {
+        Expression::CTrue => Ok(true),
+        Expression::CFalse => Ok(false),
+        _ => Err(Exception::new("TypeError", "expecting a boolean value.")),
+    }
+}
+
+fn imported(name: &str, env: &Environment) -> Result<Environment, Exception> {
+    match env.modules.get(name) {
+        Some(module) => Ok(module.clone()),
+        None => builtins::standard_module(name).ok_or(Exception::new(
+            "ImportError",
+            &format!("no module named '{}'.", name),
+        )),
+    }
+}
+
+/* the block is left however it ends, raising included */
+fn exec_block(block: Statement, env: &mut Environment) -> Result<ControlFlow, Exception> {
+    let names = block.declarations();
+    if names.is_empty() {
+        return exec(block, env);
+    }
+    env.enter_block(names);
+    let result = exec(block, env);
+    env.leave_block();
+    result
+}
+
+fn assign(name: Name, value: Expression, env: &mut Environment) -> Result<(), Exception> {
+    let owner = env.owner(&name);
+    let mut scope = owner.0.borrow_mut();
+    if scope.vals.contains(&name) && scope.bindings.contains_key(&name) {
+        return Err(Exception::new(
+            "TypeError",
+            &format!("cannot reassign '{}', declared with 'val'.", name),
+        ));
+    }
+    scope.bindings.insert(name, value);
+    Ok(())
+}
+
+/* the names bound by a matching pattern are visible in the guard and
+ * the body, and remain bound after the 'match', as in Python */
+fn match_cases(
+    exp: Expression,
+    cases: Vec<MatchCase>,
+    env: &mut Environment,
+) -> Result<ControlFlow, Exception> {
+    let body = select_case(exp, cases, env)?;
+    exec_block(body, env)
+}
+
+/* the body of the first case that matches, once its names are bound */
+fn select_case(
+    exp: Expression,
+    cases: Vec<MatchCase>,
+    env: &mut Environment,
+) -> Result<Statement, Exception> {
+    let value = eval(exp, env)?;
+    for case in cases {
+        let mut bindings = vec![];
+        if !pattern_matches(&case.pattern, &value, &mut bindings) {
+            continue;
+        }
+        for (name, v) in bindings {
+            assign(name, v, env)?;
+        }
+        let holds = match case.guard {
+            None => true,
+            Some(guard) => match eval(guard, env)? {
+                Expression::CTrue => true,
+                Expression::CFalse => false,
+                _ => return Err(Exception::new("TypeError", "expecting a boolean value.")),
+            },
+        };
+        if holds {
+            return Ok(*case.body);
+        }
+    }
+    Err(Exception::new(
+        "MatchError",
+        &format!("no case matches the value {}.", repr(&value)),
+    ))
+}
+
+fn pattern_matches(
+    pattern: &Pattern,
+    value: &Expression,
+    bindings: &mut Vec<(Name, Expression)>,
+) -> bool {
+    match (pattern, value) {
+        (Pattern::Wildcard, _) => true,
+        (Pattern::Binding(name), _) => {
+            bindings.push((name.clone(), value.clone()));
+            true
+        }
+        (Pattern::Literal(literal), _) => values_equal(literal, value),
+        (Pattern::Tuple(patterns), Expression::Tuple(elements)) => {
+            patterns.len() == elements.len()
+                && patterns
+                    .iter()
+                    .zip(elements.iter())
+                    .all(|(p, e)| pattern_matches(p, e, bindings))
+        }
+        (Pattern::Constructor(name, patterns), Expression::ADTConstructor(c, args)) => {
+            name == c
+                && patterns.len() == args.len()
+                && patterns
+                    .iter()
+                    .zip(args.iter())
+                    .all(|(p, e)| pattern_matches(p, e, bindings))
+        }
+        _ => false,
+    }
+}
+
+/* when a relational condition fails, the error message reports the
+ * values its operands evaluated to, e.g. "totals match: 3 == 4 does not
+ * hold". The message itself is only evaluated on failure. */
+fn assert(cond: Expression, message: Expression, env: &Environment) -> Result<(), Exception> {
+    let chain = match cond {
+        Expression::EQ(l, r) => Ok((*l, vec![(RelationalOp::EQ, *r)])),
+        Expression::NEQ(l, r) => Ok((*l, vec![(RelationalOp::NEQ, *r)])),
+        Expression::GT(l, r) => Ok((*l, vec![(RelationalOp::GT, *r)])),
+        Expression::LT(l, r) => Ok((*l, vec![(RelationalOp::LT, *r)])),
+        Expression::GTE(l, r) => Ok((*l, vec![(RelationalOp::GTE, *r)])),
+        Expression::LTE(l, r) => Ok((*l, vec![(RelationalOp::LTE, *r)])),
+        Expression::Comparison(first, links) => Ok((*first, links)),
+        cond => Err(cond),
+    };
+    let detail = match chain {
+        Ok((first, links)) => match failing_link(first, links, env)? {
+            None => return Ok(()),
+            Some((left, op, right)) => Some(format!(
+                "{} {} {} does not hold",
+                repr(&left),
+                op,
+                repr(&right)
+            )),
+        },
+        Err(cond) => match eval(cond, env)? {
+            Expression::CTrue => return Ok(()),
+            Expression::CFalse => None,
+            _ => {
+                return Err(Exception::new(
+                    "TypeError",
+                    "'assert' expects a boolean condition.",
+                ))
+            }
+        },
+    };
+    let message = eval(message, env)?.to_string();
+    let message = match detail {
+        Some(detail) if message.is_empty() => detail,
+        Some(detail) => format!("{}: {}", message, detail),
+        None => message,
+    };
+    Err(Exception::new("AssertionError", &message))
+}
+
+/* the 'finally' block runs whatever happens in the body and handlers;
+ * if it raises or returns, that replaces the pending outcome */
+fn try_except(
+    body: Statement,
+    handlers: Vec<ExceptHandler>,
+    finally: Option<Box<Statement>>,
+    env: &mut Environment,
+) -> Result<ControlFlow, Exception> {
+    let result = match exec_block(body, env) {
+        Err(exception) => handle(exception, handlers, env),
+        flow => flow,
+    };
+    if let Some(finally) = finally {
+        if let ControlFlow::Return(value) = exec_block(*finally, env)? {
+            return Ok(ControlFlow::Return(value));
+        }
+    }
+    result
+}
+
+/* the value bound by 'with' is the file itself, or what the '__enter__'
+ * method of the object gives; if the body raises, that exception wins
+ * over one raised on exit, unless '__exit__' suppresses it */
+fn with(
+    manager: Expression,
+    name: Option<Name>,
+    body: Statement,
+    env: &mut Environment,
+) -> Result<ControlFlow, Exception> {
+    let manager = eval(manager, env)?;
+    let value = enter(&manager, env)?;
+    if let Some(name) = name {
+        assign(name, value, env)?;
+    }
+    let result = exec_block(body, env);
+    let exited = exit(&manager, raised(result.as_ref().err()), env);
+    match (result, exited) {
+        (Err(_), Ok(true)) => Ok(ControlFlow::Continue),
+        (result, exited) => {
+            let flow = result?;
+            exited?;
+            Ok(flow)
+        }
+    }
+}
+
+fn enter(manager: &Expression, env: &Environment) -> Result<Expression, Exception> {
+    match manager {
+        Expression::File(_) => Ok(manager.clone()),
+        Expression::Object(object) if find_method(&object.class(), "__exit__", env).is_some() => {
+            special_method(manager, "__enter__", vec![], env)?
+                .ok_or_else(|| no_such_attribute(object, "__enter__"))
+        }
+        _ => Err(Exception::new(
+            "TypeError",
+            &format!(
+                "'{}' object does not support the context manager protocol.",
+                builtins::type_name(manager, env)
+            ),
+        )),
+    }
+}
+
+/* closes the file, or calls '__exit__' with the type, the value and the
+ * traceback (always None) of the exception the body raises, or with three
+ * Nones; whether '__exit__' suppresses the exception by returning a true
+ * value */
+fn exit(
+    manager: &Expression,
+    exception: Option<&Exception>,
+    env: &Environment,
+) -> Result<bool, Exception> {
+    if let Expression::File(file) = manager {
+        builtins::call_file_method(file, "close", vec![], env)?;
+        return Ok(false);
+    }
+    let args = match exception {
+        Some(exception) => vec![
+            Expression::ClassValue(exception.kind.clone()),
+            exception.value(),
+            Expression::CNone,
+        ],
+        None => vec![Expression::CNone; 3],
+    };
+    let result = special_method(manager, "__exit__", args, env)?.unwrap_or(Expression::CNone);
+    Ok(exception.is_some() && builtins::truthy(&result, env)?)
+}
+
+/* the exception a 'with' body raises; a propagation by '?' is a return */
+fn raised(exception: Option<&Exception>) -> Option<&Exception> {
+    exception.filter(|e| e.propagated.is_none())
+}
+
+/* a propagation by '?' is not an error, so no handler catches it */
+fn handle(
+    exception: Exception,
+    handlers: Vec<ExceptHandler>,
+    env: &mut Environment,
+) -> Result<ControlFlow, Exception> {
+    if exception.propagated.is_some() {
+        return Err(exception);
+    }
+    for handler in handlers {
+        if catches(&handler, &exception) {
+            if let Some(name) = handler.name {
+                env.insert(name, exception.value());
+            }
+            return exec_block(*handler.body, env);
+        }
+    }
+    Err(exception)
+}
+
+fn catches(handler: &ExceptHandler, exception: &Exception) -> bool {
+    handler
+        .kind
+        .as_ref()
+        .is_none_or(|k| exception.is_caught_by(k))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Exception;
+    use super::*;
+    use crate::interpreter::builtins::files::DiskFileSystem;
+    use crate::interpreter::builtins::files::MemoryFileSystem;
+    use crate::ir::ast::Class;
+    use crate::ir::ast::Expression::*;
+    use crate::ir::ast::Function;
+    use crate::ir::ast::Statement::*;
+    use approx::relative_eq;
+
+    #[test]
+    fn eval_constant() {
+        let env = Environment::new();
+        let c10 = CInt(10);
+        let c20 = CInt(20);
+
+        assert_eq!(eval(c10, &env), Ok(CInt(10)));
+        assert_eq!(eval(c20, &env), Ok(CInt(20)));
+    }
+
+    #[test]
+    fn eval_add_expression1() {
+        let env = Environment::new();
+        let c10 = CInt(10);
+        let c20 = CInt(20);
+        let add1 = Add(Box::new(c10), Box::new(c20));
+        assert_eq!(eval(add1, &env), Ok(CInt(30)));
+    }
+
+    #[test]
+    fn eval_add_expression2() {
+        let env = Environment::new();
+        let c10 = CInt(10);
+        let c20 = CInt(20);
+        let c30 = CInt(30);
+        let add1 = Add(Box::new(c10), Box::new(c20));
+        let add2 = Add(Box::new(add1), Box::new(c30));
+        assert_eq!(eval(add2, &env), Ok(CInt(60)));
+    }
+
+    #[test]
+    fn eval_add_expression3() {
+        let env = Environment::new();
+        let c10 = CInt(10);
+        let c20 = CReal(20.5);
+        let add1 = Add(Box::new(c10), Box::new(c20));
+        assert_eq!(eval(add1, &env), Ok(CReal(30.5)));
+    }
+
+    #[test]
+    fn eval_sub_expression1() {
+        let env = Environment::new();
+        let c10 = CInt(10);
+        let c20 = CInt(20);
+        let sub1 = Sub(Box::new(c20), Box::new(c10));
+        assert_eq!(eval(sub1, &env), Ok(CInt(10)));
+    }
+
+    #[test]
+    fn eval_sub_expression2() {
+        let env = Environment::new();
+        let c100 = CInt(100);
+        let c200 = CInt(300);
+        let sub1 = Sub(Box::new(c200), Box::new(c100));
+        assert_eq!(eval(sub1, &env), Ok(CInt(200)));
+    }
+
+    #[test]
+    fn eval_sub_expression3() {
+        let env = Environment::new();
+        let c100 = CReal(100.5);
+        let c300 = CInt(300);
+        let sub1 = Sub(Box::new(c300), Box::new(c100));
+        assert_eq!(eval(sub1, &env), Ok(CReal(199.5)));
+    }
+
+    #[test]
+    fn eval_mul_expression1() {
+        let env = Environment::new();
+        let c10 = CInt(10);
+        let c20 = CInt(20);
+        let mul1 = Mul(Box::new(c10), Box::new(c20));
+        assert_eq!(eval(mul1, &env), Ok(CInt(200)));
+    }
+
+    #[test]
+    fn eval_mul_expression2() {
+        let env = Environment::new();
+        let c10 = CReal(10.5);
+        let c20 = CInt(20);
+        let mul1 = Mul(Box::new(c10), Box::new(c20));
+        assert_eq!(eval(mul1, &env), Ok(CReal(210.0)));
+    }
+
+    #[test]
+    fn eval_div_expression1() {
+        let env = Environment::new();
+        let c10 = CInt(10);
+        let c20 = CInt(20);
+        let div1 = Div(Box::new(c20), Box::new(c10));
+        assert_eq!(eval(div1, &env), Ok(CInt(2)));
+    }
+
+    #[test]
+    fn eval_div_expression2() {
+        let env = Environment::new();
+        let c10 = CInt(10);
+        let c3 = CInt(3);
+        let div1 = Div(Box::new(c10), Box::new(c3));
+        assert_eq!(eval(div1, &env), Ok(CInt(3)));
+    }
+
+    #[test]
+    fn eval_div_expression3() {
+        let env = Environment::new();
+        let c3 = CInt(3);
+        let c21 = CInt(21);
+        let div1 = Div(Box::new(c21), Box::new(c3));
+        assert_eq!(eval(div1, &env), Ok(CInt(7)));
+    }
+
+    #[test]
+    fn eval_div_expression4() {
+        let env = Environment::new();
+        let c10 = CInt(10);
+        let c3 = CReal(3.0);
+        let div1 = Div(Box::new(c10), Box::new(c3));
+        let res = eval(div1, &env);
+        match res {
+            Ok(CReal(v)) => assert!(relative_eq!(v, 3.3333333333333335, epsilon = f64::EPSILON)),
+            Err(msg) => panic!("{}", msg),
+            _ => panic!("Not expected."),
+        }
+    }
+
+    #[test]
+    fn eval_div_integer_by_zero() {
+        let env = Environment::new();
+        let div1 = Div(Box::new(CInt(10)), Box::new(CInt(0)));
+        assert_eq!(
+            eval(div1, &env),
+            Err(Exception::new("ZeroDivisionError", "division by zero."))
+        );
+    }
+
+    #[test]
+    fn eval_div_integer_overflow() {
+        let env = Environment::new();
+        let div1 = Div(Box::new(CInt(i32::MIN)), Box::new(CInt(-1)));
+        assert_eq!(
+            eval(div1, &env),
+            Err(Exception::new(
+                "OverflowError",
+                "integer overflow in division '(/)'."
+            ))
+        );
+    }
+
+    #[test]
+    fn eval_div_real_by_zero_ieee() {
+        let env = Environment::new();
+        let div1 = Div(Box::new(CReal(1.0)), Box::new(CInt(0)));
+        let div2 = Div(Box::new(CReal(-1.0)), Box::new(CReal(0.0)));
+        let div3 = Div(Box::new(CReal(0.0)), Box::new(CReal(0.0)));
+        assert_eq!(eval(div1, &env), Ok(CReal(f64::INFINITY)));
+        assert_eq!(eval(div2, &env), Ok(CReal(f64::NEG_INFINITY)));
+        match eval(div3, &env) {
+            Ok(CReal(v)) => assert!(v.is_nan()),
+            other => panic!("Not expected: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn eval_div_real_by_zero_strict() {
+        let env = Environment::with_options(Options {
+            real_division: RealDivision::Strict,
+            ..Options::default()
+        });
+        let div1 = Div(Box::new(CReal(1.0)), Box::new(CReal(0.0)));
+        let div2 = Div(Box::new(CReal(1.0)), Box::new(CReal(4.0)));
+        assert_eq!(
+            eval(div1, &env),
+            Err(Exception::new("ZeroDivisionError", "division by zero."))
+        );
+        assert_eq!(eval(div2, &env), Ok(CReal(0.25)));
+    }
+
+    #[test]
+    fn eval_comparisons_with_non_finite_reals() {
+        let env = Environment::from([
+            (String::from("inf"), CReal(f64::INFINITY)),
+            (String::from("nan"), CReal(f64::NAN)),
+        ]);
+        let inf = || Box::new(Var(String::from("inf")));
+        let nan = || Box::new(Var(String::from("nan")));
+
+        assert_eq!(eval(GT(inf(), Box::new(CInt(i32::MAX))), &env), Ok(CTrue));
+        assert_eq!(eval(EQ(inf(), inf()), &env), Ok(CTrue));
+        assert_eq!(eval(EQ(nan(), nan()), &env), Ok(CFalse));
+        assert_eq!(eval(LT(nan(), Box::new(CInt(0))), &env), Ok(CFalse));
+        assert_eq!(eval(GTE(nan(), Box::new(CInt(0))), &env), Ok(CFalse));
+    }
+
+    #[test]
+    fn display_reals() {
+        assert_eq!(CReal(2.0).to_string(), "2.0");
+        assert_eq!(CReal(2.5).to_string(), "2.5");
+        assert_eq!(CReal(f64::INFINITY).to_string(), "inf");
+        assert_eq!(CReal(f64::NEG_INFINITY).to_string(), "-inf");
+        assert_eq!(CReal(f64::NAN).to_string(), "nan");
+        assert_eq!(CReal(-f64::NAN).to_string(), "nan");
+    }
+
+    #[test]
+    fn display_large_and_tiny_reals_with_an_exponent() {
+        assert_eq!(CReal(1e15).to_string(), "1000000000000000.0");
+        assert_eq!(CReal(1e16).to_string(), "1e+16");
+        assert_eq!(CReal(-1.5e300).to_string(), "-1.5e+300");
+        assert_eq!(CReal(0.0001).to_string(), "0.0001");
+        assert_eq!(CReal(1e-7).to_string(), "1e-07");
+        assert_eq!(CReal(2.5e-123).to_string(), "2.5e-123");
+        assert_eq!(CReal(0.0).to_string(), "0.0");
+    }
+
+    #[test]
+    fn eval_variable() {
+        let env = Environment::from([(String::from("x"), CInt(10)), (String::from("y"), CInt(20))]);
+        let v1 = Var(String::from("x"));
+        let v2 = Var(String::from("y"));
+        assert_eq!(eval(v1, &env), Ok(CInt(10)));
+        assert_eq!(eval(v2, &env), Ok(CInt(20)));
+    }
+
+    #[test]
+    fn eval_expression_with_variables() {
+        let env = Environment::from([(String::from("a"), CInt(5)), (String::from("b"), CInt(3))]);
+        let expr = Mul(
+            Box::new(Var(String::from("a"))),
+            Box::new(Add(Box::new(Var(String::from("b"))), Box::new(CInt(2)))),
+        );
+        assert_eq!(eval(expr, &env), Ok(CInt(25)));
+    }
+
+    #[test]
+    fn eval_nested_expressions() {
+        let env = Environment::new();
+        let expr = Add(
+            Box::new(Mul(Box::new(CInt(2)), Box::new(CInt(3)))),
+            Box::new(Sub(Box::new(CInt(10)), Box::new(CInt(4)))),
+        );
+        assert_eq!(eval(expr, &env), Ok(CInt(12)));
+    }
+
+    #[test]
+    fn eval_variable_not_found() {
+        let env = Environment::new();
+        let var_expr = Var(String::from("z"));
+
+        assert_eq!(
+            eval(var_expr, &env),
+            Err(Exception::new("NameError", "Variable z not found"))
+        );
+    }
+
+    #[test]
+    fn execute_assignment() {
+        let env = Environment::new();
+        let assign_stmt = Assignment(Box::from(String::from("x")), Box::new(CInt(42)));
+
+        match execute(assign_stmt, env) {
+            Ok(new_env) => assert_eq!(new_env.get("x"), Some(CInt(42))),
+            Err(s) => panic!("{}", s),
+        }
+    }
+
+    #[test]
+    fn eval_summation() {
+        /*
+         * (a test case for the following program)
+         *
+         * > x = 10
+         * > y = 0
+         * > while x >= 0:
+         * >   y = y + x
+         * >   x = x - 1
+         *
+         * After executing this program, 'x' must be zero and
+         * 'y' must be 55.
+         */
+        let env = Environment::new();
+
+        let a1 = Statement::Assignment(Box::new(String::from("x")), Box::new(CInt(10)));
+        let a2 = Statement::Assignment(Box::new(String::from("y")), Box::new(CInt(0)));
+        let a3 = Statement::Assignment(
+            Box::new(String::from("y")),
+            Box::new(Add(
+                Box::new(Var(String::from("y"))),
+                Box::new(Var(String::from("x"))),
+            )),
+        );
+        let a4 = Statement::Assignment(
+            Box::new(String::from("x")),
+            Box::new(Sub(Box::new(Var(String::from("x"))), Box::new(CInt(1)))),
+        );
+
+        let seq1 = Statement::Sequence(Box::new(a3), Box::new(a4));
+
+        let while_statement = Statement::While(
+            Box::new(GT(Box::new(Var(String::from("x"))), Box::new(CInt(0)))),
+            Box::new(seq1),
+        );
+
+        let seq2 = Statement::Sequence(Box::new(a2), Box::new(while_statement));
+        let program = Statement::Sequence(Box::new(a1), Box::new(seq2));
+
+        match execute(program, env) {
+            Ok(new_env) => {
+                assert_eq!(new_env.get("y"), Some(CInt(55)));
+                assert_eq!(new_env.get("x"), Some(CInt(0)));
+            }
+            Err(s) => panic!("{}", s),
+        }
+    }
+
+    #[test]
+    fn eval_simple_if_then_else() {
+        /*
+         * Test for simple if-then-else statement
+         *
+         * > x = 10
+         * > if x > 5:
+         * >   y = 1
+         * > else:
+         * >   y = 0
+         *
+         * After executing, 'y' should be 1.
+         */
+        let env = Environment::new();
+
+        let condition = GT(Box::new(Var(String::from("x"))), Box::new(CInt(5)));
+        let then_stmt = Statement::Assignment(Box::new(String::from("y")), Box::new(CInt(1)));
+        let else_stmt = Statement::Assignment(Box::new(String::from("y")), Box::new(CInt(0)));
+
+        let if_statement = Statement::IfThenElse(
+            Box::new(condition),
+            Box::new(then_stmt),
+            Box::new(else_stmt),
+        );
+
+        let setup_stmt = Statement::Assignment(Box::new(String::from("x")), Box::new(CInt(10)));
+        let program = Statement::Sequence(Box::new(setup_stmt), Box::new(if_statement));
+
+        match execute(program, env) {
+            Ok(new_env) => assert_eq!(new_env.get("y"), Some(CInt(1))),
+            Err(s) => panic!("{}", s),
+        }
+    }
+
+    #[test]
+    fn eval_equality_over_strings_and_booleans() {
+        let env = Environment::new();
+        let s = |v: &str| Box::new(CString(String::from(v)));
+
+        assert_eq!(eval(EQ(s("a"), s("a")), &env), Ok(CTrue));
+        assert_eq!(eval(EQ(s("a"), s("b")), &env), Ok(CFalse));
+        assert_eq!(eval(NEQ(s("a"), s("b")), &env), Ok(CTrue));
+        assert_eq!(
+            eval(EQ(Box::new(CTrue), Box::new(CFalse)), &env),
+            Ok(CFalse)
+        );
+        assert_eq!(
+            eval(NEQ(Box::new(CTrue), Box::new(CTrue)), &env),
+            Ok(CFalse)
+        );
+        assert_eq!(eval(EQ(s("1"), Box::new(CInt(1))), &env), Ok(CFalse));
+        assert_eq!(
+            eval(EQ(Box::new(CInt(1)), Box::new(CReal(1.0))), &env),
+            Ok(CTrue)
+        );
+    }
+
+    #[test]
+    fn eval_structural_equality() {
+        let env = Environment::from([(String::from("x"), CInt(2))]);
+        let l1 = ListValue(vec![CInt(1), Var(String::from("x"))]);
+        let l2 = ListValue(vec![CInt(1), CReal(2.0)]);
+        let t1 = Tuple(vec![CString(String::from("a")), l1.clone()]);
+        let t2 = Tuple(vec![CString(String::from("a")), l2.clone()]);
+        let t3 = Tuple(vec![CString(String::from("a")), ListValue(vec![])]);
+
+        assert_eq!(eval(EQ(Box::new(l1), Box::new(l2)), &env), Ok(CTrue));
+        assert_eq!(
+            eval(EQ(Box::new(t1.clone()), Box::new(t2)), &env),
+            Ok(CTrue)
+        );
+        assert_eq!(eval(NEQ(Box::new(t1), Box::new(t3)), &env), Ok(CTrue));
+    }
+
+    #[test]
+    fn eval_equality_across_types() {
+        let env = Environment::new();
+        let eq = EQ(Box::new(CInt(1)), Box::new(CString(String::from("1"))));
+
+        assert_eq!(eval(eq, &env), Ok(CFalse));
+    }
+
+    #[test]
+    fn eval_lexicographic_ordering() {
+        let env = Environment::new();
+        let s = |v: &str| Box::new(CString(String::from(v)));
+        let l = |v: Vec<i32>| Box::new(ListValue(v.into_iter().map(CInt).collect()));
+
+        assert_eq!(eval(LT(s("apple"), s("banana")), &env), Ok(CTrue));
+        assert_eq!(eval(GT(s("b"), s("abc")), &env), Ok(CTrue));
+        assert_eq!(eval(LTE(s("ab"), s("ab")), &env), Ok(CTrue));
+        assert_eq!(eval(LT(l(vec![1, 2]), l(vec![1, 3])), &env), Ok(CTrue));
+        assert_eq!(eval(LT(l(vec![1, 2]), l(vec![1, 2, 0])), &env), Ok(CTrue));
+        assert_eq!(eval(GTE(l(vec![2]), l(vec![1, 9])), &env), Ok(CTrue));
+    }
+
+    #[test]
+    fn eval_ordering_of_booleans() {
+        let env = Environment::new();
+        let gt1 = GT(Box::new(CTrue), Box::new(CFalse));
+        let lt1 = LT(Box::new(CTrue), Box::new(CInt(2)));
+        assert_eq!(eval(gt1, &env), Ok(CTrue));
+        assert_eq!(
+            eval(lt1, &env),
+            Err(Exception::new(
+                "TypeError",
+                "(<) is only defined for numbers, booleans, strings, lists and tuples."
+            ))
+        );
+    }
+
+    #[test]
+    fn display_compound_values() {
+        let list = ListValue(vec![CInt(1), CString(String::from("it's")), CReal(2.0)]);
+        assert_eq!(list.to_string(), "[1, 'it\\'s', 2.0]");
+        assert_eq!(Tuple(vec![CTrue]).to_string(), "(True,)");
+        assert_eq!(Tuple(vec![CInt(1), CInt(2)]).to_string(), "(1, 2)");
+    }
+
+    #[test]
+    fn eval_and_skips_right_operand() {
+        /*
+         * > x = 0
+         * > x != 0 and 10 / x > 1
+         *
+         * The division is never evaluated, so no error is raised.
+         */
+        let env = Environment::from([(String::from("x"), CInt(0))]);
+        let x = || Box::new(Var(String::from("x")));
+        let guard = NEQ(x(), Box::new(CInt(0)));
+        let division = GT(Box::new(Div(Box::new(CInt(10)), x())), Box::new(CInt(1)));
+
+        assert_eq!(
+            eval(division.clone(), &env),
+            Err(Exception::new("ZeroDivisionError", "division by zero."))
+        );
+        assert_eq!(
+            eval(And(Box::new(guard), Box::new(division)), &env),
+            Ok(CFalse)
+        );
+    }
+
+    #[test]
+    fn eval_or_skips_right_operand() {
+        let env = Environment::new();
+        let undefined = || Box::new(Var(String::from("undefined")));
+
+        assert_eq!(eval(Or(Box::new(CTrue), undefined()), &env), Ok(CTrue));
+        assert_eq!(
+            eval(Or(Box::new(CFalse), undefined()), &env),
+            Err(Exception::new("NameError", "Variable undefined not found"))
+        );
+        assert_eq!(
+            eval(And(Box::new(CTrue), undefined()), &env),
+            Err(Exception::new("NameError", "Variable undefined not found"))
+        );
+    }
+
+    #[test]
+    fn eval_boolean_operands_must_be_booleans() {
+        let env = Environment::new();
+
+        assert_eq!(eval(And(Box::new(CTrue), Box::new(CTrue)), &env), Ok(CTrue));
+        assert_eq!(
+            eval(Or(Box::new(CFalse), Box::new(CFalse)), &env),
+            Ok(CFalse)
+        );
+        assert_eq!(
+            eval(And(Box::new(CTrue), Box::new(CInt(1))), &env),
+            Err(Exception::new(
+                "TypeError",
+                "'and' is only defined for booleans."
+            ))
+        );
+        assert_eq!(
+            eval(Or(Box::new(CInt(1)), Box::new(CTrue)), &env),
+            Err(Exception::new(
+                "TypeError",
+                "'or' is only defined for booleans."
+            ))
+        );
+    }
+
+    #[test]
+    fn eval_chained_comparison() {
+        /*
+         * > 0 <= i < n
+         */
+        let env = Environment::from([(String::from("i"), CInt(3)), (String::from("n"), CInt(3))]);
+        let chain = |i: Expression| {
+            Comparison(
+                Box::new(CInt(0)),
+                vec![
+                    (RelationalOp::LTE, i),
+                    (RelationalOp::LT, Var(String::from("n"))),
+                ],
+            )
+        };
+
+        assert_eq!(eval(chain(CInt(0)), &env), Ok(CTrue));
+        assert_eq!(eval(chain(CInt(2)), &env), Ok(CTrue));
+        assert_eq!(eval(chain(Var(String::from("i"))), &env), Ok(CFalse));
+        assert_eq!(eval(chain(CInt(-1)), &env), Ok(CFalse));
+    }
+
+    #[test]
+    fn eval_chained_comparison_stops_at_first_false_link() {
+        /*
+         * > 1 > 2 < undefined
+         *
+         * The first link is false, so 'undefined' is never evaluated.
+         */
+        let env = Environment::new();
+        let chain = Comparison(
+            Box::new(CInt(1)),
+            vec![
+                (RelationalOp::GT, CInt(2)),
+                (RelationalOp::LT, Var(String::from("undefined"))),
+            ],
+        );
+        assert_eq!(eval(chain, &env), Ok(CFalse));
+    }
+
+    #[test]
+    fn eval_chained_equalities() {
+        let env = Environment::new();
+        let s = |v: &str| CString(String::from(v));
+        let chain = Comparison(
+            Box::new(s("a")),
+            vec![(RelationalOp::EQ, s("a")), (RelationalOp::NEQ, s("b"))],
+        );
+        assert_eq!(eval(chain, &env), Ok(CTrue));
+    }
+
+    #[test]
+    fn eval_string_concatenation_and_repetition() {
+        let env = Environment::new();
+        let s = |v: &str| Box::new(CString(String::from(v)));
+
+        assert_eq!(
+            eval(Add(s("ab"), s("cd")), &env),
+            Ok(CString(String::from("abcd")))
+        );
+        assert_eq!(
+            eval(Mul(s("ab"), Box::new(CInt(3))), &env),
+            Ok(CString(String::from("ababab")))
+        );
+        assert_eq!(
+            eval(Mul(Box::new(CInt(-1)), s("ab")), &env),
+            Ok(CString(String::new()))
+        );
+    }
+
+    #[test]
+    fn eval_string_indexing_is_unicode_aware() {
+        let env = Environment::from([(String::from("s"), CString(String::from("ação")))]);
+        let s = || Box::new(Var(String::from("s")));
+        let c = |v: &str| Ok(CString(String::from(v)));
+
+        assert_eq!(eval(Index(s(), Box::new(CInt(1))), &env), c("ç"));
+        assert_eq!(eval(Index(s(), Box::new(CInt(-1))), &env), c("o"));
+        assert_eq!(
+            eval(Index(s(), Box::new(CInt(4))), &env),
+            Err(Exception::new("IndexError", "string index out of range."))
+        );
+        assert_eq!(eval(Len(s()), &env), Ok(CInt(4)));
+    }
+
+    #[test]
+    fn eval_slicing() {
+        let env = Environment::from([(String::from("s"), CString(String::from("héllo")))]);
+        let s = || Box::new(Var(String::from("s")));
+        let i = |v: i32| Some(Box::new(CInt(v)));
+        let c = |v: &str| Ok(CString(String::from(v)));
+
+        assert_eq!(eval(Slice(s(), i(1), i(3), None), &env), c("él"));
+        assert_eq!(eval(Slice(s(), None, i(-2), None), &env), c("hél"));
+        assert_eq!(eval(Slice(s(), None, None, i(-1)), &env), c("olléh"));
+        assert_eq!(eval(Slice(s(), i(10), None, None), &env), c(""));
+        assert_eq!(eval(Slice(s(), None, None, i(2)), &env), c("hlo"));
+        assert_eq!(
+            eval(Slice(s(), None, None, i(0)), &env),
+            Err(Exception::new("ValueError", "slice step cannot be zero."))
+        );
+
+        let list = ListValue(vec![CInt(1), CInt(2), CInt(3)]);
+        assert_eq!(
+            eval(Slice(Box::new(list), i(-2), None, None), &env),
+            Ok(ListValue(vec![CInt(2), CInt(3)]))
+        );
+    }
+
+    #[test]
+    fn eval_in_expression() {
+        let env = Environment::new();
+        let s = |v: &str| Box::new(CString(String::from(v)));
+        let list = Box::new(ListValue(vec![CInt(1), CReal(2.5)]));
+
+        assert_eq!(eval(In(s("ell"), s("hello")), &env), Ok(CTrue));
+        assert_eq!(eval(In(s("xyz"), s("hello")), &env), Ok(CFalse));
+        assert_eq!(eval(In(Box::new(CReal(1.0)), list), &env), Ok(CTrue));
+        assert_eq!(
+            eval(In(Box::new(CInt(1)), s("1")), &env),
+            Err(Exception::new(
+                "TypeError",
+                "'in <string>' requires a string as left operand."
+            ))
+        );
+    }
+
+    #[test]
+    fn eval_string_methods() {
+        let env = Environment::new();
+        let s = |v: &str| CString(String::from(v));
+        let call = |receiver: &str, m: &str, args: Vec<Expression>| {
+            eval(
+                MethodCall(Box::new(s(receiver)), String::from(m), args),
+                &env,
+            )
+        };
+
+        assert_eq!(call("Straße", "upper", vec![]), Ok(s("STRASSE")));
+        assert_eq!(call("ÀB", "lower", vec![]), Ok(s("àb")));
+        assert_eq!(call("  hi \n", "strip", vec![]), Ok(s("hi")));
+        assert_eq!(call("xxhixx", "strip", vec![s("x")]), Ok(s("hi")));
+        assert_eq!(
+            call(" a  b ", "split", vec![]),
+            Ok(ListValue(vec![s("a"), s("b")]))
+        );
+        assert_eq!(
+            call("a,,b", "split", vec![s(",")]),
+            Ok(ListValue(vec![s("a"), s(""), s("b")]))
+        );
+        assert_eq!(
+            call("-", "join", vec![ListValue(vec![s("a"), s("b")])]),
+            Ok(s("a-b"))
+        );
+        assert_eq!(call("aXbX", "replace", vec![s("X"), s("_")]), Ok(s("a_b_")));
+        assert_eq!(call("prefix", "startswith", vec![s("pre")]), Ok(CTrue));
+        assert_eq!(call("prefix", "endswith", vec![s("pre")]), Ok(CFalse));
+        assert_eq!(call("éaé", "find", vec![s("a")]), Ok(CInt(1)));
+        assert_eq!(call("abc", "find", vec![s("z")]), Ok(CInt(-1)));
+        assert_eq!(
+            call("abc", "upper", vec![s("z")]),
+            Err(Exception::new(
+                "TypeError",
+                "wrong number of arguments to string method 'upper'."
+            ))
+        );
+        assert_eq!(
+            call("abc", "shout", vec![]),
+            Err(Exception::new(
+                "AttributeError",
+                "'shout' is not a string method."
+            ))
+        );
+    }
+
+    #[test]
+    fn eval_fstring() {
+        /*
+         * > x = 9.5
+         * > f"total: {x + 1:>8.2f} ({name})"
+         */
+        let env = Environment::from([
+            (String::from("x"), CReal(9.5)),
+            (String::from("name"), CString(String::from("ok"))),
+        ]);
+        let fstring = FString(vec![
+            FStringPart::Literal(String::from("total: ")),
+            FStringPart::Field(
+                Box::new(Add(Box::new(Var(String::from("x"))), Box::new(CInt(1)))),
+                Some((String::from(">8.2f"), 15)),
+            ),
+            FStringPart::Literal(String::from(" (")),
+            FStringPart::Field(Box::new(Var(String::from("name"))), None),
+            FStringPart::Literal(String::from(")")),
+        ]);
+
+        assert_eq!(
+            eval(fstring, &env),
+            Ok(CString(String::from("total:    10.50 (ok)")))
+        );
+    }
+
+    #[test]
+    fn eval_fstring_with_malformed_spec() {
+        let env = Environment::new();
+        let fstring = FString(vec![
+            FStringPart::Literal(String::from("n=")),
+            FStringPart::Field(Box::new(CInt(1)), Some((String::from("5z"), 7))),
+        ]);
+
+        assert_eq!(
+            eval(fstring, &env),
+            Err(Exception::new(
+                "ValueError",
+                "[Format Error] invalid format spec at position 8: unknown format type 'z'."
+            ))
+        );
+    }
+
+    fn assign(name: &str, exp: Expression) -> Statement {
+        Assignment(Box::new(String::from(name)), Box::new(exp))
+    }
+
+    fn seq(s1: Statement, s2: Statement) -> Statement {
+        Sequence(Box::new(s1), Box::new(s2))
+    }
+
+    fn handler(kind: Option<&str>, name: Option<&str>, body: Statement) -> ExceptHandler {
+        ExceptHandler {
+            kind: kind.map(String::from),
+            name: name.map(String::from),
+            body: Box::new(body),
+        }
+    }
+
+    fn raise(kind: &str, message: &str) -> Statement {
+        Raise(Box::new(Expression::Exception(
+            String::from(kind),
+            Box::new(CString(String::from(message))),
+        )))
+    }
+
+    #[test]
+    fn execute_raise_and_catch() {
+        /*
+         * > try:
+         * >   x = 1
+         * >   raise ValueError("bad")
+         * >   x = 2
+         * > except TypeError:
+         * >   y = 0
+         * > except ValueError as e:
+         * >   y = e
+         *
+         * 'x' keeps the value assigned before the exception.
+         */
+        let program = Try(
+            Box::new(seq(
+                assign("x", CInt(1)),
+                seq(raise("ValueError", "bad"), assign("x", CInt(2))),
+            )),
+            vec![
+                handler(Some("TypeError"), None, assign("y", CInt(0))),
+                handler(
+                    Some("ValueError"),
+                    Some("e"),
+                    assign("y", Var(String::from("e"))),
+                ),
+            ],
+            None,
+        );
+
+        match execute(program, Environment::new()) {
+            Ok(new_env) => {
+                assert_eq!(new_env.get("x"), Some(CInt(1)));
+                assert_eq!(
+                    new_env.get("y"),
+                    Some(Expression::Exception(
+                        String::from("ValueError"),
+                        Box::new(CString(String::from("bad")))
+                    ))
+                );
+            }
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    #[test]
+    fn execute_builtin_errors_are_catchable() {
+        let catch = |body: Statement, kind: &str| {
+            let program = Try(
+                Box::new(body),
+                vec![handler(Some(kind), Some("e"), assign("caught", CTrue))],
+                None,
+            );
+            execute(program, Environment::new()).map(|env| env.get("caught"))
+        };
+        let div = assign("z", Div(Box::new(CInt(1)), Box::new(CInt(0))));
+        let name = assign("z", Var(String::from("unbound")));
+        let index = assign("z", Index(Box::new(ListValue(vec![])), Box::new(CInt(0))));
+
+        assert_eq!(catch(div.clone(), "ZeroDivisionError"), Ok(Some(CTrue)));
+        assert_eq!(catch(div.clone(), "ArithmeticError"), Ok(Some(CTrue)));
+        assert_eq!(catch(name, "NameError"), Ok(Some(CTrue)));
+        assert_eq!(catch(index.clone(), "IndexError"), Ok(Some(CTrue)));
+        assert_eq!(catch(index, "Exception"), Ok(Some(CTrue)));
+        assert_eq!(
+            catch(div, "IndexError"),
+            Err(Exception::new("ZeroDivisionError", "division by zero."))
+        );
+    }
+
+    #[test]
+    fn execute_finally_always_runs() {
+        /*
+         * > try:
+         * >   try:
+         * >     raise KeyError("k")
+         * >   except ValueError:
+         * >     handled = True
+         * >   finally:
+         * >     cleaned = True
+         * > except:
+         * >   outer = True
+         * > finally:
+         * >   done = True
+         */
+        let inner = Try(
+            Box::new(raise("KeyError", "k")),
+            vec![handler(Some("ValueError"), None, assign("handled", CTrue))],
+            Some(Box::new(assign("cleaned", CTrue))),
+        );
+        let program = Try(
+            Box::new(inner),
+            vec![handler(None, None, assign("outer", CTrue))],
+            Some(Box::new(assign("done", CTrue))),
+        );
+
+        match execute(program, Environment::new()) {
+            Ok(new_env) => {
+                assert_eq!(new_env.get("handled"), None);
+                assert_eq!(new_env.get("cleaned"), Some(CTrue));
+                assert_eq!(new_env.get("outer"), Some(CTrue));
+                assert_eq!(new_env.get("done"), Some(CTrue));
+            }
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    #[test]
+    fn execute_exception_raised_by_handler_propagates() {
+        let program = Try(
+            Box::new(raise("ValueError", "first")),
+            vec![handler(
+                Some("ValueError"),
+                None,
+                raise("RuntimeError", "second"),
+            )],
+            Some(Box::new(assign("done", CTrue))),
+        );
+
+        assert_eq!(
+            execute(program, Environment::new()),
+            Err(Exception::new("RuntimeError", "second"))
+        );
+    }
+
+    #[test]
+    fn execute_raise_requires_an_exception() {
+        let program = Raise(Box::new(CInt(1)));
+
+        assert_eq!(
+            execute(program, Environment::new()),
+            Err(Exception::new(
+                "TypeError",
+                "exceptions must be raised from exception values."
+            ))
+        );
+    }
+
+    #[test]
+    fn execute_assert_reports_operand_values() {
+        /*
+         * > x = 3
+         * > assert x + 1 == 5, "totals match"
+         */
+        let message = || Box::new(CString(String::from("totals match")));
+        let x = || Box::new(Var(String::from("x")));
+        let env = Environment::from([(String::from("x"), CInt(3))]);
+        let failing = Assert(
+            Box::new(EQ(Box::new(Add(x(), Box::new(CInt(1)))), Box::new(CInt(5)))),
+            message(),
+        );
+        let passing = Assert(Box::new(GT(x(), Box::new(CInt(2)))), message());
+
+        assert_eq!(
+            execute(passing, env.clone()).map(|env| env.get("x")),
+            Ok(Some(CInt(3)))
+        );
+        assert_eq!(
+            execute(failing, env),
+            Err(Exception::new(
+                "AssertionError",
+                "totals match: 4 == 5 does not hold"
+            ))
+        );
+    }
+
+    #[test]
+    fn execute_assert_on_comparison_chain() {
+        let env = Environment::from([(String::from("s"), CString(String::from("b")))]);
+        let chain = Comparison(
+            Box::new(CString(String::from("a"))),
+            vec![
+                (RelationalOp::LT, Var(String::from("s"))),
+                (RelationalOp::LT, CString(String::from("b"))),
+            ],
+        );
+        let program = Assert(Box::new(chain), Box::new(CString(String::new())));
+
+        assert_eq!(
+            execute(program, env),
+            Err(Exception::new("AssertionError", "'b' < 'b' does not hold"))
+        );
+    }
+
+    #[test]
+    fn execute_assert_on_plain_condition() {
+        let env = Environment::from([(String::from("ok"), CFalse)]);
+        let failing = Assert(
+            Box::new(Var(String::from("ok"))),
+            Box::new(CString(String::from("not ok"))),
+        );
+        let not_boolean = Assert(Box::new(CInt(1)), Box::new(CString(String::new())));
+
+        assert_eq!(
+            execute(failing, env.clone()),
+            Err(Exception::new("AssertionError", "not ok"))
+        );
+        assert_eq!(
+            execute(not_boolean, env),
+            Err(Exception::new(
+                "TypeError",
+                "'assert' expects a boolean condition."
+            ))
+        );
+    }
+
+    fn point_declaration() -> Statement {
+        RecordDeclaration(
+            String::from("Point"),
+            vec![
+                (String::from("x"), Type::TInteger),
+                (String::from("y"), Type::TInteger),
+            ],
+        )
+    }
+
+    fn point(x: Expression, y: Expression) -> Expression {
+        Record(
+            String::from("Point"),
+            vec![(String::from("y"), y), (String::from("x"), x)],
+        )
+    }
+
+    #[test]
+    fn execute_record_construction_and_access() {
+        /*
+         * > record Point: x: int, y: int
+         * > p = Point(y=2, x=1)
+         * > s = p.x + p.y
+         */
+        let p = || Box::new(Var(String::from("p")));
+        let program = seq(
+            point_declaration(),
+            seq(
+                assign("p", point(CInt(1), CInt(2))),
+                assign(
+                    "s",
+                    Add(
+                        Box::new(FieldAccess(p(), String::from("x"))),
+                        Box::new(FieldAccess(p(), String::from("y"))),
+                    ),
+                ),
+            ),
+        );
+
+        match execute(program, Environment::new()) {
+            Ok(new_env) => {
+                let expected = Record(
+                    String::from("Point"),
+                    vec![(String::from("x"), CInt(1)), (String::from("y"), CInt(2))],
+                );
+                assert_eq!(new_env.get("p"), Some(expected.clone()));
+                assert_eq!(new_env.get("s"), Some(CInt(3)));
+                assert_eq!(expected.to_string(), "Point(x=1, y=2)");
+            }
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    #[test]
+    fn execute_record_construction_errors() {
+        let build = |exp: Expression| {
+            execute(
+                seq(point_declaration(), assign("p", exp)),
+                Environment::new(),
+            )
+        };
+        let missing = Record(String::from("Point"), vec![(String::from("x"), CInt(1))]);
+        let unknown = point(CInt(1), CInt(2));
+        let unknown = match unknown {
+            Record(name, mut fields) => {
+                fields.push((String::from("z"), CInt(3)));
+                Record(name, fields)
+            }
+            _ => unreachable!(),
+        };
+
+        assert_eq!(
+            build(missing),
+            Err(Exception::new(
+                "TypeError",
+                "missing field 'y' of record 'Point'."
+            ))
+        );
+        assert_eq!(
+            build(unknown),
+            Err(Exception::new(
+                "AttributeError",
+                "record 'Point' has no field 'z'."
+            ))
+        );
+        assert_eq!(
+            build(Record(String::from("Line"), vec![])),
+            Err(Exception::new(
+                "NameError",
+                "record type 'Line' is not declared."
+            ))
+        );
+    }
+
+    #[test]
+    fn execute_nested_field_update() {
+        /*
+         * > record Point: x: int, y: int
+         * > record Segment: a: Point, b: Point
+         * > var s
+         * > s = Segment(a=Point(x=0, y=0), b=Point(x=1, y=1))
+         * > q = s
+         * > s.b.y = 5
+         *
+         * 'q' keeps its own copy: records are values.
+         */
+        let segment = RecordDeclaration(
+            String::from("Segment"),
+            vec![
+                (
+                    String::from("a"),
+                    Type::TRecord(String::from("Point"), vec![]),
+                ),
+                (
+                    String::from("b"),
+                    Type::TRecord(String::from("Point"), vec![]),
+                ),
+            ],
+        );
+        let value = Record(
+            String::from("Segment"),
+            vec![
+                (String::from("a"), point(CInt(0), CInt(0))),
+                (String::from("b"), point(CInt(1), CInt(1))),
+            ],
+        );
+        let update = FieldAssignment(
+            Box::new(FieldAccess(
+                Box::new(Var(String::from("s"))),
+                String::from("b"),
+            )),
+            String::from("y"),
+            Box::new(CInt(5)),
+        );
+        let program = seq(
+            seq(point_declaration(), segment),
+            seq(
+                seq(
+                    VarDeclaration(Box::new(String::from("s"))),
+                    assign("s", value),
+                ),
+                seq(assign("q", Var(String::from("s"))), update),
+            ),
+        );
+
+        match execute(program, Environment::new()) {
+            Ok(new_env) => {
+                let s = new_env.get("s").unwrap().to_string();
+                let q = new_env.get("q").unwrap().to_string();
+                assert_eq!(s, "Segment(a=Point(x=0, y=0), b=Point(x=1, y=5))");
+                assert_eq!(q, "Segment(a=Point(x=0, y=0), b=Point(x=1, y=1))");
+            }
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    #[test]
+    fn execute_val_records_are_immutable() {
+        /*
+         * > val p
+         * > p = Point(x=1, y=2)
+         * > p.x = 3
+         */
+        let declare = seq(
+            point_declaration(),
+            ValDeclaration(Box::new(String::from("p"))),
+        );
+        let init = seq(declare, assign("p", point(CInt(1), CInt(2))));
+        let update = FieldAssignment(
+            Box::new(Var(String::from("p"))),
+            String::from("x"),
+            Box::new(CInt(3)),
+        );
+
+        assert_eq!(
+            execute(seq(init.clone(), update), Environment::new()),
+            Err(Exception::new(
+                "TypeError",
+                "cannot modify 'p', declared with 'val'."
+            ))
+        );
+        assert_eq!(
+            execute(seq(init, assign("p", CInt(0))), Environment::new()),
+            Err(Exception::new(
+                "TypeError",
+                "cannot reassign 'p', declared with 'val'."
+            ))
+        );
+    }
+
+    #[test]
+    fn eval_record_equality() {
+        let env = Environment::new();
+        let p1 = point(CInt(1), CReal(2.0));
+        let p2 = Record(
+            String::from("Point"),
+            vec![(String::from("x"), CInt(1)), (String::from("y"), CInt(2))],
+        );
+        let p3 = Record(
+            String::from("Pair"),
+            vec![(String::from("x"), CInt(1)), (String::from("y"), CInt(2))],
+        );
+        let pair = RecordDeclaration(
+            String::from("Pair"),
+            vec![
+                (String::from("x"), Type::TInteger),
+                (String::from("y"), Type::TInteger),
+            ],
+        );
+        let env = execute(seq(point_declaration(), pair), env).unwrap();
+
+        assert_eq!(
+            eval(EQ(Box::new(p1), Box::new(p2.clone())), &env),
+            Ok(CTrue)
+        );
+        assert_eq!(eval(NEQ(Box::new(p2), Box::new(p3)), &env), Ok(CTrue));
+    }
+
+    fn shape_declaration() -> Statement {
+        TypeDeclaration(
+            String::from("Shape"),
+            vec![
+                ValueConstructor {
+                    name: String::from("Circle"),
+                    types: vec![Type::TReal],
+                },
+                ValueConstructor {
+                    name: String::from("Rect"),
+                    types: vec![Type::TReal, Type::TReal],
+                },
+            ],
+        )
+    }
+
+    fn case(pattern: Pattern, guard: Option<Expression>, body: Statement) -> MatchCase {
+        MatchCase {
+            pattern,
+            guard,
+            body: Box::new(body),
+        }
+    }
+
+    fn binding(name: &str) -> Pattern {
+        Pattern::Binding(String::from(name))
+    }
+
+    #[test]
+    fn execute_match_on_constructors() {
+        /*
+         * > data Shape = Circle(real) | Rect(real, real)
+         * > match s:
+         * >   case Circle(r): area = 3.0 * r * r
+         * >   case Rect(w, h): area = w * h
+         */
+        let var = |name: &str| Box::new(Var(String::from(name)));
+        let area = |s: Expression| {
+            seq(
+                seq(shape_declaration(), assign("s", s)),
+                Match(
+                    var("s"),
+                    vec![
+                        case(
+                            Pattern::Constructor(String::from("Circle"), vec![binding("r")]),
+                            None,
+                            assign(
+                                "area",
+                                Mul(Box::new(Mul(Box::new(CReal(3.0)), var("r"))), var("r")),
+                            ),
+                        ),
+                        case(
+                            Pattern::Constructor(
+                                String::from("Rect"),
+                                vec![binding("w"), binding("h")],
+                            ),
+                            None,
+                            assign("area", Mul(var("w"), var("h"))),
+                        ),
+                    ],
+                ),
+            )
+        };
+        let circle = ADTConstructor(String::from("Circle"), vec![CReal(2.0)]);
+        let rect = ADTConstructor(String::from("Rect"), vec![CReal(2.0), CReal(1.5)]);
+
+        match execute(area(circle), Environment::new()) {
+            Ok(new_env) => {
+                assert_eq!(new_env.get("area"), Some(CReal(12.0)));
+                assert_eq!(new_env.get("s").unwrap().to_string(), "Circle(2.0)");
+            }
+            Err(e) => panic!("{}", e),
+        }
+        match execute(area(rect), Environment::new()) {
+            Ok(new_env) => assert_eq!(new_env.get("area"), Some(CReal(3.0))),
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    #[test]
+    fn execute_match_with_literals_tuples_and_guards() {
+        /*
+         * > match (x, y):
+         * >   case (0, _): r = "origin column"
+         * >   case (a, b) if a == b: r = "diagonal"
+         * >   case _: r = "elsewhere"
+         */
+        let classify = |x: i32, y: i32| {
+            let program = Match(
+                Box::new(Tuple(vec![CInt(x), CInt(y)])),
+                vec![
+                    case(
+                        Pattern::Tuple(vec![Pattern::Literal(CInt(0)), Pattern::Wildcard]),
+                        None,
+                        assign("r", CString(String::from("origin column"))),
+                    ),
+                    case(
+                        Pattern::Tuple(vec![binding("a"), binding("b")]),
+                        Some(EQ(
+                            Box::new(Var(String::from("a"))),
+                            Box::new(Var(String::from("b"))),
+                        )),
+                        assign("r", CString(String::from("diagonal"))),
+                    ),
+                    case(
+                        Pattern::Wildcard,
+                        None,
+                        assign("r", CString(String::from("elsewhere"))),
+                    ),
+                ],
+            );
+            execute(program, Environment::new()).map(|env| env.get("r").unwrap().to_string())
+        };
+
+        assert_eq!(classify(0, 5), Ok(String::from("origin column")));
+        assert_eq!(classify(3, 3), Ok(String::from("diagonal")));
+        assert_eq!(classify(3, 4), Ok(String::from("elsewhere")));
+    }
+
+    #[test]
+    fn execute_match_errors() {
+        let program = seq(
+            shape_declaration(),
+            Match(
+                Box::new(ADTConstructor(String::from("Circle"), vec![CReal(1.0)])),
+                vec![case(
+                    Pattern::Constructor(
+                        String::from("Rect"),
+                        vec![Pattern::Wildcard, Pattern::Wildcard],
+                    ),
+                    None,
+                    assign("r", CInt(1)),
+                )],
+            ),
+        );
+        let bad_arity = seq(
+            shape_declaration(),
+            assign("s", ADTConstructor(String::from("Circle"), vec![])),
+        );
+
+        assert_eq!(
+            execute(program, Environment::new()),
+            Err(Exception::new(
+                "MatchError",
+                "no case matches the value Circle(1.0)."
+            ))
+        );
+        assert_eq!(
+            execute(bad_arity, Environment::new()),
+            Err(Exception::new(
+                "TypeError",
+                "constructor 'Circle' expects 1 argument(s), but 0 were given."
+            ))
+        );
+        assert_eq!(
+            execute(
+                assign("s", ADTConstructor(String::from("Square"), vec![])),
+                Environment::new()
+            ),
+            Err(Exception::new(
+                "NameError",
+                "constructor 'Square' is not defined."
+            ))
+        );
+    }
+
+    fn function(name: &str, params: &[&str], body: Statement) -> Statement {
+        FuncDef(Box::new(Function {
+            name: String::from(name),
+            kind: Type::TAny,
+            params: params
+                .iter()
+                .map(|p| (String::from(*p), Type::TAny))
+                .collect(),
+            defaults: vec![],
+            varargs: None,
+            kwargs: None,
+            body: Box::new(body),
+        }))
+    }
+
+    fn call(name: &str, args: Vec<Expression>) -> Expression {
+        FuncCall(String::from(name), args)
+    }
+
+    fn var(name: &str) -> Box<Expression> {
+        Box::new(Var(String::from(name)))
+    }
+
+    #[test]
+    fn execute_recursive_function() {
+        /*
+         * > def fact(n):
+         * >   if n <= 1:
+         * >     return 1
+         * >   else:
+         * >     return n * fact(n - 1)
+         * > x = fact(5)
+         */
+        let body = IfThenElse(
+            Box::new(LTE(var("n"), Box::new(CInt(1)))),
+            Box::new(Return(Box::new(CInt(1)))),
+            Box::new(Return(Box::new(Mul(
+                var("n"),
+                Box::new(call("fact", vec![Sub(var("n"), Box::new(CInt(1)))])),
+            )))),
+        );
+        let program = seq(
+            function("fact", &["n"], body),
+            assign("x", call("fact", vec![CInt(5)])),
+        );
+
+        match execute(program, Environment::new()) {
+            Ok(new_env) => {
+                assert_eq!(new_env.get("x"), Some(CInt(120)));
+                assert_eq!(new_env.get("n"), None);
+            }
+            Err(e) => panic!("{}", e),
+        }
+        assert_eq!(
+            execute(Return(Box::new(CInt(1))), Environment::new()),
+            Err(Exception::new("SyntaxError", "'return' outside function."))
+        );
+    }
+
+    #[test]
+    fn execute_leaves_the_given_environment_unchanged() {
+        /*
+         * > x = 1
+         * > def f():
+         * >   return x
+         */
+        let module = seq(assign("x", CInt(1)), function("f", &[], Return(var("x"))));
+        let env = execute(module, Environment::new()).unwrap();
+
+        /* > x = 2; raise ValueError("") */
+        let failing = seq(
+            assign("x", CInt(2)),
+            Raise(Box::new(Exception(
+                String::from("ValueError"),
+                Box::new(CString(String::new())),
+            ))),
+        );
+        assert!(execute(failing, env.clone()).is_err());
+        assert_eq!(env.get("x"), Some(CInt(1)));
+
+        /* > x = 3; y = f() */
+        let updating = seq(assign("x", CInt(3)), assign("y", call("f", vec![])));
+        let updated = execute(updating, env.clone()).unwrap();
+        assert_eq!(updated.get("y"), Some(CInt(3)));
+        let calling = assign("y", call("f", vec![]));
+        assert_eq!(
+            execute(calling, env).map(|env| env.get("y")),
+            Ok(Some(CInt(1)))
+        );
+    }
+
+    #[test]
+    fn execute_functions_do_not_keep_their_module_alive() {
+        let env = execute(
+            function("f", &[], Return(Box::new(CInt(1)))),
+            Environment::new(),
+        )
+        .unwrap();
+        let globals = env.globals.downgrade();
+        drop(env);
+        assert_eq!(globals.upgrade(), None);
+    }
+
+    #[test]
+    fn execute_long_programs() {
+        /*
+         * > x = 0
+         * > x = x + 1    # 5000 times
+         */
+        let incr = || assign("x", add(*var("x"), CInt(1)));
+        let program = (0..5000).fold(incr(), |rest, _| seq(incr(), rest));
+        let program = seq(assign("x", CInt(0)), program);
+
+        let env = execute(program, Environment::new()).unwrap();
+        assert_eq!(env.get("x"), Some(CInt(5001)));
+    }
+
+    #[test]
+    fn execute_unbounded_recursion() {
+        /*
+         * > def f(n):
+         * >   return f(n + 1)
+         * > x = f(0)
+         */
+        let body = Return(Box::new(call("f", vec![add(*var("n"), CInt(1))])));
+        let program = seq(
+            function("f", &["n"], body),
+            assign("x", call("f", vec![CInt(0)])),
+        );
+        let env = Environment::with_options(Options {
+            recursion_limit: 20,
+            ..Options::default()
+        });
+
+        assert_eq!(
+            execute(program, env),
+            Err(Exception::new(
+                "RecursionError",
+                "maximum recursion depth exceeded."
+            ))
+        );
+    }
+
+    #[test]
+    fn execute_unbounded_recursion_of_generators() {
+        /*
+         * > def g(n):
+         * >   for x in g(n - 1):
+         * >     yield x
+         * > x = sum(g(300))
+         */
+        let body = For(
+            String::from("x"),
+            Box::new(call("g", vec![Sub(var("n"), Box::new(CInt(1)))])),
+            Box::new(Yield(var("x"))),
+        );
+        let program = seq(
+            function("g", &["n"], body),
+            assign("x", call("sum", vec![call("g", vec![CInt(300)])])),
+        );
+
+        assert_eq!(
+            execute(program, Environment::new()),
+            Err(Exception::new(
+                "RecursionError",
+                "maximum recursion depth exceeded."
+            ))
+        );
+    }
+
+    #[test]
+    fn eval_maybe_and_result_helpers() {
+        let env = Environment::from([
+            (String::from("j"), CJust(Box::new(CInt(1)))),
+            (String::from("n"), CNothing),
+            (
+                String::from("e"),
+                CErr(Box::new(CString(String::from("boom")))),
+            ),
+        ]);
+
+        assert_eq!(eval(Unwrap(var("j")), &env), Ok(CInt(1)));
+        assert_eq!(
+            eval(UnwrapOr(var("n"), Box::new(CInt(0))), &env),
+            Ok(CInt(0))
+        );
+        assert_eq!(
+            eval(UnwrapOr(var("j"), var("undefined")), &env),
+            Ok(CInt(1))
+        );
+        assert_eq!(eval(IsError(var("e")), &env), Ok(CTrue));
+        assert_eq!(eval(IsNothing(var("j")), &env), Ok(CFalse));
+        assert_eq!(
+            eval(Unwrap(var("e")), &env),
+            Err(Exception::new(
+                "ValueError",
+                "called 'unwrap' on Err('boom')."
+            ))
+        );
+        assert_eq!(
+            eval(IsError(var("j")), &env),
+            Err(Exception::new(
+                "TypeError",
+                "'is_error' expects a Result value."
+            ))
+        );
+    }
+
+    #[test]
+    fn execute_error_propagation() {
+        /*
+         * > def divide(a, b):
+         * >   if b == 0:
+         * >     return Err("division by zero")
+         * >   else:
+         * >     return Ok(a / b)
+         * > def mean_ratio(a, b, c):
+         * >   try:
+         * >     x = divide(a, b)?
+         * >     y = divide(a, c)?
+         * >   except Exception:
+         * >     x = 0
+         * >   return Ok(x + y)
+         */
+        let divide = function(
+            "divide",
+            &["a", "b"],
+            IfThenElse(
+                Box::new(EQ(var("b"), Box::new(CInt(0)))),
+                Box::new(Return(Box::new(CErr(Box::new(CString(String::from(
+                    "division by zero",
+                ))))))),
+                Box::new(Return(Box::new(COk(Box::new(Div(var("a"), var("b"))))))),
+            ),
+        );
+        let body = seq(
+            Try(
+                Box::new(seq(
+                    assign(
+                        "x",
+                        Propagate(Box::new(call("divide", vec![*var("a"), *var("b")]))),
+                    ),
+                    assign(
+                        "y",
+                        Propagate(Box::new(call("divide", vec![*var("a"), *var("c")]))),
+                    ),
+                )),
+                vec![handler(Some("Exception"), None, assign("x", CInt(0)))],
+                None,
+            ),
+            Return(Box::new(COk(Box::new(Add(var("x"), var("y")))))),
+        );
+        let program = |b: i32, c: i32| {
+            seq(
+                seq(
+                    divide.clone(),
+                    function("mean_ratio", &["a", "b", "c"], body.clone()),
+                ),
+                assign("r", call("mean_ratio", vec![CInt(12), CInt(b), CInt(c)])),
+            )
+        };
+
+        match execute(program(3, 4), Environment::new()) {
+            Ok(new_env) => assert_eq!(new_env.get("r"), Some(COk(Box::new(CInt(7))))),
+            Err(e) => panic!("{}", e),
+        }
+        match execute(program(3, 0), Environment::new()) {
+            Ok(new_env) => assert_eq!(
+                new_env.get("r").unwrap().to_string(),
+                "Err('division by zero')"
+            ),
+            Err(e) => panic!("{}", e),
+        }
+        assert_eq!(
+            execute(
+                assign("x", Propagate(Box::new(CNothing))),
+                Environment::new()
+            ),
+            Err(Exception::new(
+                "SyntaxError",
+                "'?' used outside a function."
+            ))
+        );
+    }
+
+    fn method(name: &str, params: &[&str], body: Statement) -> Function {
+        let mut all = vec![(String::from("self"), Type::TAny)];
+        all.extend(params.iter().map(|p| (String::from(*p), Type::TAny)));
+        Function {
+            name: String::from(name),
+            kind: Type::TAny,
+            params: all,
+            defaults: vec![],
+            varargs: None,
+            kwargs: None,
+            body: Box::new(body),
+        }
+    }
+
+    fn class(name: &str, base: Option<&str>, methods: Vec<Function>) -> Statement {
+        ClassDef(Class {
+            name: String::from(name),
+            base: base.map(String::from),
+            fields: vec![],
+            methods,
+        })
+    }
+
+    fn self_field(field: &str) -> Expression {
+        FieldAccess(var("self"), String::from(field))
+    }
+
+    fn set_field(field: &str, value: Expression) -> Statement {
+        FieldAssignment(var("self"), String::from(field), Box::new(value))
+    }
+
+    /*
+     * > class Animal:
+     * >   def __init__(self, name): self.name = name
+     * >   def speak(self): return "..."
+     * >   def describe(self): return self.name + " says " + self.speak()
+     * > class Dog(Animal):
+     * >   def __init__(self, name):
+     * >     super().__init__(name + " the dog")
+     * >     self.tricks = 0
+     * >   def speak(self): return "woof"
+     */
+    fn animals() -> Statement {
+        let animal = class(
+            "Animal",
+            None,
+            vec![
+                method("__init__", &["name"], set_field("name", *var("name"))),
+                method("speak", &[], Return(Box::new(CString(String::from("..."))))),
+                method(
+                    "describe",
+                    &[],
+                    Return(Box::new(Add(
+                        Box::new(Add(
+                            Box::new(self_field("name")),
+                            Box::new(CString(String::from(" says "))),
+                        )),
+                        Box::new(MethodCall(var("self"), String::from("speak"), vec![])),
+                    ))),
+                ),
+            ],
+        );
+        let dog = class(
+            "Dog",
+            Some("Animal"),
+            vec![
+                method(
+                    "__init__",
+                    &["name"],
+                    seq(
+                        assign(
+                            "_",
+                            MethodCall(
+                                Box::new(Super),
+                                String::from("__init__"),
+                                vec![Add(
+                                    var("name"),
+                                    Box::new(CString(String::from(" the dog"))),
+                                )],
+                            ),
+                        ),
+                        set_field("tricks", CInt(0)),
+                    ),
+                ),
+                method(
+                    "speak",
+                    &[],
+                    Return(Box::new(CString(String::from("woof")))),
+                ),
+            ],
+        );
+        seq(animal, dog)
+    }
+
+    #[test]
+    fn execute_inheritance_and_overriding() {
+        let describe =
+            |exp: Expression| MethodCall(Box::new(exp), String::from("describe"), vec![]);
+        let program = seq(
+            animals(),
+            seq(
+                assign(
+                    "a",
+                    describe(call("Animal", vec![CString(String::from("Tom"))])),
+                ),
+                seq(
+                    assign("rex", call("Dog", vec![CString(String::from("Rex"))])),
+                    assign("d", describe(*var("rex"))),
+                ),
+            ),
+        );
+
+        match execute(program, Environment::new()) {
+            Ok(new_env) => {
+                assert_eq!(
+                    new_env.get("a"),
+                    Some(CString(String::from("Tom says ...")))
+                );
+                assert_eq!(
+                    new_env.get("d"),
+                    Some(CString(String::from("Rex the dog says woof")))
+                );
+                let rex = *var("rex");
+                assert_eq!(
+                    eval(
+                        FieldAccess(Box::new(rex.clone()), String::from("tricks")),
+                        &new_env
+                    ),
+                    Ok(CInt(0))
+                );
+                assert_eq!(
+                    eval(
+                        IsInstance(Box::new(rex.clone()), String::from("Animal")),
+                        &new_env
+                    ),
+                    Ok(CTrue)
+                );
+                assert_eq!(
+                    eval(IsInstance(Box::new(CInt(1)), String::from("Dog")), &new_env),
+                    Ok(CFalse)
+                );
+                assert_eq!(eval(rex, &new_env).unwrap().to_string(), "<Dog object>");
+            }
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    #[test]
+    fn execute_objects_are_shared() {
+        /*
+         * > class Counter:
+         * >   def __init__(self): self.count = 0
+         * >   def increment(self): self.count = self.count + 1
+         * > a = Counter()
+         * > b = a
+         * > b.increment()
+         * > c = a.count
+         */
+        let counter = class(
+            "Counter",
+            None,
+            vec![
+                method("__init__", &[], set_field("count", CInt(0))),
+                method(
+                    "increment",
+                    &[],
+                    set_field(
+                        "count",
+                        Add(Box::new(self_field("count")), Box::new(CInt(1))),
+                    ),
+                ),
+            ],
+        );
+        let program = seq(
+            seq(counter, assign("a", call("Counter", vec![]))),
+            seq(
+                seq(
+                    assign("b", *var("a")),
+                    assign("_", MethodCall(var("b"), String::from("increment"), vec![])),
+                ),
+                seq(
+                    assign("c", FieldAccess(var("a"), String::from("count"))),
+                    assign("same", EQ(var("a"), var("b"))),
+                ),
+            ),
+        );
+
+        match execute(program, Environment::new()) {
+            Ok(new_env) => {
+                assert_eq!(new_env.get("c"), Some(CInt(1)));
+                assert_eq!(new_env.get("same"), Some(CTrue));
+                assert_eq!(
+                    eval(EQ(Box::new(call("Counter", vec![])), var("a")), &new_env),
+                    Ok(CFalse)
+                );
+            }
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    #[test]
+    fn execute_classes_defined_in_functions_outlive_the_call() {
+        /*
+         * > def f():
+         * >   class C:
+         * >     def m(self): return 1
+         * >   return C()
+         * > x = f().m()
+         * > y = f().m()
+         */
+        let c = class("C", None, vec![method("m", &[], Return(Box::new(CInt(1))))]);
+        let f = function("f", &[], seq(c, Return(Box::new(call("C", vec![])))));
+        let m = || MethodCall(Box::new(call("f", vec![])), String::from("m"), vec![]);
+        let program = seq(f, seq(assign("x", m()), assign("y", m())));
+
+        let env = execute(program, Environment::new()).unwrap();
+        assert_eq!(env.get("x"), Some(CInt(1)));
+        assert_eq!(env.get("y"), Some(CInt(1)));
+    }
+
+    #[test]
+    fn execute_class_errors() {
+        let env = execute(animals(), Environment::new()).unwrap();
+        let tom = || call("Animal", vec![CString(String::from("Tom"))]);
+
+        assert_eq!(
+            eval(FieldAccess(Box::new(tom()), String::from("age")), &env),
+            Err(Exception::new(
+                "AttributeError",
+                "'Animal' object has no attribute 'age'."
+            ))
+        );
+        assert_eq!(
+            eval(
+                MethodCall(Box::new(tom()), String::from("speak"), vec![CInt(1)]),
+                &env
+            ),
+            Err(Exception::new(
+                "TypeError",
+                "speak() takes 1 argument(s) but 2 were given."
+            ))
+        );
+        assert_eq!(
+            eval(
+                MethodCall(Box::new(Super), String::from("speak"), vec![]),
+                &env
+            ),
+            Err(Exception::new(
+                "RuntimeError",
+                "'super()' used outside a method."
+            ))
+        );
+        assert_eq!(
+            execute(class("Cat", Some("Pet"), vec![]), env),
+            Err(Exception::new("NameError", "class 'Pet' is not defined."))
+        );
+    }
+
+    /*
+     * > class Pair:
+     * >   def __init__(self, a, b):
+     * >     self.a = a
+     * >     self.b = b
+     * >   def __add__(self, other): return Pair(self.a + other.a, self.b + other.b)
+     * >   def __eq__(self, other): return self.a == other.a and self.b == other.b
+     * >   def __lt__(self, other): return self.a + self.b < other.a + other.b
+     * >   def __getitem__(self, i): return self.a if i == 0 else self.b
+     * >   def __len__(self): return 2
+     */
+    fn pair_class() -> Statement {
+        let other = |field: &str| FieldAccess(var("other"), String::from(field));
+        let sum = |a: Expression, b: Expression| Add(Box::new(a), Box::new(b));
+        class(
+            "Pair",
+            None,
+            vec![
+                method(
+                    "__init__",
+                    &["a", "b"],
+                    seq(set_field("a", *var("a")), set_field("b", *var("b"))),
+                ),
+                method(
+                    "__add__",
+                    &["other"],
+                    Return(Box::new(call(
+                        "Pair",
+                        vec![
+                            sum(self_field("a"), other("a")),
+                            sum(self_field("b"), other("b")),
+                        ],
+                    ))),
+                ),
+                method(
+                    "__eq__",
+                    &["other"],
+                    Return(Box::new(And(
+                        Box::new(EQ(Box::new(self_field("a")), Box::new(other("a")))),
+                        Box::new(EQ(Box::new(self_field("b")), Box::new(other("b")))),
+                    ))),
+                ),
+                method(
+                    "__lt__",
+                    &["other"],
+                    Return(Box::new(LT(
+                        Box::new(sum(self_field("a"), self_field("b"))),
+                        Box::new(sum(other("a"), other("b"))),
+                    ))),
+                ),
+                method(
+                    "__getitem__",
+                    &["i"],
+                    IfThenElse(
+                        Box::new(EQ(var("i"), Box::new(CInt(0)))),
+                        Box::new(Return(Box::new(self_field("a")))),
+                        Box::new(Return(Box::new(self_field("b")))),
+                    ),
+                ),
+                method("__len__", &[], Return(Box::new(CInt(2)))),
+            ],
+        )
+    }
+
+    #[test]
+    fn eval_reflected_orderings() {
+        /*
+         * > class Version:
+         * >   def __init__(self, n): self.n = n
+         * >   def __ge__(self, other): return self.n >= other.n
+         */
+        let version = class(
+            "Version",
+            None,
+            vec![
+                method("__init__", &["n"], set_field("n", *var("n"))),
+                method(
+                    "__ge__",
+                    &["other"],
+                    Return(Box::new(GTE(
+                        Box::new(self_field("n")),
+                        Box::new(FieldAccess(var("other"), String::from("n"))),
+                    ))),
+                ),
+            ],
+        );
+        let env = execute(version, Environment::new()).unwrap();
+        let v = |n: i32| Box::new(call("Version", vec![CInt(n)]));
+
+        assert_eq!(eval(GTE(v(2), v(1)), &env), Ok(CTrue));
+        assert_eq!(eval(LTE(v(2), v(1)), &env), Ok(CFalse));
+        assert_eq!(
+            eval(GT(v(2), v(1)), &env),
+            Err(Exception::new(
+                "TypeError",
+                "(>) is only defined for numbers, booleans, strings, lists and tuples."
+            ))
+        );
+    }
+
+    #[test]
+    fn eval_overloaded_operators() {
+        let env = execute(pair_class(), Environment::new()).unwrap();
+        let pair = |a: i32, b: i32| Box::new(call("Pair", vec![CInt(a), CInt(b)]));
+        let sum = Add(pair(1, 2), pair(3, 4));
+
+        assert_eq!(eval(EQ(Box::new(sum.clone()), pair(4, 6)), &env), Ok(CTrue));
+        assert_eq!(
+            eval(NEQ(Box::new(sum.clone()), pair(4, 6)), &env),
+            Ok(CFalse)
+        );
+        assert_eq!(eval(LT(pair(1, 2), pair(0, 4)), &env), Ok(CTrue));
+        assert_eq!(eval(GT(pair(1, 2), pair(0, 4)), &env), Ok(CFalse));
+        assert_eq!(
+            eval(Index(Box::new(sum.clone()), Box::new(CInt(1))), &env),
+            Ok(CInt(6))
+        );
+        assert_eq!(eval(Len(Box::new(sum)), &env), Ok(CInt(2)));
+        assert_eq!(
+            eval(Sub(pair(1, 2), pair(3, 4)), &env),
+            Err(Exception::new(
+                "TypeError",
+                "subtraction '(-)' is only defined for numbers (integers and real)."
+            ))
+        );
+    }
+
+    #[test]
+    fn eval_special_methods_results_are_checked() {
+        let program = class(
+            "Odd",
+            None,
+            vec![
+                method("__eq__", &["other"], Return(Box::new(CInt(1)))),
+                method(
+                    "__len__",
+                    &[],
+                    Return(Box::new(CString(String::from("two")))),
+                ),
+            ],
+        );
+        let env = execute(program, Environment::new()).unwrap();
+        let odd = || Box::new(call("Odd", vec![]));
+
+        assert_eq!(
+            eval(EQ(odd(), odd()), &env),
+            Err(Exception::new(
+                "TypeError",
+                "'__eq__' must return a boolean."
+            ))
+        );
+        assert_eq!(
+            eval(Len(odd()), &env),
+            Err(Exception::new(
+                "TypeError",
+                "'__len__' must return an integer."
+            ))
+        );
+    }
+
+    fn for_loop(name: &str, iterable: Expression, body: Statement) -> Statement {
+        For(String::from(name), Box::new(iterable), Box::new(body))
+    }
+
+    fn add(l: Expression, r: Expression) -> Expression {
+        Add(Box::new(l), Box::new(r))
+    }
+
+    #[test]
+    fn execute_for_loops() {
+        /*
+         * > total = 0
+         * > for x in [1, 2, 3]: total = total + x
+         * > s = ""
+         * > for c in "abc": s = c + s
+         */
+        let program = seq(
+            seq(
+                assign("total", CInt(0)),
+                for_loop(
+                    "x",
+                    ListValue(vec![CInt(1), CInt(2), CInt(3)]),
+                    assign("total", add(*var("total"), *var("x"))),
+                ),
+            ),
+            seq(
+                assign("s", CString(String::new())),
+                for_loop(
+                    "c",
+                    CString(String::from("abc")),
+                    assign("s", add(*var("c"), *var("s"))),
+                ),
+            ),
+        );
+
+        let env = execute(program, Environment::new()).unwrap();
+        assert_eq!(env.get("total"), Some(CInt(6)));
+        assert_eq!(env.get("s"), Some(CString(String::from("cba"))));
+        assert_eq!(
+            execute(
+                for_loop("x", CInt(1), assign("y", CInt(0))),
+                Environment::new()
+            ),
+            Err(Exception::new("TypeError", "1 is not iterable."))
+        );
+        assert_eq!(
+            execute(Yield(Box::new(CInt(1))), Environment::new()),
+            Err(Exception::new("SyntaxError", "'yield' outside function."))
+        );
+    }
+
+    #[test]
+    fn execute_generators_are_lazy() {
+        /*
+         * > def naturals():
+         * >   n = 0
+         * >   while True:
+         * >     yield n
+         * >     n = n + 1
+         * > def first_square_above(limit):
+         * >   for n in naturals():
+         * >     if n * n > limit: return n
+         * > x = first_square_above(50)
+         */
+        let naturals = function(
+            "naturals",
+            &[],
+            seq(
+                assign("n", CInt(0)),
+                While(
+                    Box::new(CTrue),
+                    Box::new(seq(Yield(var("n")), assign("n", add(*var("n"), CInt(1))))),
+                ),
+            ),
+        );
+        let first_square_above = function(
+            "first_square_above",
+            &["limit"],
+            for_loop(
+                "n",
+                call("naturals", vec![]),
+                IfThenElse(
+                    Box::new(GT(Box::new(Mul(var("n"), var("n"))), var("limit"))),
+                    Box::new(Return(var("n"))),
+                    Box::new(assign("skipped", *var("n"))),
+                ),
+            ),
+        );
+        let program = seq(
+            seq(naturals, first_square_above),
+            assign("x", call("first_square_above", vec![CInt(50)])),
+        );
+
+        let env = execute(program, Environment::new()).unwrap();
+        assert_eq!(env.get("x"), Some(CInt(8)));
+    }
+
+    #[test]
+    fn execute_generators_keep_their_state() {
+        /*
+         * > def fib():
+         * >   a = 0
+         * >   b = 1
+         * >   while True:
+         * >     yield a
+         * >     t = a + b
+         * >     a = b
+         * >     b = t
+         * > def take(it, k):
+         * >   total = 0
+         * >   for x in it:
+         * >     total = total + x
+         * >     k = k - 1
+         * >     if k == 0: return total
+         * > g = fib()
+         * > first = take(g, 3)
+         * > second = take(g, 3)
+         */
+        let fib = function(
+            "fib",
+            &[],
+            seq(
+                seq(assign("a", CInt(0)), assign("b", CInt(1))),
+                While(
+                    Box::new(CTrue),
+                    Box::new(seq(
+                        Yield(var("a")),
+                        seq(
+                            assign("t", add(*var("a"), *var("b"))),
+                            seq(assign("a", *var("b")), assign("b", *var("t"))),
+                        ),
+                    )),
+                ),
+            ),
+        );
+        let take = function(
+            "take",
+            &["it", "k"],
+            seq(
+                assign("total", CInt(0)),
+                for_loop(
+                    "x",
+                    *var("it"),
+                    seq(
+                        assign("total", add(*var("total"), *var("x"))),
+                        seq(
+                            assign("k", Sub(var("k"), Box::new(CInt(1)))),
+                            IfThenElse(
+                                Box::new(EQ(var("k"), Box::new(CInt(0)))),
+                                Box::new(Return(var("total"))),
+                                Box::new(assign("total", *var("total"))),
+                            ),
+                        ),
+                    ),
+                ),
+            ),
+        );
+        let program = seq(
+            seq(fib, take),
+            seq(
+                assign("g", call("fib", vec![])),
+                seq(
+                    assign("first", call("take", vec![*var("g"), CInt(3)])),
+                    assign("second", call("take", vec![*var("g"), CInt(3)])),
+                ),
+            ),
+        );
+
+        let env = execute(program, Environment::new()).unwrap();
+        assert_eq!(env.get("first"), Some(CInt(2)));
+        assert_eq!(env.get("second"), Some(CInt(10)));
+        assert_eq!(env.get("a"), None);
+    }
+
+    #[test]
+    fn execute_iterator_protocol() {
+        /*
+         * > class Countdown:
+         * >   def __init__(self, n): self.n = n
+         * >   def __iter__(self): return self
+         * >   def __next__(self):
+         * >     if self.n == 0: raise StopIteration("")
+         * >     self.n = self.n - 1
+         * >     return self.n + 1
+         * > total = 0
+         * > for x in Countdown(3): total = total * 10 + x
+         */
+        let countdown = class(
+            "Countdown",
+            None,
+            vec![
+                method("__init__", &["n"], set_field("n", *var("n"))),
+                method("__iter__", &[], Return(var("self"))),
+                method(
+                    "__next__",
+                    &[],
+                    seq(
+                        IfThenElse(
+                            Box::new(EQ(Box::new(self_field("n")), Box::new(CInt(0)))),
+                            Box::new(raise("StopIteration", "")),
+                            Box::new(set_field(
+                                "n",
+                                Sub(Box::new(self_field("n")), Box::new(CInt(1))),
+                            )),
+                        ),
+                        Return(Box::new(add(self_field("n"), CInt(1)))),
+                    ),
+                ),
+            ],
+        );
+        let program = seq(
+            seq(countdown, assign("total", CInt(0))),
+            for_loop(
+                "x",
+                call("Countdown", vec![CInt(3)]),
+                assign(
+                    "total",
+                    add(Mul(var("total"), Box::new(CInt(10))), *var("x")),
+                ),
+            ),
+        );
+
+        let env = execute(program, Environment::new()).unwrap();
+        assert_eq!(env.get("total"), Some(CInt(321)));
+    }
+
+    #[test]
+    fn eval_comprehensions() {
+        /*
+         * > x = "outer"
+         * > pairs = [(x, c) for x in [1, 2, 3] if x != 2 for c in "ab"]
+         * > squares = {x * x for x in [-1, 1, 2]}
+         * > lengths = {w: len(w) for w in ["a", "bc", "a"]}
+         */
+        let s = |v: &str| CString(String::from(v));
+        let program = seq(
+            assign("x", s("outer")),
+            seq(
+                assign(
+                    "pairs",
+                    ListComprehension(
+                        Box::new(Tuple(vec![*var("x"), *var("c")])),
+                        vec![
+                            Clause::For(
+                                String::from("x"),
+                                ListValue(vec![CInt(1), CInt(2), CInt(3)]),
+                            ),
+                            Clause::If(NEQ(var("x"), Box::new(CInt(2)))),
+                            Clause::For(String::from("c"), s("ab")),
+                        ],
+                    ),
+                ),
+                seq(
+                    assign(
+                        "squares",
+                        SetComprehension(
+                            Box::new(Mul(var("x"), var("x"))),
+                            vec![Clause::For(
+                                String::from("x"),
+                                ListValue(vec![CInt(-1), CInt(1), CInt(2)]),
+                            )],
+                        ),
+                    ),
+                    assign(
+                        "lengths",
+                        DictComprehension(
+                            var("w"),
+                            Box::new(Len(var("w"))),
+                            vec![Clause::For(
+                                String::from("w"),
+                                ListValue(vec![s("a"), s("bc"), s("a")]),
+                            )],
+                        ),
+                    ),
+                ),
+            ),
+        );
+
+        let env = execute(program, Environment::new()).unwrap();
+        assert_eq!(
+            env.get("pairs"),
+            Some(ListValue(vec![
+                Tuple(vec![CInt(1), s("a")]),
+                Tuple(vec![CInt(1), s("b")]),
+                Tuple(vec![CInt(3), s("a")]),
+                Tuple(vec![CInt(3), s("b")]),
+            ]))
+        );
+        assert_eq!(env.get("x"), Some(s("outer")));
+        assert_eq!(env.get("c"), None);
+        assert_eq!(env.get("squares").unwrap().to_string(), "{1, 4}");
+        assert_eq!(env.get("lengths").unwrap().to_string(), "{'a': 1, 'bc': 2}");
+        assert_eq!(
+            eval(
+                ListComprehension(var("x"), vec![Clause::For(String::from("x"), CInt(3))]),
+                &env
+            ),
+            Err(Exception::new("TypeError", "3 is not iterable."))
+        );
+    }
+
+    #[test]
+    fn eval_sets_and_dictionaries() {
+        let s = |v: &str| CString(String::from(v));
+        let env = Environment::from([
+            (
+                String::from("d"),
+                DictValue(vec![(s("a"), CInt(1)), (s("b"), CInt(2))]),
+            ),
+            (String::from("e"), SetValue(vec![CInt(1), CInt(2)])),
+        ]);
+
+        assert_eq!(eval(Index(var("d"), Box::new(s("b"))), &env), Ok(CInt(2)));
+        assert_eq!(
+            eval(Index(var("d"), Box::new(s("z"))), &env),
+            Err(Exception::new("KeyError", "'z'"))
+        );
+        assert_eq!(eval(In(Box::new(s("a")), var("d")), &env), Ok(CTrue));
+        assert_eq!(eval(In(Box::new(CInt(3)), var("e")), &env), Ok(CFalse));
+        assert_eq!(eval(Len(var("d")), &env), Ok(CInt(2)));
+        assert_eq!(
+            eval(
+                EQ(
+                    var("e"),
+                    Box::new(SetValue(vec![CInt(2), CInt(1), CReal(2.0)]))
+                ),
+                &env
+            ),
+            Ok(CTrue)
+        );
+        assert_eq!(eval(SetValue(vec![]), &env).unwrap().to_string(), "set()");
+    }
+
+    #[test]
+    fn execute_objects_are_keys_by_identity() {
+        /*
+         * > class C: pass
+         * > a = C()
+         * > b = C()
+         * > n = len({C(), C(), a, a})
+         * > d = {a: 1, b: 2}
+         * > x = d[a]
+         * > y = d[b]
+         */
+        let program = seq(
+            seq(
+                class("C", None, vec![]),
+                seq(
+                    assign("a", call("C", vec![])),
+                    assign("b", call("C", vec![])),
+                ),
+            ),
+            seq(
+                assign(
+                    "n",
+                    Len(Box::new(SetValue(vec![
+                        call("C", vec![]),
+                        call("C", vec![]),
+                        *var("a"),
+                        *var("a"),
+                    ]))),
+                ),
+                seq(
+                    assign(
+                        "d",
+                        DictValue(vec![(*var("a"), CInt(1)), (*var("b"), CInt(2))]),
+                    ),
+                    seq(
+                        assign("x", Index(var("d"), var("a"))),
+                        assign("y", Index(var("d"), var("b"))),
+                    ),
+                ),
+            ),
+        );
+
+        let env = execute(program, Environment::new()).unwrap();
+        assert_eq!(env.get("n"), Some(CInt(3)));
+        assert_eq!(env.get("x"), Some(CInt(1)));
+        assert_eq!(env.get("y"), Some(CInt(2)));
+    }
+
+    /*
+     * > class P:
+     * >   def __init__(self, x): self.x = x
+     * >   def __hash__(self): return self.x % 2
+     * >   def __eq__(self, other): return self.x == other.x
+     */
+    fn hashable_points() -> Statement {
+        let x = |object: &str| FieldAccess(var(object), String::from("x"));
+        class(
+            "P",
+            None,
+            vec![
+                method("__init__", &["x"], set_field("x", *var("x"))),
+                method(
+                    "__hash__",
+                    &[],
+                    Return(Box::new(Mod(Box::new(x("self")), Box::new(CInt(2))))),
+                ),
+                method(
+                    "__eq__",
+                    &["other"],
+                    Return(Box::new(EQ(Box::new(x("self")), Box::new(x("other"))))),
+                ),
+            ],
+        )
+    }
+
+    #[test]
+    fn execute_objects_are_keys_by_hash_and_eq() {
+        /*
+         * > n = len({P(1), P(1), P(3)})
+         * > d = {P(1): "a", P(3): "b"}
+         * > v = d[P(3)]
+         * > missing = P(5) in d
+         */
+        let p = |x| call("P", vec![CInt(x)]);
+        let program = seq(
+            hashable_points(),
+            seq(
+                assign("n", Len(Box::new(SetValue(vec![p(1), p(1), p(3)])))),
+                seq(
+                    assign(
+                        "d",
+                        DictValue(vec![(p(1), string("a")), (p(3), string("b"))]),
+                    ),
+                    seq(
+                        assign("v", Index(var("d"), Box::new(p(3)))),
+                        assign("missing", In(Box::new(p(5)), var("d"))),
+                    ),
+                ),
+            ),
+        );
+
+        let env = execute(program, Environment::new()).unwrap();
+        assert_eq!(env.get("n"), Some(CInt(2)));
+        assert_eq!(env.get("v"), Some(string("b")));
+        assert_eq!(env.get("missing"), Some(CFalse));
+    }
+
+    #[test]
+    fn execute_objects_with_eq_but_no_hash_are_unhashable() {
+        /*
+         * > class Q:
+         * >   def __eq__(self, other): return True
+         * > s = {Q()}
+         */
+        let q = class(
+            "Q",
+            None,
+            vec![method("__eq__", &["other"], Return(Box::new(CTrue)))],
+        );
+        let program = seq(q, assign("s", SetValue(vec![call("Q", vec![])])));
+
+        assert_eq!(
+            execute(program, Environment::new()),
+            Err(Exception::new("TypeError", "unhashable type: 'Q'."))
+        );
+    }
+
+    #[test]
+    fn eval_unhashable_keys() {
+        let env = Environment::new();
+        let list = || ListValue(vec![CInt(1)]);
+        let unhashable = |kind: &str| {
+            Err(Exception::new(
+                "TypeError",
+                &format!("unhashable type: '{kind}'."),
+            ))
+        };
+
+        assert_eq!(eval(SetValue(vec![list()]), &env), unhashable("list"));
+        assert_eq!(
+            eval(DictValue(vec![(SetValue(vec![]), CInt(1))]), &env),
+            unhashable("set")
+        );
+        assert_eq!(
+            eval(
+                In(Box::new(list()), Box::new(SetValue(vec![CInt(1)]))),
+                &env
+            ),
+            unhashable("list")
+        );
+        assert_eq!(
+            eval(
+                Index(
+                    Box::new(DictValue(vec![(CInt(1), CInt(2))])),
+                    Box::new(DictValue(vec![]))
+                ),
+                &env
+            ),
+            unhashable("dict")
+        );
+        assert_eq!(
+            eval(
+                In(Box::new(list()), Box::new(ListValue(vec![list()]))),
+                &env
+            ),
+            Ok(CTrue)
+        );
+        assert_eq!(
+            eval(
+                SetValue(vec![
+                    Tuple(vec![CInt(1), CString(String::from("a"))]),
+                    Tuple(vec![CReal(1.0), CString(String::from("a"))]),
+                    CReal(-0.0),
+                    CInt(0),
+                ]),
+                &env
+            ),
+            Ok(SetValue(vec![
+                Tuple(vec![CInt(1), CString(String::from("a"))]),
+                CReal(-0.0)
+            ]))
+        );
+    }
+
+    #[test]
+    fn eval_conditional_expressions_and_modulo() {
+        let env = Environment::new();
+        let division_by_zero = || Box::new(Div(Box::new(CInt(1)), Box::new(CInt(0))));
+        let modulo = |a: Expression, b: Expression| eval(Mod(Box::new(a), Box::new(b)), &env);
+
+        assert_eq!(
+            eval(
+                Conditional(Box::new(CTrue), Box::new(CInt(1)), division_by_zero()),
+                &env
+            ),
+            Ok(CInt(1))
+        );
+        assert_eq!(
+            eval(
+                Conditional(Box::new(CFalse), division_by_zero(), Box::new(CInt(2))),
+                &env
+            ),
+            Ok(CInt(2))
+        );
+        assert_eq!(
+            eval(
+                Conditional(Box::new(CInt(0)), Box::new(CInt(1)), Box::new(CInt(2))),
+                &env
+            ),
+            Err(Exception::new("TypeError", "expecting a boolean value."))
+        );
+        assert_eq!(modulo(CInt(7), CInt(3)), Ok(CInt(1)));
+        assert_eq!(modulo(CInt(-7), CInt(3)), Ok(CInt(2)));
+        assert_eq!(modulo(CInt(7), CInt(-2)), Ok(CInt(-1)));
+        assert_eq!(modulo(CReal(7.5), CInt(2)), Ok(CReal(1.5)));
+        assert_eq!(
+            modulo(CInt(1), CInt(0)),
+            Err(Exception::new("ZeroDivisionError", "modulo by zero."))
+        );
+    }
+
+    #[test]
+    fn execute_augmented_assignments() {
+        /*
+         * > class Counter:
+         * >   def __init__(self): self.calls = 0
+         * >   def tick(self):
+         * >     self.calls += 1
+         * >     return 0
+         * > c = Counter()
+         * > n = 10
+         * > n -= 4
+         * > n %= 4
+         * > xs = [1, 2]
+         * > xs[c.tick()] *= 5
+         * > p = Point(x=1, y=2)
+         * > p.y += n
+         */
+        let augment = |target: Expression, op: ArithmeticOp, exp: Expression| {
+            AugmentedAssignment(Box::new(target), op, Box::new(exp))
+        };
+        let counter = class(
+            "Counter",
+            None,
+            vec![
+                method("__init__", &[], set_field("calls", CInt(0))),
+                method(
+                    "tick",
+                    &[],
+                    seq(
+                        augment(self_field("calls"), ArithmeticOp::Add, CInt(1)),
+                        Return(Box::new(CInt(0))),
+                    ),
+                ),
+            ],
+        );
+        let program = seq(
+            seq(
+                seq(counter, point_declaration()),
+                seq(assign("c", call("Counter", vec![])), assign("n", CInt(10))),
+            ),
+            seq(
+                seq(
+                    augment(*var("n"), ArithmeticOp::Sub, CInt(4)),
+                    augment(*var("n"), ArithmeticOp::Mod, CInt(4)),
+                ),
+                seq(
+                    seq(
+                        assign("xs", ListValue(vec![CInt(1), CInt(2)])),
+                        augment(
+                            Index(
+                                var("xs"),
+                                Box::new(MethodCall(var("c"), String::from("tick"), vec![])),
+                            ),
+                            ArithmeticOp::Mul,
+                            CInt(5),
+                        ),
+                    ),
+                    seq(
+                        assign("p", point(CInt(1), CInt(2))),
+                        augment(
+                            FieldAccess(var("p"), String::from("y")),
+                            ArithmeticOp::Add,
+                            *var("n"),
+                        ),
+                    ),
+                ),
+            ),
+        );
+
+        let env = execute(program, Environment::new()).unwrap();
+        assert_eq!(env.get("n"), Some(CInt(2)));
+        assert_eq!(env.get("xs"), Some(ListValue(vec![CInt(5), CInt(2)])));
+        assert_eq!(
+            eval(FieldAccess(var("c"), String::from("calls")), &env),
+            Ok(CInt(1))
+        );
+        assert_eq!(
+            eval(FieldAccess(var("p"), String::from("y")), &env),
+            Ok(CInt(4))
+        );
+        assert_eq!(
+            execute(
+                augment(
+                    Index(var("xs"), Box::new(CInt(2))),
+                    ArithmeticOp::Add,
+                    CInt(1)
+                ),
+                env.clone()
+            ),
+            Err(Exception::new("IndexError", "index out of range."))
+        );
+        assert_eq!(
+            execute(
+                seq(
+                    ValDeclaration(Box::new(String::from("v"))),
+                    seq(
+                        assign("v", CInt(1)),
+                        augment(*var("v"), ArithmeticOp::Div, CInt(2))
+                    ),
+                ),
+                env
+            ),
+            Err(Exception::new(
+                "TypeError",
+                "cannot modify 'v', declared with 'val'."
+            ))
+        );
+    }
+
+    fn keyword(name: &str, value: Expression) -> Expression {
+        KeywordArgument(String::from(name), Box::new(value))
+    }
+
+    #[test]
+    fn execute_default_keyword_and_variadic_arguments() {
+        /*
+         * > d = "world"
+         * > def greet(greeting, name=d, *rest, **options):
+         * >   return (greeting, name, rest, options)
+         * > d = "moon"
+         */
+        let s = |v: &str| CString(String::from(v));
+        let greet = FuncDef(Box::new(Function {
+            name: String::from("greet"),
+            kind: Type::TAny,
+            params: vec![
+                (String::from("greeting"), Type::TString),
+                (String::from("name"), Type::TString),
+            ],
+            defaults: vec![*var("d")],
+            varargs: Some((String::from("rest"), Type::TAny)),
+            kwargs: Some((String::from("options"), Type::TAny)),
+            body: Box::new(Return(Box::new(Tuple(vec![
+                *var("greeting"),
+                *var("name"),
+                *var("rest"),
+                *var("options"),
+            ])))),
+        }));
+        let program = seq(seq(assign("d", s("world")), greet), assign("d", s("moon")));
+        let env = execute(program, Environment::new()).unwrap();
+        let greet = |args: Vec<Expression>| eval(call("greet", args), &env);
+
+        assert_eq!(
+            greet(vec![s("hi")]),
+            Ok(Tuple(vec![
+                s("hi"),
+                s("world"),
+                ListValue(vec![]),
+                DictValue(vec![])
+            ]))
+        );
+        assert_eq!(
+            greet(vec![
+                keyword("name", s("bob")),
+                keyword("greeting", s("yo"))
+            ]),
+            Ok(Tuple(vec![
+                s("yo"),
+                s("bob"),
+                ListValue(vec![]),
+                DictValue(vec![])
+            ]))
+        );
+        assert_eq!(
+            greet(vec![
+                s("a"),
+                s("b"),
+                CInt(1),
+                CInt(2),
+                keyword("sep", s("-"))
+            ]),
+            Ok(Tuple(vec![
+                s("a"),
+                s("b"),
+                ListValue(vec![CInt(1), CInt(2)]),
+                DictValue(vec![(s("sep"), s("-"))]),
+            ]))
+        );
+        assert_eq!(
+            greet(vec![keyword("name", s("bob"))]),
+            Err(Exception::new(
+                "TypeError",
+                "greet() missing argument 'greeting'."
+            ))
+        );
+        assert_eq!(
+            greet(vec![s("a"), keyword("greeting", s("b"))]),
+            Err(Exception::new(
+                "TypeError",
+                "greet() got multiple values for argument 'greeting'."
+            ))
+        );
+        assert_eq!(
+            greet(vec![keyword("name", s("bob")), s("hi")]),
+            Err(Exception::new(
+                "TypeError",
+                "positional argument follows keyword argument."
+            ))
+        );
+    }
+
+    #[test]
+    fn execute_argument_errors_without_variadic_parameters() {
+        /*
+         * > def area(width, height=1):
+         * >   return width * height
+         */
+        let area = FuncDef(Box::new(Function {
+            name: String::from("area"),
+            kind: Type::TAny,
+            params: vec![
+                (String::from("width"), Type::TInteger),
+                (String::from("height"), Type::TInteger),
+            ],
+            defaults: vec![CInt(1)],
+            varargs: None,
+            kwargs: None,
+            body: Box::new(Return(Box::new(Mul(var("width"), var("height"))))),
+        }));
+        let env = execute(area, Environment::new()).unwrap();
+        let area = |args: Vec<Expression>| eval(call("area", args), &env);
+
+        assert_eq!(
+            area(vec![keyword("height", CInt(3)), keyword("width", CInt(2))]),
+            Ok(CInt(6))
+        );
+        assert_eq!(area(vec![CInt(5)]), Ok(CInt(5)));
+        assert_eq!(
+            area(vec![CInt(1), CInt(2), CInt(3)]),
+            Err(Exception::new(
+                "TypeError",
+                "area() takes from 1 to 2 argument(s) but 3 were given."
+            ))
+        );
+        assert_eq!(
+            area(vec![CInt(1), keyword("depth", CInt(2))]),
+            Err(Exception::new(
+                "TypeError",
+                "area() got an unexpected keyword argument 'depth'."
+            ))
+        );
+        assert_eq!(
+            eval(keyword("width", CInt(1)), &env),
+            Err(Exception::new(
+                "SyntaxError",
+                "keyword arguments are only allowed in calls."
+            ))
+        );
+    }
+
+    fn global(names: &[&str]) -> Statement {
+        Global(names.iter().map(|n| String::from(*n)).collect())
+    }
+
+    fn nonlocal(names: &[&str]) -> Statement {
+        Nonlocal(names.iter().map(|n| String::from(*n)).collect())
+    }
+
+    #[test]
+    fn execute_closures_with_nonlocal() {
+        /*
+         * > def make_counter():
+         * >   count = 0
+         * >   def increment():
+         * >     nonlocal count
+         * >     count += 1
+         * >     return count
+         * >   return increment
+         * > c1 = make_counter()
+         * > c2 = make_counter()
+         * > a = c1(); b = c1(); c = c2()
+         */
+        let increment = function(
+            "increment",
+            &[],
+            seq(
+                nonlocal(&["count"]),
+                seq(
+                    AugmentedAssignment(var("count"), ArithmeticOp::Add, Box::new(CInt(1))),
+                    Return(var("count")),
+                ),
+            ),
+        );
+        let make_counter = function(
+            "make_counter",
+            &[],
+            seq(
+                assign("count", CInt(0)),
+                seq(increment, Return(var("increment"))),
+            ),
+        );
+        let program = seq(
+            make_counter,
+            seq(
+                seq(
+                    assign("c1", call("make_counter", vec![])),
+                    assign("c2", call("make_counter", vec![])),
+                ),
+                seq(
+                    seq(
+                        assign("a", call("c1", vec![])),
+                        assign("b", call("c1", vec![])),
+                    ),
+                    assign("c", call("c2", vec![])),
+                ),
+            ),
+        );
+        let env = execute(program, Environment::new()).unwrap();
+        assert_eq!(env.get("a"), Some(CInt(1)));
+        assert_eq!(env.get("b"), Some(CInt(2)));
+        assert_eq!(env.get("c"), Some(CInt(1)));
+        assert_eq!(env.get("count"), None);
+    }
+
+    #[test]
+    fn execute_global_declarations() {
+        /*
+         * > total = 0
+         * > def add(n):
+         * >   global total
+         * >   total = total + n
+         * > def shadow(n):
+         * >   total = n
+         * > _ = add(5); _ = shadow(100); _ = add(2)
+         * > x = [add(1), total]    # calls update module variables even
+         * >                        # in the middle of an expression
+         */
+        let add_to_total = function(
+            "add",
+            &["n"],
+            seq(
+                global(&["total"]),
+                assign("total", add(*var("total"), *var("n"))),
+            ),
+        );
+        let shadow = function("shadow", &["n"], assign("total", *var("n")));
+        let program = seq(
+            seq(assign("total", CInt(0)), seq(add_to_total, shadow)),
+            seq(
+                seq(
+                    assign("_", call("add", vec![CInt(5)])),
+                    assign("_", call("shadow", vec![CInt(100)])),
+                ),
+                seq(
+                    assign("_", call("add", vec![CInt(2)])),
+                    assign(
+                        "x",
+                        ListValue(vec![call("add", vec![CInt(1)]), *var("total")]),
+                    ),
+                ),
+            ),
+        );
+        let env = execute(program, Environment::new()).unwrap();
+        assert_eq!(env.get("x"), Some(ListValue(vec![CNone, CInt(8)])));
+        assert_eq!(env.get("total"), Some(CInt(8)));
+        assert_eq!(
+            execute(nonlocal(&["total"]), env.clone()),
+            Err(Exception::new(
+                "SyntaxError",
+                "nonlocal declaration not allowed at module level."
+            ))
+        );
+        let program = seq(
+            function("f", &[], nonlocal(&["total"])),
+            assign("_", call("f", vec![])),
+        );
+        assert_eq!(
+            execute(program, env),
+            Err(Exception::new(
+                "SyntaxError",
+                "no binding for nonlocal 'total' found."
+            ))
+        );
+    }
+
+    #[test]
+    fn execute_lexical_scoping() {
+        /*
+         * > x = 1
+         * > def get():
+         * >   return x
+         * > def caller():
+         * >   x = 2
+         * >   return get()
+         * > def broken():
+         * >   y = x
+         * >   x = 3
+         * >   return y
+         * > a = caller()
+         */
+        let get = function("get", &[], Return(var("x")));
+        let caller = function(
+            "caller",
+            &[],
+            seq(assign("x", CInt(2)), Return(Box::new(call("get", vec![])))),
+        );
+        let broken = function(
+            "broken",
+            &[],
+            seq(
+                seq(assign("y", *var("x")), assign("x", CInt(3))),
+                Return(var("y")),
+            ),
+        );
+        let program = seq(
+            seq(assign("x", CInt(1)), seq(get, seq(caller, broken))),
+            assign("a", call("caller", vec![])),
+        );
+        let env = execute(program, Environment::new()).unwrap();
+        assert_eq!(env.get("a"), Some(CInt(1)));
+        assert_eq!(env.get("x"), Some(CInt(1)));
+        assert_eq!(
+            eval(call("broken", vec![]), &env),
+            Err(Exception::new(
+                "UnboundLocalError",
+                "local variable 'x' referenced before assignment."
+            ))
+        );
+    }
+
+    #[test]
+    fn execute_block_declarations_shadow() {
+        /*
+         * > val x = 1
+         * > if True:
+         * >   var x
+         * >   x = 2
+         * >   y = x * 10
+         * > else:
+         * >   y = 0
+         * > for i in [1, 2]:
+         * >   val z = i   # a new 'z' on each iteration
+         */
+        let block = seq(
+            seq(
+                VarDeclaration(Box::new(String::from("x"))),
+                assign("x", CInt(2)),
+            ),
+            assign("y", Mul(var("x"), Box::new(CInt(10)))),
+        );
+        let program = seq(
+            seq(
+                ValDeclaration(Box::new(String::from("x"))),
+                assign("x", CInt(1)),
+            ),
+            seq(
+                IfThenElse(
+                    Box::new(CTrue),
+                    Box::new(block),
+                    Box::new(assign("y", CInt(0))),
+                ),
+                for_loop(
+                    "i",
+                    ListValue(vec![CInt(1), CInt(2)]),
+                    seq(
+                        ValDeclaration(Box::new(String::from("z"))),
+                        assign("z", *var("i")),
+                    ),
+                ),
+            ),
+        );
+        let env = execute(program, Environment::new()).unwrap();
+        assert_eq!(env.get("x"), Some(CInt(1)));
+        assert_eq!(env.get("y"), Some(CInt(20)));
+        assert_eq!(env.get("z"), None);
+
+        /*
+         * > if True:
+         * >   y = x       # the block's x, not bound yet
+         * >   var x
+         * > else:
+         * >   y = 0
+         */
+        let unbound = IfThenElse(
+            Box::new(CTrue),
+            Box::new(seq(
+                assign("y", *var("x")),
+                VarDeclaration(Box::new(String::from("x"))),
+            )),
+            Box::new(assign("y", CInt(0))),
+        );
+        assert_eq!(
+            execute(unbound, env),
+            Err(Exception::new(
+                "UnboundLocalError",
+                "local variable 'x' referenced before assignment."
+            ))
+        );
+    }
+
+    #[test]
+    fn execute_generator_with_block_declarations() {
+        /*
+         * > def gen():
+         * >   x = 0
+         * >   for i in [1, 2]:
+         * >     var x
+         * >     x = i * 10
+         * >     yield x
+         * >   yield x
+         * > xs = [v for v in gen()]
+         */
+        let body = seq(
+            seq(
+                VarDeclaration(Box::new(String::from("x"))),
+                assign("x", Mul(var("i"), Box::new(CInt(10)))),
+            ),
+            Yield(var("x")),
+        );
+        let gen = function(
+            "gen",
+            &[],
+            seq(
+                assign("x", CInt(0)),
+                seq(
+                    for_loop("i", ListValue(vec![CInt(1), CInt(2)]), body),
+                    Yield(var("x")),
+                ),
+            ),
+        );
+        let xs = ListComprehension(
+            var("v"),
+            vec![Clause::For(String::from("v"), call("gen", vec![]))],
+        );
+        let env = execute(seq(gen, assign("xs", xs)), Environment::new()).unwrap();
+        assert_eq!(
+            env.get("xs"),
+            Some(ListValue(vec![CInt(10), CInt(20), CInt(0)]))
+        );
+    }
+
+    fn string(s: &str) -> Expression {
+        CString(String::from(s))
+    }
+
+    fn generated(gen: Statement, call: Expression) -> Result<Option<Expression>, Exception> {
+        let xs = ListComprehension(var("v"), vec![Clause::For(String::from("v"), call)]);
+        execute(seq(gen, assign("xs", xs)), Environment::new()).map(|env| env.get("xs"))
+    }
+
+    #[test]
+    fn execute_generator_yielding_in_try() {
+        /*
+         * > def gen():
+         * >   try:
+         * >     yield 1
+         * >     raise ValueError("boom")
+         * >   except ValueError:
+         * >     yield 2
+         * >   finally:
+         * >     yield 3
+         * >   yield 4
+         */
+        let gen = function(
+            "gen",
+            &[],
+            seq(
+                Try(
+                    Box::new(seq(Yield(Box::new(CInt(1))), raise("ValueError", "boom"))),
+                    vec![handler(Some("ValueError"), None, Yield(Box::new(CInt(2))))],
+                    Some(Box::new(Yield(Box::new(CInt(3))))),
+                ),
+                Yield(Box::new(CInt(4))),
+            ),
+        );
+
+        assert_eq!(
+            generated(gen, call("gen", vec![])),
+            Ok(Some(ListValue(vec![CInt(1), CInt(2), CInt(3), CInt(4)])))
+        );
+    }
+
+    #[test]
+    fn execute_generator_leaving_try() {
+        /*
+         * > def gen(fail):
+         * >   try:
+         * >     yield 1
+         * >     if fail: raise KeyError("k")
+         * >     else: return None
+         * >   finally:
+         * >     yield 2
+         * >   yield 3
+         */
+        let gen = || {
+            function(
+                "gen",
+                &["fail"],
+                seq(
+                    Try(
+                        Box::new(seq(
+                            Yield(Box::new(CInt(1))),
+                            IfThenElse(
+                                var("fail"),
+                                Box::new(raise("KeyError", "k")),
+                                Box::new(Return(Box::new(CNone))),
+                            ),
+                        )),
+                        vec![],
+                        Some(Box::new(Yield(Box::new(CInt(2))))),
+                    ),
+                    Yield(Box::new(CInt(3))),
+                ),
+            )
+        };
+
+        assert_eq!(
+            generated(gen(), call("gen", vec![CFalse])),
+            Ok(Some(ListValue(vec![CInt(1), CInt(2)])))
+        );
+        assert_eq!(
+            generated(gen(), call("gen", vec![CTrue])),
+            Err(Exception::new("KeyError", "k"))
+        );
+    }
+
+    #[test]
+    fn execute_generator_yielding_in_match() {
+        /*
+         * > def gen(x):
+         * >   match x:
+         * >     case 1: yield "one"
+         * >     case _: yield "other"
+         */
+        let case = |pattern: Pattern, name: &str| MatchCase {
+            pattern,
+            guard: None,
+            body: Box::new(Yield(Box::new(string(name)))),
+        };
+        let gen = function(
+            "gen",
+            &["x"],
+            Match(
+                var("x"),
+                vec![
+                    case(Pattern::Literal(CInt(1)), "one"),
+                    case(Pattern::Wildcard, "other"),
+                ],
+            ),
+        );
+
+        assert_eq!(
+            generated(gen, call("gen", vec![CInt(2)])),
+            Ok(Some(ListValue(vec![string("other")])))
+        );
+    }
+
+    #[test]
+    fn execute_generator_yielding_in_with() {
+        /*
+         * > class Manager:
+         * >   def __enter__(self): self.open = True; return self
+         * >   def __exit__(self, type, value, traceback): self.open = False
+         * > def gen(m):
+         * >   with m as n:
+         * >     yield n.open
+         * > m = Manager()
+         * > xs = [v for v in gen(m)]
+         * > still_open = m.open
+         */
+        let manager = class(
+            "Manager",
+            None,
+            vec![
+                method(
+                    "__enter__",
+                    &[],
+                    seq(set_field("open", CTrue), Return(var("self"))),
+                ),
+                method(
+                    "__exit__",
+                    &["type", "value", "traceback"],
+                    set_field("open", CFalse),
+                ),
+            ],
+        );
+        let gen = function(
+            "gen",
+            &["m"],
+            With(
+                var("m"),
+                Some(String::from("n")),
+                Box::new(Yield(Box::new(FieldAccess(var("n"), String::from("open"))))),
+            ),
+        );
+        let xs = ListComprehension(
+            var("v"),
+            vec![Clause::For(String::from("v"), call("gen", vec![*var("m")]))],
+        );
+        let program = seq(
+            seq(manager, gen),
+            seq(
+                assign("m", call("Manager", vec![])),
+                seq(
+                    assign("xs", xs),
+                    assign("still_open", FieldAccess(var("m"), String::from("open"))),
+                ),
+            ),
+        );
+
+        let env = execute(program, Environment::new()).unwrap();
+        assert_eq!(env.get("xs"), Some(ListValue(vec![CTrue])));
+        assert_eq!(env.get("still_open"), Some(CFalse));
+    }
+
+    #[test]
+    fn execute_while_with_non_boolean_condition() {
+        /*
+         * > def gen():
+         * >   while 1:
+         * >     yield 1
+         */
+        let gen = function(
+            "gen",
+            &[],
+            While(Box::new(CInt(1)), Box::new(Yield(Box::new(CInt(1))))),
+        );
+        let error = Exception::new("TypeError", "expecting a boolean value.");
+
+        assert_eq!(generated(gen, call("gen", vec![])), Err(error.clone()));
+        assert_eq!(
+            execute(
+                While(Box::new(CInt(1)), Box::new(assign("x", CInt(1)))),
+                Environment::new()
+            ),
+            Err(error)
+        );
+    }
+
+    #[test]
+    fn execute_print_and_input() {
+        /*
+         * > name = input("name? ")
+         * > _ = print("hello", name, sep=", ", end="!\n")
+         * > _ = print(1, 2.5, [True, None])
+         * > _ = print()
+         */
+        let program = seq(
+            assign("name", call("input", vec![string("name? ")])),
+            seq(
+                assign(
+                    "_",
+                    call(
+                        "print",
+                        vec![
+                            string("hello"),
+                            *var("name"),
+                            keyword("sep", string(", ")),
+                            keyword("end", string("!\n")),
+                        ],
+                    ),
+                ),
+                seq(
+                    assign(
+                        "_",
+                        call(
+                            "print",
+                            vec![CInt(1), CReal(2.5), ListValue(vec![CTrue, CNone])],
+                        ),
+                    ),
+                    assign("_", call("print", vec![])),
+                ),
+            ),
+        );
+        let console = Rc::new(RefCell::new(builtins::BufferConsole::new(&["Ada"])));
+        let mut env = Environment::new();
+        env.set_console(console.clone());
+        let env = execute(program, env).unwrap();
+        assert_eq!(env.get("name"), Some(string("Ada")));
+        assert_eq!(
+            console.borrow().output,
+            "name? hello, Ada!\n1 2.5 [True, None]\n\n"
+        );
+
+        /* the input is exhausted */
+        assert_eq!(
+            eval(call("input", vec![]), &env),
+            Err(Exception::new("EOFError", "EOF when reading a line."))
+        );
+        assert_eq!(
+            eval(call("print", vec![keyword("file", CNone)]), &env),
+            Err(Exception::new(
+                "TypeError",
+                "print() got an unexpected keyword argument 'file'."
+            ))
+        );
+    }
+
+    #[test]
+    fn eval_len_abs_min_max_and_sum() {
+        let env = Environment::new();
+        let list = |elements: &[i32]| ListValue(elements.iter().map(|i| CInt(*i)).collect());
+        let eval = |exp: Expression| eval(exp, &env).unwrap();
+        assert_eq!(eval(call("len", vec![string("abc")])), CInt(3));
+        assert_eq!(eval(call("abs", vec![CInt(-3)])), CInt(3));
+        assert_eq!(eval(call("abs", vec![CReal(-2.5)])), CReal(2.5));
+        assert_eq!(
+            eval(call("min", vec![CInt(3), CReal(1.5), CInt(2)])),
+            CReal(1.5)
+        );
+        assert_eq!(eval(call("max", vec![list(&[1, 5, 2])])), CInt(5));
+        assert_eq!(
+            eval(call("max", vec![string("pear"), string("apple")])),
+            string("pear")
+        );
+        assert_eq!(eval(call("sum", vec![list(&[1, 2, 3])])), CInt(6));
+        assert_eq!(
+            eval(call("sum", vec![list(&[1, 2]), CReal(0.5)])),
+            CReal(3.5)
+        );
+    }
+
+    #[test]
+    fn eval_conversions() {
+        let env = Environment::new();
+        let eval = |exp: Expression| eval(exp, &env).unwrap();
+        assert_eq!(eval(call("int", vec![string(" -42 ")])), CInt(-42));
+        assert_eq!(eval(call("int", vec![CReal(-3.9)])), CInt(-3));
+        assert_eq!(eval(call("int", vec![CTrue])), CInt(1));
+        assert_eq!(eval(call("int", vec![])), CInt(0));
+        assert_eq!(eval(call("float", vec![string("2.5")])), CReal(2.5));
+        assert_eq!(eval(call("float", vec![CInt(2)])), CReal(2.0));
+        assert_eq!(eval(call("str", vec![CInt(12)])), string("12"));
+        assert_eq!(eval(call("str", vec![string("x")])), string("x"));
+        assert_eq!(eval(call("bool", vec![ListValue(vec![])])), CFalse);
+        assert_eq!(eval(call("bool", vec![string("x")])), CTrue);
+        assert_eq!(eval(call("bool", vec![CReal(0.0)])), CFalse);
+    }
+
+    #[test]
+    fn eval_range() {
+        let env = Environment::new();
+        let range = |args: Vec<Expression>| {
+            let range = eval(call("range", args), &env).unwrap();
+            builtins::collect(range, &env).unwrap()
+        };
+        let ints = |elements: &[i32]| elements.iter().map(|i| CInt(*i)).collect::<Vec<_>>();
+        assert_eq!(range(vec![CInt(3)]), ints(&[0, 1, 2]));
+        assert_eq!(range(vec![CInt(1), CInt(10), CInt(3)]), ints(&[1, 4, 7]));
+        assert_eq!(range(vec![CInt(5), CInt(0), CInt(-2)]), ints(&[5, 3, 1]));
+        assert_eq!(range(vec![CInt(2), CInt(1)]), ints(&[]));
+    }
+
+    #[test]
+    fn eval_len_membership_and_indexing_of_ranges() {
+        let env = Environment::new();
+        let range = |args: Vec<Expression>| Box::new(call("range", args));
+        let up = || range(vec![CInt(1), CInt(10), CInt(3)]);
+        let down = || range(vec![CInt(5), CInt(0), CInt(-2)]);
+
+        assert_eq!(eval(Len(up()), &env), Ok(CInt(3)));
+        assert_eq!(eval(Len(down()), &env), Ok(CInt(3)));
+        assert_eq!(eval(Len(range(vec![CInt(2), CInt(1)])), &env), Ok(CInt(0)));
+        assert_eq!(eval(In(Box::new(CInt(7)), up()), &env), Ok(CTrue));
+        assert_eq!(eval(In(Box::new(CReal(4.0)), up()), &env), Ok(CTrue));
+        assert_eq!(eval(In(Box::new(CInt(8)), up()), &env), Ok(CFalse));
+        assert_eq!(eval(In(Box::new(CInt(10)), up()), &env), Ok(CFalse));
+        assert_eq!(eval(In(Box::new(CInt(1)), down()), &env), Ok(CTrue));
+        assert_eq!(eval(In(Box::new(CInt(0)), down()), &env), Ok(CFalse));
+        assert_eq!(eval(Index(up(), Box::new(CInt(1))), &env), Ok(CInt(4)));
+        assert_eq!(eval(Index(down(), Box::new(CInt(-1))), &env), Ok(CInt(1)));
+        assert_eq!(
+            eval(Index(up(), Box::new(CInt(3))), &env),
+            Err(Exception::new(
+                "IndexError",
+                "range object index out of range."
+            ))
+        );
+        assert_eq!(
+            eval(Len(range(vec![CInt(i32::MIN), CInt(i32::MAX)])), &env),
+            Err(Exception::new(
+                "OverflowError",
+                "range has too many elements."
+            ))
+        );
+        assert_eq!(
+            eval(call("reversed", vec![*up()]), &env),
+            Ok(ListValue(vec![CInt(7), CInt(4), CInt(1)]))
+        );
+    }
+
+    #[test]
+    fn execute_range_is_lazy() {
+        /*
+         * > r = range(2147483647)
+         * > def first():
+         * >   for i in r: return i
+         * > x = first()
+         */
+        let program = seq(
+            assign("r", call("range", vec![CInt(i32::MAX)])),
+            seq(
+                function(
+                    "first",
+                    &[],
+                    For(String::from("i"), var("r"), Box::new(Return(var("i")))),
+                ),
+                assign("x", call("first", vec![])),
+            ),
+        );
+        let env = execute(program, Environment::new()).unwrap();
+        assert_eq!(env.get("x"), Some(CInt(0)));
+        assert_eq!(env.get("r").unwrap().to_string(), "<range object>");
+    }
+
+    #[test]
+    fn eval_type_gives_the_class_of_a_value() {
+        let env = Environment::new();
+        let float = eval(call("type", vec![CReal(1.0)]), &env).unwrap();
+        assert_eq!(float, ClassValue(String::from("float")));
+        assert_eq!(float.to_string(), "<class 'float'>");
+        assert_eq!(
+            eval(call("type", vec![ListValue(vec![])]), &env),
+            Ok(ClassValue(String::from("list")))
+        );
+        assert_eq!(
+            eval(call("type", vec![CNone]), &env),
+            Ok(ClassValue(String::from("NoneType")))
+        );
+        assert_eq!(
+            eval(call("type", vec![float]), &env),
+            Ok(ClassValue(String::from("type")))
+        );
+    }
+
+    #[test]
+    fn execute_str_and_print_use_dunder_str() {
+        /*
+         * > class Point:
+         * >   def __str__(self): return "P"
+         * > p = Point()
+         * > s = str(p)
+         * > _ = print(p, 1)
+         */
+        let program = seq(
+            class(
+                "Point",
+                None,
+                vec![method("__str__", &[], Return(Box::new(string("P"))))],
+            ),
+            seq(
+                assign("p", call("Point", vec![])),
+                seq(
+                    assign("s", call("str", vec![*var("p")])),
+                    assign("_", call("print", vec![*var("p"), CInt(1)])),
+                ),
+            ),
+        );
+        let console = Rc::new(RefCell::new(builtins::BufferConsole::new(&[])));
+        let mut env = Environment::new();
+        env.set_console(console.clone());
+        let env = execute(program, env).unwrap();
+        assert_eq!(env.get("s"), Some(string("P")));
+        assert_eq!(console.borrow().output, "P 1\n");
+    }
+
+    #[test]
+    fn execute_dunder_str_must_return_a_string() {
+        /*
+         * > class Point:
+         * >   def __str__(self): return 1
+         * > s = str(Point())
+         */
+        let program = seq(
+            class(
+                "Point",
+                None,
+                vec![method("__str__", &[], Return(Box::new(CInt(1))))],
+            ),
+            assign("s", call("str", vec![call("Point", vec![])])),
+        );
+        assert_eq!(
+            execute(program, Environment::new()),
+            Err(Exception::new(
+                "TypeError",
+                "'__str__' must return a string."
+            ))
+        );
+    }
+
+    #[test]
+    fn eval_range_takes_no_keyword_arguments() {
+        let env = Environment::new();
+        assert_eq!(
+            eval(call("range", vec![keyword("stop", CInt(5))]), &env),
+            Err(Exception::new(
+                "TypeError",
+                "range() takes no keyword arguments."
+            ))
+        );
+    }
 
     #[test]
-    fn eval_constant() {
-        let env = HashMap::new();
-        let c10 = CInt(10);
-        let c20 = CInt(20);
-
-        assert_eq!(eval(c10, &env), Ok(CInt(10)));
-        assert_eq!(eval(c20, &env), Ok(CInt(20)));
+    fn eval_range_with_zero_step() {
+        let env = Environment::new();
+        assert_eq!(
+            eval(call("range", vec![CInt(1), CInt(5), CInt(0)]), &env),
+            Err(Exception::new(
+                "ValueError",
+                "range() arg 3 must not be zero."
+            ))
+        );
     }
 
     #[test]
-    fn eval_add_expression1() {
-        let env = HashMap::new();
-        let c10 = CInt(10);
-        let c20 = CInt(20);
-        let add1 = Add(Box::new(c10), Box::new(c20));
-        assert_eq!(eval(add1, &env), Ok(CInt(30)));
+    fn eval_min_and_max_errors() {
+        let env = Environment::new();
+        assert_eq!(
+            eval(call("min", vec![ListValue(vec![])]), &env),
+            Err(Exception::new(
+                "ValueError",
+                "min() arg is an empty sequence."
+            ))
+        );
+        assert_eq!(
+            eval(call("max", vec![CInt(1), string("a")]), &env),
+            Err(Exception::new(
+                "TypeError",
+                "(<) is only defined for numbers, booleans, strings, lists and tuples.",
+            ))
+        );
     }
 
     #[test]
-    fn eval_add_expression2() {
-        let env = HashMap::new();
-        let c10 = CInt(10);
-        let c20 = CInt(20);
-        let c30 = CInt(30);
-        let add1 = Add(Box::new(c10), Box::new(c20));
-        let add2 = Add(Box::new(add1), Box::new(c30));
-        assert_eq!(eval(add2, &env), Ok(CInt(60)));
+    fn eval_conversion_errors() {
+        let env = Environment::new();
+        assert_eq!(
+            eval(call("int", vec![string("4x")]), &env),
+            Err(Exception::new(
+                "ValueError",
+                "invalid literal for int() with base 10: '4x'.",
+            ))
+        );
+        assert_eq!(
+            eval(call("int", vec![ListValue(vec![])]), &env),
+            Err(Exception::new(
+                "TypeError",
+                "int() argument must be a string or a number, not 'list'.",
+            ))
+        );
+        assert_eq!(
+            eval(call("float", vec![string("one")]), &env),
+            Err(Exception::new(
+                "ValueError",
+                "could not convert string to float: 'one'."
+            ))
+        );
     }
 
     #[test]
-    fn eval_add_expression3() {
-        let env = HashMap::new();
-        let c10 = CInt(10);
-        let c20 = CReal(20.5);
-        let add1 = Add(Box::new(c10), Box::new(c20));
-        assert_eq!(eval(add1, &env), Ok(CReal(30.5)));
+    fn eval_sum_of_strings() {
+        let env = Environment::new();
+        assert_eq!(
+            eval(call("sum", vec![ListValue(vec![string("a")])]), &env),
+            Err(Exception::new(
+                "TypeError",
+                "unsupported operand type for sum(): 'str'."
+            ))
+        );
     }
 
     #[test]
-    fn eval_sub_expression1() {
-        let env = HashMap::new();
-        let c10 = CInt(10);
-        let c20 = CInt(20);
-        let sub1 = Sub(Box::new(c20), Box::new(c10));
-        assert_eq!(eval(sub1, &env), Ok(CInt(10)));
+    fn eval_builtin_with_wrong_number_of_arguments() {
+        let env = Environment::new();
+        assert_eq!(
+            eval(call("len", vec![CInt(1), CInt(2)]), &env),
+            Err(Exception::new(
+                "TypeError",
+                "len() takes 1 argument(s) but 2 were given."
+            ))
+        );
     }
 
     #[test]
-    fn eval_sub_expression2() {
-        let env = HashMap::new();
-        let c100 = CInt(100);
-        let c200 = CInt(300);
-        let sub1 = Sub(Box::new(c200), Box::new(c100));
-        assert_eq!(eval(sub1, &env), Ok(CInt(200)));
+    fn execute_functions_hide_builtins_of_the_same_name() {
+        /* a program's own function of the same name takes precedence */
+        let program = seq(
+            function("len", &["x"], Return(Box::new(CInt(0)))),
+            assign("n", call("len", vec![string("abc")])),
+        );
+        let env = execute(program, Environment::new()).unwrap();
+        assert_eq!(env.get("n"), Some(CInt(0)));
+    }
+
+    /*
+     * > def double(x): return x * 2
+     * > def add(a, b): return a + b
+     * > def odd(x): return x % 2
+     */
+    fn higher_order_definitions() -> Environment {
+        let definitions = seq(
+            function(
+                "double",
+                &["x"],
+                Return(Box::new(Mul(var("x"), Box::new(CInt(2))))),
+            ),
+            seq(
+                function(
+                    "add",
+                    &["a", "b"],
+                    Return(Box::new(add(*var("a"), *var("b")))),
+                ),
+                function(
+                    "odd",
+                    &["x"],
+                    Return(Box::new(Mod(var("x"), Box::new(CInt(2))))),
+                ),
+            ),
+        );
+        execute(definitions, Environment::new()).unwrap()
+    }
+
+    /* the values of the iterator an expression gives */
+    fn collected(exp: Expression, env: &Environment) -> Result<Vec<Expression>, Exception> {
+        builtins::collect(eval(exp, env)?, env)
+    }
+
+    fn ints(elements: &[i32]) -> Expression {
+        ListValue(elements.iter().map(|i| CInt(*i)).collect())
     }
 
     #[test]
-    fn eval_sub_expression3() {
-        let env = HashMap::new();
-        let c100 = CReal(100.5);
-        let c300 = CInt(300);
-        let sub1 = Sub(Box::new(c300), Box::new(c100));
-        assert_eq!(eval(sub1, &env), Ok(CReal(199.5)));
+    fn eval_map() {
+        let env = higher_order_definitions();
+        let map = |args: Vec<Expression>| collected(call("map", args), &env).map(ListValue);
+        assert_eq!(
+            map(vec![*var("double"), ints(&[1, 2, 3])]),
+            Ok(ints(&[2, 4, 6]))
+        );
+        assert_eq!(
+            map(vec![*var("add"), ints(&[1, 2, 3]), ints(&[10, 20])]),
+            Ok(ints(&[11, 22]))
+        );
+        assert_eq!(
+            map(vec![*var("str"), ints(&[1, 2])]),
+            Ok(ListValue(vec![string("1"), string("2")]))
+        );
+        assert_eq!(
+            eval(call("map", vec![*var("double"), ints(&[])]), &env)
+                .unwrap()
+                .to_string(),
+            "<map object>"
+        );
     }
 
     #[test]
-    fn eval_mul_expression1() {
-        let env = HashMap::new();
-        let c10 = CInt(10);
-        let c20 = CInt(20);
-        let mul1 = Mul(Box::new(c10), Box::new(c20));
-        assert_eq!(eval(mul1, &env), Ok(CInt(200)));
+    fn execute_map_over_an_infinite_generator() {
+        /*
+         * > def naturals():
+         * >   n = 0
+         * >   while True:
+         * >     yield n
+         * >     n = n + 1
+         * > def first():
+         * >   for x in map(double, filter(odd, naturals())): return x
+         * > x = first()
+         */
+        let naturals = function(
+            "naturals",
+            &[],
+            seq(
+                assign("n", CInt(0)),
+                While(
+                    Box::new(CTrue),
+                    Box::new(seq(Yield(var("n")), assign("n", add(*var("n"), CInt(1))))),
+                ),
+            ),
+        );
+        let odd_naturals = call("filter", vec![*var("odd"), call("naturals", vec![])]);
+        let first = function(
+            "first",
+            &[],
+            For(
+                String::from("x"),
+                Box::new(call("map", vec![*var("double"), odd_naturals])),
+                Box::new(Return(var("x"))),
+            ),
+        );
+        let program = seq(naturals, seq(first, assign("x", call("first", vec![]))));
+        let env = execute(program, higher_order_definitions()).unwrap();
+        assert_eq!(env.get("x"), Some(CInt(2)));
     }
 
     #[test]
-    fn eval_mul_expression2() {
-        let env = HashMap::new();
-        let c10 = CReal(10.5);
-        let c20 = CInt(20);
-        let mul1 = Mul(Box::new(c10), Box::new(c20));
-        assert_eq!(eval(mul1, &env), Ok(CReal(210.0)));
+    fn eval_filter() {
+        let env = higher_order_definitions();
+        assert_eq!(
+            collected(call("filter", vec![*var("odd"), ints(&[1, 2, 3, 4])]), &env),
+            Ok(vec![CInt(1), CInt(3)])
+        );
+        let values = ListValue(vec![CInt(0), CInt(1), string(""), string("a")]);
+        assert_eq!(
+            collected(call("filter", vec![CNone, values]), &env),
+            Ok(vec![CInt(1), string("a")])
+        );
     }
 
     #[test]
-    fn eval_div_expression1() {
-        let env = HashMap::new();
-        let c10 = CInt(10);
-        let c20 = CInt(20);
-        let div1 = Div(Box::new(c20), Box::new(c10));
-        assert_eq!(eval(div1, &env), Ok(CInt(2)));
+    fn eval_reduce() {
+        let env = higher_order_definitions();
+        assert_eq!(
+            eval(call("reduce", vec![*var("add"), ints(&[1, 2, 3, 4])]), &env),
+            Ok(CInt(10))
+        );
+        assert_eq!(
+            eval(call("reduce", vec![*var("add"), ints(&[]), CInt(5)]), &env),
+            Ok(CInt(5))
+        );
+        assert_eq!(
+            eval(call("reduce", vec![*var("add"), ints(&[])]), &env),
+            Err(Exception::new(
+                "TypeError",
+                "reduce() of empty iterable with no initial value."
+            ))
+        );
     }
 
     #[test]
-    fn eval_div_expression2() {
-        let env = HashMap::new();
-        let c10 = CInt(10);
-        let c3 = CInt(3);
-        let div1 = Div(Box::new(c10), Box::new(c3));
-        assert_eq!(eval(div1, &env), Ok(CInt(3)));
+    fn eval_any_and_all() {
+        let env = Environment::new();
+        let values = ListValue(vec![CInt(0), string(""), CInt(3)]);
+        assert_eq!(eval(call("any", vec![values]), &env), Ok(CTrue));
+        assert_eq!(
+            eval(call("all", vec![ListValue(vec![CInt(1), ints(&[])])]), &env),
+            Ok(CFalse)
+        );
+        assert_eq!(eval(call("all", vec![ints(&[])]), &env), Ok(CTrue));
     }
 
     #[test]
-    fn eval_div_expression3() {
-        let env = HashMap::new();
-        let c3 = CInt(3);
-        let c21 = CInt(21);
-        let div1 = Div(Box::new(c21), Box::new(c3));
-        assert_eq!(eval(div1, &env), Ok(CInt(7)));
+    fn eval_zip_and_enumerate() {
+        let env = Environment::new();
+        let pair = |i: i32, s: &str| Tuple(vec![CInt(i), string(s)]);
+        assert_eq!(
+            collected(call("zip", vec![ints(&[1, 2, 3]), string("ab")]), &env),
+            Ok(vec![pair(1, "a"), pair(2, "b")])
+        );
+        assert_eq!(collected(call("zip", vec![]), &env), Ok(vec![]));
+        let letters = ListValue(vec![string("a"), string("b")]);
+        assert_eq!(
+            collected(call("enumerate", vec![letters, CInt(1)]), &env),
+            Ok(vec![pair(1, "a"), pair(2, "b")])
+        );
     }
 
     #[test]
-    fn eval_div_expression4() {
-        let env = HashMap::new();
-        let c10 = CInt(10);
-        let c3 = CReal(3.0);
-        let div1 = Div(Box::new(c10), Box::new(c3));
-        let res = eval(div1, &env);
-        match res {
-            Ok(CReal(v)) => assert!(relative_eq!(v, 3.3333333333333335, epsilon = f64::EPSILON)),
-            Err(msg) => assert!(false, "{}", msg),
-            _ => assert!(false, "Not expected."),
-        }
+    fn eval_reversed() {
+        let env = Environment::new();
+        assert_eq!(
+            eval(
+                call("reversed", vec![Tuple(vec![CInt(1), CInt(2), CInt(3)])]),
+                &env
+            ),
+            Ok(ints(&[3, 2, 1]))
+        );
+        assert_eq!(
+            eval(call("reversed", vec![SetValue(vec![CInt(1)])]), &env),
+            Err(Exception::new(
+                "TypeError",
+                "'set' object is not reversible."
+            ))
+        );
     }
 
     #[test]
-    fn eval_variable() {
-        let env = HashMap::from([(String::from("x"), CInt(10)), (String::from("y"), CInt(20))]);
-        let v1 = Var(String::from("x"));
-        let v2 = Var(String::from("y"));
-        assert_eq!(eval(v1, &env), Ok(CInt(10)));
-        assert_eq!(eval(v2, &env), Ok(CInt(20)));
+    fn eval_sorted() {
+        let env = Environment::new();
+        assert_eq!(
+            eval(call("sorted", vec![ints(&[3, 1, 2])]), &env),
+            Ok(ints(&[1, 2, 3]))
+        );
+        assert_eq!(
+            eval(call("sorted", vec![ListValue(vec![CTrue, CFalse])]), &env),
+            Ok(ListValue(vec![CFalse, CTrue]))
+        );
+        assert_eq!(
+            eval(
+                call("sorted", vec![ListValue(vec![CInt(1), string("a")])]),
+                &env
+            ),
+            Err(Exception::new(
+                "TypeError",
+                "(<) is only defined for numbers, booleans, strings, lists and tuples.",
+            ))
+        );
     }
 
     #[test]
-    fn eval_expression_with_variables() {
-        let env = HashMap::from([(String::from("a"), CInt(5)), (String::from("b"), CInt(3))]);
-        let expr = Mul(
-            Box::new(Var(String::from("a"))),
-            Box::new(Add(Box::new(Var(String::from("b"))), Box::new(CInt(2)))),
+    fn eval_sorted_is_stable_in_both_directions() {
+        let env = Environment::new();
+        let strings = |elements: &[&str]| ListValue(elements.iter().map(|s| string(s)).collect());
+        let words = strings(&["bb", "a", "cc", "d"]);
+        assert_eq!(
+            eval(
+                call("sorted", vec![words.clone(), keyword("key", *var("len"))]),
+                &env
+            ),
+            Ok(strings(&["a", "d", "bb", "cc"]))
         );
-        assert_eq!(eval(expr, &env), Ok(CInt(25)));
+        let descending = call(
+            "sorted",
+            vec![
+                words,
+                keyword("key", *var("len")),
+                keyword("reverse", CTrue),
+            ],
+        );
+        assert_eq!(eval(descending, &env), Ok(strings(&["bb", "cc", "a", "d"])));
     }
 
     #[test]
-    fn eval_nested_expressions() {
-        let env = HashMap::new();
-        let expr = Add(
-            Box::new(Mul(Box::new(CInt(2)), Box::new(CInt(3)))),
-            Box::new(Sub(Box::new(CInt(10)), Box::new(CInt(4)))),
+    fn eval_callback_errors() {
+        let env = higher_order_definitions();
+        /* 'map' calls its function only when its values are needed */
+        assert_eq!(
+            collected(call("map", vec![*var("add"), ints(&[1, 2])]), &env),
+            Err(Exception::new(
+                "TypeError",
+                "map() expects a function of 1 argument(s), but 'add' takes 2."
+            ))
+        );
+        assert_eq!(
+            collected(call("map", vec![CInt(1), ints(&[1])]), &env),
+            Err(Exception::new("TypeError", "'int' object is not callable."))
+        );
+        assert_eq!(
+            eval(call("reduce", vec![*var("double"), ints(&[1, 2])]), &env),
+            Err(Exception::new(
+                "TypeError",
+                "reduce() expects a function of 2 argument(s), but 'double' takes 1."
+            ))
         );
-        assert_eq!(eval(expr, &env), Ok(CInt(12)));
     }
 
     #[test]
-    fn eval_variable_not_found() {
-        let env = HashMap::new();
-        let var_expr = Var(String::from("z"));
+    fn execute_seeded_random_module() {
+        /*
+         * > import random
+         * > _ = random.seed(5)
+         * > a = [random.randint(1, 100), random.random()]
+         * > _ = random.seed(5)
+         * > b = [random.randint(1, 100), random.random()]
+         */
+        let random = |name: &str, args| MethodCall(var("random"), String::from(name), args);
+        let draw = || {
+            ListValue(vec![
+                random("randint", vec![CInt(1), CInt(100)]),
+                random("random", vec![]),
+            ])
+        };
+        let program = seq(
+            seq(
+                Import(String::from("random")),
+                assign("_", random("seed", vec![CInt(5)])),
+            ),
+            seq(
+                assign("a", draw()),
+                seq(
+                    assign("_", random("seed", vec![CInt(5)])),
+                    assign("b", draw()),
+                ),
+            ),
+        );
+        let env = execute(program, Environment::new()).unwrap();
+        assert_eq!(env.get("a"), env.get("b"));
+        assert!(matches!(
+            env.get("a"),
+            Some(ListValue(draws)) if matches!(draws[..], [CInt(1..=100), CReal(_)])
+        ));
+    }
 
+    #[test]
+    fn execute_json_module() {
+        /*
+         * > import json
+         * > config = json.loads('{"port": 80, "hosts": ["a", "b"]}')
+         * > text = json.dumps(config, sort_keys=True)
+         * > try:
+         * >   broken = json.loads('{"port": }')
+         * > except ValueError as e:
+         * >   broken = e
+         */
+        let json = |name: &str, args| MethodCall(var("json"), String::from(name), args);
+        let load = |text: &str| json("loads", vec![string(text)]);
+        let program = seq(
+            seq(
+                Import(String::from("json")),
+                assign("config", load(r#"{"port": 80, "hosts": ["a", "b"]}"#)),
+            ),
+            seq(
+                assign(
+                    "text",
+                    json("dumps", vec![*var("config"), keyword("sort_keys", CTrue)]),
+                ),
+                Try(
+                    Box::new(assign("broken", load(r#"{"port": }"#))),
+                    vec![handler(
+                        Some("ValueError"),
+                        Some("e"),
+                        assign("broken", *var("e")),
+                    )],
+                    None,
+                ),
+            ),
+        );
+        let env = execute(program, Environment::new()).unwrap();
         assert_eq!(
-            eval(var_expr, &env),
-            Err(String::from("Variable z not found"))
+            env.get("config"),
+            Some(DictValue(vec![
+                (string("port"), CInt(80)),
+                (string("hosts"), ListValue(vec![string("a"), string("b")])),
+            ]))
+        );
+        assert_eq!(
+            env.get("text"),
+            Some(string(r#"{"hosts": ["a", "b"], "port": 80}"#))
+        );
+        assert_eq!(
+            env.get("broken"),
+            Some(Expression::Exception(
+                String::from("JSONDecodeError"),
+                Box::new(string("Expecting value: line 1 column 10 (char 9)."))
+            ))
         );
     }
 
-    #[test]
-    fn execute_assignment() {
-        let env = HashMap::new();
-        let assign_stmt = Assignment(Box::from(String::from("x")), Box::new(CInt(42)));
+    fn with(manager: Expression, name: &str, body: Statement) -> Statement {
+        With(Box::new(manager), Some(String::from(name)), Box::new(body))
+    }
 
-        match execute(assign_stmt, env) {
-            Ok(new_env) => assert_eq!(new_env.get("x"), Some(&CInt(42))),
-            Err(s) => assert!(false, "{}", s),
-        }
+    fn file_call(file: &str, method: &str, args: Vec<Expression>) -> Expression {
+        MethodCall(var(file), String::from(method), args)
     }
 
     #[test]
-    fn eval_summation() {
+    fn execute_file_io_with_statement() {
         /*
-         * (a test case for the following program)
-         *
-         * > x = 10
-         * > y = 0
-         * > while x >= 0:
-         * >   y = y + x
-         * >   x = x - 1
-         *
-         * After executing this program, 'x' must be zero and
-         * 'y' must be 55.
+         * > with open("log.txt", "w") as f:
+         * >   _ = f.write("one\n")
+         * >   _ = f.write("two\n")
+         * > with open("log.txt", "a") as f: _ = f.write("three")
+         * > with open("log.txt") as f: lines = f.readlines()
+         * > try:
+         * >   with open("log.txt") as g: raise ValueError("stop")
+         * > except ValueError: _ = None
+         * > try:
+         * >   missing = open("missing.txt")
+         * > except OSError as e:
+         * >   missing = e
          */
-        let env = HashMap::new();
+        let open = |args: Vec<Expression>| call("open", args);
+        let write = |text: &str| assign("_", file_call("f", "write", vec![string(text)]));
+        let program = seq(
+            seq(
+                with(
+                    open(vec![string("log.txt"), string("w")]),
+                    "f",
+                    seq(write("one\n"), write("two\n")),
+                ),
+                with(
+                    open(vec![string("log.txt"), string("a")]),
+                    "f",
+                    write("three"),
+                ),
+            ),
+            seq(
+                with(
+                    open(vec![string("log.txt")]),
+                    "f",
+                    assign("lines", file_call("f", "readlines", vec![])),
+                ),
+                seq(
+                    Try(
+                        Box::new(with(
+                            open(vec![string("log.txt")]),
+                            "g",
+                            raise("ValueError", "stop"),
+                        )),
+                        vec![handler(Some("ValueError"), None, assign("_", CNone))],
+                        None,
+                    ),
+                    Try(
+                        Box::new(assign("missing", open(vec![string("missing.txt")]))),
+                        vec![handler(
+                            Some("OSError"),
+                            Some("e"),
+                            assign("missing", *var("e")),
+                        )],
+                        None,
+                    ),
+                ),
+            ),
+        );
+        let files = Rc::new(RefCell::new(MemoryFileSystem::default()));
+        let mut env = Environment::new();
+        env.set_file_system(files.clone());
+        let env = execute(program, env).unwrap();
 
-        let a1 = Statement::Assignment(Box::new(String::from("x")), Box::new(CInt(10)));
-        let a2 = Statement::Assignment(Box::new(String::from("y")), Box::new(CInt(0)));
-        let a3 = Statement::Assignment(
-            Box::new(String::from("y")),
-            Box::new(Add(
-                Box::new(Var(String::from("y"))),
-                Box::new(Var(String::from("x"))),
-            )),
+        assert_eq!(files.borrow().files["log.txt"], "one\ntwo\nthree");
+        assert_eq!(
+            env.get("lines"),
+            Some(ListValue(vec![
+                string("one\n"),
+                string("two\n"),
+                string("three")
+            ]))
         );
-        let a4 = Statement::Assignment(
-            Box::new(String::from("x")),
-            Box::new(Sub(Box::new(Var(String::from("x"))), Box::new(CInt(1)))),
+        /* the file is closed even though the body raised */
+        assert_eq!(
+            eval(file_call("g", "read", vec![]), &env),
+            Err(Exception::new(
+                "ValueError",
+                "I/O operation on closed file."
+            ))
         );
+        assert_eq!(
+            env.get("missing"),
+            Some(Expression::Exception(
+                String::from("FileNotFoundError"),
+                Box::new(string("No such file or directory: 'missing.txt'."))
+            ))
+        );
+    }
 
-        let seq1 = Statement::Sequence(Box::new(a3), Box::new(a4));
-
-        let while_statement = Statement::While(
-            Box::new(GT(Box::new(Var(String::from("x"))), Box::new(CInt(0)))),
-            Box::new(seq1),
+    #[test]
+    fn execute_with_objects_and_restricted_files() {
+        /*
+         * > class Session:
+         * >   def __enter__(self):
+         * >     self.state = "open"
+         * >     return self
+         * >   def __exit__(self, type, value, traceback): self.state = "closed"
+         * > with Session() as s: inside = s.state
+         * > try:
+         * >   secret = open("../secret.txt").read()
+         * > except OSError as e:
+         * >   secret = e
+         */
+        let session = class(
+            "Session",
+            None,
+            vec![
+                method(
+                    "__enter__",
+                    &[],
+                    seq(set_field("state", string("open")), Return(var("self"))),
+                ),
+                method(
+                    "__exit__",
+                    &["type", "value", "traceback"],
+                    set_field("state", string("closed")),
+                ),
+            ],
+        );
+        let state = |object: &str| FieldAccess(var(object), String::from("state"));
+        let program = seq(
+            seq(
+                session,
+                with(call("Session", vec![]), "s", assign("inside", state("s"))),
+            ),
+            Try(
+                Box::new(assign(
+                    "secret",
+                    MethodCall(
+                        Box::new(call("open", vec![string("../secret.txt")])),
+                        String::from("read"),
+                        vec![],
+                    ),
+                )),
+                vec![handler(
+                    Some("OSError"),
+                    Some("e"),
+                    assign("secret", *var("e")),
+                )],
+                None,
+            ),
         );
 
-        let seq2 = Statement::Sequence(Box::new(a2), Box::new(while_statement));
-        let program = Statement::Sequence(Box::new(a1), Box::new(seq2));
+        let root = std::env::temp_dir().join(format!("rpy-with-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("allowed")).unwrap();
+        std::fs::write(root.join("secret.txt"), "secret").unwrap();
+        let mut env = Environment::new();
+        env.set_file_system(Rc::new(RefCell::new(
+            DiskFileSystem::within(root.join("allowed")).unwrap(),
+        )));
+        let env = execute(program, env).unwrap();
+        std::fs::remove_dir_all(root).unwrap();
 
-        match execute(program, env) {
-            Ok(new_env) => {
-                assert_eq!(new_env.get("y"), Some(&CInt(55)));
-                assert_eq!(new_env.get("x"), Some(&CInt(0)));
-            }
-            Err(s) => assert!(false, "{}", s),
-        }
+        assert_eq!(env.get("inside"), Some(string("open")));
+        assert_eq!(eval(state("s"), &env), Ok(string("closed")));
+        assert_eq!(
+            env.get("secret"),
+            Some(Expression::Exception(
+                String::from("PermissionError"),
+                Box::new(string("Permission denied: '../secret.txt'."))
+            ))
+        );
+        assert_eq!(
+            execute(with(CInt(1), "x", assign("_", CNone)), Environment::new()),
+            Err(Exception::new(
+                "TypeError",
+                "'int' object does not support the context manager protocol."
+            ))
+        );
+    }
+
+    /*
+     * > class Suppress:
+     * >   def __enter__(self): return self
+     * >   def __exit__(self, type, value, traceback):
+     * >     self.seen = [type, value, traceback]
+     * >     return True
+     */
+    fn suppress() -> Statement {
+        let seen = ListValue(vec![*var("type"), *var("value"), *var("traceback")]);
+        class(
+            "Suppress",
+            None,
+            vec![
+                method("__enter__", &[], Return(var("self"))),
+                method(
+                    "__exit__",
+                    &["type", "value", "traceback"],
+                    seq(set_field("seen", seen), Return(Box::new(CTrue))),
+                ),
+            ],
+        )
     }
 
     #[test]
-    fn eval_simple_if_then_else() {
+    fn execute_exit_sees_and_suppresses_exceptions() {
         /*
-         * Test for simple if-then-else statement
-         *
-         * > x = 10
-         * > if x > 5:
-         * >   y = 1
-         * > else:
-         * >   y = 0
-         *
-         * After executing, 'y' should be 1.
+         * > with Suppress() as s:
+         * >   raise ValueError("stop")
+         * > with Suppress() as t:
+         * >   ok = 1
          */
-        let env = HashMap::new();
-
-        let condition = GT(Box::new(Var(String::from("x"))), Box::new(CInt(5)));
-        let then_stmt = Statement::Assignment(Box::new(String::from("y")), Box::new(CInt(1)));
-        let else_stmt = Statement::Assignment(Box::new(String::from("y")), Box::new(CInt(0)));
+        let program = seq(
+            suppress(),
+            seq(
+                with(call("Suppress", vec![]), "s", raise("ValueError", "stop")),
+                with(call("Suppress", vec![]), "t", assign("ok", CInt(1))),
+            ),
+        );
 
-        let if_statement = Statement::IfThenElse(
-            Box::new(condition),
-            Box::new(then_stmt),
-            Box::new(else_stmt),
+        let env = execute(program, Environment::new()).unwrap();
+        let seen = |object: &str| FieldAccess(var(object), String::from("seen"));
+        assert_eq!(
+            eval(seen("s"), &env),
+            Ok(ListValue(vec![
+                ClassValue(String::from("ValueError")),
+                Expression::Exception(String::from("ValueError"), Box::new(string("stop"))),
+                CNone
+            ]))
         );
+        assert_eq!(eval(seen("t"), &env), Ok(ListValue(vec![CNone; 3])));
+    }
 
-        let setup_stmt = Statement::Assignment(Box::new(String::from("x")), Box::new(CInt(10)));
-        let program = Statement::Sequence(Box::new(setup_stmt), Box::new(if_statement));
+    #[test]
+    fn execute_generator_goes_on_after_a_suppressed_exception() {
+        /*
+         * > def gen():
+         * >   with Suppress():
+         * >     yield 1
+         * >     raise ValueError("stop")
+         * >   yield 2
+         */
+        let body = seq(
+            With(
+                Box::new(call("Suppress", vec![])),
+                None,
+                Box::new(seq(Yield(Box::new(CInt(1))), raise("ValueError", "stop"))),
+            ),
+            Yield(Box::new(CInt(2))),
+        );
+        let gen = seq(suppress(), function("gen", &[], body));
 
-        match execute(program, env) {
-            Ok(new_env) => assert_eq!(new_env.get("y"), Some(&CInt(1))),
-            Err(s) => assert!(false, "{}", s),
-        }
+        assert_eq!(
+            generated(gen, call("gen", vec![])),
+            Ok(Some(ListValue(vec![CInt(1), CInt(2)])))
+        );
     }
 
     // #[test]
@@ -584,7 +7179,7 @@ mod tests {
     //      *
     //      * After executing, 'y' should be 7 and 'x' should be 0.
     //      */
-    //     let env = HashMap::new();
+    //     let env = Environment::new();
 
     //     let a1 = Statement::Assignment(Box::new(String::from("x")), Box::new(CInt(3)));
     //     let a2 = Statement::Assignment(Box::new(String::from("y")), Box::new(CInt(10)));
@@ -636,7 +7231,7 @@ mod tests {
     //      *
     //      * After executing, 'y' should be 1.
     //      */
-    //     let env = HashMap::new();
+    //     let env = Environment::new();
 
     //     let inner_then_stmt =
     //         Statement::Assignment(Box::new(String::from("y")), Box::new(CInt(1)));
@@ -677,7 +7272,7 @@ mod tests {
     //      *
     //      * After executing, 'x' should be 5, 'y' should be 0, and 'z' should be 13.
     //      */
-    //     let env = HashMap::new();
+    //     let env = Environment::new();
 
     //     let a1 = Statement::Assignment(Box::new(String::from("x")), Box::new(CInt(5)));
     //     let a2 = Statement::Assignment(Box::new(String::from("y")), Box::new(CInt(0)));
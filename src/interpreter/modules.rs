@@ -0,0 +1,642 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::interpreter::interpreter::execute;
+use crate::interpreter::interpreter::Environment;
+use crate::interpreter::interpreter::Exception;
+use crate::ir::ast::Name;
+use crate::ir::ast::Statement;
+use crate::ir::ast::Type;
+use crate::ir::builtins::standard_module;
+use crate::tc::type_checker;
+
+/// Why a program or one of the modules it imports could not run.
+#[derive(Debug, PartialEq)]
+pub enum ModuleError {
+    /* no source was found for the module */
+    NotFound(Name),
+    /* modules that import each other: the chain of imports, from the
+     * first module back to itself */
+    Cycle(Vec<Name>),
+    /* the source of the module could not be read or parsed */
+    Load(Name, String),
+    /* the module does not type check */
+    Type(Name, String),
+    /* executing the module raised an exception */
+    Runtime(Name, Exception),
+}
+
+impl fmt::Display for ModuleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ModuleError::NotFound(name) => write!(f, "ImportError: no module named '{}'.", name),
+            ModuleError::Cycle(chain) => {
+                write!(f, "ImportError: circular import: {}.", chain.join(" -> "))
+            }
+            ModuleError::Load(name, message) => {
+                write!(f, "ImportError: cannot load module '{}': {}", name, message)
+            }
+            ModuleError::Type(name, message) => write!(f, "in module '{}': {}", name, message),
+            ModuleError::Runtime(name, exception) => {
+                write!(f, "in module '{}': {}", name, exception)
+            }
+        }
+    }
+}
+
+/// Finds the program of a module, given its name.
+pub trait Loader {
+    fn load(&self, module: &str) -> Result<Statement, ModuleError>;
+}
+
+/* modules built in memory, by name */
+impl Loader for HashMap<Name, Statement> {
+    fn load(&self, module: &str) -> Result<Statement, ModuleError> {
+        self.get(module)
+            .cloned()
+            .ok_or(ModuleError::NotFound(module.to_string()))
+    }
+}
+
+/// Loads module `a.b` from the file `a/b.rpy`, found in the directory
+/// of the main script or, failing that, in the directories of the
+/// search path, in order. The source is turned into a program by the
+/// parser the loader is given.
+pub struct FileLoader {
+    roots: Vec<PathBuf>,
+    parse: fn(&str) -> Result<Statement, String>,
+}
+
+impl FileLoader {
+    pub fn new(
+        main: &Path,
+        search_path: Vec<PathBuf>,
+        parse: fn(&str) -> Result<Statement, String>,
+    ) -> FileLoader {
+        let mut roots = vec![main.parent().unwrap_or(Path::new("")).to_path_buf()];
+        roots.extend(search_path);
+        FileLoader { roots, parse }
+    }
+
+    pub fn locate(&self, module: &str) -> Option<PathBuf> {
+        let file = PathBuf::from(format!("{}.rpy", module.replace('.', "/")));
+        self.roots
+            .iter()
+            .map(|root| root.join(&file))
+            .find(|path| path.is_file())
+    }
+}
+
+impl Loader for FileLoader {
+    fn load(&self, module: &str) -> Result<Statement, ModuleError> {
+        let path = self
+            .locate(module)
+            .ok_or(ModuleError::NotFound(module.to_string()))?;
+        let source = fs::read_to_string(path)
+            .map_err(|e| ModuleError::Load(module.to_string(), e.to_string()))?;
+        (self.parse)(&source).map_err(|message| ModuleError::Load(module.to_string(), message))
+    }
+}
+
+/* a module that was type checked and executed */
+struct Module {
+    module_type: Type,
+    env: Environment,
+}
+
+/// Runs programs together with the modules they import. Each module is
+/// type checked and executed once, before the program that imports it,
+/// and later imports share the result. Loading is eager: every import
+/// of a program counts, even one in a function that is never called or
+/// in a branch that is never taken, as the type checker needs the types
+/// of all the modules the program may use.
+pub struct Modules<L: Loader> {
+    loader: L,
+    loaded: HashMap<Name, Module>,
+    /* the modules being imported, outermost first */
+    loading: Vec<Name>,
+}
+
+impl<L: Loader> Modules<L> {
+    pub fn new(loader: L) -> Modules<L> {
+        Modules {
+            loader,
+            loaded: HashMap::new(),
+            loading: vec![],
+        }
+    }
+
+    /// Type checks and executes the main program in the given
+    /// environment, once the modules it imports are ready; the result is
+    /// the environment it leaves. The modules share the options, the
+    /// console, the random generator and the files of the environment.
+    pub fn run(
+        &mut self,
+        program: Statement,
+        env: Environment,
+    ) -> Result<Environment, ModuleError> {
+        let (_, env) = self.execute("__main__", program, env)?;
+        Ok(env)
+    }
+
+    fn import(&mut self, name: &str, importer: &Environment) -> Result<&Module, ModuleError> {
+        if let Some(start) = self.loading.iter().position(|m| m == name) {
+            let mut chain = self.loading[start..].to_vec();
+            chain.push(name.to_string());
+            return Err(ModuleError::Cycle(chain));
+        }
+        if !self.loaded.contains_key(name) {
+            let program = self.loader.load(name)?;
+            self.loading.push(name.to_string());
+            let result = self.execute(name, program, importer.for_module(name));
+            self.loading.pop();
+            let (module_type, env) = result?;
+            self.loaded
+                .insert(name.to_string(), Module { module_type, env });
+        }
+        Ok(&self.loaded[name])
+    }
+
+    fn execute(
+        &mut self,
+        name: &str,
+        program: Statement,
+        mut env: Environment,
+    ) -> Result<(Type, Environment), ModuleError> {
+        let mut types = type_checker::Environment::new();
+        /* the interpreter and the type checker provide the standard
+         * modules themselves */
+        let dependencies = imports(&program).into_iter();
+        for dependency in dependencies.filter(|m| standard_module(m).is_none()) {
+            let module = self.import(&dependency, &env)?;
+            type_checker::declare_module(&dependency, module.module_type.clone(), &mut types);
+            env.add_module(dependency, module.env.clone());
+        }
+        let checked = type_checker::check_stmt(program.clone(), &types)
+            .map_err(|message| ModuleError::Type(name.to_string(), message))?;
+        let env = execute(program, env)
+            .map_err(|exception| ModuleError::Runtime(name.to_string(), exception))?;
+        Ok((type_checker::module_type(name, &checked), env))
+    }
+}
+
+/* the modules a program imports, anywhere in it (dead code included),
+ * in order */
+fn imports(stmt: &Statement) -> Vec<Name> {
+    let mut modules = vec![];
+    collect_imports(stmt, &mut modules);
+    modules
+}
+
+fn collect_imports(stmt: &Statement, modules: &mut Vec<Name>) {
+    match stmt {
+        Statement::Import(module) | Statement::FromImport(module, _)
+            if !modules.contains(module) =>
+        {
+            modules.push(module.clone())
+        }
+        Statement::Sequence(s1, s2) | Statement::IfThenElse(_, s1, s2) => {
+            collect_imports(s1, modules);
+            collect_imports(s2, modules);
+        }
+        Statement::While(_, body) | Statement::For(_, _, body) | Statement::With(_, _, body) => {
+            collect_imports(body, modules)
+        }
+        Statement::FuncDef(function) => collect_imports(&function.body, modules),
+        Statement::ClassDef(class) => class
+            .methods
+            .iter()
+            .for_each(|method| collect_imports(&method.body, modules)),
+        Statement::Match(_, cases) => cases
+            .iter()
+            .for_each(|case| collect_imports(&case.body, modules)),
+        Statement::Try(body, handlers, finally) => {
+            collect_imports(body, modules);
+            handlers
+                .iter()
+                .for_each(|handler| collect_imports(&handler.body, modules));
+            if let Some(finally) = finally {
+                collect_imports(finally, modules);
+            }
+        }
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Exception;
+    use super::*;
+    use crate::interpreter::builtins::files::MemoryFileSystem;
+    use crate::ir::ast::ArithmeticOp;
+    use crate::ir::ast::Class;
+    use crate::ir::ast::Expression;
+    use crate::ir::ast::Expression::*;
+    use crate::ir::ast::Function;
+    use crate::ir::ast::Statement::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn seq(statements: Vec<Statement>) -> Statement {
+        statements
+            .into_iter()
+            .reduce(|s1, s2| Sequence(Box::new(s1), Box::new(s2)))
+            .unwrap()
+    }
+
+    fn assign(name: &str, exp: Expression) -> Statement {
+        Assignment(Box::new(String::from(name)), Box::new(exp))
+    }
+
+    fn var(name: &str) -> Box<Expression> {
+        Box::new(Var(String::from(name)))
+    }
+
+    fn import(module: &str) -> Statement {
+        Import(String::from(module))
+    }
+
+    fn from_import(module: &str, names: &[&str]) -> Statement {
+        FromImport(
+            String::from(module),
+            names.iter().map(|n| String::from(*n)).collect(),
+        )
+    }
+
+    fn function(name: &str, kind: Type, body: Statement) -> Function {
+        Function {
+            name: String::from(name),
+            kind,
+            params: vec![],
+            defaults: vec![],
+            varargs: None,
+            kwargs: None,
+            body: Box::new(body),
+        }
+    }
+
+    fn call(module: &str, name: &str) -> Expression {
+        MethodCall(var(module), String::from(name), vec![])
+    }
+
+    /*
+     * > # counter.rpy
+     * > count = 0
+     * > def incr() -> int:
+     * >   global count
+     * >   count += 1
+     * >   return count
+     * > def helper() -> int:
+     * >   hidden = 1
+     * >   return hidden
+     * > class Counter:
+     * >   def current(self) -> int:
+     * >     return count
+     */
+    fn counter() -> Statement {
+        let incr = function(
+            "incr",
+            Type::TInteger,
+            seq(vec![
+                Global(vec![String::from("count")]),
+                AugmentedAssignment(var("count"), ArithmeticOp::Add, Box::new(CInt(1))),
+                Return(var("count")),
+            ]),
+        );
+        let helper = function(
+            "helper",
+            Type::TInteger,
+            seq(vec![assign("hidden", CInt(1)), Return(var("hidden"))]),
+        );
+        let mut current = function("current", Type::TInteger, Return(var("count")));
+        current.params = vec![(String::from("self"), Type::TObject(String::from("Counter")))];
+        let class = Class {
+            name: String::from("Counter"),
+            base: None,
+            fields: vec![],
+            methods: vec![current],
+        };
+        seq(vec![
+            assign("count", CInt(0)),
+            FuncDef(Box::new(incr)),
+            FuncDef(Box::new(helper)),
+            ClassDef(class),
+        ])
+    }
+
+    fn modules(sources: Vec<(&str, Statement)>) -> Modules<HashMap<Name, Statement>> {
+        Modules::new(
+            sources
+                .into_iter()
+                .map(|(name, program)| (String::from(name), program))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn import_modules_once() {
+        /*
+         * > # user.rpy
+         * > import counter
+         * > first = counter.incr()
+         *
+         * > import user
+         * > from counter import incr, Counter
+         * > second = incr()
+         * > count = 100          # not the module's variable
+         * > current = Counter().current()
+         * > third = user.counter.count
+         */
+        let user = seq(vec![
+            import("counter"),
+            assign("first", call("counter", "incr")),
+        ]);
+        let program = seq(vec![
+            import("user"),
+            from_import("counter", &["incr", "Counter"]),
+            assign("second", FuncCall(String::from("incr"), vec![])),
+            assign("count", CInt(100)),
+            assign(
+                "current",
+                MethodCall(
+                    Box::new(FuncCall(String::from("Counter"), vec![])),
+                    String::from("current"),
+                    vec![],
+                ),
+            ),
+            assign(
+                "third",
+                FieldAccess(
+                    Box::new(FieldAccess(var("user"), String::from("counter"))),
+                    String::from("count"),
+                ),
+            ),
+        ]);
+        let env = modules(vec![("counter", counter()), ("user", user)])
+            .run(program, Environment::new())
+            .unwrap();
+        assert_eq!(env.get("second"), Some(CInt(2)));
+        assert_eq!(env.get("current"), Some(CInt(2)));
+        assert_eq!(env.get("third"), Some(CInt(2)));
+        assert_eq!(
+            env.get("user").map(|m| m.to_string()),
+            Some(String::from("<module 'user'>"))
+        );
+    }
+
+    /*
+     * > class Node:
+     * >   def kind(self) -> str:
+     * >     return <module>
+     * > def make() -> Node:
+     * >   return Node()
+     */
+    fn nodes(module: &str) -> Statement {
+        let node = || Type::TObject(String::from("Node"));
+        let mut kind = function(
+            "kind",
+            Type::TString,
+            Return(Box::new(CString(String::from(module)))),
+        );
+        kind.params = vec![(String::from("self"), node())];
+        let class = Class {
+            name: String::from("Node"),
+            base: None,
+            fields: vec![],
+            methods: vec![kind],
+        };
+        let make = function(
+            "make",
+            node(),
+            Return(Box::new(FuncCall(String::from("Node"), vec![]))),
+        );
+        seq(vec![ClassDef(class), FuncDef(Box::new(make))])
+    }
+
+    #[test]
+    fn import_classes_of_the_same_name() {
+        /*
+         * > import a
+         * > from b import Node
+         * > first = a.Node().kind()
+         * > second = Node().kind()
+         * > third = a.make().kind()
+         */
+        let kind = |object: Expression| MethodCall(Box::new(object), String::from("kind"), vec![]);
+        let program = seq(vec![
+            import("a"),
+            from_import("b", &["Node"]),
+            assign("first", kind(call("a", "Node"))),
+            assign("second", kind(FuncCall(String::from("Node"), vec![]))),
+            assign("third", kind(call("a", "make"))),
+        ]);
+        let env = modules(vec![("a", nodes("a")), ("b", nodes("b"))])
+            .run(program, Environment::new())
+            .unwrap();
+        assert_eq!(env.get("first"), Some(CString(String::from("a"))));
+        assert_eq!(env.get("second"), Some(CString(String::from("b"))));
+        assert_eq!(env.get("third"), Some(CString(String::from("a"))));
+
+        /* > import a; x = Node() */
+        let bare = seq(vec![
+            import("a"),
+            assign("x", FuncCall(String::from("Node"), vec![])),
+        ]);
+        assert_eq!(
+            modules(vec![("a", nodes("a"))]).run(bare, Environment::new()),
+            Err(ModuleError::Type(
+                String::from("__main__"),
+                String::from("[Name Error] 'Node' is not defined.")
+            ))
+        );
+    }
+
+    #[test]
+    fn import_standard_modules() {
+        /*
+         * > import math
+         * > from math import floor, pi
+         * > root = math.sqrt(16)
+         * > three = floor(pi)
+         */
+        let sqrt = MethodCall(var("math"), String::from("sqrt"), vec![CInt(16)]);
+        let floor = FuncCall(String::from("floor"), vec![*var("pi")]);
+        let program = seq(vec![
+            import("math"),
+            from_import("math", &["floor", "pi"]),
+            assign("root", sqrt),
+            assign("three", floor),
+        ]);
+        let env = modules(vec![]).run(program, Environment::new()).unwrap();
+        assert_eq!(env.get("root"), Some(CReal(4.0)));
+        assert_eq!(env.get("three"), Some(CInt(3)));
+
+        let text = CString(String::from("16"));
+        let program = seq(vec![
+            import("math"),
+            assign(
+                "root",
+                MethodCall(var("math"), String::from("sqrt"), vec![text]),
+            ),
+        ]);
+        assert_eq!(
+            modules(vec![]).run(program, Environment::new()),
+            Err(ModuleError::Type(
+                String::from("__main__"),
+                String::from(
+                    "[Type Error] argument 1 of sqrt() has type TReal but is given a value of type TString."
+                )
+            ))
+        );
+    }
+
+    #[test]
+    fn modules_share_the_files_of_the_importer() {
+        /*
+         * > # data.rpy
+         * > text = open("data.txt").read()
+         *
+         * > import data
+         * > text = data.text
+         */
+        let open = FuncCall(
+            String::from("open"),
+            vec![CString(String::from("data.txt"))],
+        );
+        let data = assign(
+            "text",
+            MethodCall(Box::new(open), String::from("read"), vec![]),
+        );
+        let program = seq(vec![
+            import("data"),
+            assign("text", FieldAccess(var("data"), String::from("text"))),
+        ]);
+        let mut env = Environment::new();
+        env.set_file_system(Rc::new(RefCell::new(MemoryFileSystem::new(&[(
+            "data.txt", "shared",
+        )]))));
+        let env = modules(vec![("data", data)]).run(program, env).unwrap();
+        assert_eq!(env.get("text"), Some(CString(String::from("shared"))));
+    }
+
+    #[test]
+    fn import_inside_with() {
+        /*
+         * > with open("d.txt") as f:
+         * >   import counter
+         * >   n = counter.incr()
+         */
+        let open = FuncCall(String::from("open"), vec![CString(String::from("d.txt"))]);
+        let program = With(
+            Box::new(open),
+            Some(String::from("f")),
+            Box::new(seq(vec![
+                import("counter"),
+                assign("n", call("counter", "incr")),
+            ])),
+        );
+        let mut env = Environment::new();
+        env.set_file_system(Rc::new(RefCell::new(MemoryFileSystem::new(&[(
+            "d.txt", "",
+        )]))));
+        let env = modules(vec![("counter", counter())])
+            .run(program, env)
+            .unwrap();
+        assert_eq!(env.get("n"), Some(CInt(1)));
+    }
+
+    #[test]
+    fn import_errors() {
+        let run = |sources, program| modules(sources).run(program, Environment::new());
+
+        assert_eq!(
+            run(vec![], import("missing")),
+            Err(ModuleError::NotFound(String::from("missing")))
+        );
+        let cycle = run(vec![("a", import("b")), ("b", import("a"))], import("a")).unwrap_err();
+        assert_eq!(
+            cycle.to_string(),
+            "ImportError: circular import: a -> b -> a."
+        );
+        assert_eq!(
+            run(
+                vec![("counter", counter())],
+                from_import("counter", &["hidden"])
+            ),
+            Err(ModuleError::Type(
+                String::from("__main__"),
+                String::from("[Name Error] cannot import name 'hidden' from 'counter'.")
+            ))
+        );
+        assert_eq!(
+            run(
+                vec![("counter", counter())],
+                seq(vec![
+                    import("counter"),
+                    assign("x", call("counter", "reset"))
+                ])
+            ),
+            Err(ModuleError::Type(
+                String::from("__main__"),
+                String::from("[Type Error] module 'counter' has no attribute 'reset'.")
+            ))
+        );
+        let failing = assign("x", Div(Box::new(CInt(1)), Box::new(CInt(0))));
+        assert_eq!(
+            run(vec![("failing", failing)], import("failing")),
+            Err(ModuleError::Runtime(
+                String::from("failing"),
+                Exception::new("ZeroDivisionError", "division by zero.")
+            ))
+        );
+        assert_eq!(
+            execute(import("counter"), Environment::new()),
+            Err(Exception::new("ImportError", "no module named 'counter'."))
+        );
+    }
+
+    /* a stand-in for a parser: the program binds the source to 'source' */
+    fn parse(source: &str) -> Result<Statement, String> {
+        match source.strip_prefix("error: ") {
+            Some(message) => Err(String::from(message)),
+            None => Ok(assign("source", CString(source.trim().to_string()))),
+        }
+    }
+
+    #[test]
+    fn load_modules_from_files() {
+        let root = std::env::temp_dir().join(format!("rpy-modules-{}", std::process::id()));
+        let (scripts, library) = (root.join("scripts"), root.join("lib"));
+        fs::create_dir_all(scripts.join("pkg")).unwrap();
+        fs::create_dir_all(&library).unwrap();
+        fs::write(scripts.join("pkg").join("local.rpy"), "beside main").unwrap();
+        fs::write(scripts.join("shared.rpy"), "beside main").unwrap();
+        fs::write(library.join("shared.rpy"), "in the search path").unwrap();
+        fs::write(library.join("util.rpy"), "in the search path").unwrap();
+        fs::write(library.join("broken.rpy"), "error: unexpected token").unwrap();
+
+        let loader = FileLoader::new(&scripts.join("main.rpy"), vec![library.clone()], parse);
+        let source = |module| match loader.load(module) {
+            Ok(Assignment(_, source)) => Ok(source.to_string()),
+            other => Err(other),
+        };
+        assert_eq!(source("pkg.local"), Ok(String::from("beside main")));
+        assert_eq!(source("shared"), Ok(String::from("beside main")));
+        assert_eq!(source("util"), Ok(String::from("in the search path")));
+        assert_eq!(
+            source("broken"),
+            Err(Err(ModuleError::Load(
+                String::from("broken"),
+                String::from("unexpected token")
+            )))
+        );
+        assert_eq!(
+            source("nowhere"),
+            Err(Err(ModuleError::NotFound(String::from("nowhere"))))
+        );
+        fs::remove_dir_all(root).unwrap();
+    }
+}
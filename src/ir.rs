@@ -1 +1,3 @@
 pub mod ast;
+pub mod builtins;
+pub mod format;
@@ -1,6 +1,13 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt;
+use std::rc::Rc;
+use std::rc::Weak;
+
 pub type Name = String;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum Type {
     TInteger,
     TBool,
@@ -8,6 +15,49 @@ pub enum Type {
     TString,
     TList(Box<Type>),
     TTuple(Vec<Type>),
+    TSet(Box<Type>),
+    /* dictionary: the types of its keys and of its values */
+    TDict(Box<Type>, Box<Type>),
+    TException,
+    /* the type of 'None', returned by functions without a result */
+    TNone,
+    /* 'Maybe[T]' holds 'Just(x)' or 'Nothing' */
+    TMaybe(Box<Type>),
+    /* 'Result[T, E]' holds 'Ok(x)' of type T or 'Err(e)' of type E */
+    TResult(Box<Type>, Box<Type>),
+    /* function type: its result type and its parameters */
+    TFunction(Box<Type>, Signature),
+    /* class: its name, base class, and the types of its fields and of
+     * its methods (without 'self'), inherited ones included */
+    TClass(Name, Option<Name>, Vec<(Name, Type)>, Vec<(Name, Type)>),
+    /* instance of the named class */
+    TObject(Name),
+    /* iterator over values of the given type, such as a generator */
+    TIterator(Box<Type>),
+    /* the integers 'range' gives: an iterator that also has a length,
+     * membership and indexing */
+    TRange,
+    /* file opened by 'open' */
+    TFile,
+    /* imported module: its name and the types of its top-level names */
+    TModule(Name, Vec<(Name, Type)>),
+    /* the class of a value, as 'type' gives it */
+    TType,
+    /* record type: its name and its fields, in declaration order */
+    TRecord(Name, Vec<(Name, Type)>),
+    /* algebraic data type: its name and its value constructors */
+    TAlgebraicData(Name, Vec<ValueConstructor>),
+    /* placeholder for types that cannot be inferred (e.g., the
+     * element type of an empty list); it is compatible with any type */
+    TAny,
+}
+
+/* 'Rect(real, real)': a constructor of an algebraic data type and the
+ * types of its payload */
+#[derive(Debug, PartialEq, Clone)]
+pub struct ValueConstructor {
+    pub name: Name,
+    pub types: Vec<Type>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -18,27 +68,830 @@ pub enum Expression {
     CInt(i32),
     CReal(f64),
     CString(String),
+    CNone,
+
+    /* optional and fallible values */
+    CJust(Box<Expression>),
+    CNothing,
+    COk(Box<Expression>),
+    CErr(Box<Expression>),
+    Unwrap(Box<Expression>),
+    UnwrapOr(Box<Expression>, Box<Expression>),
+    IsError(Box<Expression>),
+    IsNothing(Box<Expression>),
+    /* 'e?': the value inside 'Just' or 'Ok'; on 'Nothing' or 'Err', the
+     * enclosing function returns it right away */
+    Propagate(Box<Expression>),
+
+    /* instance of a class, shared by every value that refers to it */
+    Object(ObjectRef),
+    /* iterator over a sequence or a running generator */
+    Iterator(IteratorRef),
+    /* file opened by 'open', shared by every value that refers to it */
+    File(FileRef),
+    /* imported module: its name and its top-level variables */
+    Module(Name, ScopeRef),
+    /* the class of a value, as 'type' gives it, by its name */
+    ClassValue(Name),
+    /* 'super()', only valid as the receiver of a method call */
+    Super,
+    IsInstance(Box<Expression>, Name),
+
+    /* function value, bound to its name by a definition */
+    FunctionValue(Box<Function>, Closure),
+    /* function provided by the interpreter, by its qualified name (e.g.,
+     * 'math.sqrt') */
+    BuiltinFunction(Name),
+    FuncCall(Name, Vec<Expression>),
+    /* 'name=value', only valid among the arguments of a call */
+    KeywordArgument(Name, Box<Expression>),
+
+    /* exception value: its kind (e.g., ValueError) and message */
+    Exception(Name, Box<Expression>),
+
+    /* compound values */
+    ListValue(Vec<Expression>),
+    Tuple(Vec<Expression>),
+    /* sets and dictionaries keep their elements in insertion order,
+     * without duplicates (keys, for dictionaries) */
+    SetValue(Vec<Expression>),
+    DictValue(Vec<(Expression, Expression)>),
+
+    /* comprehensions '[e for x in xs if c]', '{e for x in xs}' and
+     * '{k: v for x in xs}': the element(s) and the clauses, in order */
+    ListComprehension(Box<Expression>, Vec<Clause>),
+    SetComprehension(Box<Expression>, Vec<Clause>),
+    DictComprehension(Box<Expression>, Box<Expression>, Vec<Clause>),
+
+    /* record 'Point(x=1, y=2)': its type name and its fields */
+    Record(Name, Vec<(Name, Expression)>),
+    FieldAccess(Box<Expression>, Name),
+
+    /* value of an algebraic data type 'Circle(1.0)': the constructor
+     * and its payload */
+    ADTConstructor(Name, Vec<Expression>),
 
     /* variable reference */
     Var(Name),
 
-    /* arithmetic expressions over numbers */
+    /* arithmetic expressions over numbers ('+' also concatenates
+     * strings and '*' repeats a string) */
     Add(Box<Expression>, Box<Expression>),
     Sub(Box<Expression>, Box<Expression>),
     Mul(Box<Expression>, Box<Expression>),
     Div(Box<Expression>, Box<Expression>),
+    /* the remainder takes the sign of the divisor, as in Python */
+    Mod(Box<Expression>, Box<Expression>),
+
+    /* operations over strings, lists and tuples */
+    Index(Box<Expression>, Box<Expression>),
+    Slice(
+        Box<Expression>,
+        Option<Box<Expression>>,
+        Option<Box<Expression>>,
+        Option<Box<Expression>>,
+    ),
+    Len(Box<Expression>),
+    In(Box<Expression>, Box<Expression>),
+
+    /* interpolated string f"...": literal text and embedded fields */
+    FString(Vec<FStringPart>),
+
+    /* method call 'receiver.method(args)' */
+    MethodCall(Box<Expression>, Name, Vec<Expression>),
 
     /* boolean expressions over booleans */
     And(Box<Expression>, Box<Expression>),
     Or(Box<Expression>, Box<Expression>),
     Not(Box<Expression>),
 
-    /* relational expressions over numbers */
+    /* relational expressions: equality is defined for every value,
+     * ordering for numbers, strings, lists and tuples */
     EQ(Box<Expression>, Box<Expression>),
+    NEQ(Box<Expression>, Box<Expression>),
     GT(Box<Expression>, Box<Expression>),
     LT(Box<Expression>, Box<Expression>),
     GTE(Box<Expression>, Box<Expression>),
     LTE(Box<Expression>, Box<Expression>),
+
+    /* chained comparison 'a < b <= c': the first operand followed by
+     * each operator and its right operand */
+    Comparison(Box<Expression>, Vec<(RelationalOp, Expression)>),
+
+    /* 'a if cond else b': the condition and both branches, of which
+     * only the chosen one is evaluated */
+    Conditional(Box<Expression>, Box<Expression>, Box<Expression>),
+}
+
+/* 'def name(params, *args, **kwargs) -> kind: body' */
+#[derive(Debug, PartialEq, Clone)]
+pub struct Function {
+    pub name: Name,
+    pub kind: Type,
+    pub params: Vec<(Name, Type)>,
+    /* default values of the last parameters */
+    pub defaults: Vec<Expression>,
+    /* '*args' collects the extra positional arguments into a list and
+     * '**kwargs' the extra keyword arguments into a dictionary; their
+     * types are those of the collected values */
+    pub varargs: Option<(Name, Type)>,
+    pub kwargs: Option<(Name, Type)>,
+    pub body: Box<Statement>,
+}
+
+impl Function {
+    pub fn signature(&self) -> Signature {
+        Signature {
+            params: self.params.clone(),
+            defaults: self.defaults.len(),
+            varargs: self.varargs.as_ref().map(|(_, t)| Box::new(t.clone())),
+            kwargs: self.kwargs.as_ref().map(|(_, t)| Box::new(t.clone())),
+        }
+    }
+
+    /* as in Python, the parameters and every name the body assigns are
+     * local to the whole body, unless declared 'global' or 'nonlocal';
+     * names assigned in a block that declares them belong to the block */
+    pub fn scope(&self) -> FunctionScope {
+        let mut scope = FunctionScope::default();
+        let params = self.params.iter().map(|(name, _)| name);
+        let collected = self.varargs.iter().chain(self.kwargs.iter());
+        scope
+            .locals
+            .extend(params.chain(collected.map(|(name, _)| name)).cloned());
+        collect_scope(&self.body, &HashSet::new(), &mut scope);
+        scope.locals.retain(|name| !scope.globals.contains(name));
+        scope.locals.retain(|name| !scope.nonlocals.contains(name));
+        scope
+    }
+}
+
+/* the variables a function value closes over: those of the module that
+ * defines it and, for a nested function, the local scope it is defined
+ * in. The module scope is held weakly, as the module binds the function
+ * in turn. */
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Closure {
+    pub globals: WeakScopeRef,
+    pub locals: Option<ScopeRef>,
+}
+
+/* the local variables of a function and the names it declares 'global'
+ * and 'nonlocal' */
+#[derive(Debug, PartialEq, Default)]
+pub struct FunctionScope {
+    pub locals: HashSet<Name>,
+    pub globals: HashSet<Name>,
+    pub nonlocals: HashSet<Name>,
+}
+
+/* 'shadowed' holds the names declared by the enclosing blocks; nested
+ * functions and classes have scopes of their own */
+fn collect_scope(stmt: &Statement, shadowed: &HashSet<Name>, scope: &mut FunctionScope) {
+    let local = |name: &Name, scope: &mut FunctionScope| {
+        if !shadowed.contains(name) {
+            scope.locals.insert(name.clone());
+        }
+    };
+    match stmt {
+        Statement::VarDeclaration(name)
+        | Statement::ValDeclaration(name)
+        | Statement::Assignment(name, _) => local(name, scope),
+        Statement::AugmentedAssignment(target, _, _) => {
+            if let Expression::Var(name) = target.as_ref() {
+                local(name, scope)
+            }
+        }
+        Statement::FuncDef(function) => local(&function.name, scope),
+        Statement::Import(module) => local(module, scope),
+        Statement::FromImport(_, names) => names.iter().for_each(|name| local(name, scope)),
+        Statement::Global(names) => scope.globals.extend(names.iter().cloned()),
+        Statement::Nonlocal(names) => scope.nonlocals.extend(names.iter().cloned()),
+        Statement::Sequence(s1, s2) => {
+            collect_scope(s1, shadowed, scope);
+            collect_scope(s2, shadowed, scope);
+        }
+        Statement::IfThenElse(_, s1, s2) => {
+            collect_block(s1, shadowed, scope);
+            collect_block(s2, shadowed, scope);
+        }
+        Statement::While(_, body) => collect_block(body, shadowed, scope),
+        Statement::For(name, _, body) => {
+            local(name, scope);
+            collect_block(body, shadowed, scope);
+        }
+        Statement::Try(body, handlers, finally) => {
+            collect_block(body, shadowed, scope);
+            for handler in handlers {
+                if let Some(name) = &handler.name {
+                    local(name, scope);
+                }
+                collect_block(&handler.body, shadowed, scope);
+            }
+            if let Some(finally) = finally {
+                collect_block(finally, shadowed, scope);
+            }
+        }
+        Statement::Match(_, cases) => {
+            for case in cases {
+                let mut names = vec![];
+                case.pattern.bindings(&mut names);
+                names.iter().for_each(|name| local(name, scope));
+                collect_block(&case.body, shadowed, scope);
+            }
+        }
+        _ => (),
+    }
+}
+
+fn collect_block(block: &Statement, shadowed: &HashSet<Name>, scope: &mut FunctionScope) {
+    let mut shadowed = shadowed.clone();
+    shadowed.extend(block.declarations());
+    collect_scope(block, &shadowed, scope);
+}
+
+/* parameters of a function type: their names and types, how many of
+ * the last ones have default values, and the types of the values
+ * collected by '*args' and '**kwargs', if the function takes them */
+#[derive(Debug, PartialEq, Clone)]
+pub struct Signature {
+    pub params: Vec<(Name, Type)>,
+    pub defaults: usize,
+    pub varargs: Option<Box<Type>>,
+    pub kwargs: Option<Box<Type>>,
+}
+
+/* arguments of a call matched to the parameters: one per parameter
+ * (None when it takes its default value), then those collected by
+ * '*args' and by '**kwargs' */
+#[derive(Debug, PartialEq)]
+pub struct Binding<T> {
+    pub args: Vec<Option<T>>,
+    pub varargs: Vec<T>,
+    pub kwargs: Vec<(Name, T)>,
+}
+
+impl Signature {
+    /* whether the function takes 'n' positional arguments */
+    pub fn accepts(&self, n: usize) -> bool {
+        let arity = self.params.len();
+        let required = arity - self.defaults.min(arity);
+        n >= required && (n <= arity || self.varargs.is_some())
+    }
+
+    /* the number of positional arguments the function takes, as error
+     * messages state it */
+    pub fn arity(&self) -> String {
+        let arity = self.params.len();
+        let required = arity - self.defaults.min(arity);
+        match self.varargs {
+            Some(_) => format!("at least {}", required),
+            None if required == arity => arity.to_string(),
+            None => format!("from {} to {}", required, arity),
+        }
+    }
+
+    /* matches positional (None) and keyword arguments to parameters,
+     * following Python's rules; both the interpreter and the type
+     * checker use it, so that they report the same errors */
+    pub fn bind<T>(
+        &self,
+        function: &str,
+        args: Vec<(Option<Name>, T)>,
+    ) -> Result<Binding<T>, String> {
+        let positional = args.iter().take_while(|(k, _)| k.is_none()).count();
+        if args[positional..].iter().any(|(k, _)| k.is_none()) {
+            return Err(String::from(
+                "positional argument follows keyword argument.",
+            ));
+        }
+        let arity = self.params.len();
+        let required = arity - self.defaults.min(arity);
+        if positional > arity && self.varargs.is_none() {
+            return Err(format!(
+                "{}() takes {} argument(s) but {} were given.",
+                function,
+                self.arity(),
+                positional
+            ));
+        }
+        let mut binding = Binding {
+            args: self.params.iter().map(|_| None).collect(),
+            varargs: vec![],
+            kwargs: vec![],
+        };
+        for (i, (keyword, value)) in args.into_iter().enumerate() {
+            let Some(keyword) = keyword else {
+                match binding.args.get_mut(i) {
+                    Some(arg) => *arg = Some(value),
+                    None => binding.varargs.push(value),
+                }
+                continue;
+            };
+            let repeated = match self.params.iter().position(|(p, _)| *p == keyword) {
+                Some(p) => binding.args[p].replace(value).is_some(),
+                None if self.kwargs.is_some() => {
+                    let repeated = binding.kwargs.iter().any(|(k, _)| *k == keyword);
+                    binding.kwargs.push((keyword.clone(), value));
+                    repeated
+                }
+                None => {
+                    return Err(format!(
+                        "{}() got an unexpected keyword argument '{}'.",
+                        function, keyword
+                    ))
+                }
+            };
+            if repeated {
+                return Err(format!(
+                    "{}() got multiple values for argument '{}'.",
+                    function, keyword
+                ));
+            }
+        }
+        if let Some(i) = (0..required).find(|i| binding.args[*i].is_none()) {
+            return Err(format!(
+                "{}() missing argument '{}'.",
+                function, self.params[i].0
+            ));
+        }
+        Ok(binding)
+    }
+}
+
+/* 'class Name(Base): fields and methods'; methods take 'self' as their
+ * first parameter */
+#[derive(Debug, PartialEq, Clone)]
+pub struct Class {
+    pub name: Name,
+    pub base: Option<Name>,
+    pub fields: Vec<(Name, Type)>,
+    pub methods: Vec<Function>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Object {
+    pub class: Name,
+    pub fields: HashMap<Name, Expression>,
+}
+
+/// A reference to an object on the heap. Objects are mutable and
+/// shared: cloning the reference does not copy the object, and two
+/// references are equal only if they refer to the same object.
+#[derive(Clone)]
+pub struct ObjectRef(pub Rc<RefCell<Object>>);
+
+impl ObjectRef {
+    pub fn new(class: &str) -> ObjectRef {
+        ObjectRef(Rc::new(RefCell::new(Object {
+            class: class.to_string(),
+            fields: HashMap::new(),
+        })))
+    }
+
+    pub fn class(&self) -> Name {
+        self.0.borrow().class.clone()
+    }
+}
+
+impl PartialEq for ObjectRef {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/* objects may refer to themselves, so their fields are not printed */
+impl fmt::Debug for ObjectRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ObjectRef({})", self.class())
+    }
+}
+
+/// The state of an iterator. Like objects, iterators are shared:
+/// advancing one through any reference advances it for all of them.
+#[derive(Clone)]
+pub struct IteratorRef(pub Rc<RefCell<IteratorState>>);
+
+impl IteratorRef {
+    pub fn new(state: IteratorState) -> IteratorRef {
+        IteratorRef(Rc::new(RefCell::new(state)))
+    }
+}
+
+impl PartialEq for IteratorRef {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl fmt::Debug for IteratorRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "IteratorRef")
+    }
+}
+
+/// A file opened by a program. Like objects, files are shared: closing
+/// one through any reference closes it for all of them.
+#[derive(Clone)]
+pub struct FileRef(pub Rc<RefCell<FileState>>);
+
+impl FileRef {
+    pub fn new(state: FileState) -> FileRef {
+        FileRef(Rc::new(RefCell::new(state)))
+    }
+}
+
+impl PartialEq for FileRef {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl fmt::Debug for FileRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "FileRef({})", self.0.borrow().path)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct FileState {
+    pub path: String,
+    /* 'r', 'w' or 'a' */
+    pub mode: String,
+    /* the text of a file opened for reading, read when it is opened, and
+     * the position of the next character to read */
+    pub text: String,
+    pub position: usize,
+    pub closed: bool,
+}
+
+/// A scope of local variables: those of a function call, or those
+/// declared by a block. Scopes are shared, so that closures see (and,
+/// with 'nonlocal', update) the variables of the functions that define
+/// them.
+#[derive(Clone, Default)]
+pub struct ScopeRef(pub Rc<RefCell<Scope>>);
+
+impl ScopeRef {
+    pub fn new(scope: Scope) -> ScopeRef {
+        ScopeRef(Rc::new(RefCell::new(scope)))
+    }
+
+    pub fn downgrade(&self) -> WeakScopeRef {
+        WeakScopeRef(Rc::downgrade(&self.0))
+    }
+}
+
+impl PartialEq for ScopeRef {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/* a scope may hold a closure over itself, so it is not printed */
+impl fmt::Debug for ScopeRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ScopeRef")
+    }
+}
+
+/// A scope that is not kept alive by the reference.
+#[derive(Clone, Default)]
+pub struct WeakScopeRef(pub Weak<RefCell<Scope>>);
+
+impl WeakScopeRef {
+    pub fn upgrade(&self) -> Option<ScopeRef> {
+        self.0.upgrade().map(ScopeRef)
+    }
+}
+
+impl PartialEq for WeakScopeRef {
+    fn eq(&self, other: &Self) -> bool {
+        Weak::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl fmt::Debug for WeakScopeRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "WeakScopeRef")
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct Scope {
+    pub bindings: HashMap<Name, Expression>,
+    /* names declared with 'val', which cannot be reassigned once bound */
+    pub vals: HashSet<Name>,
+    /* the names that belong to this scope, even before they are bound */
+    pub locals: HashSet<Name>,
+    /* a function scope also owns the names it assigns dynamically, and
+     * may declare names 'global' or 'nonlocal'; a block owns only the
+     * names it declares */
+    pub function: bool,
+    pub globals: HashSet<Name>,
+    pub nonlocals: HashSet<Name>,
+    /* the enclosing scope; the module scope is not part of the chain */
+    pub parent: Option<ScopeRef>,
+    /* a module scope also has the name of its module, which qualifies
+     * the classes it defines (the main program has none), the types it
+     * declares or imports, and the qualified names of the classes it
+     * knows by a plain name */
+    pub module: Name,
+    pub types: HashMap<Name, Type>,
+    pub classes: HashMap<Name, Name>,
+    /* the classes the module defines, wherever it defines them, and
+     * those of the modules it imports, by their qualified names, each
+     * with the module scope its methods see */
+    pub definitions: HashMap<Name, (Rc<Class>, WeakScopeRef)>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum IteratorState {
+    /* the elements of a list, tuple or string, and the position of the
+     * next one */
+    Sequence(Vec<Expression>, usize),
+    /* the integers from 'next' up to, but excluding, 'stop', 'step'
+     * apart; they are produced as they are needed */
+    Range {
+        next: i64,
+        stop: i64,
+        step: i64,
+    },
+    /* the values of 'map': its function, applied to a value of each of
+     * the iterators */
+    Map(Expression, Vec<Expression>),
+    /* the values of 'filter': its function, or None, and the iterator it
+     * takes them from */
+    Filter(Expression, Expression),
+    /* the tuples of 'zip', of a value of each of the iterators */
+    Zip(Vec<Expression>),
+    /* the pairs of 'enumerate': the iterator and the index of its next
+     * value */
+    Enumerate(Expression, i64),
+    /* a suspended generator: the variables of its module, its innermost
+     * scope and the work left to do, innermost last; it is exhausted
+     * when there is none */
+    Generator {
+        globals: ScopeRef,
+        scope: ScopeRef,
+        frames: Vec<Frame>,
+        running: bool,
+    },
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Frame {
+    /* a statement yet to run */
+    Run(Statement),
+    /* a 'for' loop: its variable, iterator and body */
+    Loop(Name, Expression, Statement),
+    /* the end of a block that declares variables, whose scope is left */
+    Leave,
+    /* a 'try' statement whose body runs in the frames above: its
+     * handlers and its 'finally' block */
+    Try(Vec<ExceptHandler>, Option<Box<Statement>>),
+    /* a 'with' statement whose body runs in the frames above: its
+     * context manager */
+    Exit(Expression),
+    /* the end of a 'finally' block that runs while the generator raises
+     * the exception, or returns if there is none */
+    Resume(Option<Expression>),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum FStringPart {
+    Literal(String),
+    /* an embedded expression and, optionally, its format spec together
+     * with the position where the spec starts in the f-string literal */
+    Field(Box<Expression>, Option<(String, usize)>),
+}
+
+/* clauses of a comprehension: 'for name in iterable' binds each
+ * element in turn, 'if condition' skips those for which it is false */
+#[derive(Debug, PartialEq, Clone)]
+pub enum Clause {
+    For(Name, Expression),
+    If(Expression),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum RelationalOp {
+    EQ,
+    NEQ,
+    GT,
+    LT,
+    GTE,
+    LTE,
+}
+
+impl RelationalOp {
+    /* the special method that implements the operator on objects */
+    pub fn special_method(&self) -> &'static str {
+        match self {
+            RelationalOp::EQ => "__eq__",
+            RelationalOp::NEQ => "__ne__",
+            RelationalOp::GT => "__gt__",
+            RelationalOp::LT => "__lt__",
+            RelationalOp::GTE => "__ge__",
+            RelationalOp::LTE => "__le__",
+        }
+    }
+
+    /* the operator with its operands swapped: 'a > b' is 'b < a' */
+    pub fn reflected(&self) -> RelationalOp {
+        match self {
+            RelationalOp::GT => RelationalOp::LT,
+            RelationalOp::LT => RelationalOp::GT,
+            RelationalOp::GTE => RelationalOp::LTE,
+            RelationalOp::LTE => RelationalOp::GTE,
+            op => op.clone(),
+        }
+    }
+}
+
+/* operators of augmented assignments such as '+=' */
+#[derive(Debug, PartialEq, Clone)]
+pub enum ArithmeticOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+}
+
+impl ArithmeticOp {
+    /* the expression 'lhs op rhs' */
+    pub fn apply(&self, lhs: Expression, rhs: Expression) -> Expression {
+        let (lhs, rhs) = (Box::new(lhs), Box::new(rhs));
+        match self {
+            ArithmeticOp::Add => Expression::Add(lhs, rhs),
+            ArithmeticOp::Sub => Expression::Sub(lhs, rhs),
+            ArithmeticOp::Mul => Expression::Mul(lhs, rhs),
+            ArithmeticOp::Div => Expression::Div(lhs, rhs),
+            ArithmeticOp::Mod => Expression::Mod(lhs, rhs),
+        }
+    }
+}
+
+impl fmt::Display for RelationalOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let symbol = match self {
+            RelationalOp::EQ => "==",
+            RelationalOp::NEQ => "!=",
+            RelationalOp::GT => ">",
+            RelationalOp::LT => "<",
+            RelationalOp::GTE => ">=",
+            RelationalOp::LTE => "<=",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+/* values are printed the way Python prints them: reals always carry
+ * a fractional part, and non-finite reals read `inf`, `-inf` and `nan`. */
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expression::CTrue => write!(f, "True"),
+            Expression::CFalse => write!(f, "False"),
+            Expression::CInt(v) => write!(f, "{}", v),
+            Expression::CReal(v) => write!(f, "{}", format_real(*v)),
+            Expression::CString(s) => write!(f, "{}", s),
+            Expression::CNone => write!(f, "None"),
+            Expression::CJust(v) => write!(f, "Just({})", repr(v)),
+            Expression::CNothing => write!(f, "Nothing"),
+            Expression::COk(v) => write!(f, "Ok({})", repr(v)),
+            Expression::CErr(e) => write!(f, "Err({})", repr(e)),
+            Expression::FunctionValue(function, _) => write!(f, "<function {}>", function.name),
+            Expression::BuiltinFunction(name) => write!(f, "<built-in function {}>", name),
+            Expression::Object(object) => write!(f, "<{} object>", object.class()),
+            Expression::Module(name, _) => write!(f, "<module '{}'>", name),
+            Expression::ClassValue(name) => write!(f, "<class '{}'>", name),
+            Expression::Iterator(iterator) => match &*iterator.0.borrow() {
+                IteratorState::Sequence(..) => write!(f, "<iterator object>"),
+                IteratorState::Range { .. } => write!(f, "<range object>"),
+                IteratorState::Map(..) => write!(f, "<map object>"),
+                IteratorState::Filter(..) => write!(f, "<filter object>"),
+                IteratorState::Zip(..) => write!(f, "<zip object>"),
+                IteratorState::Enumerate(..) => write!(f, "<enumerate object>"),
+                IteratorState::Generator { .. } => write!(f, "<generator object>"),
+            },
+            Expression::File(file) => {
+                let file = file.0.borrow();
+                write!(f, "<file {} mode={}>", quote(&file.path), quote(&file.mode))
+            }
+            Expression::ListValue(elements) => {
+                write!(f, "[{}]", format_elements(elements))
+            }
+            Expression::Tuple(elements) if elements.len() == 1 => {
+                write!(f, "({},)", format_elements(elements))
+            }
+            Expression::Tuple(elements) => write!(f, "({})", format_elements(elements)),
+            Expression::SetValue(elements) if elements.is_empty() => write!(f, "set()"),
+            Expression::SetValue(elements) => write!(f, "{{{}}}", format_elements(elements)),
+            Expression::DictValue(entries) => {
+                let entries = entries
+                    .iter()
+                    .map(|(key, value)| format!("{}: {}", repr(key), repr(value)))
+                    .collect::<Vec<String>>();
+                write!(f, "{{{}}}", entries.join(", "))
+            }
+            Expression::Exception(_, message) => write!(f, "{}", message),
+            Expression::Record(name, fields) => {
+                let fields = fields
+                    .iter()
+                    .map(|(field, value)| format!("{}={}", field, repr(value)))
+                    .collect::<Vec<String>>();
+                write!(f, "{}({})", name, fields.join(", "))
+            }
+            Expression::ADTConstructor(name, args) if args.is_empty() => write!(f, "{}", name),
+            Expression::ADTConstructor(name, args) => {
+                write!(f, "{}({})", name, format_elements(args))
+            }
+            _ => write!(f, "{:?}", self),
+        }
+    }
+}
+
+/* elements of compound values print their strings quoted */
+fn format_elements(elements: &[Expression]) -> String {
+    elements
+        .iter()
+        .map(repr)
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Prints a value as Python's `repr` does: like `Display`, but with
+/// strings quoted.
+pub fn repr(value: &Expression) -> String {
+    match value {
+        Expression::CString(s) => quote(s),
+        _ => value.to_string(),
+    }
+}
+
+fn quote(s: &str) -> String {
+    let mut quoted = String::from("'");
+    for c in s.chars() {
+        match c {
+            '\'' => quoted.push_str("\\'"),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('\'');
+    quoted
+}
+
+/* the shortest digits that read back as the same real, as Python's repr:
+ * in exponent notation below 1e-4 and from 1e16 on */
+pub fn format_real(v: f64) -> String {
+    if v.is_nan() {
+        return String::from("nan");
+    } else if v.is_infinite() {
+        return String::from(if v > 0.0 { "inf" } else { "-inf" });
+    }
+    let scientific = format!("{:e}", v);
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let exponent: i32 = exponent.parse().unwrap();
+    if !(-4..16).contains(&exponent) {
+        let sign = if exponent < 0 { '-' } else { '+' };
+        format!("{}e{}{:02}", mantissa, sign, exponent.abs())
+    } else if v.fract() == 0.0 {
+        format!("{:.1}", v)
+    } else {
+        format!("{}", v)
+    }
+}
+
+/* positions selected by 'start:stop:step' over a sequence of 'len'
+ * elements, following Python's rules for omitted and out-of-range bounds */
+pub fn slice_positions(len: usize, start: Option<i64>, stop: Option<i64>, step: i64) -> Vec<usize> {
+    let len = len as i64;
+    let adjust = |b: i64, lower: i64, upper: i64| {
+        let b = if b < 0 { b + len } else { b };
+        b.clamp(lower, upper)
+    };
+    let mut positions = vec![];
+    if step > 0 {
+        let mut i = start.map_or(0, |b| adjust(b, 0, len));
+        let stop = stop.map_or(len, |b| adjust(b, 0, len));
+        while i < stop {
+            positions.push(i as usize);
+            i += step;
+        }
+    } else {
+        let mut i = start.map_or(len - 1, |b| adjust(b, -1, len - 1));
+        let stop = stop.map_or(-1, |b| adjust(b, -1, len - 1));
+        while i > stop {
+            positions.push(i as usize);
+            i += step;
+        }
+    }
+    positions
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -46,7 +899,147 @@ pub enum Statement {
     VarDeclaration(Box<Name>),
     ValDeclaration(Box<Name>),
     Assignment(Box<Name>, Box<Expression>),
+    /* 'target op= value', where target is a variable, a list element or
+     * a field; the target is evaluated only once */
+    AugmentedAssignment(Box<Expression>, ArithmeticOp, Box<Expression>),
     IfThenElse(Box<Expression>, Box<Statement>, Box<Statement>),
     While(Box<Expression>, Box<Statement>),
+    /* 'for name in iterable: body' */
+    For(Name, Box<Expression>, Box<Statement>),
+    /* a function whose body yields is a generator */
+    Yield(Box<Expression>),
     Sequence(Box<Statement>, Box<Statement>),
+    FuncDef(Box<Function>),
+    ClassDef(Class),
+    Return(Box<Expression>),
+    /* 'record Point: x: int, y: int' */
+    RecordDeclaration(Name, Vec<(Name, Type)>),
+    /* 'target.field = value', where target is a variable or a field */
+    FieldAssignment(Box<Expression>, Name, Box<Expression>),
+    /* 'data Shape = Circle(real) | Rect(real, real)' */
+    TypeDeclaration(Name, Vec<ValueConstructor>),
+    /* 'match value: case pattern if guard: body ...'; the first case
+     * whose pattern matches and whose guard holds is executed */
+    Match(Box<Expression>, Vec<MatchCase>),
+    Raise(Box<Expression>),
+    /* 'assert condition, message' */
+    Assert(Box<Expression>, Box<Expression>),
+    /* try body, handlers (tried in order) and an optional 'finally' */
+    Try(Box<Statement>, Vec<ExceptHandler>, Option<Box<Statement>>),
+    /* 'global x, y': in a function, the names refer to module variables */
+    Global(Vec<Name>),
+    /* 'nonlocal x, y': in a nested function, the names refer to the
+     * variables of an enclosing function */
+    Nonlocal(Vec<Name>),
+    /* 'import module' binds the module to its name */
+    Import(Name),
+    /* 'from module import x, y' binds top-level names of the module */
+    FromImport(Name, Vec<Name>),
+    /* 'with manager as name: body': the file opened by the manager is
+     * closed, or the '__exit__' method of the object called, however the
+     * body ends */
+    With(Box<Expression>, Option<Name>, Box<Statement>),
+}
+
+impl Statement {
+    /* the names declared with 'var' or 'val' directly in a block (not in
+     * nested ones); they are local to the whole block, shadowing the
+     * variables of the enclosing scopes */
+    pub fn declarations(&self) -> Vec<Name> {
+        match self {
+            Statement::VarDeclaration(name) | Statement::ValDeclaration(name) => {
+                vec![*name.clone()]
+            }
+            Statement::Sequence(s1, s2) => {
+                let mut names = s1.declarations();
+                names.extend(s2.declarations());
+                names
+            }
+            _ => vec![],
+        }
+    }
+
+    /* whether the statement yields, which makes the function containing
+     * it a generator; nested definitions do not count */
+    pub fn yields(&self) -> bool {
+        match self {
+            Statement::Yield(_) => true,
+            Statement::Sequence(s1, s2) | Statement::IfThenElse(_, s1, s2) => {
+                s1.yields() || s2.yields()
+            }
+            Statement::While(_, body)
+            | Statement::For(_, _, body)
+            | Statement::With(_, _, body) => body.yields(),
+            Statement::Try(body, handlers, finally) => {
+                body.yields()
+                    || handlers.iter().any(|h| h.body.yields())
+                    || finally.as_ref().is_some_and(|f| f.yields())
+            }
+            Statement::Match(_, cases) => cases.iter().any(|c| c.body.yields()),
+            _ => false,
+        }
+    }
+}
+
+/* 'except Kind as name: body'; without a kind, the handler catches
+ * every exception */
+#[derive(Debug, PartialEq, Clone)]
+pub struct ExceptHandler {
+    pub kind: Option<Name>,
+    pub name: Option<Name>,
+    pub body: Box<Statement>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct MatchCase {
+    pub pattern: Pattern,
+    pub guard: Option<Expression>,
+    pub body: Box<Statement>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Pattern {
+    /* '_' matches anything */
+    Wildcard,
+    /* 'x' matches anything and binds it to 'x' */
+    Binding(Name),
+    /* a constant, compared with '==' */
+    Literal(Expression),
+    Tuple(Vec<Pattern>),
+    /* 'Rect(w, h)' */
+    Constructor(Name, Vec<Pattern>),
+}
+
+impl Pattern {
+    /* the names the pattern binds when it matches */
+    pub fn bindings(&self, names: &mut Vec<Name>) {
+        match self {
+            Pattern::Binding(name) => names.push(name.clone()),
+            Pattern::Tuple(patterns) | Pattern::Constructor(_, patterns) => {
+                patterns.iter().for_each(|p| p.bindings(names))
+            }
+            Pattern::Wildcard | Pattern::Literal(_) => (),
+        }
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let join = |patterns: &[Pattern]| {
+            patterns
+                .iter()
+                .map(|p| p.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+        match self {
+            Pattern::Wildcard => write!(f, "_"),
+            Pattern::Binding(name) => write!(f, "{}", name),
+            Pattern::Literal(value) => write!(f, "{}", repr(value)),
+            Pattern::Tuple(patterns) if patterns.len() == 1 => write!(f, "({},)", join(patterns)),
+            Pattern::Tuple(patterns) => write!(f, "({})", join(patterns)),
+            Pattern::Constructor(name, patterns) if patterns.is_empty() => write!(f, "{}", name),
+            Pattern::Constructor(name, patterns) => write!(f, "{}({})", name, join(patterns)),
+        }
+    }
 }
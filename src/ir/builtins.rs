@@ -0,0 +1,267 @@
+use crate::ir::ast::Name;
+use crate::ir::ast::Signature;
+use crate::ir::ast::Type;
+
+/* the functions every program can call without defining them, with
+ * the signatures their arguments are matched to; a program's own
+ * definition of one of these names takes precedence. Their parameter
+ * types are those the type checker can state up front: the others are
+ * TAny, and the type checker has a rule for each built-in. */
+pub fn builtin_signature(name: &str) -> Option<Signature> {
+    let param = |name: &str, t: Type| (name.to_string(), t);
+    let (params, defaults, varargs, kwargs) = match name {
+        /* 'print(*values, sep=" ", end="\n")' */
+        "print" => (vec![], 0, Some(Type::TAny), Some(Type::TString)),
+        "input" => (vec![param("prompt", Type::TString)], 1, None, None),
+        "len" | "abs" | "type" => (vec![param("x", Type::TAny)], 0, None, None),
+        /* 'range(stop)' or 'range(start, stop, step=1)' */
+        "range" => (
+            vec![
+                param("start", Type::TInteger),
+                param("stop", Type::TInteger),
+                param("step", Type::TInteger),
+            ],
+            2,
+            None,
+            None,
+        ),
+        /* 'min(iterable)' or 'min(a, b, ...)' */
+        "min" | "max" => (vec![], 0, Some(Type::TAny), None),
+        "sum" => (
+            vec![param("iterable", Type::TAny), param("start", Type::TAny)],
+            1,
+            None,
+            None,
+        ),
+        "int" | "float" | "str" | "bool" => (vec![param("x", Type::TAny)], 1, None, None),
+        /* 'map(function, *iterables)' */
+        "map" => (
+            vec![param("function", Type::TAny)],
+            0,
+            Some(Type::TAny),
+            None,
+        ),
+        "filter" => (
+            vec![param("function", Type::TAny), param("iterable", Type::TAny)],
+            0,
+            None,
+            None,
+        ),
+        "reduce" => (
+            vec![
+                param("function", Type::TAny),
+                param("iterable", Type::TAny),
+                param("initial", Type::TAny),
+            ],
+            1,
+            None,
+            None,
+        ),
+        "any" | "all" => (vec![param("iterable", Type::TAny)], 0, None, None),
+        "zip" => (vec![], 0, Some(Type::TAny), None),
+        "enumerate" => (
+            vec![
+                param("iterable", Type::TAny),
+                param("start", Type::TInteger),
+            ],
+            1,
+            None,
+            None,
+        ),
+        "reversed" => (vec![param("sequence", Type::TAny)], 0, None, None),
+        "sorted" => (
+            vec![
+                param("iterable", Type::TAny),
+                param("key", Type::TAny),
+                param("reverse", Type::TBool),
+            ],
+            2,
+            None,
+            None,
+        ),
+        /* 'open(file, mode="r")' */
+        "open" => (
+            vec![param("file", Type::TString), param("mode", Type::TString)],
+            1,
+            None,
+            None,
+        ),
+        _ => return None,
+    };
+    Some(Signature {
+        params,
+        defaults,
+        varargs: varargs.map(Box::new),
+        kwargs: kwargs.map(Box::new),
+    })
+}
+
+/* the keyword arguments 'print' takes */
+pub const PRINT_KEYWORDS: [&str; 2] = ["sep", "end"];
+
+/* the built-ins that take no keyword arguments, as in Python */
+pub const POSITIONAL_ONLY: [&str; 14] = [
+    "input", "len", "abs", "type", "range", "int", "float", "bool", "map", "filter", "reduce",
+    "any", "all", "reversed",
+];
+
+/* the modules every program can import without providing them, with the
+ * types of the names they define, sorted by name as those of any module.
+ * A module of the same name provided by the embedder takes precedence. */
+pub fn standard_module(name: &str) -> Option<Vec<(Name, Type)>> {
+    let mut exports = match name {
+        "math" => math(),
+        "random" => random(),
+        "json" => json(),
+        _ => return None,
+    };
+    exports.sort_by(|(a, _), (b, _)| a.cmp(b));
+    Some(exports)
+}
+
+/* the type of a function given by its qualified name, such as
+ * 'math.sqrt'; the top-level built-ins have a rule of the type checker
+ * for their result, which is TAny here */
+pub fn builtin_function_type(name: &str) -> Option<Type> {
+    match name.split_once('.') {
+        Some((module, function)) => standard_module(module)?
+            .into_iter()
+            .find(|(n, t)| n == function && matches!(t, Type::TFunction(..)))
+            .map(|(_, t)| t),
+        None => builtin_signature(name)
+            .map(|signature| Type::TFunction(Box::new(Type::TAny), signature)),
+    }
+}
+
+/* the functions of the standard modules whose result type depends on
+ * the types of their arguments, for which the type checker has a rule as
+ * it has for the top-level built-ins */
+pub fn generic_builtin(name: &str) -> bool {
+    matches!(name, "random.choice" | "random.shuffle" | "random.sample")
+}
+
+/* the type of a method of the files 'open' gives; 'write' gives the
+ * number of characters written */
+pub fn file_method(name: &str) -> Option<Type> {
+    match name {
+        "read" => Some(function(&[], 0, Type::TString)),
+        "readlines" => Some(function(&[], 0, Type::TList(Box::new(Type::TString)))),
+        "write" => Some(function(&[("text", Type::TString)], 0, Type::TInteger)),
+        "close" => Some(function(&[], 0, Type::TNone)),
+        _ => None,
+    }
+}
+
+/* a function with the given parameters, of which the last 'defaults'
+ * have default values */
+fn function(params: &[(&str, Type)], defaults: usize, result: Type) -> Type {
+    Type::TFunction(
+        Box::new(result),
+        Signature {
+            params: params
+                .iter()
+                .map(|(name, t)| (name.to_string(), t.clone()))
+                .collect(),
+            defaults,
+            varargs: None,
+            kwargs: None,
+        },
+    )
+}
+
+fn math() -> Vec<(Name, Type)> {
+    let real = |result: Type| function(&[("x", Type::TReal)], 0, result);
+    let mut exports = vec![];
+    for name in ["pi", "e", "inf", "nan"] {
+        exports.push((name.to_string(), Type::TReal));
+    }
+    for name in ["sqrt", "exp", "sin", "cos", "tan", "asin", "acos", "atan"] {
+        exports.push((name.to_string(), real(Type::TReal)));
+    }
+    for name in ["floor", "ceil", "round"] {
+        exports.push((name.to_string(), real(Type::TInteger)));
+    }
+    let binary = [
+        ("pow", ("x", "y"), Type::TReal, Type::TReal),
+        ("atan2", ("y", "x"), Type::TReal, Type::TReal),
+        ("gcd", ("a", "b"), Type::TInteger, Type::TInteger),
+    ];
+    for (name, (a, b), param, result) in binary {
+        let params = [(a, param.clone()), (b, param)];
+        exports.push((name.to_string(), function(&params, 0, result)));
+    }
+    /* 'log(x)' is the natural logarithm */
+    let log = function(&[("x", Type::TReal), ("base", Type::TReal)], 1, Type::TReal);
+    exports.push((String::from("log"), log));
+    let isclose = function(
+        &[
+            ("a", Type::TReal),
+            ("b", Type::TReal),
+            ("rel_tol", Type::TReal),
+            ("abs_tol", Type::TReal),
+        ],
+        2,
+        Type::TBool,
+    );
+    exports.push((String::from("isclose"), isclose));
+    exports
+}
+
+/* 'shuffle' and 'sample' give new lists, lists being values */
+fn random() -> Vec<(Name, Type)> {
+    let list = Type::TList(Box::new(Type::TAny));
+    vec![
+        (
+            String::from("seed"),
+            function(&[("a", Type::TInteger)], 0, Type::TNone),
+        ),
+        (String::from("random"), function(&[], 0, Type::TReal)),
+        (
+            String::from("randint"),
+            function(
+                &[("a", Type::TInteger), ("b", Type::TInteger)],
+                0,
+                Type::TInteger,
+            ),
+        ),
+        (
+            String::from("choice"),
+            function(&[("seq", Type::TAny)], 0, Type::TAny),
+        ),
+        (
+            String::from("shuffle"),
+            function(&[("x", Type::TAny)], 0, list.clone()),
+        ),
+        (
+            String::from("sample"),
+            function(
+                &[("population", Type::TAny), ("k", Type::TInteger)],
+                0,
+                list,
+            ),
+        ),
+    ]
+}
+
+/* 'loads' gives values of any type; 'dumps' writes everything on one
+ * line unless given an indentation */
+fn json() -> Vec<(Name, Type)> {
+    vec![
+        (
+            String::from("loads"),
+            function(&[("s", Type::TString)], 0, Type::TAny),
+        ),
+        (
+            String::from("dumps"),
+            function(
+                &[
+                    ("obj", Type::TAny),
+                    ("indent", Type::TInteger),
+                    ("sort_keys", Type::TBool),
+                ],
+                2,
+                Type::TString,
+            ),
+        ),
+    ]
+}
@@ -0,0 +1,159 @@
+type ErrorMessage = String;
+
+/* the largest width and precision a spec may ask for, so that a field
+ * cannot make the formatter allocate an arbitrarily long string */
+pub const MAX_WIDTH: usize = 10_000;
+
+/// A format spec of an f-string field, following Python's mini-language:
+/// `[[fill]align][sign][#][0][width][,][.precision][type]`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Spec {
+    pub fill: char,
+    pub align: Option<char>,
+    pub sign: char,
+    pub alternate: bool,
+    pub width: usize,
+    pub grouping: bool,
+    pub precision: Option<usize>,
+    pub kind: Option<char>,
+}
+
+impl Default for Spec {
+    fn default() -> Self {
+        Spec {
+            fill: ' ',
+            align: None,
+            sign: '-',
+            alternate: false,
+            width: 0,
+            grouping: false,
+            precision: None,
+            kind: None,
+        }
+    }
+}
+
+/// Parses the text of a format spec. `offset` is the position of its
+/// first character in the enclosing f-string, so that errors point at
+/// the exact offending character.
+pub fn parse_spec(spec: &str, offset: usize) -> Result<Spec, ErrorMessage> {
+    let chars: Vec<char> = spec.chars().collect();
+    let error = |i: usize, msg: String| {
+        Err(format!(
+            "[Format Error] invalid format spec at position {}: {}",
+            offset + i,
+            msg
+        ))
+    };
+    let is_align = |c: char| matches!(c, '<' | '>' | '^' | '=');
+    let mut result = Spec::default();
+    let mut i = 0;
+
+    if chars.len() >= 2 && is_align(chars[1]) {
+        result.fill = chars[0];
+        result.align = Some(chars[1]);
+        i = 2;
+    } else if !chars.is_empty() && is_align(chars[0]) {
+        result.align = Some(chars[0]);
+        i = 1;
+    }
+    if i < chars.len() && matches!(chars[i], '+' | '-' | ' ') {
+        result.sign = chars[i];
+        i += 1;
+    }
+    if i < chars.len() && chars[i] == '#' {
+        result.alternate = true;
+        i += 1;
+    }
+    /* '0' pads numbers with zeros after their sign, and strings after
+     * their text, unless an alignment is given */
+    if i < chars.len() && chars[i] == '0' {
+        if result.align.is_none() {
+            result.fill = '0';
+        }
+        i += 1;
+    }
+    let (width, next) = digits(&chars, i);
+    if let Some(width) = width {
+        match width.parse() {
+            Ok(width) if width <= MAX_WIDTH => result.width = width,
+            _ => return error(i, format!("width is larger than {}.", MAX_WIDTH)),
+        }
+    }
+    i = next;
+    if i < chars.len() && chars[i] == ',' {
+        result.grouping = true;
+        i += 1;
+    }
+    if i < chars.len() && chars[i] == '.' {
+        let (precision, next) = digits(&chars, i + 1);
+        match precision.map(|p| p.parse()) {
+            Some(Ok(precision)) if precision <= MAX_WIDTH => result.precision = Some(precision),
+            Some(_) => return error(i + 1, format!("precision is larger than {}.", MAX_WIDTH)),
+            None => return error(i + 1, String::from("expecting a precision after '.'.")),
+        }
+        i = next;
+    }
+    if i < chars.len() {
+        if !"sdboxXfFeEgG%".contains(chars[i]) {
+            return error(i, format!("unknown format type '{}'.", chars[i]));
+        }
+        result.kind = Some(chars[i]);
+        i += 1;
+    }
+    if i < chars.len() {
+        return error(i, format!("unexpected '{}'.", chars[i]));
+    }
+    Ok(result)
+}
+
+fn digits(chars: &[char], start: usize) -> (Option<String>, usize) {
+    let mut end = start;
+    while end < chars.len() && chars[end].is_ascii_digit() {
+        end += 1;
+    }
+    if end == start {
+        (None, start)
+    } else {
+        (Some(chars[start..end].iter().collect()), end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_spec_reports_error_positions() {
+        assert_eq!(
+            parse_spec(">8.2q", 10),
+            Err(String::from(
+                "[Format Error] invalid format spec at position 14: unknown format type 'q'."
+            ))
+        );
+        assert_eq!(
+            parse_spec("8.f", 3),
+            Err(String::from(
+                "[Format Error] invalid format spec at position 5: expecting a precision after '.'."
+            ))
+        );
+        assert_eq!(
+            parse_spec("dd", 0),
+            Err(String::from(
+                "[Format Error] invalid format spec at position 1: unexpected 'd'."
+            ))
+        );
+        assert_eq!(
+            parse_spec("20000", 0),
+            Err(String::from(
+                "[Format Error] invalid format spec at position 0: width is larger than 10000."
+            ))
+        );
+        assert_eq!(
+            parse_spec(".99999999999999999999f", 2),
+            Err(String::from(
+                "[Format Error] invalid format spec at position 3: precision is larger than 10000."
+            ))
+        );
+    }
+}
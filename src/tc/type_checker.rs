@@ -1,12 +1,251 @@
 use std::collections::HashMap;
+use std::collections::HashSet;
 
+use crate::ir::ast::slice_positions;
+use crate::ir::ast::Binding;
+use crate::ir::ast::Class;
+use crate::ir::ast::Clause;
 use crate::ir::ast::Expression;
+use crate::ir::ast::FStringPart;
+use crate::ir::ast::Function;
+use crate::ir::ast::MatchCase;
 use crate::ir::ast::Name;
+use crate::ir::ast::Pattern;
+use crate::ir::ast::RelationalOp;
+use crate::ir::ast::Signature;
+use crate::ir::ast::Statement;
 use crate::ir::ast::Type;
+use crate::ir::ast::ValueConstructor;
+use crate::ir::builtins::builtin_function_type;
+use crate::ir::builtins::builtin_signature;
+use crate::ir::builtins::file_method;
+use crate::ir::builtins::generic_builtin;
+use crate::ir::builtins::standard_module;
+use crate::ir::builtins::POSITIONAL_ONLY;
+use crate::ir::builtins::PRINT_KEYWORDS;
+use crate::ir::format;
 
 type ErrorMessage = String;
 
-type Environment = HashMap<Name, Type>;
+/// The types of the variables of a program, and the types it declares.
+/// Types have a namespace of their own, as in the interpreter, so that a
+/// variable does not hide a type of the same name. While a function body
+/// is checked, its local variables live in a scope of their own, chained
+/// to those of the functions that enclose it.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Environment {
+    /* the module variables */
+    globals: HashMap<Name, Type>,
+    /* the scopes of the function bodies being checked, innermost last */
+    scopes: Vec<FunctionScope>,
+    /* user-declared types, such as records and algebraic data types */
+    types: HashMap<Name, Type>,
+    classes: HashMap<Name, Type>,
+    /* the modules a program may import */
+    modules: HashMap<Name, Type>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct FunctionScope {
+    variables: HashMap<Name, Type>,
+    /* the names that belong to the function, even before they are bound */
+    locals: HashSet<Name>,
+    /* the names it declares 'global' */
+    globals: HashSet<Name>,
+    /* the type of the values it returns */
+    returns: Type,
+}
+
+/* where a variable lives: in the module or in the scope of a function */
+enum Owner {
+    Module,
+    Function(usize),
+}
+
+impl Environment {
+    pub fn new() -> Environment {
+        Environment::default()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Type> {
+        match self.owner(name) {
+            Some(Owner::Function(i)) => self.scopes[i].variables.get(name),
+            _ => self.globals.get(name),
+        }
+    }
+
+    pub fn insert(&mut self, name: Name, t: Type) -> Option<Type> {
+        self.bindings(&name).insert(name, t)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Type> {
+        self.bindings(name).remove(name)
+    }
+
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /* the variables in sight, each with the type of the innermost one */
+    fn variables(&self) -> Vec<(Name, Type)> {
+        let mut names: Vec<&Name> = self.globals.keys().collect();
+        names.extend(self.scopes.iter().flat_map(|scope| scope.variables.keys()));
+        names.sort();
+        names.dedup();
+        names
+            .into_iter()
+            .filter_map(|name| Some((name.clone(), self.get(name)?.clone())))
+            .collect()
+    }
+
+    /* the innermost scope that owns the name: one declared 'global'
+     * belongs to the module, and one declared 'nonlocal' to an enclosing
+     * function; None if no function scope claims it */
+    fn owner(&self, name: &str) -> Option<Owner> {
+        for (i, scope) in self.scopes.iter().enumerate().rev() {
+            if scope.globals.contains(name) {
+                return Some(Owner::Module);
+            }
+            if scope.locals.contains(name) {
+                return Some(Owner::Function(i));
+            }
+        }
+        None
+    }
+
+    /* the bindings a name is assigned in: a name no scope claims belongs
+     * to the innermost function, if any, from then on */
+    fn bindings(&mut self, name: &str) -> &mut HashMap<Name, Type> {
+        match self.owner(name) {
+            Some(Owner::Function(i)) => &mut self.scopes[i].variables,
+            Some(Owner::Module) => &mut self.globals,
+            None => match self.scopes.last_mut() {
+                Some(scope) => {
+                    scope.locals.insert(name.to_string());
+                    &mut scope.variables
+                }
+                None => &mut self.globals,
+            },
+        }
+    }
+
+    /* the type the function being checked returns; None at module level */
+    fn returns(&self) -> Option<&Type> {
+        self.scopes.last().map(|scope| &scope.returns)
+    }
+
+    pub fn get_type(&self, name: &str) -> Option<&Type> {
+        self.types.get(name)
+    }
+
+    pub fn declare_type(&mut self, name: Name, t: Type) {
+        self.types.insert(name, t);
+    }
+
+    pub fn get_class(&self, name: &str) -> Option<&Type> {
+        self.classes.get(name)
+    }
+
+    pub fn declare_class(&mut self, name: Name, class: Type) {
+        self.classes.insert(name, class);
+    }
+}
+
+impl<const N: usize> From<[(Name, Type); N]> for Environment {
+    fn from(variables: [(Name, Type); N]) -> Environment {
+        Environment {
+            globals: HashMap::from(variables),
+            ..Environment::default()
+        }
+    }
+}
+
+/// Makes a module available to 'import', given its type (see
+/// `module_type`).
+pub fn declare_module(name: &str, module: Type, env: &mut Environment) {
+    env.modules.insert(name.to_string(), module);
+}
+
+/// The type of a module: the types of the top-level names bound by its
+/// program, given the environment the program checks to. The classes
+/// and types the module declares are qualified with its name, as in
+/// 'geometry.Shape', so that they do not clash with those of the
+/// modules that import it.
+pub fn module_type(name: &str, env: &Environment) -> Type {
+    let declarations = env.types.iter().chain(env.classes.iter());
+    let own: HashSet<&Name> = declarations
+        .filter(|(n, t)| declared_name(t) == Some(*n) && !n.contains('.'))
+        .map(|(n, _)| n)
+        .collect();
+    let mut exports: Vec<(Name, Type)> = env
+        .globals
+        .iter()
+        .chain(env.types.iter())
+        .chain(env.classes.iter())
+        .map(|(n, t)| (n.clone(), qualify(t, name, &own)))
+        .collect();
+    exports.sort_by(|(a, _), (b, _)| a.cmp(b));
+    Type::TModule(name.to_string(), exports)
+}
+
+fn declared_name(t: &Type) -> Option<&Name> {
+    match t {
+        Type::TClass(name, ..) | Type::TRecord(name, _) | Type::TAlgebraicData(name, _) => {
+            Some(name)
+        }
+        _ => None,
+    }
+}
+
+/* the type with the names of the module's own classes and types, which
+ * it declares by their plain names, qualified with the module name */
+fn qualify(t: &Type, module: &str, own: &HashSet<&Name>) -> Type {
+    let name = |n: &Name| match own.contains(n) {
+        true => format!("{}.{}", module, n),
+        false => n.clone(),
+    };
+    let boxed = |t: &Type| Box::new(qualify(t, module, own));
+    let all = |members: &[(Name, Type)]| {
+        members
+            .iter()
+            .map(|(n, t)| (n.clone(), qualify(t, module, own)))
+            .collect()
+    };
+    match t {
+        Type::TList(t) => Type::TList(boxed(t)),
+        Type::TSet(t) => Type::TSet(boxed(t)),
+        Type::TMaybe(t) => Type::TMaybe(boxed(t)),
+        Type::TIterator(t) => Type::TIterator(boxed(t)),
+        Type::TTuple(ts) => Type::TTuple(ts.iter().map(|t| qualify(t, module, own)).collect()),
+        Type::TDict(k, v) => Type::TDict(boxed(k), boxed(v)),
+        Type::TResult(ok, err) => Type::TResult(boxed(ok), boxed(err)),
+        Type::TFunction(result, signature) => Type::TFunction(
+            boxed(result),
+            Signature {
+                params: all(&signature.params),
+                defaults: signature.defaults,
+                varargs: signature.varargs.as_deref().map(boxed),
+                kwargs: signature.kwargs.as_deref().map(boxed),
+            },
+        ),
+        Type::TClass(n, base, fields, methods) => {
+            Type::TClass(name(n), base.as_ref().map(name), all(fields), all(methods))
+        }
+        Type::TObject(n) => Type::TObject(name(n)),
+        Type::TRecord(n, fields) => Type::TRecord(name(n), all(fields)),
+        Type::TAlgebraicData(n, constructors) => Type::TAlgebraicData(
+            name(n),
+            constructors
+                .iter()
+                .map(|c| ValueConstructor {
+                    name: c.name.clone(),
+                    types: c.types.iter().map(|t| qualify(t, module, own)).collect(),
+                })
+                .collect(),
+        ),
+        _ => t.clone(),
+    }
+}
 
 pub fn check(exp: Expression, env: &Environment) -> Result<Type, ErrorMessage> {
     match exp {
@@ -15,213 +254,4731 @@ pub fn check(exp: Expression, env: &Environment) -> Result<Type, ErrorMessage> {
         Expression::CInt(_) => Ok(Type::TInteger),
         Expression::CReal(_) => Ok(Type::TReal),
         Expression::CString(_) => Ok(Type::TString),
-        Expression::Add(l, r) => check_bin_arithmetic_expression(*l, *r, env),
+        Expression::CNone => Ok(Type::TNone),
+        Expression::CJust(e) => Ok(Type::TMaybe(Box::new(check(*e, env)?))),
+        Expression::CNothing => Ok(Type::TMaybe(Box::new(Type::TAny))),
+        Expression::COk(e) => Ok(Type::TResult(
+            Box::new(check(*e, env)?),
+            Box::new(Type::TAny),
+        )),
+        Expression::CErr(e) => Ok(Type::TResult(
+            Box::new(Type::TAny),
+            Box::new(check(*e, env)?),
+        )),
+        Expression::Unwrap(e) => check_unwrap(*e, "unwrap", env),
+        Expression::UnwrapOr(e, default) => check_unwrap_or(*e, *default, env),
+        Expression::IsError(e) => match check(*e, env)? {
+            Type::TResult(..) => Ok(Type::TBool),
+            _ => Err(String::from(
+                "[Type Error] 'is_error' expects a Result value.",
+            )),
+        },
+        Expression::IsNothing(e) => match check(*e, env)? {
+            Type::TMaybe(_) => Ok(Type::TBool),
+            _ => Err(String::from(
+                "[Type Error] 'is_nothing' expects a Maybe value.",
+            )),
+        },
+        Expression::Propagate(e) => check_propagate(*e, env),
+        Expression::FunctionValue(function, _) => Ok(function_type(&function)),
+        Expression::BuiltinFunction(name) => builtin_function_type(&name)
+            .ok_or_else(|| format!("[Name Error] '{}' is not defined.", name)),
+        Expression::FuncCall(name, args) => check_func_call(name, args, env),
+        Expression::Object(object) => Ok(Type::TObject(object.class())),
+        Expression::Module(name, globals) => {
+            let mut exports = vec![];
+            for (n, value) in globals.0.borrow().bindings.iter() {
+                exports.push((n.clone(), check(value.clone(), env)?));
+            }
+            exports.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Ok(Type::TModule(name, exports))
+        }
+        Expression::Iterator(_) => Ok(Type::TIterator(Box::new(Type::TAny))),
+        Expression::File(_) => Ok(Type::TFile),
+        Expression::ClassValue(_) => Ok(Type::TType),
+        Expression::Super => Err(String::from(
+            "[Type Error] 'super()' can only be used to call a method.",
+        )),
+        Expression::KeywordArgument(..) => Err(String::from(
+            "[Type Error] keyword arguments are only allowed in calls.",
+        )),
+        Expression::IsInstance(e, class) => {
+            check(*e, env)?;
+            class_members(&class, env)?;
+            Ok(Type::TBool)
+        }
+        Expression::Var(name) => check_var_name(name, env),
+        Expression::Exception(_, message) => {
+            check(*message, env)?;
+            Ok(Type::TException)
+        }
+        Expression::Record(name, fields) => check_record(name, fields, env),
+        Expression::FieldAccess(e, field) => check_field_access(*e, field, env),
+        Expression::ADTConstructor(name, args) => check_adt_constructor(name, args, env),
+        Expression::ListValue(elements) => Ok(Type::TList(Box::new(check_elements(
+            elements,
+            "list elements",
+            env,
+        )?))),
+        Expression::SetValue(elements) => Ok(Type::TSet(Box::new(check_elements(
+            elements,
+            "set elements",
+            env,
+        )?))),
+        Expression::DictValue(entries) => {
+            let (keys, values) = entries.into_iter().unzip();
+            Ok(Type::TDict(
+                Box::new(check_elements(keys, "dictionary keys", env)?),
+                Box::new(check_elements(values, "dictionary values", env)?),
+            ))
+        }
+        Expression::ListComprehension(element, clauses) => {
+            let scope = check_clauses(clauses, env)?;
+            Ok(Type::TList(Box::new(check(*element, &scope)?)))
+        }
+        Expression::SetComprehension(element, clauses) => {
+            let scope = check_clauses(clauses, env)?;
+            Ok(Type::TSet(Box::new(check(*element, &scope)?)))
+        }
+        Expression::DictComprehension(key, value, clauses) => {
+            let scope = check_clauses(clauses, env)?;
+            Ok(Type::TDict(
+                Box::new(check(*key, &scope)?),
+                Box::new(check(*value, &scope)?),
+            ))
+        }
+        Expression::Tuple(elements) => Ok(Type::TTuple(
+            elements
+                .into_iter()
+                .map(|e| check(e, env))
+                .collect::<Result<Vec<Type>, ErrorMessage>>()?,
+        )),
+        Expression::Add(l, r) => check_add_expression(*l, *r, env),
         Expression::Sub(l, r) => check_bin_arithmetic_expression(*l, *r, env),
-        Expression::Mul(l, r) => check_bin_arithmetic_expression(*l, *r, env),
+        Expression::Mul(l, r) => check_mul_expression(*l, *r, env),
         Expression::Div(l, r) => check_bin_arithmetic_expression(*l, *r, env),
+        Expression::Mod(l, r) => check_bin_arithmetic_expression(*l, *r, env),
+        Expression::Conditional(cond, then, otherwise) => {
+            check_condition(*cond, env)?;
+            let then_type = check(*then, env)?;
+            let else_type = check(*otherwise, env)?;
+            unify(&then_type, &else_type, env).ok_or(format!(
+                "[Type Error] the branches of a conditional expression have different types: {:?} and {:?}.",
+                then_type, else_type
+            ))
+        }
+        Expression::Index(e, i) => check_index_expression(*e, *i, env),
+        Expression::Slice(e, start, stop, step) => {
+            check_slice_expression(*e, start, stop, step, env)
+        }
+        Expression::Len(e) => check_len_expression(*e, env),
+        Expression::In(e, c) => check_in_expression(*e, *c, env),
+        Expression::FString(parts) => check_fstring(parts, env),
+        Expression::MethodCall(receiver, method, args) => {
+            check_method_call(*receiver, method, args, env)
+        }
         Expression::And(l, r) => check_bin_boolean_expression(*l, *r, env),
         Expression::Or(l, r) => check_bin_boolean_expression(*l, *r, env),
         Expression::Not(e) => check_not_expression(*e, env),
-        Expression::EQ(l, r) => check_bin_relational_expression(*l, *r, env),
-        Expression::GT(l, r) => check_bin_relational_expression(*l, *r, env),
-        Expression::LT(l, r) => check_bin_relational_expression(*l, *r, env),
-        Expression::GTE(l, r) => check_bin_relational_expression(*l, *r, env),
-        Expression::LTE(l, r) => check_bin_boolean_expression(*l, *r, env),
-        _ => Err(String::from("not implemented yet")),
+        Expression::EQ(l, r) => check_bin_equality_expression(*l, *r, env),
+        Expression::NEQ(l, r) => check_bin_equality_expression(*l, *r, env),
+        Expression::GT(l, r) => check_bin_relational_expression(*l, *r, RelationalOp::GT, env),
+        Expression::LT(l, r) => check_bin_relational_expression(*l, *r, RelationalOp::LT, env),
+        Expression::GTE(l, r) => check_bin_relational_expression(*l, *r, RelationalOp::GTE, env),
+        Expression::LTE(l, r) => check_bin_relational_expression(*l, *r, RelationalOp::LTE, env),
+        Expression::Comparison(first, links) => check_comparison_chain(*first, links, env),
+    }
+}
+
+pub fn check_stmt(stmt: Statement, env: &Environment) -> Result<Environment, ErrorMessage> {
+    match stmt {
+        Statement::VarDeclaration(_) | Statement::ValDeclaration(_) => Ok(env.clone()),
+        /* both take effect when the function body is entered */
+        Statement::Global(_) => Ok(env.clone()),
+        Statement::Nonlocal(_) if env.returns().is_none() => Err(String::from(
+            "[Type Error] nonlocal declaration not allowed at module level.",
+        )),
+        Statement::Nonlocal(_) => Ok(env.clone()),
+        Statement::Import(module) => {
+            let exports = imported_names(&module, env)?;
+            let mut new_env = env.clone();
+            import_declarations(&exports, &mut new_env);
+            new_env.insert(module.clone(), Type::TModule(module, exports));
+            Ok(new_env)
+        }
+        /* a name may stand for a variable, a class and a type of the
+         * module at once; each is imported */
+        Statement::FromImport(module, names) => {
+            let exports = imported_names(&module, env)?;
+            let mut new_env = env.clone();
+            import_declarations(&exports, &mut new_env);
+            for name in names {
+                let mut found = exports.iter().filter(|(n, _)| *n == name).peekable();
+                if found.peek().is_none() {
+                    return Err(format!(
+                        "[Name Error] cannot import name '{}' from '{}'.",
+                        name, module
+                    ));
+                }
+                for (_, t) in found {
+                    match t {
+                        Type::TClass(..) => new_env.declare_class(name.clone(), t.clone()),
+                        Type::TRecord(..) | Type::TAlgebraicData(..) => {
+                            new_env.declare_type(name.clone(), t.clone())
+                        }
+                        _ => {
+                            new_env.insert(name.clone(), t.clone());
+                        }
+                    }
+                }
+            }
+            Ok(new_env)
+        }
+        Statement::Assignment(name, exp) => {
+            let t = check_assignment(&name, check(*exp, env)?, env)?;
+            let mut new_env = env.clone();
+            new_env.insert(*name, t);
+            Ok(new_env)
+        }
+        Statement::AugmentedAssignment(target, op, exp) => {
+            match &*target {
+                Expression::Var(_) | Expression::FieldAccess(..) => (),
+                Expression::Index(container, _) => match check(*container.clone(), env)? {
+                    Type::TList(_) | Type::TDict(..) | Type::TAny => (),
+                    _ => return Err(String::from(
+                        "[Type Error] item assignment is only defined for lists and dictionaries.",
+                    )),
+                },
+                _ => {
+                    return Err(String::from(
+                        "[Type Error] cannot assign to this expression.",
+                    ))
+                }
+            }
+            let target_type = check(*target.clone(), env)?;
+            let result = check(op.apply(*target, *exp), env)?;
+            if assignable(&target_type, &result, env) {
+                Ok(env.clone())
+            } else {
+                Err(format!(
+                    "[Type Error] the target has type {:?} but is assigned a value of type {:?}.",
+                    target_type, result
+                ))
+            }
+        }
+        Statement::IfThenElse(cond, stmt_then, stmt_else) => {
+            check_condition(*cond, env)?;
+            let then_env = check_block(*stmt_then, env)?;
+            let else_env = check_block(*stmt_else, env)?;
+            Ok(merge_branches(env, &then_env, &else_env))
+        }
+        Statement::While(cond, stmt) => {
+            check_condition(*cond, env)?;
+            check_block(*stmt, env)?;
+            Ok(env.clone())
+        }
+        Statement::For(name, iterable, stmt) => {
+            let element = check_iterable(check(*iterable, env)?, env)?;
+            let element = check_assignment(&name, element, env)?;
+            let mut body_env = env.clone();
+            body_env.insert(name, element);
+            check_block(*stmt, &body_env)?;
+            Ok(env.clone())
+        }
+        Statement::Yield(exp) => {
+            let exp_type = check(*exp, env)?;
+            match env.returns() {
+                None => Err(String::from("[Type Error] 'yield' outside function.")),
+                Some(Type::TIterator(expected)) if assignable(expected, &exp_type, env) => {
+                    Ok(env.clone())
+                }
+                Some(Type::TIterator(expected)) => Err(format!(
+                    "[Type Error] expecting a yielded value of type {:?}, found {:?}.",
+                    expected, exp_type
+                )),
+                Some(other) => Err(format!(
+                    "[Type Error] a generator must return an iterator, not {:?}.",
+                    other
+                )),
+            }
+        }
+        Statement::Sequence(s1, s2) => check_stmt(*s2, &check_stmt(*s1, env)?),
+        Statement::FuncDef(function) => check_func_def(*function, env),
+        Statement::ClassDef(class) => check_class_def(class, env),
+        Statement::Return(exp) => {
+            let exp_type = check(*exp, env)?;
+            match env.returns() {
+                None => Err(String::from("[Type Error] 'return' outside function.")),
+                Some(expected) if assignable(expected, &exp_type, env) => Ok(env.clone()),
+                Some(Type::TIterator(_)) if exp_type == Type::TNone => Ok(env.clone()),
+                Some(expected) => Err(format!(
+                    "[Type Error] expecting a return value of type {:?}, found {:?}.",
+                    expected, exp_type
+                )),
+            }
+        }
+        Statement::RecordDeclaration(name, fields) => {
+            let mut new_env = env.clone();
+            new_env.declare_type(name.clone(), Type::TRecord(name, fields));
+            Ok(new_env)
+        }
+        Statement::TypeDeclaration(name, constructors) => {
+            check_type_declaration(name, constructors, env)
+        }
+        Statement::Match(exp, cases) => check_match(*exp, cases, env),
+        Statement::FieldAssignment(target, field, exp) => {
+            let field_type = check_field_access(*target, field.clone(), env)?;
+            let exp_type = check(*exp, env)?;
+            if assignable(&field_type, &exp_type, env) {
+                Ok(env.clone())
+            } else {
+                Err(format!(
+                    "[Type Error] field '{}' has type {:?} but is assigned a value of type {:?}.",
+                    field, field_type, exp_type
+                ))
+            }
+        }
+        Statement::Raise(exp) => match check(*exp, env)? {
+            Type::TException => Ok(env.clone()),
+            _ => Err(String::from("[Type Error] expecting an exception value.")),
+        },
+        Statement::Assert(cond, message) => {
+            check_condition(*cond, env)?;
+            check(*message, env)?;
+            Ok(env.clone())
+        }
+        Statement::Try(body, handlers, finally) => {
+            check_block(*body, env)?;
+            for handler in handlers {
+                let mut handler_env = env.clone();
+                if let Some(name) = handler.name {
+                    handler_env.insert(name, Type::TException);
+                }
+                check_block(*handler.body, &handler_env)?;
+            }
+            match finally {
+                Some(finally) => check_block(*finally, env),
+                None => Ok(env.clone()),
+            }
+        }
+        Statement::With(manager, name, body) => {
+            let value = check_context_manager(check(*manager, env)?, env)?;
+            let mut body_env = env.clone();
+            if let Some(name) = name {
+                let value = check_assignment(&name, value, env)?;
+                body_env.insert(name, value);
+            }
+            check_block(*body, &body_env)
+        }
+    }
+}
+
+/* the type a variable has after it is assigned a value: the type it
+ * already has, if any, with the placeholders the value fills in (so
+ * that 'x = []' and then 'x = [1]' make x a list of ints) */
+fn check_assignment(name: &str, value: Type, env: &Environment) -> Result<Type, ErrorMessage> {
+    match env.get(name) {
+        None => Ok(value),
+        Some(declared) if assignable(declared, &value, env) => {
+            Ok(unify(declared, &value, env).unwrap_or(declared.clone()))
+        }
+        Some(declared) => Err(format!(
+            "[Type Error] '{}' has type {:?} but is assigned a value of type {:?}.",
+            name, declared, value
+        )),
+    }
+}
+
+/* the type of the value 'with' binds: the file itself, or the result of
+ * the '__enter__' method of an object that also defines '__exit__', which
+ * takes the type, the value and the traceback of an exception */
+fn check_context_manager(manager: Type, env: &Environment) -> Result<Type, ErrorMessage> {
+    match manager {
+        Type::TFile | Type::TAny => Ok(manager),
+        Type::TObject(class) => {
+            let object = Type::TObject(class.clone());
+            check_special_method(&object, "__exit__", &[Type::TAny, Type::TAny, Type::TAny], env)?
+                .ok_or_else(|| undefined_operator(&class, "__exit__"))?;
+            check_special_method(&object, "__enter__", &[], env)?
+                .ok_or_else(|| undefined_operator(&class, "__enter__"))
+        }
+        other => Err(format!(
+            "[Type Error] 'with' expects a file or an object with '__enter__' and '__exit__', found {:?}.",
+            other
+        )),
+    }
+}
+
+/* the variables a block declares shadow those of the enclosing scopes
+ * until the block ends, when the outer ones are visible again */
+fn check_block(block: Statement, env: &Environment) -> Result<Environment, ErrorMessage> {
+    let names = block.declarations();
+    let mut block_env = env.clone();
+    for name in names.iter() {
+        block_env.remove(name);
+    }
+    let mut new_env = check_stmt(block, &block_env)?;
+    for name in names {
+        match env.get(&name) {
+            Some(t) => new_env.insert(name, t.clone()),
+            None => new_env.remove(&name),
+        };
+    }
+    Ok(new_env)
+}
+
+fn imported_names(module: &str, env: &Environment) -> Result<Vec<(Name, Type)>, ErrorMessage> {
+    match env.modules.get(module) {
+        Some(Type::TModule(_, exports)) => Ok(exports.clone()),
+        _ => standard_module(module)
+            .ok_or_else(|| format!("[Name Error] no module named '{}'.", module)),
+    }
+}
+
+/* the classes and types a module declares are known to the modules that
+ * import it by their qualified names, so that its values can be used
+ * there; only 'from ... import' makes them known by their plain names */
+fn import_declarations(exports: &[(Name, Type)], env: &mut Environment) {
+    for (_, t) in exports {
+        match t {
+            Type::TClass(name, ..) => env.declare_class(name.clone(), t.clone()),
+            Type::TRecord(name, _) | Type::TAlgebraicData(name, _) => {
+                env.declare_type(name.clone(), t.clone())
+            }
+            _ => (),
+        }
+    }
+}
+
+fn check_condition(cond: Expression, env: &Environment) -> Result<(), ErrorMessage> {
+    match check(cond, env)? {
+        Type::TBool => Ok(()),
+        _ => Err(String::from("[Type Error] expecting a boolean condition.")),
+    }
+}
+
+/* after an if-then-else, only the bindings made by both branches (with
+ * the same type) are known to be defined */
+fn merge_branches(
+    env: &Environment,
+    then_env: &Environment,
+    else_env: &Environment,
+) -> Environment {
+    let mut merged = env.clone();
+    for (name, t) in then_env.variables() {
+        if else_env.get(&name) == Some(&t) {
+            merged.insert(name, t);
+        }
+    }
+    merged
+}
+
+/* a type for values of either type: the more general one, such as
+ * 'real' for 'int' and 'real', with placeholders filled in by the other */
+fn unify(t1: &Type, t2: &Type, env: &Environment) -> Option<Type> {
+    let unify_boxed = |a: &Type, b: &Type| unify(a, b, env).map(Box::new);
+    match (t1, t2) {
+        (Type::TAny, t) | (t, Type::TAny) => Some(t.clone()),
+        (Type::TList(a), Type::TList(b)) => Some(Type::TList(unify_boxed(a, b)?)),
+        (Type::TSet(a), Type::TSet(b)) => Some(Type::TSet(unify_boxed(a, b)?)),
+        (Type::TMaybe(a), Type::TMaybe(b)) => Some(Type::TMaybe(unify_boxed(a, b)?)),
+        (Type::TDict(k1, v1), Type::TDict(k2, v2)) => {
+            Some(Type::TDict(unify_boxed(k1, k2)?, unify_boxed(v1, v2)?))
+        }
+        (Type::TResult(ok1, err1), Type::TResult(ok2, err2)) => Some(Type::TResult(
+            unify_boxed(ok1, ok2)?,
+            unify_boxed(err1, err2)?,
+        )),
+        _ if assignable(t1, t2, env) => Some(t1.clone()),
+        _ if assignable(t2, t1, env) => Some(t2.clone()),
+        _ => None,
+    }
+}
+
+/* whether a value of type 'actual' may be stored where 'expected' is
+ * required; integers are promoted to reals */
+fn assignable(expected: &Type, actual: &Type, env: &Environment) -> bool {
+    match (expected, actual) {
+        (Type::TAny, _) | (_, Type::TAny) => true,
+        (Type::TReal, Type::TInteger) => true,
+        (Type::TRecord(e, _), Type::TRecord(a, _)) => e == a,
+        (Type::TAlgebraicData(e, _), Type::TAlgebraicData(a, _)) => e == a,
+        (Type::TObject(e), Type::TObject(a)) => is_subclass(a, e, env),
+        (Type::TList(e), Type::TList(a)) | (Type::TSet(e), Type::TSet(a)) => assignable(e, a, env),
+        (Type::TDict(e_key, e_value), Type::TDict(a_key, a_value)) => {
+            assignable(e_key, a_key, env) && assignable(e_value, a_value, env)
+        }
+        (Type::TMaybe(e), Type::TMaybe(a)) => assignable(e, a, env),
+        (Type::TResult(e_ok, e_err), Type::TResult(a_ok, a_err)) => {
+            assignable(e_ok, a_ok, env) && assignable(e_err, a_err, env)
+        }
+        (Type::TTuple(e), Type::TTuple(a)) => {
+            e.len() == a.len() && e.iter().zip(a.iter()).all(|(e, a)| assignable(e, a, env))
+        }
+        /* a range is an iterator over integers */
+        (Type::TIterator(e), Type::TRange) => assignable(e, &Type::TInteger, env),
+        _ => expected == actual,
+    }
+}
+
+fn check_record(
+    name: Name,
+    fields: Vec<(Name, Expression)>,
+    env: &Environment,
+) -> Result<Type, ErrorMessage> {
+    let declared = match env.get_type(&name) {
+        Some(Type::TRecord(_, declared)) => declared.clone(),
+        _ => {
+            return Err(format!(
+                "[Type Error] record type '{}' is not declared.",
+                name
+            ))
+        }
+    };
+    for (field, exp) in fields.iter() {
+        let field_type = declared
+            .iter()
+            .find(|(d, _)| d == field)
+            .map(|(_, t)| t)
+            .ok_or(format!(
+                "[Type Error] record '{}' has no field '{}'.",
+                name, field
+            ))?;
+        let exp_type = check(exp.clone(), env)?;
+        if !assignable(field_type, &exp_type, env) {
+            return Err(format!(
+                "[Type Error] field '{}' has type {:?} but is given a value of type {:?}.",
+                field, field_type, exp_type
+            ));
+        }
+    }
+    for (field, _) in declared.iter() {
+        match fields.iter().filter(|(f, _)| f == field).count() {
+            1 => (),
+            0 => {
+                return Err(format!(
+                    "[Type Error] missing field '{}' of record '{}'.",
+                    field, name
+                ))
+            }
+            _ => {
+                return Err(format!(
+                    "[Type Error] field '{}' of record '{}' is given twice.",
+                    field, name
+                ))
+            }
+        }
+    }
+    Ok(Type::TRecord(name, declared))
+}
+
+/* user-declared types are nominal: inside declarations, they may be
+ * referred to by name only (e.g., 'TRecord("Point", [])'), so their
+ * definition is looked up in the environment */
+fn resolve(t: Type, env: &Environment) -> Type {
+    match &t {
+        Type::TRecord(name, fields) if fields.is_empty() => match env.get_type(name) {
+            Some(declared @ Type::TRecord(..)) => declared.clone(),
+            _ => t,
+        },
+        Type::TAlgebraicData(name, constructors) if constructors.is_empty() => {
+            match env.get_type(name) {
+                Some(declared @ Type::TAlgebraicData(..)) => declared.clone(),
+                _ => t,
+            }
+        }
+        _ => t,
+    }
+}
+
+fn check_field_access(
+    exp: Expression,
+    field: Name,
+    env: &Environment,
+) -> Result<Type, ErrorMessage> {
+    match resolve(check(exp, env)?, env) {
+        Type::TObject(class) => {
+            let (_, fields, _) = class_members(&class, env)?;
+            fields
+                .into_iter()
+                .find(|(f, _)| *f == field)
+                .map(|(_, t)| t)
+                .ok_or(format!(
+                    "[Type Error] class '{}' has no field '{}'.",
+                    class, field
+                ))
+        }
+        Type::TRecord(name, fields) => fields
+            .into_iter()
+            .find(|(f, _)| *f == field)
+            .map(|(_, t)| t)
+            .ok_or(format!(
+                "[Type Error] record '{}' has no field '{}'.",
+                name, field
+            )),
+        Type::TModule(module, exports) => exports
+            .into_iter()
+            .find(|(n, _)| *n == field)
+            .map(|(_, t)| t)
+            .ok_or(no_module_attribute(&module, &field)),
+        t => Err(format!(
+            "[Type Error] cannot access field '{}' of a value of type {:?}.",
+            field, t
+        )),
+    }
+}
+
+fn function_type(function: &Function) -> Type {
+    Type::TFunction(Box::new(function.kind.clone()), function.signature())
+}
+
+/* the environment a function body starts with: a scope of its own, in
+ * which its local variables are not bound until assigned, the names it
+ * declares 'global' refer to the module variables, and those it declares
+ * 'nonlocal' must be variables of an enclosing function */
+fn enter_function(function: &Function, env: &Environment) -> Result<Environment, ErrorMessage> {
+    let scope = function.scope();
+    if let Some(name) = scope
+        .nonlocals
+        .iter()
+        .find(|name| !matches!(env.owner(name), Some(Owner::Function(_))))
+    {
+        return Err(format!(
+            "[Type Error] no binding for nonlocal '{}' found.",
+            name
+        ));
+    }
+    let mut body_env = env.clone();
+    body_env.scopes.push(FunctionScope {
+        variables: HashMap::new(),
+        locals: scope.locals,
+        globals: scope.globals,
+        returns: function.kind.clone(),
+    });
+    Ok(body_env)
+}
+
+/* binds the parameters of a function in the environment of its body,
+ * after checking their default values; '*args' is a list and '**kwargs'
+ * a dictionary from names to values */
+fn bind_params(
+    function: &Function,
+    env: &Environment,
+    body_env: &mut Environment,
+) -> Result<(), ErrorMessage> {
+    let Some(first_default) = function.params.len().checked_sub(function.defaults.len()) else {
+        return Err(format!(
+            "[Type Error] {}() has more default values than parameters.",
+            function.name
+        ));
+    };
+    for ((param, t), default) in function.params[first_default..]
+        .iter()
+        .zip(&function.defaults)
+    {
+        let default_type = check(default.clone(), env)?;
+        if !assignable(t, &default_type, env) {
+            return Err(format!(
+                "[Type Error] parameter '{}' has type {:?} but its default value has type {:?}.",
+                param, t, default_type
+            ));
+        }
+    }
+    for (param, t) in function.params.iter() {
+        body_env.insert(param.clone(), t.clone());
+    }
+    if let Some((varargs, t)) = &function.varargs {
+        body_env.insert(varargs.clone(), Type::TList(Box::new(t.clone())));
+    }
+    if let Some((kwargs, t)) = &function.kwargs {
+        body_env.insert(
+            kwargs.clone(),
+            Type::TDict(Box::new(Type::TString), Box::new(t.clone())),
+        );
+    }
+    Ok(())
+}
+
+/* the function is bound before its body is checked, so that it may
+ * call itself */
+fn check_func_def(function: Function, env: &Environment) -> Result<Environment, ErrorMessage> {
+    let mut new_env = env.clone();
+    new_env.insert(function.name.clone(), function_type(&function));
+    let mut body_env = enter_function(&function, &new_env)?;
+    bind_params(&function, &new_env, &mut body_env)?;
+    check_stmt(*function.body.clone(), &body_env)?;
+    check_returns(&function)?;
+    Ok(new_env)
+}
+
+/* a function that does not return None must return a value, or raise an
+ * exception, on every path through its body; generators need not */
+fn check_returns(function: &Function) -> Result<(), ErrorMessage> {
+    match function.kind {
+        Type::TNone | Type::TAny => Ok(()),
+        _ if function.body.yields() || always_returns(&function.body) => Ok(()),
+        _ => Err(format!(
+            "[Type Error] function '{}' may end without returning a value of type {:?}.",
+            function.name, function.kind
+        )),
+    }
+}
+
+/* an infinite loop never ends normally, as there is no 'break'; a match
+ * is exhaustive, as checked */
+fn always_returns(stmt: &Statement) -> bool {
+    match stmt {
+        Statement::Return(_) | Statement::Raise(_) => true,
+        Statement::Sequence(s1, s2) => always_returns(s1) || always_returns(s2),
+        Statement::IfThenElse(_, s1, s2) => always_returns(s1) && always_returns(s2),
+        Statement::While(cond, _) => **cond == Expression::CTrue,
+        Statement::With(_, _, body) => always_returns(body),
+        Statement::Match(_, cases) => {
+            !cases.is_empty() && cases.iter().all(|c| always_returns(&c.body))
+        }
+        Statement::Try(body, handlers, finally) => {
+            finally.as_ref().is_some_and(|f| always_returns(f))
+                || (always_returns(body) && handlers.iter().all(|h| always_returns(&h.body)))
+        }
+        _ => false,
+    }
+}
+
+/* calling a class creates an instance of it, and takes the arguments
+ * of its '__init__' method */
+fn check_func_call(
+    name: Name,
+    args: Vec<Expression>,
+    env: &Environment,
+) -> Result<Type, ErrorMessage> {
+    if env.get(&name).is_none() {
+        if let Some(class) = env.get_class(&name) {
+            return check_call(&name, class.clone(), args, env);
+        }
+        if let Some(signature) = builtin_signature(&name) {
+            return check_builtin(&name, signature, args, env);
+        }
+    }
+    check_call(&name, check_var_name(name.clone(), env)?, args, env)
+}
+
+fn check_call(
+    name: &str,
+    callee: Type,
+    args: Vec<Expression>,
+    env: &Environment,
+) -> Result<Type, ErrorMessage> {
+    let (result, signature) = match callee {
+        Type::TFunction(result, signature) => (*result, signature),
+        Type::TClass(class, _, _, methods) => {
+            let signature = match methods.into_iter().find(|(m, _)| m == "__init__") {
+                Some((_, Type::TFunction(_, signature))) => signature,
+                _ => Signature {
+                    params: vec![],
+                    defaults: 0,
+                    varargs: None,
+                    kwargs: None,
+                },
+            };
+            (Type::TObject(class), signature)
+        }
+        _ => return Err(format!("[Type Error] '{}' is not a function.", name)),
+    };
+    check_args(name, &signature, args, env)?;
+    Ok(result)
+}
+
+fn no_module_attribute(module: &str, attribute: &str) -> ErrorMessage {
+    format!(
+        "[Type Error] module '{}' has no attribute '{}'.",
+        module, attribute
+    )
+}
+
+/* matches the arguments to the parameters as a call would, and checks
+ * the type of each argument against its parameter */
+fn check_args(
+    name: &str,
+    signature: &Signature,
+    args: Vec<Expression>,
+    env: &Environment,
+) -> Result<(), ErrorMessage> {
+    let mut typed = vec![];
+    for arg in args {
+        typed.push(match arg {
+            Expression::KeywordArgument(keyword, value) => (Some(keyword), check(*value, env)?),
+            _ => (None, check(arg, env)?),
+        });
+    }
+    check_arg_types(name, signature, typed, env)
+}
+
+/* the types of the arguments, positional (None) or keyword ones, can be
+ * assigned to the parameters they are matched to */
+fn check_arg_types(
+    name: &str,
+    signature: &Signature,
+    args: Vec<(Option<Name>, Type)>,
+    env: &Environment,
+) -> Result<(), ErrorMessage> {
+    let mut typed = vec![];
+    for (i, (keyword, arg_type)) in args.into_iter().enumerate() {
+        let label = match &keyword {
+            Some(keyword) => format!("'{}'", keyword),
+            None => (i + 1).to_string(),
+        };
+        typed.push((keyword, (label, arg_type)));
+    }
+    let binding = signature
+        .bind(name, typed)
+        .map_err(|message| format!("[Type Error] {}", message))?;
+    let mut expected_types = vec![];
+    for ((_, expected), arg) in signature.params.iter().zip(binding.args) {
+        expected_types.extend(arg.map(|arg| (expected, arg)));
+    }
+    if let Some(expected) = &signature.varargs {
+        expected_types.extend(binding.varargs.into_iter().map(|arg| (&**expected, arg)));
+    }
+    if let Some(expected) = &signature.kwargs {
+        expected_types.extend(
+            binding
+                .kwargs
+                .into_iter()
+                .map(|(_, arg)| (&**expected, arg)),
+        );
+    }
+    for (expected, (label, arg_type)) in expected_types {
+        if !assignable(expected, &arg_type, env) {
+            return Err(format!(
+                "[Type Error] argument {} of {}() has type {:?} but is given a value of type {:?}.",
+                label, name, expected, arg_type
+            ));
+        }
+    }
+    Ok(())
+}
+
+/* the arguments of a built-in function are matched to its signature
+ * like those of any function; the type of the result, and the types its
+ * arguments may take besides those of its signature, follow a rule for
+ * each built-in */
+fn check_builtin(
+    name: &str,
+    signature: Signature,
+    args: Vec<Expression>,
+    env: &Environment,
+) -> Result<Type, ErrorMessage> {
+    for arg in &args {
+        match arg {
+            Expression::KeywordArgument(keyword, _)
+                if name == "print" && !PRINT_KEYWORDS.contains(&keyword.as_str()) =>
+            {
+                return Err(format!(
+                    "[Type Error] print() got an unexpected keyword argument '{}'.",
+                    keyword
+                ))
+            }
+            Expression::KeywordArgument(..) if POSITIONAL_ONLY.contains(&name) => {
+                return Err(format!(
+                    "[Type Error] {}() takes no keyword arguments.",
+                    name
+                ))
+            }
+            _ => (),
+        }
+    }
+    let mut typed = vec![];
+    for arg in args {
+        typed.push(match arg {
+            Expression::KeywordArgument(keyword, value) => {
+                (Some(keyword), check_builtin_arg(*value, env)?)
+            }
+            _ => (None, check_builtin_arg(arg, env)?),
+        });
+    }
+    let types = typed
+        .iter()
+        .map(|(keyword, (t, _))| (keyword.clone(), t.clone()))
+        .collect();
+    check_arg_types(name, &signature, types, env)?;
+    let binding = signature
+        .bind(name, typed)
+        .map_err(|message| format!("[Type Error] {}", message))?;
+    builtin_result(name, binding, env)
+}
+
+/* the type of an argument of a built-in function, and the name of the
+ * built-in function it is, if it is one: when it is called back, the
+ * type of its result follows its own rule */
+type BuiltinArg = (Type, Option<Name>);
+
+fn check_builtin_arg(arg: Expression, env: &Environment) -> Result<BuiltinArg, ErrorMessage> {
+    let builtin = match &arg {
+        Expression::Var(name) | Expression::BuiltinFunction(name)
+            if env.get(name).is_none() && builtin_signature(name).is_some() =>
+        {
+            Some(name.clone())
+        }
+        _ => None,
+    };
+    Ok((check(arg, env)?, builtin))
+}
+
+/* the type of the result of a built-in function, given the arguments
+ * matched to its parameters */
+fn builtin_result(
+    name: &str,
+    binding: Binding<BuiltinArg>,
+    env: &Environment,
+) -> Result<Type, ErrorMessage> {
+    let callees: Vec<Option<Name>> = binding
+        .args
+        .iter()
+        .map(|arg| arg.as_ref().and_then(|(_, builtin)| builtin.clone()))
+        .collect();
+    let args: Vec<Option<Type>> = binding
+        .args
+        .into_iter()
+        .map(|arg| arg.map(|(t, _)| t))
+        .collect();
+    let varargs: Vec<Type> = binding.varargs.into_iter().map(|(t, _)| t).collect();
+    let arg = |i: usize| args[i].clone().unwrap_or(Type::TAny);
+    let callback = |i: usize| (arg(i), callees[i].clone());
+    let list = |t: Type| Type::TList(Box::new(t));
+    let iterator = |t: Type| Type::TIterator(Box::new(t));
+    match name {
+        "print" => Ok(Type::TNone),
+        "input" | "str" => Ok(Type::TString),
+        "type" => Ok(Type::TType),
+        "open" => Ok(Type::TFile),
+        "len" => len_type(arg(0), env),
+        "range" => Ok(Type::TRange),
+        "abs" => match arg(0) {
+            t @ (Type::TInteger | Type::TReal | Type::TAny) => Ok(t),
+            Type::TObject(class) => {
+                check_special_method(&Type::TObject(class.clone()), "__abs__", &[], env)?
+                    .ok_or_else(|| undefined_operator(&class, "__abs__"))
+            }
+            t => Err(format!("[Type Error] abs() expects a number, not {:?}.", t)),
+        },
+        "min" | "max" => check_extremum(name, varargs, env),
+        "sum" => {
+            let element = check_iterable(arg(0), env)?;
+            let start = args[1].clone().unwrap_or(Type::TInteger);
+            match (&element, &start) {
+                (Type::TInteger | Type::TReal | Type::TAny, Type::TInteger | Type::TReal) => {
+                    Ok(unify(&start, &element, env).unwrap_or(Type::TReal))
+                }
+                _ => Err(format!(
+                    "[Type Error] sum() expects numbers, not {:?}.",
+                    element
+                )),
+            }
+        }
+        "int" | "float" => match arg(0) {
+            Type::TInteger | Type::TReal | Type::TBool | Type::TString | Type::TAny => match name {
+                "int" => Ok(Type::TInteger),
+                _ => Ok(Type::TReal),
+            },
+            t => Err(format!(
+                "[Type Error] {}() argument must be a string or a number, not {:?}.",
+                name, t
+            )),
+        },
+        "random.choice" => check_iterable(arg(0), env),
+        "random.shuffle" | "random.sample" => Ok(list(check_iterable(arg(0), env)?)),
+        "bool" | "any" | "all" => {
+            if name != "bool" {
+                check_iterable(arg(0), env)?;
+            }
+            Ok(Type::TBool)
+        }
+        "map" => {
+            if varargs.is_empty() {
+                return Err(String::from(
+                    "[Type Error] map() must have at least two arguments.",
+                ));
+            }
+            let mut elements = vec![];
+            for iterable in varargs {
+                elements.push(check_iterable(iterable, env)?);
+            }
+            Ok(iterator(check_callback(name, callback(0), &elements, env)?))
+        }
+        "filter" => {
+            let element = check_iterable(arg(1), env)?;
+            if arg(0) != Type::TNone {
+                check_callback(name, callback(0), std::slice::from_ref(&element), env)?;
+            }
+            Ok(iterator(element))
+        }
+        "reduce" => {
+            let element = check_iterable(arg(1), env)?;
+            let initial = args[2].clone().unwrap_or(element.clone());
+            let result = check_callback(name, callback(0), &[initial.clone(), element], env)?;
+            unify(&initial, &result, env).ok_or_else(|| {
+                format!(
+                    "[Type Error] reduce() combines values of type {:?} with a function that returns {:?}.",
+                    initial, result
+                )
+            })
+        }
+        "zip" => {
+            let mut elements = vec![];
+            for iterable in varargs {
+                elements.push(check_iterable(iterable, env)?);
+            }
+            Ok(iterator(Type::TTuple(elements)))
+        }
+        "enumerate" => {
+            let element = check_iterable(arg(0), env)?;
+            Ok(iterator(Type::TTuple(vec![Type::TInteger, element])))
+        }
+        "reversed" => match arg(0) {
+            t @ (Type::TSet(_) | Type::TIterator(_) | Type::TObject(_)) => Err(format!(
+                "[Type Error] reversed() expects a sequence, not {:?}.",
+                t
+            )),
+            t => Ok(list(check_iterable(t, env)?)),
+        },
+        "sorted" => {
+            let element = check_iterable(arg(0), env)?;
+            let key = match args[1].clone() {
+                None | Some(Type::TNone) => element.clone(),
+                Some(_) => check_callback(name, callback(1), std::slice::from_ref(&element), env)?,
+            };
+            check_orderable(name, &key, env)?;
+            Ok(list(element))
+        }
+        _ => Err(format!("[Name Error] '{}' is not defined.", name)),
+    }
+}
+
+/* 'min' and 'max' take the elements of a single iterable argument, or
+ * all their arguments, which must be of one orderable type */
+fn check_extremum(name: &str, args: Vec<Type>, env: &Environment) -> Result<Type, ErrorMessage> {
+    let element = match args.len() {
+        0 => {
+            return Err(format!(
+                "[Type Error] {}() expected at least 1 argument, got 0.",
+                name
+            ))
+        }
+        1 => check_iterable(args[0].clone(), env)?,
+        _ => args.iter().skip(1).try_fold(args[0].clone(), |t1, t2| {
+            unify(&t1, t2, env).ok_or_else(|| {
+                format!(
+                    "[Type Error] arguments of {}() have incompatible types {:?} and {:?}.",
+                    name, t1, t2
+                )
+            })
+        })?,
+    };
+    check_orderable(name, &element, env)?;
+    Ok(element)
+}
+
+/* values of the type can be ordered with '<' */
+fn check_orderable(name: &str, t: &Type, env: &Environment) -> Result<(), ErrorMessage> {
+    match t {
+        Type::TAny => Ok(()),
+        Type::TObject(class) => {
+            check_special_method(t, "__lt__", std::slice::from_ref(t), env)?
+                .ok_or_else(|| undefined_operator(class, "__lt__"))?;
+            Ok(())
+        }
+        t if orderable(t, t) => Ok(()),
+        t => Err(format!(
+            "[Type Error] {}() expects orderable values, not {:?}.",
+            name, t
+        )),
+    }
+}
+
+/* the result type of the function a built-in calls with arguments of
+ * the given types, such as the one 'map' applies to each value; that of
+ * a built-in function follows its rule */
+fn check_callback(
+    name: &str,
+    (function, builtin): BuiltinArg,
+    args: &[Type],
+    env: &Environment,
+) -> Result<Type, ErrorMessage> {
+    let (result, signature) = match function {
+        Type::TFunction(result, signature) => (*result, signature),
+        Type::TAny => return Ok(Type::TAny),
+        t => {
+            return Err(format!(
+                "[Type Error] {}() expects a function, not {:?}.",
+                name, t
+            ))
+        }
+    };
+    if !signature.accepts(args.len()) {
+        return Err(format!(
+            "[Type Error] {}() expects a function of {} argument(s), but is given one that takes {}.",
+            name,
+            args.len(),
+            signature.arity()
+        ));
+    }
+    for ((_, expected), actual) in signature.params.iter().zip(args) {
+        if !assignable(expected, actual, env) {
+            return Err(format!(
+                "[Type Error] {}() passes values of type {:?} to a function that expects {:?}.",
+                name, actual, expected
+            ));
+        }
+    }
+    match builtin {
+        Some(builtin) => {
+            let args = args.iter().map(|t| (None, (t.clone(), None))).collect();
+            let binding = signature
+                .bind(&builtin, args)
+                .map_err(|message| format!("[Type Error] {}", message))?;
+            builtin_result(&builtin, binding, env)
+        }
+        None => Ok(result),
+    }
+}
+
+/* a class inherits the fields and methods of its base; an overriding
+ * method must keep the signature of the overridden one, except for
+ * '__init__'. The class is bound before the methods are checked, so
+ * that they may use it. */
+fn check_class_def(class: Class, env: &Environment) -> Result<Environment, ErrorMessage> {
+    let (mut fields, mut methods) = match &class.base {
+        Some(base) => {
+            let (_, fields, methods) = class_members(base, env)?;
+            (fields, methods)
+        }
+        None => (vec![], vec![]),
+    };
+    for (field, t) in class.fields.iter() {
+        if fields.iter().any(|(f, _)| f == field) {
+            return Err(format!(
+                "[Type Error] field '{}' of class '{}' is already declared.",
+                field, class.name
+            ));
+        }
+        fields.push((field.clone(), t.clone()));
+    }
+    for method in class.methods.iter() {
+        if method.params.first().map(|(p, _)| p.as_str()) != Some("self") {
+            return Err(format!(
+                "[Type Error] method '{}' of class '{}' must take 'self' as its first parameter.",
+                method.name, class.name
+            ));
+        }
+        let mut without_self = method.signature();
+        without_self.params.remove(0);
+        without_self.defaults = without_self.defaults.min(without_self.params.len());
+        let signature = Type::TFunction(Box::new(method.kind.clone()), without_self);
+        match methods.iter_mut().find(|(m, _)| *m == method.name) {
+            Some((_, inherited)) if *inherited == signature || method.name == "__init__" => {
+                *inherited = signature
+            }
+            Some(_) => {
+                return Err(format!(
+                "[Type Error] method '{}' of class '{}' does not match the signature it overrides.",
+                method.name, class.name
+            ))
+            }
+            None => methods.push((method.name.clone(), signature)),
+        }
+    }
+    /* a base imported by its plain name is recorded by its qualified
+     * name, which the modules importing this one know it by */
+    let base = class.base.map(|base| qualified_class(&base, env));
+    let mut new_env = env.clone();
+    new_env.declare_class(
+        class.name.clone(),
+        Type::TClass(class.name.clone(), base, fields, methods),
+    );
+    let instance = Type::TObject(class.name);
+    for method in class.methods {
+        let mut body_env = enter_function(&method, &new_env)?;
+        bind_params(&method, &new_env, &mut body_env)?;
+        body_env.insert(String::from("__class__"), instance.clone());
+        body_env.insert(String::from("self"), instance.clone());
+        check_stmt(*method.body.clone(), &body_env)?;
+        check_returns(&method)?;
+    }
+    Ok(new_env)
+}
+
+type Members = Vec<(Name, Type)>;
+
+/* the name of a class as its module declares it, for a class imported
+ * by its plain name */
+fn qualified_class(name: &str, env: &Environment) -> Name {
+    match env.get_class(name) {
+        Some(Type::TClass(qualified, ..)) => qualified.clone(),
+        _ => name.to_string(),
+    }
+}
+
+/* the base class, the fields and the methods of a class */
+fn class_members(
+    name: &str,
+    env: &Environment,
+) -> Result<(Option<Name>, Members, Members), ErrorMessage> {
+    match env.get_class(name) {
+        Some(Type::TClass(_, base, fields, methods)) => {
+            Ok((base.clone(), fields.clone(), methods.clone()))
+        }
+        _ => Err(format!("[Name Error] class '{}' is not defined.", name)),
+    }
+}
+
+fn is_subclass(class: &str, ancestor: &str, env: &Environment) -> bool {
+    let ancestor = qualified_class(ancestor, env);
+    let mut current = Some(class.to_string());
+    while let Some(c) = current {
+        if qualified_class(&c, env) == ancestor {
+            return true;
+        }
+        current = match env.get_class(&c) {
+            Some(Type::TClass(_, base, _, _)) => base.clone(),
+            _ => None,
+        };
+    }
+    false
+}
+
+fn check_object_method_call(
+    class: Name,
+    method: Name,
+    args: Vec<Expression>,
+    env: &Environment,
+) -> Result<Type, ErrorMessage> {
+    let (_, _, methods) = class_members(&class, env)?;
+    match methods.into_iter().find(|(m, _)| *m == method) {
+        Some((_, Type::TFunction(result, signature))) => {
+            check_args(&method, &signature, args, env)?;
+            Ok(*result)
+        }
+        _ => Err(format!(
+            "[Type Error] class '{}' has no method '{}'.",
+            class, method
+        )),
+    }
+}
+
+/* 'super().method(args)' looks for the method starting at the base of
+ * the class whose method is being checked */
+fn check_super_call(
+    method: Name,
+    args: Vec<Expression>,
+    env: &Environment,
+) -> Result<Type, ErrorMessage> {
+    let class = match env.get("__class__") {
+        Some(Type::TObject(class)) => class,
+        _ => {
+            return Err(String::from(
+                "[Type Error] 'super()' used outside a method.",
+            ))
+        }
+    };
+    match class_members(class, env)? {
+        (Some(base), _, _) => check_object_method_call(base, method, args, env),
+        (None, _, _) => Err(format!("[Type Error] class '{}' has no base class.", class)),
+    }
+}
+
+/* the type of the value inside a Maybe or a Result */
+fn check_unwrap(exp: Expression, operation: &str, env: &Environment) -> Result<Type, ErrorMessage> {
+    match check(exp, env)? {
+        Type::TMaybe(t) | Type::TResult(t, _) => Ok(*t),
+        _ => Err(format!(
+            "[Type Error] '{}' expects a Maybe or Result value.",
+            operation
+        )),
+    }
+}
+
+fn check_unwrap_or(
+    exp: Expression,
+    default: Expression,
+    env: &Environment,
+) -> Result<Type, ErrorMessage> {
+    let inner = check_unwrap(exp, "unwrap_or", env)?;
+    let default_type = check(default, env)?;
+    match inner {
+        Type::TAny => Ok(default_type),
+        _ if assignable(&inner, &default_type, env) => Ok(inner),
+        _ => Err(format!(
+            "[Type Error] the default value has type {:?}, but {:?} was expected.",
+            default_type, inner
+        )),
+    }
+}
+
+/* 'e?' returns early from the enclosing function, so the function must
+ * return the same kind of value, and, for a Result, an error type that
+ * accepts the propagated error */
+fn check_propagate(exp: Expression, env: &Environment) -> Result<Type, ErrorMessage> {
+    let exp_type = check(exp, env)?;
+    let returned = env
+        .returns()
+        .ok_or(String::from("[Type Error] '?' used outside a function."))?;
+    match (&exp_type, returned) {
+        (Type::TMaybe(t), Type::TMaybe(_)) => Ok(*t.clone()),
+        (Type::TResult(t, err), Type::TResult(_, expected)) if assignable(expected, err, env) => {
+            Ok(*t.clone())
+        }
+        (Type::TMaybe(_) | Type::TResult(..), _) => Err(format!(
+            "[Type Error] cannot propagate {:?} from a function returning {:?}.",
+            exp_type, returned
+        )),
+        _ => Err(String::from(
+            "[Type Error] '?' expects a Maybe or Result value.",
+        )),
+    }
+}
+
+/* the result type of the special method that implements an operator
+ * over an object; None when the receiver is not an object or its class
+ * does not define the method */
+fn check_special_method(
+    receiver: &Type,
+    method: &str,
+    args: &[Type],
+    env: &Environment,
+) -> Result<Option<Type>, ErrorMessage> {
+    let class = match receiver {
+        Type::TObject(class) => class,
+        _ => return Ok(None),
+    };
+    let (_, _, methods) = class_members(class, env)?;
+    match methods.into_iter().find(|(m, _)| m == method) {
+        Some((_, Type::TFunction(result, signature))) => {
+            let params = signature.params;
+            let accepted = params.len() == args.len()
+                && params
+                    .iter()
+                    .zip(args)
+                    .all(|((_, p), a)| assignable(p, a, env));
+            if accepted {
+                Ok(Some(*result))
+            } else {
+                Err(format!(
+                    "[Type Error] '{}' of class '{}' does not accept operands of type {:?}.",
+                    method, class, args
+                ))
+            }
+        }
+        _ => Ok(None),
+    }
+}
+
+/* the type of the elements produced by iterating over a value of the
+ * given type; objects are iterable through '__iter__', which must return
+ * an iterator or an object with '__next__' */
+fn check_iterable(iterable: Type, env: &Environment) -> Result<Type, ErrorMessage> {
+    match iterable {
+        Type::TList(element) | Type::TSet(element) | Type::TIterator(element) => Ok(*element),
+        Type::TRange => Ok(Type::TInteger),
+        Type::TDict(key, _) => Ok(*key),
+        Type::TString => Ok(Type::TString),
+        Type::TTuple(elements) => match elements.split_first() {
+            Some((first, rest)) if rest.iter().all(|t| t == first) => Ok(first.clone()),
+            _ => Err(String::from(
+                "[Type Error] only tuples whose elements share a type can be iterated.",
+            )),
+        },
+        Type::TAny => Ok(Type::TAny),
+        Type::TObject(class) => {
+            let iterator =
+                check_special_method(&Type::TObject(class.clone()), "__iter__", &[], env)?
+                    .ok_or_else(|| undefined_operator(&class, "__iter__"))?;
+            match iterator {
+                Type::TIterator(element) => Ok(*element),
+                Type::TObject(iterator) => {
+                    check_special_method(&Type::TObject(iterator.clone()), "__next__", &[], env)?
+                        .ok_or_else(|| undefined_operator(&iterator, "__next__"))
+                }
+                other => Err(format!(
+                    "[Type Error] '__iter__' must return an iterator, not {:?}.",
+                    other
+                )),
+            }
+        }
+        other => Err(format!(
+            "[Type Error] expecting an iterable value, found {:?}.",
+            other
+        )),
+    }
+}
+
+fn undefined_operator(class: &str, method: &str) -> ErrorMessage {
+    format!(
+        "[Type Error] class '{}' does not define '{}'.",
+        class, method
+    )
+}
+
+fn expect_result(result: Type, expected: Type, method: &str) -> Result<Type, ErrorMessage> {
+    if result == expected {
+        Ok(result)
+    } else {
+        Err(format!(
+            "[Type Error] '{}' must return {:?}, not {:?}.",
+            method, expected, result
+        ))
+    }
+}
+
+fn check_type_declaration(
+    name: Name,
+    constructors: Vec<ValueConstructor>,
+    env: &Environment,
+) -> Result<Environment, ErrorMessage> {
+    for (i, constructor) in constructors.iter().enumerate() {
+        let redefined = constructors[..i].iter().any(|c| c.name == constructor.name)
+            || matches!(
+                find_constructor(&constructor.name, env),
+                Some((Type::TAlgebraicData(other, _), _)) if other != name
+            );
+        if redefined {
+            return Err(format!(
+                "[Type Error] constructor '{}' is already defined.",
+                constructor.name
+            ));
+        }
+    }
+    let mut new_env = env.clone();
+    new_env.declare_type(name.clone(), Type::TAlgebraicData(name, constructors));
+    Ok(new_env)
+}
+
+/* the algebraic data type that declares a constructor, and the
+ * constructor itself */
+fn find_constructor(name: &str, env: &Environment) -> Option<(Type, ValueConstructor)> {
+    /* the types imported by their qualified names only are not in sight */
+    let in_sight = env.types.iter().filter(|(n, _)| !n.contains('.'));
+    in_sight.map(|(_, t)| t).find_map(|t| match t {
+        Type::TAlgebraicData(_, constructors) => constructors
+            .iter()
+            .find(|c| c.name == name)
+            .map(|c| (t.clone(), c.clone())),
+        _ => None,
+    })
+}
+
+fn check_adt_constructor(
+    name: Name,
+    args: Vec<Expression>,
+    env: &Environment,
+) -> Result<Type, ErrorMessage> {
+    let (adt, constructor) = find_constructor(&name, env).ok_or(format!(
+        "[Name Error] constructor '{}' is not defined.",
+        name
+    ))?;
+    if args.len() != constructor.types.len() {
+        return Err(format!(
+            "[Type Error] constructor '{}' expects {} argument(s), but {} were given.",
+            name,
+            constructor.types.len(),
+            args.len()
+        ));
+    }
+    for (i, (arg, expected)) in args.into_iter().zip(constructor.types.iter()).enumerate() {
+        let arg_type = check(arg, env)?;
+        if !assignable(expected, &arg_type, env) {
+            return Err(format!(
+                "[Type Error] argument {} of constructor '{}' has type {:?} but is given a value of type {:?}.",
+                i + 1,
+                name,
+                expected,
+                arg_type
+            ));
+        }
+    }
+    Ok(adt)
+}
+
+/* besides typing each case, checks that the cases are exhaustive and
+ * that none of them is redundant. Cases with a guard may fail even if
+ * their pattern matches, so they do not cover any value. */
+fn check_match(
+    exp: Expression,
+    cases: Vec<MatchCase>,
+    env: &Environment,
+) -> Result<Environment, ErrorMessage> {
+    let scrutinee = check(exp, env)?;
+    let mut covered: Vec<Vec<Pattern>> = vec![];
+    let mut case_envs = vec![];
+    for case in cases {
+        let mut bindings = HashMap::new();
+        check_pattern(&case.pattern, &scrutinee, env, &mut bindings)?;
+        let row = vec![case.pattern.clone()];
+        if uncovered(&covered, &row, std::slice::from_ref(&scrutinee), env).is_empty() {
+            return Err(format!(
+                "[Type Error] redundant case: pattern {} is already covered by previous cases.",
+                case.pattern
+            ));
+        }
+        let mut case_env = env.clone();
+        for (name, t) in bindings {
+            case_env.insert(name, t);
+        }
+        match case.guard {
+            Some(guard) => check_condition(guard, &case_env)?,
+            None => covered.push(row),
+        }
+        case_envs.push(check_block(*case.body, &case_env)?);
+    }
+    let missing = uncovered(&covered, &[Pattern::Wildcard], &[scrutinee], env);
+    if !missing.is_empty() {
+        let missing = missing
+            .iter()
+            .map(|row| row[0].to_string())
+            .collect::<Vec<String>>();
+        return Err(format!(
+            "[Type Error] non-exhaustive match: {} not covered.",
+            missing.join(", ")
+        ));
+    }
+    let mut merged = env.clone();
+    if let Some((first, others)) = case_envs.split_first() {
+        for (name, t) in first.variables() {
+            if others.iter().all(|e| e.get(&name) == Some(&t)) {
+                merged.insert(name, t);
+            }
+        }
+    }
+    Ok(merged)
+}
+
+fn check_pattern(
+    pattern: &Pattern,
+    expected: &Type,
+    env: &Environment,
+    bindings: &mut HashMap<Name, Type>,
+) -> Result<(), ErrorMessage> {
+    let expected = resolve(expected.clone(), env);
+    let mismatch = || {
+        Err(format!(
+            "[Type Error] pattern {} cannot match a value of type {:?}.",
+            pattern, expected
+        ))
+    };
+    match (pattern, &expected) {
+        (Pattern::Wildcard, _) => Ok(()),
+        (Pattern::Binding(name), _) => {
+            if bindings.contains_key(name) {
+                return Err(format!(
+                    "[Type Error] '{}' is bound more than once in a pattern.",
+                    name
+                ));
+            }
+            bindings.insert(name.clone(), expected.clone());
+            Ok(())
+        }
+        (Pattern::Literal(value), _) => {
+            if comparable_for_equality(&check(value.clone(), env)?, &expected) {
+                Ok(())
+            } else {
+                mismatch()
+            }
+        }
+        (Pattern::Tuple(patterns), Type::TTuple(types)) if patterns.len() == types.len() => {
+            for (p, t) in patterns.iter().zip(types.iter()) {
+                check_pattern(p, t, env, bindings)?;
+            }
+            Ok(())
+        }
+        (Pattern::Constructor(name, patterns), Type::TAlgebraicData(adt, constructors)) => {
+            let constructor = constructors
+                .iter()
+                .find(|c| c.name == *name)
+                .ok_or(format!(
+                    "[Type Error] constructor '{}' is not defined for type '{}'.",
+                    name, adt
+                ))?;
+            if patterns.len() != constructor.types.len() {
+                return Err(format!(
+                    "[Type Error] constructor '{}' expects {} argument(s), but the pattern has {}.",
+                    name,
+                    constructor.types.len(),
+                    patterns.len()
+                ));
+            }
+            for (p, t) in patterns.iter().zip(constructor.types.iter()) {
+                check_pattern(p, t, env, bindings)?;
+            }
+            Ok(())
+        }
+        (_, Type::TAny) => Ok(()),
+        _ => mismatch(),
+    }
+}
+
+/* Exhaustiveness and redundancy follow the usefulness algorithm of
+ * Maranget ("Warnings for pattern matching", 2007): 'uncovered' returns
+ * the rows of patterns, of the given column types, that match values
+ * not matched by any row of 'rows' but matched by 'row'. Each witness
+ * names the missing constructors, with wildcards for their payloads. */
+fn uncovered(
+    rows: &[Vec<Pattern>],
+    row: &[Pattern],
+    types: &[Type],
+    env: &Environment,
+) -> Vec<Vec<Pattern>> {
+    let (first, rest) = match row.split_first() {
+        None if rows.is_empty() => return vec![vec![]],
+        None => return vec![],
+        Some(split) => split,
+    };
+    let column = resolve(types[0].clone(), env);
+    let signature = constructors(&column);
+    match head(first) {
+        Some((h, args)) => {
+            let fields = head_fields(&h, args.len(), &signature);
+            let mut row = args;
+            row.extend(rest.iter().cloned());
+            specialized(rows, &h, row, fields, &types[1..], env)
+        }
+        None => {
+            let seen: Vec<Head> = rows
+                .iter()
+                .filter_map(|r| head(&r[0]).map(|(h, _)| h))
+                .collect();
+            match signature {
+                Some(all) if all.iter().all(|(h, _)| seen.contains(h)) => all
+                    .into_iter()
+                    .flat_map(|(h, fields)| {
+                        let mut row = vec![Pattern::Wildcard; fields.len()];
+                        row.extend(rest.iter().cloned());
+                        specialized(rows, &h, row, fields, &types[1..], env)
+                    })
+                    .collect(),
+                signature => {
+                    let defaults: Vec<Vec<Pattern>> = rows
+                        .iter()
+                        .filter(|r| head(&r[0]).is_none())
+                        .map(|r| r[1..].to_vec())
+                        .collect();
+                    let witnesses = uncovered(&defaults, rest, &types[1..], env);
+                    let missing = match signature {
+                        Some(all) if !seen.is_empty() => all
+                            .into_iter()
+                            .filter(|(h, _)| !seen.contains(h))
+                            .map(|(h, fields)| h.to_pattern(vec![Pattern::Wildcard; fields.len()]))
+                            .collect(),
+                        _ => vec![Pattern::Wildcard],
+                    };
+                    witnesses
+                        .iter()
+                        .flat_map(|w| {
+                            missing.iter().map(move |p| {
+                                let mut witness = vec![p.clone()];
+                                witness.extend(w.iter().cloned());
+                                witness
+                            })
+                        })
+                        .collect()
+                }
+            }
+        }
+    }
+}
+
+/* the head constructor of a pattern, which is either a literal, a
+ * tuple of some length or a value constructor */
+#[derive(Debug, PartialEq, Clone)]
+enum Head {
+    Literal(Expression),
+    Tuple(usize),
+    Constructor(Name),
+}
+
+impl Head {
+    fn to_pattern(&self, args: Vec<Pattern>) -> Pattern {
+        match self {
+            Head::Literal(value) => Pattern::Literal(value.clone()),
+            Head::Tuple(_) => Pattern::Tuple(args),
+            Head::Constructor(name) => Pattern::Constructor(name.clone(), args),
+        }
+    }
+}
+
+/* wildcards and bindings have no head */
+fn head(pattern: &Pattern) -> Option<(Head, Vec<Pattern>)> {
+    match pattern {
+        Pattern::Wildcard | Pattern::Binding(_) => None,
+        Pattern::Literal(value) => Some((Head::Literal(value.clone()), vec![])),
+        Pattern::Tuple(patterns) => Some((Head::Tuple(patterns.len()), patterns.clone())),
+        Pattern::Constructor(name, patterns) => {
+            Some((Head::Constructor(name.clone()), patterns.clone()))
+        }
+    }
+}
+
+/* every head of a type, with the types of its fields, or None when
+ * there are infinitely many (as for numbers and strings) */
+fn constructors(t: &Type) -> Option<Vec<(Head, Vec<Type>)>> {
+    match t {
+        Type::TBool => Some(vec![
+            (Head::Literal(Expression::CTrue), vec![]),
+            (Head::Literal(Expression::CFalse), vec![]),
+        ]),
+        Type::TTuple(types) => Some(vec![(Head::Tuple(types.len()), types.clone())]),
+        Type::TAlgebraicData(_, constructors) => Some(
+            constructors
+                .iter()
+                .map(|c| (Head::Constructor(c.name.clone()), c.types.clone()))
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+fn head_fields(h: &Head, arity: usize, signature: &Option<Vec<(Head, Vec<Type>)>>) -> Vec<Type> {
+    signature
+        .iter()
+        .flatten()
+        .find(|(other, _)| other == h)
+        .map(|(_, fields)| fields.clone())
+        .unwrap_or(vec![Type::TAny; arity])
+}
+
+/* 'uncovered' restricted to the values built with head 'h': 'row' and
+ * 'types' already start with the fields of 'h' */
+fn specialized(
+    rows: &[Vec<Pattern>],
+    h: &Head,
+    row: Vec<Pattern>,
+    fields: Vec<Type>,
+    types: &[Type],
+    env: &Environment,
+) -> Vec<Vec<Pattern>> {
+    let arity = fields.len();
+    let rows: Vec<Vec<Pattern>> = rows
+        .iter()
+        .filter_map(|r| {
+            let mut specialized = match head(&r[0]) {
+                None => vec![Pattern::Wildcard; arity],
+                Some((other, args)) if other == *h => args,
+                Some(_) => return None,
+            };
+            specialized.extend(r[1..].iter().cloned());
+            Some(specialized)
+        })
+        .collect();
+    let mut types_h = fields;
+    types_h.extend(types.iter().cloned());
+    uncovered(&rows, &row, &types_h, env)
+        .into_iter()
+        .map(|mut witness| {
+            let rest = witness.split_off(arity);
+            let mut rebuilt = vec![h.to_pattern(witness)];
+            rebuilt.extend(rest);
+            rebuilt
+        })
+        .collect()
+}
+
+fn check_bin_arithmetic_expression(
+    left: Expression,
+    right: Expression,
+    env: &Environment,
+) -> Result<Type, ErrorMessage> {
+    arithmetic_type(check(left, env)?, check(right, env)?)
+}
+
+fn arithmetic_type(left_type: Type, right_type: Type) -> Result<Type, ErrorMessage> {
+    match (left_type, right_type) {
+        (Type::TInteger, Type::TInteger) => Ok(Type::TInteger),
+        (Type::TInteger, Type::TReal) => Ok(Type::TReal),
+        (Type::TReal, Type::TInteger) => Ok(Type::TReal),
+        (Type::TReal, Type::TReal) => Ok(Type::TReal),
+        _ => Err(String::from("[Type Error] expecting numeric type values.")),
+    }
+}
+
+fn check_add_expression(
+    left: Expression,
+    right: Expression,
+    env: &Environment,
+) -> Result<Type, ErrorMessage> {
+    match (check(left, env)?, check(right, env)?) {
+        (Type::TObject(class), right_type) => {
+            check_special_method(&Type::TObject(class.clone()), "__add__", &[right_type], env)?
+                .ok_or(undefined_operator(&class, "__add__"))
+        }
+        (Type::TString, Type::TString) => Ok(Type::TString),
+        (left_type, right_type) => arithmetic_type(left_type, right_type),
+    }
+}
+
+fn check_mul_expression(
+    left: Expression,
+    right: Expression,
+    env: &Environment,
+) -> Result<Type, ErrorMessage> {
+    match (check(left, env)?, check(right, env)?) {
+        (Type::TString, Type::TInteger) | (Type::TInteger, Type::TString) => Ok(Type::TString),
+        (left_type, right_type) => arithmetic_type(left_type, right_type),
+    }
+}
+
+fn check_index_expression(
+    exp: Expression,
+    index: Expression,
+    env: &Environment,
+) -> Result<Type, ErrorMessage> {
+    let constant_index = match index {
+        Expression::CInt(i) => Some(i),
+        _ => None,
+    };
+    let index_type = check(index, env)?;
+    let container = check(exp, env)?;
+    if !matches!(container, Type::TObject(_) | Type::TDict(..)) && index_type != Type::TInteger {
+        return Err(String::from("[Type Error] indices must be integers."));
+    }
+    match container {
+        Type::TObject(class) => check_special_method(
+            &Type::TObject(class.clone()),
+            "__getitem__",
+            &[index_type],
+            env,
+        )?
+        .ok_or(undefined_operator(&class, "__getitem__")),
+        Type::TDict(key, value) if assignable(&key, &index_type, env) => Ok(*value),
+        Type::TDict(key, _) => Err(format!(
+            "[Type Error] expecting a key of type {:?}, found {:?}.",
+            key, index_type
+        )),
+        Type::TString => Ok(Type::TString),
+        Type::TList(t) => Ok(*t),
+        Type::TRange => Ok(Type::TInteger),
+        Type::TTuple(types) => {
+            let position = constant_index.and_then(|i| {
+                let i = if i < 0 { i + types.len() as i32 } else { i };
+                usize::try_from(i).ok()
+            });
+            match position {
+                Some(p) if p < types.len() => Ok(types[p].clone()),
+                Some(_) => Err(String::from("[Type Error] tuple index out of range.")),
+                None if types.windows(2).all(|w| w[0] == w[1]) && !types.is_empty() => {
+                    Ok(types[0].clone())
+                }
+                None => Err(String::from(
+                    "[Type Error] tuples with heterogeneous elements must be indexed by constants.",
+                )),
+            }
+        }
+        _ => Err(String::from(
+            "[Type Error] expecting a string, list, tuple, dictionary or range value.",
+        )),
+    }
+}
+
+fn check_slice_expression(
+    exp: Expression,
+    start: Option<Box<Expression>>,
+    stop: Option<Box<Expression>>,
+    step: Option<Box<Expression>>,
+    env: &Environment,
+) -> Result<Type, ErrorMessage> {
+    /* the elements of a slice of a tuple are known if its bounds are */
+    let constant = |bound: &Option<Box<Expression>>| match bound.as_deref() {
+        None => Some(None),
+        Some(Expression::CInt(i)) => Some(Some(*i as i64)),
+        Some(_) => None,
+    };
+    let bounds = (constant(&start), constant(&stop), constant(&step));
+    for bound in [start, stop, step].into_iter().flatten() {
+        if check(*bound, env)? != Type::TInteger {
+            return Err(String::from("[Type Error] slice indices must be integers."));
+        }
+    }
+    match check(exp, env)? {
+        t @ (Type::TString | Type::TList(_)) => Ok(t),
+        Type::TTuple(_) if bounds.2 == Some(Some(0)) => {
+            Err(String::from("[Type Error] slice step cannot be zero."))
+        }
+        Type::TTuple(types) => match bounds {
+            (Some(start), Some(stop), Some(step)) => Ok(Type::TTuple(
+                slice_positions(types.len(), start, stop, step.unwrap_or(1))
+                    .into_iter()
+                    .map(|p| types[p].clone())
+                    .collect(),
+            )),
+            _ => Err(String::from(
+                "[Type Error] the bounds of a slice of a tuple must be integer constants.",
+            )),
+        },
+        _ => Err(String::from(
+            "[Type Error] expecting a string, list, tuple, set, dictionary or range value.",
+        )),
+    }
+}
+
+fn check_len_expression(exp: Expression, env: &Environment) -> Result<Type, ErrorMessage> {
+    len_type(check(exp, env)?, env)
+}
+
+fn len_type(t: Type, env: &Environment) -> Result<Type, ErrorMessage> {
+    match t {
+        Type::TObject(class) => {
+            check_special_method(&Type::TObject(class.clone()), "__len__", &[], env)?
+                .ok_or(undefined_operator(&class, "__len__"))
+                .and_then(|result| expect_result(result, Type::TInteger, "__len__"))
+        }
+        Type::TString
+        | Type::TList(_)
+        | Type::TTuple(_)
+        | Type::TSet(_)
+        | Type::TDict(..)
+        | Type::TRange => Ok(Type::TInteger),
+        _ => Err(String::from(
+            "[Type Error] expecting a string, list, tuple, set, dictionary or range value.",
+        )),
+    }
+}
+
+fn check_in_expression(
+    element: Expression,
+    container: Expression,
+    env: &Environment,
+) -> Result<Type, ErrorMessage> {
+    let element_type = check(element, env)?;
+    match check(container, env)? {
+        Type::TString if element_type == Type::TString => Ok(Type::TBool),
+        Type::TString => Err(String::from(
+            "[Type Error] 'in <string>' requires a string as left operand.",
+        )),
+        Type::TList(t) | Type::TSet(t) | Type::TDict(t, _)
+            if comparable_for_equality(&element_type, &t) =>
+        {
+            Ok(Type::TBool)
+        }
+        Type::TTuple(_) => Ok(Type::TBool),
+        Type::TRange if is_numeric(&element_type) => Ok(Type::TBool),
+        Type::TRange => Err(String::from("[Type Error] expecting a number in a range.")),
+        Type::TList(_) => Err(String::from(
+            "[Type Error] expecting an element of the list type.",
+        )),
+        Type::TSet(_) => Err(String::from(
+            "[Type Error] expecting an element of the set type.",
+        )),
+        Type::TDict(..) => Err(String::from(
+            "[Type Error] expecting a key of the dictionary type.",
+        )),
+        _ => Err(String::from(
+            "[Type Error] expecting a string, list, tuple, set, dictionary or range value.",
+        )),
+    }
+}
+
+fn check_fstring(parts: Vec<FStringPart>, env: &Environment) -> Result<Type, ErrorMessage> {
+    for part in parts {
+        if let FStringPart::Field(exp, spec) = part {
+            let exp_type = check(*exp, env)?;
+            let Some((text, offset)) = spec else {
+                continue;
+            };
+            let kind = format::parse_spec(&text, offset)?.kind;
+            let accepted = match kind {
+                None => true,
+                Some('s') => exp_type == Type::TString,
+                Some('d' | 'b' | 'o' | 'x' | 'X') => exp_type == Type::TInteger,
+                Some(_) => is_numeric(&exp_type),
+            };
+            if !accepted {
+                return Err(format!(
+                    "[Type Error] format type '{}' is not defined for values of type {:?}.",
+                    kind.unwrap_or('s'),
+                    exp_type
+                ));
+            }
+        }
+    }
+    Ok(Type::TString)
+}
+
+fn check_method_call(
+    receiver: Expression,
+    method: Name,
+    args: Vec<Expression>,
+    env: &Environment,
+) -> Result<Type, ErrorMessage> {
+    if receiver == Expression::Super {
+        return check_super_call(method, args, env);
+    }
+    match check(receiver, env)? {
+        Type::TObject(class) => check_object_method_call(class, method, args, env),
+        Type::TModule(module, exports) => {
+            let qualified = format!("{}.{}", module, method);
+            let standard = standard_module(&module).as_ref() == Some(&exports);
+            match exports.into_iter().find(|(n, _)| *n == method) {
+                Some((_, Type::TFunction(_, signature)))
+                    if standard && generic_builtin(&qualified) =>
+                {
+                    check_builtin(&qualified, signature, args, env)
+                }
+                Some((_, callee)) => check_call(&method, callee, args, env),
+                None => Err(no_module_attribute(&module, &method)),
+            }
+        }
+        Type::TFile => match file_method(&method) {
+            Some(Type::TFunction(result, signature)) => {
+                check_args(&method, &signature, args, env)?;
+                Ok(*result)
+            }
+            _ => Err(format!("[Type Error] '{}' is not a file method.", method)),
+        },
+        Type::TString => {
+            let (required, optional, result) = string_method_signature(&method)
+                .ok_or(format!("[Type Error] '{}' is not a string method.", method))?;
+            if args.len() < required.len() || args.len() > required.len() + optional.len() {
+                return Err(format!(
+                    "[Type Error] wrong number of arguments to string method '{}'.",
+                    method
+                ));
+            }
+            for (arg, expected) in args.into_iter().zip(required.iter().chain(optional.iter())) {
+                if !comparable_for_equality(&check(arg, env)?, expected) {
+                    return Err(format!(
+                        "[Type Error] string method '{}' expects arguments of type {:?}.",
+                        method, expected
+                    ));
+                }
+            }
+            Ok(result)
+        }
+        _ => Err(format!(
+            "[Type Error] '{}' is not a method of this value.",
+            method
+        )),
+    }
+}
+
+/* (required parameters, optional parameters, result type) of each
+ * string method */
+fn string_method_signature(method: &str) -> Option<(Vec<Type>, Vec<Type>, Type)> {
+    let string_list = Type::TList(Box::new(Type::TString));
+    match method {
+        "upper" | "lower" => Some((vec![], vec![], Type::TString)),
+        "strip" => Some((vec![], vec![Type::TString], Type::TString)),
+        "split" => Some((vec![], vec![Type::TString], string_list)),
+        "join" => Some((vec![string_list], vec![], Type::TString)),
+        "replace" => Some((vec![Type::TString, Type::TString], vec![], Type::TString)),
+        "startswith" | "endswith" => Some((vec![Type::TString], vec![], Type::TBool)),
+        "find" => Some((vec![Type::TString], vec![], Type::TInteger)),
+        _ => None,
+    }
+}
+
+fn check_bin_boolean_expression(
+    left: Expression,
+    right: Expression,
+    env: &Environment,
+) -> Result<Type, ErrorMessage> {
+    let left_type = check(left, env)?;
+    let right_type = check(right, env)?;
+
+    match (left_type, right_type) {
+        (Type::TBool, Type::TBool) => Ok(Type::TBool),
+        _ => Err(String::from("[Type Error] expecting boolean type values.")),
+    }
+}
+
+fn check_not_expression(exp: Expression, env: &Environment) -> Result<Type, ErrorMessage> {
+    let exp_type = check(exp, env)?;
+
+    match exp_type {
+        Type::TBool => Ok(Type::TBool),
+        _ => Err(String::from("[Type Error] expecting a boolean type value.")),
+    }
+}
+
+fn check_var_name(name: Name, env: &Environment) -> Result<Type, ErrorMessage> {
+    match env.get(&name) {
+        Some(t) => Ok(t.clone()),
+        None => builtin_function_type(&name)
+            .ok_or_else(|| format!("[Name Error] '{}' is not defined.", name)),
+    }
+}
+
+/* the type shared by the elements of a collection literal, such as
+ * 'list[int]' for '[[], [1]]' */
+fn check_elements(
+    elements: Vec<Expression>,
+    kind: &str,
+    env: &Environment,
+) -> Result<Type, ErrorMessage> {
+    let mut element_type = Type::TAny;
+    for e in elements {
+        let t = check(e, env)?;
+        element_type = unify(&element_type, &t, env)
+            .ok_or(format!("[Type Error] {} must have the same type.", kind))?;
+    }
+    Ok(element_type)
+}
+
+/* the scope of a comprehension: each 'for' clause binds its variable to
+ * the element type of its iterable, and each 'if' clause must be a
+ * condition */
+fn check_clauses(clauses: Vec<Clause>, env: &Environment) -> Result<Environment, ErrorMessage> {
+    let mut scope = env.clone();
+    for clause in clauses {
+        match clause {
+            Clause::For(name, iterable) => {
+                let element = check_iterable(check(iterable, &scope)?, &scope)?;
+                scope.insert(name, element);
+            }
+            Clause::If(cond) => check_condition(cond, &scope)?,
+        }
+    }
+    Ok(scope)
+}
+
+fn check_bin_equality_expression(
+    left: Expression,
+    right: Expression,
+    env: &Environment,
+) -> Result<Type, ErrorMessage> {
+    let left_type = check(left, env)?;
+    let right_type = check(right, env)?;
+
+    /* without '__eq__', objects compare by identity; as in the
+     * interpreter, values of different types are just not equal */
+    if let Some(result) =
+        check_special_method(&left_type, "__eq__", std::slice::from_ref(&right_type), env)?
+    {
+        return expect_result(result, Type::TBool, "__eq__");
+    }
+    Ok(Type::TBool)
+}
+
+fn check_bin_relational_expression(
+    left: Expression,
+    right: Expression,
+    op: RelationalOp,
+    env: &Environment,
+) -> Result<Type, ErrorMessage> {
+    let left_type = check(left, env)?;
+    let right_type = check(right, env)?;
+
+    /* the method of the left operand, or the reflected one of the right
+     * operand: 'a > b' calls 'a.__gt__(b)' or else 'b.__lt__(a)' */
+    let method = op.special_method();
+    let reflected = op.reflected().special_method();
+    let result =
+        match check_special_method(&left_type, method, std::slice::from_ref(&right_type), env)? {
+            Some(result) => Some((method, result)),
+            None => check_special_method(
+                &right_type,
+                reflected,
+                std::slice::from_ref(&left_type),
+                env,
+            )?
+            .map(|result| (reflected, result)),
+        };
+    match (result, &left_type, &right_type) {
+        (Some((method, result)), _, _) => return expect_result(result, Type::TBool, method),
+        (None, Type::TObject(class), _) => return Err(undefined_operator(class, method)),
+        (None, _, Type::TObject(class)) => return Err(undefined_operator(class, reflected)),
+        (None, _, _) => (),
+    }
+    if orderable(&left_type, &right_type) {
+        Ok(Type::TBool)
+    } else {
+        Err(String::from(
+            "[Type Error] expecting numeric, boolean, string or sequence type values.",
+        ))
+    }
+}
+
+fn check_comparison_chain(
+    first: Expression,
+    links: Vec<(RelationalOp, Expression)>,
+    env: &Environment,
+) -> Result<Type, ErrorMessage> {
+    let mut left = first;
+    for (op, right) in links {
+        match op {
+            RelationalOp::EQ | RelationalOp::NEQ => {
+                check_bin_equality_expression(left, right.clone(), env)?
+            }
+            op => check_bin_relational_expression(left, right.clone(), op, env)?,
+        };
+        left = right;
+    }
+    Ok(Type::TBool)
+}
+
+fn comparable_for_equality(left: &Type, right: &Type) -> bool {
+    match (left, right) {
+        (Type::TAny, _) | (_, Type::TAny) => true,
+        (Type::TRecord(l, _), Type::TRecord(r, _)) => l == r,
+        (Type::TAlgebraicData(l, _), Type::TAlgebraicData(r, _)) => l == r,
+        (Type::TObject(_), Type::TObject(_)) => true,
+        (Type::TList(l), Type::TList(r)) | (Type::TSet(l), Type::TSet(r)) => {
+            comparable_for_equality(l, r)
+        }
+        (Type::TDict(l_key, l_value), Type::TDict(r_key, r_value)) => {
+            comparable_for_equality(l_key, r_key) && comparable_for_equality(l_value, r_value)
+        }
+        (Type::TMaybe(l), Type::TMaybe(r)) => comparable_for_equality(l, r),
+        (Type::TResult(l_ok, l_err), Type::TResult(r_ok, r_err)) => {
+            comparable_for_equality(l_ok, r_ok) && comparable_for_equality(l_err, r_err)
+        }
+        (Type::TTuple(l), Type::TTuple(r)) => {
+            l.len() == r.len()
+                && l.iter()
+                    .zip(r.iter())
+                    .all(|(l, r)| comparable_for_equality(l, r))
+        }
+        _ => is_numeric(left) && is_numeric(right) || left == right,
+    }
+}
+
+fn orderable(left: &Type, right: &Type) -> bool {
+    match (left, right) {
+        (Type::TString, Type::TString) | (Type::TBool, Type::TBool) => true,
+        (Type::TList(l), Type::TList(r)) => {
+            **l == Type::TAny || **r == Type::TAny || orderable(l, r)
+        }
+        /* the shorter of two tuples that agree up to its length is the
+         * smaller */
+        (Type::TTuple(l), Type::TTuple(r)) => l.iter().zip(r.iter()).all(|(l, r)| orderable(l, r)),
+        _ => is_numeric(left) && is_numeric(right),
+    }
+}
+
+fn is_numeric(t: &Type) -> bool {
+    matches!(t, Type::TInteger | Type::TReal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::ast::ArithmeticOp;
+
+    use crate::ir::ast::Expression::*;
+    use crate::ir::ast::Type::*;
+
+    #[test]
+    fn check_tlist_comparison() {
+        let t_list1 = TList(Box::new(TInteger));
+        let t_list2 = TList(Box::new(TInteger));
+
+        assert_eq!(t_list1, t_list2);
+    }
+
+    #[test]
+    fn check_tlist_comparison_different_types() {
+        let t_list1 = TList(Box::new(TInteger));
+        let t_list2 = TList(Box::new(TBool));
+
+        assert_ne!(t_list1, t_list2);
+    }
+
+    #[test]
+    fn check_ttuple_comparison() {
+        let t_tuple1 = TTuple(vec![TInteger, TBool]);
+        let t_tuple2 = TTuple(vec![TInteger, TBool]);
+
+        assert_eq!(t_tuple1, t_tuple2);
+    }
+
+    #[test]
+    fn check_ttuple_comparison_different_types() {
+        let t_tuple1 = TTuple(vec![TInteger, TBool]);
+        let t_tuple2 = TTuple(vec![TBool, TInteger]);
+
+        assert_ne!(t_tuple1, t_tuple2);
+    }
+
+    #[test]
+    fn check_constant() {
+        let env = Environment::new();
+        let c10 = CInt(10);
+        assert_eq!(check(c10, &env), Ok(TInteger));
+    }
+
+    #[test]
+    fn check_add_integers() {
+        let env = Environment::new();
+        let c10 = CInt(10);
+        let c20 = CInt(20);
+        let add = Add(Box::new(c10), Box::new(c20));
+
+        assert_eq!(check(add, &env), Ok(TInteger));
+    }
+
+    #[test]
+    fn check_add_reals() {
+        let env = Environment::new();
+        let c10 = CReal(10.5);
+        let c20 = CReal(20.3);
+        let add = Add(Box::new(c10), Box::new(c20));
+
+        assert_eq!(check(add, &env), Ok(TReal));
+    }
+
+    #[test]
+    fn check_add_real_and_integer() {
+        let env = Environment::new();
+        let c10 = CInt(10);
+        let c20 = CReal(20.3);
+        let add = Add(Box::new(c10), Box::new(c20));
+
+        assert_eq!(check(add, &env), Ok(TReal));
+    }
+
+    #[test]
+    fn check_add_integer_and_real() {
+        let env = Environment::new();
+        let c10 = CReal(10.5);
+        let c20 = CInt(20);
+        let add = Add(Box::new(c10), Box::new(c20));
+
+        assert_eq!(check(add, &env), Ok(TReal));
+    }
+
+    #[test]
+    fn check_deeply_nested_arithmetic() {
+        let env = Environment::new();
+        let mut exp = CInt(1);
+        for i in 0..64 {
+            exp = if i % 2 == 0 {
+                Add(Box::new(exp), Box::new(CInt(i)))
+            } else {
+                Mul(Box::new(exp), Box::new(CReal(1.0)))
+            };
+        }
+
+        assert_eq!(check(exp, &env), Ok(TReal));
+    }
+
+    #[test]
+    fn check_deeply_nested_indexing() {
+        let mut list_type = TInteger;
+        let mut exp = Var(String::from("xs"));
+        for _ in 0..30 {
+            list_type = TList(Box::new(list_type));
+            exp = Index(Box::new(exp), Box::new(CInt(0)));
+        }
+        let env = Environment::from([(String::from("xs"), list_type)]);
+
+        assert_eq!(check(exp, &env), Ok(TInteger));
+    }
+
+    #[test]
+    fn check_type_error_arithmetic_expression() {
+        let env = Environment::new();
+        let c10 = CInt(10);
+        let bool = CFalse;
+        let add = Add(Box::new(c10), Box::new(bool));
+
+        assert_eq!(
+            check(add, &env),
+            Err(String::from("[Type Error] expecting numeric type values."))
+        );
+    }
+
+    #[test]
+    fn check_type_error_not_expression() {
+        let env = Environment::new();
+        let c10 = CInt(10);
+        let not = Not(Box::new(c10));
+
+        assert_eq!(
+            check(not, &env),
+            Err(String::from("[Type Error] expecting a boolean type value."))
+        );
+    }
+
+    #[test]
+    fn check_type_error_and_expression() {
+        let env = Environment::new();
+        let c10 = CInt(10);
+        let bool = CTrue;
+        let and = And(Box::new(c10), Box::new(bool));
+
+        assert_eq!(
+            check(and, &env),
+            Err(String::from("[Type Error] expecting boolean type values."))
+        );
+    }
+
+    #[test]
+    fn check_type_error_or_expression() {
+        let env = Environment::new();
+        let c10 = CInt(10);
+        let bool = CTrue;
+        let or = Or(Box::new(c10), Box::new(bool));
+
+        assert_eq!(
+            check(or, &env),
+            Err(String::from("[Type Error] expecting boolean type values."))
+        );
+    }
+
+    #[test]
+    fn check_equality_over_every_type() {
+        let env = Environment::new();
+        let s = |v: &str| Box::new(CString(String::from(v)));
+
+        assert_eq!(check(EQ(s("a"), s("b")), &env), Ok(TBool));
+        assert_eq!(
+            check(NEQ(Box::new(CTrue), Box::new(CFalse)), &env),
+            Ok(TBool)
+        );
+        assert_eq!(
+            check(EQ(Box::new(CInt(1)), Box::new(CReal(1.0))), &env),
+            Ok(TBool)
+        );
+        assert_eq!(
+            check(
+                EQ(
+                    Box::new(ListValue(vec![CInt(1)])),
+                    Box::new(ListValue(vec![]))
+                ),
+                &env
+            ),
+            Ok(TBool)
+        );
+    }
+
+    #[test]
+    fn check_equality_across_types() {
+        let env = Environment::new();
+        let eq = EQ(Box::new(CInt(1)), Box::new(CString(String::from("1"))));
+
+        assert_eq!(check(eq, &env), Ok(TBool));
+    }
+
+    #[test]
+    fn check_ordering_of_tuples_of_different_lengths() {
+        let env = Environment::new();
+        let lt = LT(
+            Box::new(Tuple(vec![CInt(1)])),
+            Box::new(Tuple(vec![CInt(1), CString(String::from("a"))])),
+        );
+
+        assert_eq!(check(lt, &env), Ok(TBool));
+    }
+
+    #[test]
+    fn check_nested_list_with_an_empty_element() {
+        let env = Environment::new();
+        let nested = ListValue(vec![ListValue(vec![]), ListValue(vec![CInt(1)])]);
+
+        assert_eq!(
+            check(nested, &env),
+            Ok(TList(Box::new(TList(Box::new(TInteger)))))
+        );
+    }
+
+    #[test]
+    fn check_ordering_over_strings_and_sequences() {
+        let env = Environment::new();
+        let s = |v: &str| Box::new(CString(String::from(v)));
+        let t = |v: Expression| Box::new(Tuple(vec![CInt(1), v]));
+
+        assert_eq!(check(LT(s("a"), s("b")), &env), Ok(TBool));
+        assert_eq!(check(GTE(t(CReal(1.0)), t(CInt(2))), &env), Ok(TBool));
+        assert_eq!(
+            check(LTE(Box::new(CTrue), Box::new(CFalse)), &env),
+            Ok(TBool)
+        );
+        assert_eq!(
+            check(LTE(Box::new(CTrue), Box::new(CInt(1))), &env),
+            Err(String::from(
+                "[Type Error] expecting numeric, boolean, string or sequence type values."
+            ))
+        );
+    }
+
+    #[test]
+    fn check_list_value() {
+        let env = Environment::new();
+        let list = ListValue(vec![CInt(1), CInt(2)]);
+        let mixed = ListValue(vec![CInt(1), CTrue]);
+
+        assert_eq!(check(list, &env), Ok(TList(Box::new(TInteger))));
+        assert_eq!(check(ListValue(vec![]), &env), Ok(TList(Box::new(TAny))));
+        assert_eq!(
+            check(mixed, &env),
+            Err(String::from(
+                "[Type Error] list elements must have the same type."
+            ))
+        );
+    }
+
+    #[test]
+    fn check_comparison_chain() {
+        let env = Environment::new();
+        let chain = Comparison(
+            Box::new(CInt(0)),
+            vec![(RelationalOp::LTE, CReal(1.5)), (RelationalOp::LT, CInt(3))],
+        );
+
+        assert_eq!(check(chain, &env), Ok(TBool));
+    }
+
+    #[test]
+    fn check_type_error_in_comparison_chain() {
+        let env = Environment::new();
+        let chain = Comparison(
+            Box::new(CInt(0)),
+            vec![
+                (RelationalOp::LT, CInt(1)),
+                (RelationalOp::LT, CString(String::from("2"))),
+            ],
+        );
+
+        assert_eq!(
+            check(chain, &env),
+            Err(String::from(
+                "[Type Error] expecting numeric, boolean, string or sequence type values."
+            ))
+        );
+    }
+
+    #[test]
+    fn check_string_operations() {
+        let env = Environment::from([(String::from("s"), TString)]);
+        let s = || Box::new(Var(String::from("s")));
+
+        assert_eq!(check(Add(s(), s()), &env), Ok(TString));
+        assert_eq!(check(Mul(Box::new(CInt(3)), s()), &env), Ok(TString));
+        assert_eq!(check(Index(s(), Box::new(CInt(0))), &env), Ok(TString));
+        assert_eq!(
+            check(Slice(s(), Some(Box::new(CInt(1))), None, None), &env),
+            Ok(TString)
+        );
+        assert_eq!(check(Len(s()), &env), Ok(TInteger));
+        assert_eq!(check(In(s(), s()), &env), Ok(TBool));
+        assert_eq!(
+            check(Add(s(), Box::new(CInt(1))), &env),
+            Err(String::from("[Type Error] expecting numeric type values."))
+        );
+    }
+
+    #[test]
+    fn check_tuple_index() {
+        let env = Environment::new();
+        let tuple = || Box::new(Tuple(vec![CInt(1), CString(String::from("a"))]));
+
+        assert_eq!(check(Index(tuple(), Box::new(CInt(1))), &env), Ok(TString));
+        assert_eq!(
+            check(Index(tuple(), Box::new(CInt(-2))), &env),
+            Ok(TInteger)
+        );
+        assert_eq!(
+            check(Index(tuple(), Box::new(CInt(2))), &env),
+            Err(String::from("[Type Error] tuple index out of range."))
+        );
+    }
+
+    #[test]
+    fn check_tuple_slice() {
+        let env = Environment::from([(String::from("i"), TInteger)]);
+        let tuple = || Box::new(Tuple(vec![CInt(1), CString(String::from("a")), CReal(2.0)]));
+        let int = |i| Some(Box::new(CInt(i)));
+
+        assert_eq!(
+            check(Slice(tuple(), None, int(1), None), &env),
+            Ok(TTuple(vec![TInteger]))
+        );
+        assert_eq!(
+            check(Slice(tuple(), int(-2), None, None), &env),
+            Ok(TTuple(vec![TString, TReal]))
+        );
+        assert_eq!(
+            check(Slice(tuple(), None, None, int(-2)), &env),
+            Ok(TTuple(vec![TReal, TInteger]))
+        );
+        assert_eq!(
+            check(Slice(tuple(), int(5), None, None), &env),
+            Ok(TTuple(vec![]))
+        );
+    }
+
+    #[test]
+    fn check_tuple_slice_errors() {
+        let env = Environment::from([(String::from("i"), TInteger)]);
+        let tuple = || Box::new(Tuple(vec![CInt(1), CInt(2), CInt(3)]));
+        let int = |i| Some(Box::new(CInt(i)));
+
+        /* t[:1][2] is out of range */
+        assert_eq!(
+            check(
+                Index(
+                    Box::new(Slice(tuple(), None, int(1), None)),
+                    Box::new(CInt(2))
+                ),
+                &env
+            ),
+            Err(String::from("[Type Error] tuple index out of range."))
+        );
+        assert_eq!(
+            check(
+                Slice(tuple(), Some(Box::new(Var(String::from("i")))), None, None),
+                &env
+            ),
+            Err(String::from(
+                "[Type Error] the bounds of a slice of a tuple must be integer constants."
+            ))
+        );
+        assert_eq!(
+            check(Slice(tuple(), None, None, int(0)), &env),
+            Err(String::from("[Type Error] slice step cannot be zero."))
+        );
+    }
+
+    #[test]
+    fn check_string_method_signatures() {
+        let env = Environment::new();
+        let s = |v: &str| CString(String::from(v));
+        let call =
+            |m: &str, args: Vec<Expression>| MethodCall(Box::new(s("a b")), String::from(m), args);
+
+        assert_eq!(check(call("upper", vec![]), &env), Ok(TString));
+        assert_eq!(
+            check(call("split", vec![]), &env),
+            Ok(TList(Box::new(TString)))
+        );
+        assert_eq!(
+            check(call("split", vec![s(",")]), &env),
+            Ok(TList(Box::new(TString)))
+        );
+        assert_eq!(check(call("find", vec![s("b")]), &env), Ok(TInteger));
+        assert_eq!(check(call("startswith", vec![s("a")]), &env), Ok(TBool));
+        assert_eq!(
+            check(call("join", vec![ListValue(vec![s("x"), s("y")])]), &env),
+            Ok(TString)
+        );
+        assert_eq!(
+            check(call("replace", vec![s("a")]), &env),
+            Err(String::from(
+                "[Type Error] wrong number of arguments to string method 'replace'."
+            ))
+        );
+        assert_eq!(
+            check(call("find", vec![CInt(1)]), &env),
+            Err(String::from(
+                "[Type Error] string method 'find' expects arguments of type TString."
+            ))
+        );
+        assert_eq!(
+            check(call("shout", vec![]), &env),
+            Err(String::from("[Type Error] 'shout' is not a string method."))
+        );
+    }
+
+    #[test]
+    fn check_fstring() {
+        let env = Environment::from([(String::from("x"), TReal)]);
+        let field = |e: Expression, spec: &str| {
+            FString(vec![
+                FStringPart::Literal(String::from("v=")),
+                FStringPart::Field(Box::new(e), Some((String::from(spec), 5))),
+            ])
+        };
+
+        assert_eq!(
+            check(field(Var(String::from("x")), ".2f"), &env),
+            Ok(TString)
+        );
+        assert_eq!(check(field(CInt(3), "#x"), &env), Ok(TString));
+        assert_eq!(
+            check(field(Var(String::from("x")), "x"), &env),
+            Err(String::from(
+                "[Type Error] format type 'x' is not defined for values of type TReal."
+            ))
+        );
+        assert_eq!(
+            check(field(Var(String::from("y")), ""), &env),
+            Err(String::from("[Name Error] 'y' is not defined."))
+        );
+        assert_eq!(
+            check(field(CInt(3), "5k"), &env),
+            Err(String::from(
+                "[Format Error] invalid format spec at position 6: unknown format type 'k'."
+            ))
+        );
+    }
+
+    fn point_declaration() -> Statement {
+        Statement::RecordDeclaration(
+            String::from("Point"),
+            vec![(String::from("x"), TInteger), (String::from("y"), TReal)],
+        )
+    }
+
+    fn point_type() -> Type {
+        TRecord(
+            String::from("Point"),
+            vec![(String::from("x"), TInteger), (String::from("y"), TReal)],
+        )
+    }
+
+    #[test]
+    fn check_record_construction() {
+        let env = check_stmt(point_declaration(), &Environment::new()).unwrap();
+        let point = |y: Expression| {
+            Record(
+                String::from("Point"),
+                vec![(String::from("x"), CInt(1)), (String::from("y"), y)],
+            )
+        };
+
+        assert_eq!(check(point(CInt(2)), &env), Ok(point_type()));
+        assert_eq!(
+            check(point(CTrue), &env),
+            Err(String::from(
+                "[Type Error] field 'y' has type TReal but is given a value of type TBool."
+            ))
+        );
+        assert_eq!(
+            check(
+                Record(String::from("Point"), vec![(String::from("x"), CInt(1))]),
+                &env
+            ),
+            Err(String::from(
+                "[Type Error] missing field 'y' of record 'Point'."
+            ))
+        );
+    }
+
+    #[test]
+    fn check_field_access_and_assignment() {
+        let env = Environment::from([(String::from("p"), point_type())]);
+        let p = || Box::new(Var(String::from("p")));
+
+        assert_eq!(check(FieldAccess(p(), String::from("y")), &env), Ok(TReal));
+        assert_eq!(
+            check(FieldAccess(p(), String::from("z")), &env),
+            Err(String::from(
+                "[Type Error] record 'Point' has no field 'z'."
+            ))
+        );
+        assert_eq!(
+            check(FieldAccess(Box::new(CInt(1)), String::from("z")), &env),
+            Err(String::from(
+                "[Type Error] cannot access field 'z' of a value of type TInteger."
+            ))
+        );
+
+        let update =
+            |e: Expression| Statement::FieldAssignment(p(), String::from("x"), Box::new(e));
+        assert_eq!(check_stmt(update(CInt(2)), &env), Ok(env.clone()));
+        assert_eq!(
+            check_stmt(update(CReal(2.5)), &env),
+            Err(String::from(
+                "[Type Error] field 'x' has type TInteger but is assigned a value of type TReal."
+            ))
+        );
+    }
+
+    #[test]
+    fn check_nested_record_fields() {
+        /*
+         * > record Point: x: int, y: real
+         * > record Line: start: Point, end: Point
+         * > l = Line(start=Point(x=0, y=0), end=Point(x=1, y=1))
+         */
+        let line = Statement::RecordDeclaration(
+            String::from("Line"),
+            vec![
+                (
+                    String::from("start"),
+                    TRecord(String::from("Point"), vec![]),
+                ),
+                (String::from("end"), TRecord(String::from("Point"), vec![])),
+            ],
+        );
+        let env = check_stmt(
+            line,
+            &check_stmt(point_declaration(), &Environment::new()).unwrap(),
+        )
+        .unwrap();
+        let point = |x: i32| {
+            Record(
+                String::from("Point"),
+                vec![(String::from("x"), CInt(x)), (String::from("y"), CInt(x))],
+            )
+        };
+        let l = Record(
+            String::from("Line"),
+            vec![
+                (String::from("start"), point(0)),
+                (String::from("end"), point(1)),
+            ],
+        );
+        assert_eq!(
+            check(l.clone(), &env),
+            Ok(TRecord(
+                String::from("Line"),
+                vec![
+                    (
+                        String::from("start"),
+                        TRecord(String::from("Point"), vec![])
+                    ),
+                    (String::from("end"), TRecord(String::from("Point"), vec![])),
+                ],
+            ))
+        );
+        let start = FieldAccess(Box::new(l), String::from("start"));
+        assert_eq!(
+            check(FieldAccess(Box::new(start), String::from("y")), &env),
+            Ok(TReal)
+        );
+    }
+
+    #[test]
+    fn check_variables_do_not_hide_types() {
+        /*
+         * > record Point: x: int, y: real
+         * > Point = 1
+         * > p = Point(x=1, y=2.0)
+         */
+        let program = Statement::Sequence(
+            Box::new(point_declaration()),
+            Box::new(Statement::Sequence(
+                Box::new(Statement::Assignment(
+                    Box::new(String::from("Point")),
+                    Box::new(CInt(1)),
+                )),
+                Box::new(Statement::Assignment(
+                    Box::new(String::from("p")),
+                    Box::new(Record(
+                        String::from("Point"),
+                        vec![
+                            (String::from("x"), CInt(1)),
+                            (String::from("y"), CReal(2.0)),
+                        ],
+                    )),
+                )),
+            )),
+        );
+        let env = check_stmt(program, &Environment::new()).unwrap();
+
+        assert_eq!(env.get("Point"), Some(&TInteger));
+        assert_eq!(env.get("p"), Some(&point_type()));
+    }
+
+    #[test]
+    fn check_statements() {
+        /*
+         * > if c:
+         * >   y = 1
+         * > else:
+         * >   y = 2
+         * >   z = 3
+         */
+        let env = Environment::from([(String::from("c"), TBool)]);
+        let assign = |name: &str, e: Expression| {
+            Statement::Assignment(Box::new(String::from(name)), Box::new(e))
+        };
+        let program = Statement::IfThenElse(
+            Box::new(Var(String::from("c"))),
+            Box::new(assign("y", CInt(1))),
+            Box::new(Statement::Sequence(
+                Box::new(assign("y", CInt(2))),
+                Box::new(assign("z", CInt(3))),
+            )),
+        );
+
+        let new_env = check_stmt(program, &env).unwrap();
+        assert_eq!(new_env.get("y"), Some(&TInteger));
+        assert_eq!(new_env.get("z"), None);
+        assert_eq!(
+            check_stmt(
+                Statement::While(Box::new(CInt(1)), Box::new(assign("y", CInt(1)))),
+                &env
+            ),
+            Err(String::from("[Type Error] expecting a boolean condition."))
+        );
+    }
+
+    fn shape_declaration() -> Statement {
+        Statement::TypeDeclaration(
+            String::from("Shape"),
+            vec![
+                ValueConstructor {
+                    name: String::from("Circle"),
+                    types: vec![TReal],
+                },
+                ValueConstructor {
+                    name: String::from("Rect"),
+                    types: vec![TReal, TReal],
+                },
+                ValueConstructor {
+                    name: String::from("Dot"),
+                    types: vec![],
+                },
+            ],
+        )
+    }
+
+    fn shape_env() -> Environment {
+        let env = check_stmt(shape_declaration(), &Environment::new()).unwrap();
+        let mut env = check_stmt(
+            Statement::Assignment(
+                Box::new(String::from("s")),
+                Box::new(ADTConstructor(String::from("Dot"), vec![])),
+            ),
+            &env,
+        )
+        .unwrap();
+        env.insert(String::from("flags"), TTuple(vec![TBool, TBool]));
+        env
+    }
+
+    fn case(pattern: Pattern, guard: Option<Expression>) -> MatchCase {
+        MatchCase {
+            pattern,
+            guard,
+            body: Box::new(Statement::VarDeclaration(Box::new(String::from("_")))),
+        }
+    }
+
+    fn constructor(name: &str, args: Vec<Pattern>) -> Pattern {
+        Pattern::Constructor(String::from(name), args)
+    }
+
+    fn check_cases(scrutinee: &str, cases: Vec<MatchCase>) -> Result<Environment, ErrorMessage> {
+        let exp = Box::new(Var(String::from(scrutinee)));
+        check_stmt(Statement::Match(exp, cases), &shape_env())
+    }
+
+    #[test]
+    fn check_adt_constructors() {
+        let env = shape_env();
+
+        assert!(matches!(
+            check(ADTConstructor(String::from("Circle"), vec![CInt(1)]), &env),
+            Ok(TAlgebraicData(name, _)) if name == "Shape"
+        ));
+        assert_eq!(
+            check(ADTConstructor(String::from("Circle"), vec![CTrue]), &env),
+            Err(String::from(
+                "[Type Error] argument 1 of constructor 'Circle' has type TReal but is given a value of type TBool."
+            ))
+        );
+        assert_eq!(
+            check(ADTConstructor(String::from("Square"), vec![]), &env),
+            Err(String::from(
+                "[Name Error] constructor 'Square' is not defined."
+            ))
+        );
+        assert_eq!(
+            check_stmt(
+                Statement::TypeDeclaration(
+                    String::from("Point"),
+                    vec![ValueConstructor {
+                        name: String::from("Dot"),
+                        types: vec![],
+                    }],
+                ),
+                &env
+            ),
+            Err(String::from(
+                "[Type Error] constructor 'Dot' is already defined."
+            ))
+        );
+    }
+
+    #[test]
+    fn check_match_bindings() {
+        let cases = vec![
+            case(
+                constructor("Circle", vec![Pattern::Binding(String::from("r"))]),
+                None,
+            ),
+            case(
+                constructor(
+                    "Rect",
+                    vec![Pattern::Binding(String::from("r")), Pattern::Wildcard],
+                ),
+                None,
+            ),
+            case(Pattern::Wildcard, None),
+        ];
+        let bad_literal = vec![case(
+            constructor("Circle", vec![Pattern::Literal(CString(String::from("a")))]),
+            None,
+        )];
+
+        assert!(check_cases("s", cases).is_ok());
+        assert_eq!(
+            check_cases("s", bad_literal),
+            Err(String::from(
+                "[Type Error] pattern 'a' cannot match a value of type TReal."
+            ))
+        );
+        assert_eq!(
+            check_cases(
+                "s",
+                vec![case(constructor("Rect", vec![Pattern::Wildcard]), None)]
+            ),
+            Err(String::from(
+                "[Type Error] constructor 'Rect' expects 2 argument(s), but the pattern has 1."
+            ))
+        );
+    }
+
+    #[test]
+    fn check_match_exhaustiveness() {
+        let circle = case(constructor("Circle", vec![Pattern::Wildcard]), None);
+
+        assert_eq!(
+            check_cases("s", vec![circle]),
+            Err(String::from(
+                "[Type Error] non-exhaustive match: Rect(_, _), Dot not covered."
+            ))
+        );
+
+        let t = || Pattern::Literal(CTrue);
+        let f = || Pattern::Literal(CFalse);
+        let flags = vec![
+            case(Pattern::Tuple(vec![t(), Pattern::Wildcard]), None),
+            case(Pattern::Tuple(vec![f(), t()]), None),
+        ];
+        assert_eq!(
+            check_cases("flags", flags),
+            Err(String::from(
+                "[Type Error] non-exhaustive match: (False, False) not covered."
+            ))
+        );
+        let flags = vec![
+            case(Pattern::Tuple(vec![t(), Pattern::Wildcard]), None),
+            case(
+                Pattern::Tuple(vec![Pattern::Wildcard, Pattern::Binding(String::from("b"))]),
+                None,
+            ),
+        ];
+        assert!(check_cases("flags", flags).is_ok());
+    }
+
+    #[test]
+    fn check_match_guards_and_redundancy() {
+        let dot = || case(constructor("Dot", vec![]), None);
+        let rest = || case(Pattern::Binding(String::from("other")), None);
+        let guarded = case(
+            constructor("Circle", vec![Pattern::Binding(String::from("r"))]),
+            Some(GT(Box::new(Var(String::from("r"))), Box::new(CReal(1.0)))),
+        );
+
+        assert!(check_cases("s", vec![guarded.clone(), rest()]).is_ok());
+        assert_eq!(
+            check_cases("s", vec![guarded, dot()]),
+            Err(String::from(
+                "[Type Error] non-exhaustive match: Circle(_), Rect(_, _) not covered."
+            ))
+        );
+        assert_eq!(
+            check_cases("s", vec![rest(), dot()]),
+            Err(String::from(
+                "[Type Error] redundant case: pattern Dot is already covered by previous cases."
+            ))
+        );
+        assert_eq!(
+            check_cases(
+                "flags",
+                vec![
+                    case(Pattern::Tuple(vec![Pattern::Literal(CTrue), Pattern::Wildcard]), None),
+                    case(Pattern::Tuple(vec![Pattern::Literal(CFalse), Pattern::Wildcard]), None),
+                    case(Pattern::Tuple(vec![Pattern::Wildcard, Pattern::Literal(CTrue)]), None),
+                ]
+            ),
+            Err(String::from(
+                "[Type Error] redundant case: pattern (_, True) is already covered by previous cases."
+            ))
+        );
+    }
+
+    fn result_of(ok: Type, err: Type) -> Type {
+        TResult(Box::new(ok), Box::new(err))
+    }
+
+    fn def(kind: Type, params: Vec<(&str, Type)>, body: Statement) -> Statement {
+        Statement::FuncDef(Box::new(Function {
+            name: String::from("f"),
+            kind,
+            params: params
+                .into_iter()
+                .map(|(p, t)| (String::from(p), t))
+                .collect(),
+            defaults: vec![],
+            varargs: None,
+            kwargs: None,
+            body: Box::new(body),
+        }))
+    }
+
+    fn ret(exp: Expression) -> Statement {
+        Statement::Return(Box::new(exp))
+    }
+
+    #[test]
+    fn check_functions() {
+        let env = check_stmt(
+            def(
+                TInteger,
+                vec![("n", TInteger)],
+                ret(Mul(Box::new(Var(String::from("n"))), Box::new(CInt(2)))),
+            ),
+            &Environment::new(),
+        )
+        .unwrap();
+        let call = |args| FuncCall(String::from("f"), args);
+
+        assert_eq!(check(call(vec![CInt(1)]), &env), Ok(TInteger));
+        assert_eq!(
+            check(call(vec![CReal(1.0)]), &env),
+            Err(String::from(
+                "[Type Error] argument 1 of f() has type TInteger but is given a value of type TReal."
+            ))
+        );
+        assert_eq!(
+            check(call(vec![]), &env),
+            Err(String::from("[Type Error] f() missing argument 'n'."))
+        );
+        assert_eq!(
+            check_stmt(def(TInteger, vec![], ret(CTrue)), &Environment::new()),
+            Err(String::from(
+                "[Type Error] expecting a return value of type TInteger, found TBool."
+            ))
+        );
+        assert_eq!(
+            check_stmt(ret(CInt(1)), &Environment::new()),
+            Err(String::from("[Type Error] 'return' outside function."))
+        );
+    }
+
+    #[test]
+    fn check_functions_return_on_every_path() {
+        /*
+         * > def f(n: int) -> int:
+         * >   if n > 0:
+         * >     return n
+         */
+        let positive = |otherwise: Statement| {
+            def(
+                TInteger,
+                vec![("n", TInteger)],
+                Statement::IfThenElse(
+                    Box::new(GT(Box::new(Var(String::from("n"))), Box::new(CInt(0)))),
+                    Box::new(ret(Var(String::from("n")))),
+                    Box::new(otherwise),
+                ),
+            )
+        };
+
+        assert_eq!(
+            check_stmt(positive(Statement::Global(vec![])), &Environment::new()),
+            Err(String::from(
+                "[Type Error] function 'f' may end without returning a value of type TInteger."
+            ))
+        );
+        assert!(check_stmt(positive(ret(CInt(0))), &Environment::new()).is_ok());
+        assert!(check_stmt(
+            def(TNone, vec![], Statement::Global(vec![])),
+            &Environment::new()
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn check_assignment_keeps_the_declared_type() {
+        let env = Environment::from([(String::from("x"), TReal)]);
+        let assign = Statement::Assignment(Box::new(String::from("x")), Box::new(CInt(1)));
+
+        assert_eq!(
+            check_stmt(assign, &env).map(|e| e.get("x").cloned()),
+            Ok(Some(TReal))
+        );
+    }
+
+    #[test]
+    fn check_maybe_and_result_values() {
+        let env = Environment::from([
+            (String::from("m"), TMaybe(Box::new(TString))),
+            (String::from("r"), result_of(TInteger, TString)),
+        ]);
+        let var = |name: &str| Box::new(Var(String::from(name)));
+
+        assert_eq!(check(Unwrap(var("m")), &env), Ok(TString));
+        assert_eq!(
+            check(UnwrapOr(var("r"), Box::new(CInt(0))), &env),
+            Ok(TInteger)
+        );
+        assert_eq!(check(IsError(var("r")), &env), Ok(TBool));
+        assert_eq!(
+            check(IsNothing(var("r")), &env),
+            Err(String::from(
+                "[Type Error] 'is_nothing' expects a Maybe value."
+            ))
+        );
+        assert_eq!(
+            check(UnwrapOr(var("m"), Box::new(CInt(0))), &env),
+            Err(String::from(
+                "[Type Error] the default value has type TInteger, but TString was expected."
+            ))
+        );
+        assert_eq!(
+            check_stmt(
+                Statement::Assignment(
+                    Box::new(String::from("r")),
+                    Box::new(CErr(Box::new(CString(String::from("e")))))
+                ),
+                &env
+            )
+            .map(|e| e.get("r").cloned()),
+            Ok(Some(result_of(TInteger, TString)))
+        );
+    }
+
+    #[test]
+    fn check_error_propagation() {
+        let env = Environment::from([
+            (String::from("m"), TMaybe(Box::new(TInteger))),
+            (String::from("r"), result_of(TInteger, TString)),
+        ]);
+        let propagate =
+            |name: &str| ret(COk(Box::new(Propagate(Box::new(Var(String::from(name)))))));
+
+        assert!(check_stmt(
+            def(result_of(TInteger, TString), vec![], propagate("r")),
+            &env
+        )
+        .is_ok());
+        assert_eq!(
+            check_stmt(def(result_of(TInteger, TInteger), vec![], propagate("r")), &env),
+            Err(String::from(
+                "[Type Error] cannot propagate TResult(TInteger, TString) from a function returning TResult(TInteger, TInteger)."
+            ))
+        );
+        assert_eq!(
+            check_stmt(def(result_of(TInteger, TString), vec![], propagate("m")), &env),
+            Err(String::from(
+                "[Type Error] cannot propagate TMaybe(TInteger) from a function returning TResult(TInteger, TString)."
+            ))
+        );
+        assert_eq!(
+            check(Propagate(Box::new(Var(String::from("m")))), &env),
+            Err(String::from("[Type Error] '?' used outside a function."))
+        );
+    }
+
+    fn method(name: &str, kind: Type, params: Vec<(&str, Type)>, body: Statement) -> Function {
+        let mut all = vec![(String::from("self"), TAny)];
+        all.extend(params.into_iter().map(|(p, t)| (String::from(p), t)));
+        Function {
+            name: String::from(name),
+            kind,
+            params: all,
+            defaults: vec![],
+            varargs: None,
+            kwargs: None,
+            body: Box::new(body),
+        }
+    }
+
+    fn class(
+        name: &str,
+        base: Option<&str>,
+        fields: Vec<(&str, Type)>,
+        methods: Vec<Function>,
+    ) -> Statement {
+        Statement::ClassDef(Class {
+            name: String::from(name),
+            base: base.map(String::from),
+            fields: fields
+                .into_iter()
+                .map(|(f, t)| (String::from(f), t))
+                .collect(),
+            methods,
+        })
+    }
+
+    /*
+     * > class Shape:
+     * >   name: str
+     * >   def __init__(self, name: str): self.name = name
+     * >   def area(self) -> float: return 0.0
+     * > class Square(Shape):
+     * >   side: float
+     * >   def __init__(self, side: float):
+     * >     super().__init__("square")
+     * >     self.side = side
+     * >   def area(self) -> float: return self.side * self.side
+     */
+    fn shapes() -> Environment {
+        let self_field = |f: &str| {
+            Box::new(FieldAccess(
+                Box::new(Var(String::from("self"))),
+                String::from(f),
+            ))
+        };
+        let set = |f: &str, e: Expression| {
+            Statement::FieldAssignment(
+                Box::new(Var(String::from("self"))),
+                String::from(f),
+                Box::new(e),
+            )
+        };
+        let shape = class(
+            "Shape",
+            None,
+            vec![("name", TString)],
+            vec![
+                method(
+                    "__init__",
+                    TNone,
+                    vec![("name", TString)],
+                    set("name", Var(String::from("name"))),
+                ),
+                method("area", TReal, vec![], ret(CReal(0.0))),
+            ],
+        );
+        let square = class(
+            "Square",
+            Some("Shape"),
+            vec![("side", TReal)],
+            vec![
+                method(
+                    "__init__",
+                    TNone,
+                    vec![("side", TReal)],
+                    Statement::Sequence(
+                        Box::new(Statement::Assignment(
+                            Box::new(String::from("_")),
+                            Box::new(MethodCall(
+                                Box::new(Super),
+                                String::from("__init__"),
+                                vec![CString(String::from("square"))],
+                            )),
+                        )),
+                        Box::new(set("side", Var(String::from("side")))),
+                    ),
+                ),
+                method(
+                    "area",
+                    TReal,
+                    vec![],
+                    ret(Mul(self_field("side"), self_field("side"))),
+                ),
+            ],
+        );
+        check_stmt(
+            Statement::Sequence(Box::new(shape), Box::new(square)),
+            &Environment::new(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn check_classes_and_method_calls() {
+        let env = shapes();
+        let square = FuncCall(String::from("Square"), vec![CInt(2)]);
+        let area = |e: Expression| MethodCall(Box::new(e), String::from("area"), vec![]);
+
+        assert_eq!(
+            check(square.clone(), &env),
+            Ok(TObject(String::from("Square")))
+        );
+        assert_eq!(check(area(square.clone()), &env), Ok(TReal));
+        assert_eq!(
+            check(
+                FieldAccess(Box::new(square.clone()), String::from("name")),
+                &env
+            ),
+            Ok(TString)
+        );
+        assert_eq!(
+            check(
+                IsInstance(Box::new(square.clone()), String::from("Shape")),
+                &env
+            ),
+            Ok(TBool)
+        );
+        assert_eq!(
+            check(FuncCall(String::from("Square"), vec![CString(String::from("a"))]), &env),
+            Err(String::from(
+                "[Type Error] argument 1 of Square() has type TReal but is given a value of type TString."
+            ))
+        );
+        assert_eq!(
+            check(
+                MethodCall(Box::new(square.clone()), String::from("perimeter"), vec![]),
+                &env
+            ),
+            Err(String::from(
+                "[Type Error] class 'Square' has no method 'perimeter'."
+            ))
+        );
+        assert_eq!(
+            check(FieldAccess(Box::new(square), String::from("color")), &env),
+            Err(String::from(
+                "[Type Error] class 'Square' has no field 'color'."
+            ))
+        );
+    }
+
+    #[test]
+    fn check_variables_do_not_hide_classes() {
+        /* > Shape = 1 */
+        let assign = Statement::Assignment(Box::new(String::from("Shape")), Box::new(CInt(1)));
+        let env = check_stmt(assign, &shapes()).unwrap();
+        let square = FuncCall(String::from("Square"), vec![CInt(2)]);
+
+        assert_eq!(env.get("Shape"), Some(&TInteger));
+        assert_eq!(
+            check(
+                IsInstance(Box::new(square.clone()), String::from("Shape")),
+                &env
+            ),
+            Ok(TBool)
+        );
+        assert_eq!(
+            check(FieldAccess(Box::new(square), String::from("name")), &env),
+            Ok(TString)
+        );
+    }
+
+    #[test]
+    fn check_subclass_values_are_assignable() {
+        let mut env = shapes();
+        env.insert(String::from("s"), TObject(String::from("Shape")));
+        env.insert(String::from("q"), TObject(String::from("Square")));
+        let assign = |name: &str, class: &str, arg: Expression| {
+            Statement::Assignment(
+                Box::new(String::from(name)),
+                Box::new(FuncCall(String::from(class), vec![arg])),
+            )
+        };
+
+        assert!(check_stmt(assign("s", "Square", CReal(1.0)), &env).is_ok());
+        assert_eq!(
+            check_stmt(assign("q", "Shape", CString(String::from("blob"))), &env),
+            Err(String::from(
+                "[Type Error] 'q' has type TObject(\"Square\") but is assigned a value of type TObject(\"Shape\")."
+            ))
+        );
+    }
+
+    #[test]
+    fn check_class_declaration_errors() {
+        let env = shapes();
+        let bad_override = class(
+            "Circle",
+            Some("Shape"),
+            vec![],
+            vec![method("area", TInteger, vec![], ret(CInt(0)))],
+        );
+        let no_self = class(
+            "Point",
+            None,
+            vec![],
+            vec![Function {
+                name: String::from("norm"),
+                kind: TReal,
+                params: vec![],
+                defaults: vec![],
+                varargs: None,
+                kwargs: None,
+                body: Box::new(ret(CReal(0.0))),
+            }],
+        );
+
+        assert_eq!(
+            check_stmt(bad_override, &env),
+            Err(String::from(
+                "[Type Error] method 'area' of class 'Circle' does not match the signature it overrides."
+            ))
+        );
+        assert_eq!(
+            check_stmt(no_self, &env),
+            Err(String::from(
+                "[Type Error] method 'norm' of class 'Point' must take 'self' as its first parameter."
+            ))
+        );
+        assert_eq!(
+            check_stmt(class("Cube", Some("Solid"), vec![], vec![]), &env),
+            Err(String::from("[Name Error] class 'Solid' is not defined."))
+        );
+        assert_eq!(
+            check(
+                MethodCall(Box::new(Super), String::from("area"), vec![]),
+                &env
+            ),
+            Err(String::from(
+                "[Type Error] 'super()' used outside a method."
+            ))
+        );
+    }
+
+    /* a class with the signatures of the special methods, whose bodies
+     * are irrelevant here */
+    fn money_env() -> Environment {
+        let money = || TObject(String::from("Money"));
+        let stub = |name: &str, kind: Type, params: Vec<(&str, Type)>| {
+            method(
+                name,
+                kind,
+                params,
+                Statement::Raise(Box::new(Expression::Exception(
+                    String::from("NotImplementedError"),
+                    Box::new(CString(String::new())),
+                ))),
+            )
+        };
+        let class = class(
+            "Money",
+            None,
+            vec![],
+            vec![
+                stub("__add__", money(), vec![("other", money())]),
+                stub("__eq__", TBool, vec![("other", money())]),
+                stub("__lt__", TBool, vec![("other", money())]),
+                stub("__getitem__", TString, vec![("key", TString)]),
+                stub("__len__", TString, vec![]),
+            ],
+        );
+        let mut env = check_stmt(class, &shapes()).unwrap();
+        env.insert(String::from("m"), money());
+        env.insert(String::from("s"), TObject(String::from("Shape")));
+        env
+    }
+
+    #[test]
+    fn check_overloaded_operators() {
+        let env = money_env();
+        let m = || Box::new(Var(String::from("m")));
+        let s = || Box::new(Var(String::from("s")));
+
+        assert_eq!(
+            check(Add(m(), m()), &env),
+            Ok(TObject(String::from("Money")))
+        );
+        assert_eq!(check(EQ(m(), m()), &env), Ok(TBool));
+        assert_eq!(check(GT(m(), m()), &env), Ok(TBool));
+        assert_eq!(
+            check(Index(m(), Box::new(CString(String::from("usd")))), &env),
+            Ok(TString)
+        );
+        assert_eq!(check(EQ(s(), s()), &env), Ok(TBool));
+        assert_eq!(
+            check(Add(m(), Box::new(CInt(1))), &env),
+            Err(String::from(
+                "[Type Error] '__add__' of class 'Money' does not accept operands of type [TInteger]."
+            ))
+        );
+        assert_eq!(
+            check(LT(s(), s()), &env),
+            Err(String::from(
+                "[Type Error] class 'Shape' does not define '__lt__'."
+            ))
+        );
+        assert_eq!(
+            check(LTE(m(), m()), &env),
+            Err(String::from(
+                "[Type Error] class 'Money' does not define '__le__'."
+            ))
+        );
+        assert_eq!(
+            check(Len(m()), &env),
+            Err(String::from(
+                "[Type Error] '__len__' must return TInteger, not TString."
+            ))
+        );
+    }
+
+    fn for_loop(name: &str, iterable: Expression, body: Statement) -> Statement {
+        Statement::For(String::from(name), Box::new(iterable), Box::new(body))
+    }
+
+    #[test]
+    fn check_for_loops() {
+        let env = money_env();
+        let plus_one = |name: &str| {
+            Statement::Assignment(
+                Box::new(String::from("y")),
+                Box::new(Add(Box::new(Var(String::from(name))), Box::new(CInt(1)))),
+            )
+        };
+
+        assert!(check_stmt(
+            for_loop("x", ListValue(vec![CInt(1), CInt(2)]), plus_one("x")),
+            &env
+        )
+        .is_ok());
+        assert_eq!(
+            check_stmt(
+                for_loop("c", CString(String::from("ab")), plus_one("c")),
+                &env
+            ),
+            Err(String::from("[Type Error] expecting numeric type values."))
+        );
+        assert_eq!(
+            check_stmt(for_loop("x", CInt(1), plus_one("x")), &env),
+            Err(String::from(
+                "[Type Error] expecting an iterable value, found TInteger."
+            ))
+        );
+        assert_eq!(
+            check_stmt(for_loop("m", Var(String::from("m")), plus_one("m")), &env),
+            Err(String::from(
+                "[Type Error] class 'Money' does not define '__iter__'."
+            ))
+        );
+    }
+
+    #[test]
+    fn check_generators() {
+        let ints = TIterator(Box::new(TInteger));
+        let yield_ = |exp: Expression| Statement::Yield(Box::new(exp));
+        let env = check_stmt(
+            def(
+                ints.clone(),
+                vec![("n", TInteger)],
+                Statement::Sequence(Box::new(yield_(CInt(1))), Box::new(ret(CNone))),
+            ),
+            &Environment::new(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            check(FuncCall(String::from("f"), vec![CInt(1)]), &env),
+            Ok(ints.clone())
+        );
+        assert_eq!(
+            check_stmt(
+                for_loop(
+                    "x",
+                    FuncCall(String::from("f"), vec![CInt(1)]),
+                    Statement::Assignment(
+                        Box::new(String::from("y")),
+                        Box::new(Mul(Box::new(Var(String::from("x"))), Box::new(CInt(2)))),
+                    ),
+                ),
+                &env
+            )
+            .map(|_| ()),
+            Ok(())
+        );
+        assert_eq!(
+            check_stmt(
+                def(ints, vec![], yield_(CString(String::from("a")))),
+                &Environment::new()
+            ),
+            Err(String::from(
+                "[Type Error] expecting a yielded value of type TInteger, found TString."
+            ))
+        );
+        assert_eq!(
+            check_stmt(def(TInteger, vec![], yield_(CInt(1))), &Environment::new()),
+            Err(String::from(
+                "[Type Error] a generator must return an iterator, not TInteger."
+            ))
+        );
+        assert_eq!(
+            check_stmt(yield_(CInt(1)), &Environment::new()),
+            Err(String::from("[Type Error] 'yield' outside function."))
+        );
+    }
+
+    #[test]
+    fn check_comprehensions() {
+        let s = |v: &str| CString(String::from(v));
+        let env = Environment::from([(String::from("words"), TList(Box::new(TString)))]);
+        let over_words = || vec![Clause::For(String::from("w"), Var(String::from("words")))];
+
+        assert_eq!(
+            check(
+                ListComprehension(
+                    Box::new(Len(Box::new(Var(String::from("w"))))),
+                    over_words()
+                ),
+                &env
+            ),
+            Ok(TList(Box::new(TInteger)))
+        );
+        assert_eq!(
+            check(
+                SetComprehension(Box::new(Var(String::from("w"))), over_words()),
+                &env
+            ),
+            Ok(TSet(Box::new(TString)))
+        );
+        assert_eq!(
+            check(
+                DictComprehension(
+                    Box::new(Var(String::from("w"))),
+                    Box::new(CTrue),
+                    over_words()
+                ),
+                &env
+            ),
+            Ok(TDict(Box::new(TString), Box::new(TBool)))
+        );
+        assert_eq!(
+            check(
+                ListComprehension(
+                    Box::new(Var(String::from("w"))),
+                    vec![
+                        Clause::For(String::from("w"), Var(String::from("words"))),
+                        Clause::If(Var(String::from("w"))),
+                    ]
+                ),
+                &env
+            ),
+            Err(String::from("[Type Error] expecting a boolean condition."))
+        );
+        assert_eq!(
+            check_stmt(
+                Statement::Sequence(
+                    Box::new(Statement::Assignment(
+                        Box::new(String::from("ws")),
+                        Box::new(ListComprehension(
+                            Box::new(Var(String::from("w"))),
+                            over_words()
+                        )),
+                    )),
+                    Box::new(Statement::Assignment(
+                        Box::new(String::from("v")),
+                        Box::new(Var(String::from("w"))),
+                    )),
+                ),
+                &env
+            ),
+            Err(String::from("[Name Error] 'w' is not defined."))
+        );
+        assert_eq!(
+            check(DictValue(vec![(s("a"), CInt(1)), (CInt(2), CInt(2))]), &env),
+            Err(String::from(
+                "[Type Error] dictionary keys must have the same type."
+            ))
+        );
+        assert_eq!(
+            check(
+                Index(
+                    Box::new(DictValue(vec![(s("a"), CInt(1))])),
+                    Box::new(CInt(0))
+                ),
+                &env
+            ),
+            Err(String::from(
+                "[Type Error] expecting a key of type TString, found TInteger."
+            ))
+        );
+    }
+
+    #[test]
+    fn check_conditional_expressions() {
+        let env = Environment::new();
+        let conditional = |then: Expression, otherwise: Expression| {
+            Conditional(Box::new(CTrue), Box::new(then), Box::new(otherwise))
+        };
+
+        assert_eq!(check(conditional(CInt(1), CReal(2.0)), &env), Ok(TReal));
+        assert_eq!(
+            check(
+                conditional(ListValue(vec![]), ListValue(vec![CInt(1)])),
+                &env
+            ),
+            Ok(TList(Box::new(TInteger)))
+        );
+        assert_eq!(
+            check(conditional(CInt(1), CString(String::from("a"))), &env),
+            Err(String::from(
+                "[Type Error] the branches of a conditional expression have different types: TInteger and TString."
+            ))
+        );
+        assert_eq!(
+            check(
+                Conditional(Box::new(CInt(1)), Box::new(CInt(1)), Box::new(CInt(2))),
+                &env
+            ),
+            Err(String::from("[Type Error] expecting a boolean condition."))
+        );
+    }
+
+    #[test]
+    fn check_augmented_assignments() {
+        let env = Environment::from([
+            (String::from("n"), TInteger),
+            (String::from("xs"), TList(Box::new(TReal))),
+            (String::from("t"), TTuple(vec![TInteger])),
+        ]);
+        let augment = |target: Expression, op: ArithmeticOp, exp: Expression| {
+            Statement::AugmentedAssignment(Box::new(target), op, Box::new(exp))
+        };
+        let n = || Var(String::from("n"));
+        let element = |name: &str| Index(Box::new(Var(String::from(name))), Box::new(CInt(0)));
+
+        assert!(check_stmt(augment(n(), ArithmeticOp::Mod, CInt(2)), &env).is_ok());
+        assert!(check_stmt(augment(element("xs"), ArithmeticOp::Mul, n()), &env).is_ok());
+        assert_eq!(
+            check_stmt(augment(n(), ArithmeticOp::Add, CReal(0.5)), &env),
+            Err(String::from(
+                "[Type Error] the target has type TInteger but is assigned a value of type TReal."
+            ))
+        );
+        assert_eq!(
+            check_stmt(augment(element("t"), ArithmeticOp::Add, CInt(1)), &env),
+            Err(String::from(
+                "[Type Error] item assignment is only defined for lists and dictionaries."
+            ))
+        );
+        assert_eq!(
+            check_stmt(augment(CInt(1), ArithmeticOp::Add, CInt(1)), &env),
+            Err(String::from(
+                "[Type Error] cannot assign to this expression."
+            ))
+        );
+    }
+
+    /*
+     * > def f(x: int, scale: real = 1, *rest: int, **options: str) -> real:
+     * >   return x * scale
+     */
+    fn variadic(default: Expression) -> Statement {
+        Statement::FuncDef(Box::new(Function {
+            name: String::from("f"),
+            kind: TReal,
+            params: vec![
+                (String::from("x"), TInteger),
+                (String::from("scale"), TReal),
+            ],
+            defaults: vec![default],
+            varargs: Some((String::from("rest"), TInteger)),
+            kwargs: Some((String::from("options"), TString)),
+            body: Box::new(ret(Mul(
+                Box::new(Var(String::from("x"))),
+                Box::new(Var(String::from("scale"))),
+            ))),
+        }))
+    }
+
+    #[test]
+    fn check_default_keyword_and_variadic_arguments() {
+        let env = check_stmt(variadic(CInt(1)), &Environment::new()).unwrap();
+        let call = |args| check(FuncCall(String::from("f"), args), &env);
+        let keyword = |name: &str, value| KeywordArgument(String::from(name), Box::new(value));
+
+        assert_eq!(call(vec![CInt(1)]), Ok(TReal));
+        assert_eq!(
+            call(vec![keyword("scale", CReal(0.5)), keyword("x", CInt(2))]),
+            Ok(TReal)
+        );
+        assert_eq!(
+            call(vec![
+                CInt(1),
+                CReal(2.0),
+                CInt(3),
+                keyword("unit", CString(String::from("cm")))
+            ]),
+            Ok(TReal)
+        );
+        assert_eq!(
+            call(vec![CInt(1), CReal(2.0), CString(String::from("3"))]),
+            Err(String::from(
+                "[Type Error] argument 3 of f() has type TInteger but is given a value of type TString."
+            ))
+        );
+        assert_eq!(
+            call(vec![CInt(1), keyword("unit", CInt(2))]),
+            Err(String::from(
+                "[Type Error] argument 'unit' of f() has type TString but is given a value of type TInteger."
+            ))
+        );
+        assert_eq!(
+            call(vec![CInt(1), keyword("x", CInt(2))]),
+            Err(String::from(
+                "[Type Error] f() got multiple values for argument 'x'."
+            ))
+        );
+        assert_eq!(
+            call(vec![keyword("scale", CReal(2.0))]),
+            Err(String::from("[Type Error] f() missing argument 'x'."))
+        );
+        assert_eq!(
+            check_stmt(variadic(CString(String::from("1"))), &Environment::new()),
+            Err(String::from(
+                "[Type Error] parameter 'scale' has type TReal but its default value has type TString."
+            ))
+        );
+    }
+
+    fn assign_to(name: &str, exp: Expression) -> Statement {
+        Statement::Assignment(Box::new(String::from(name)), Box::new(exp))
+    }
+
+    fn sequence(s1: Statement, s2: Statement) -> Statement {
+        Statement::Sequence(Box::new(s1), Box::new(s2))
+    }
+
+    fn names(names: &[&str]) -> Vec<Name> {
+        names.iter().map(|n| String::from(*n)).collect()
+    }
+
+    #[test]
+    fn check_function_locals_shadow_module_variables() {
+        let env = Environment::from([(String::from("x"), TInteger)]);
+        let s = |v: &str| CString(String::from(v));
+
+        /* > def f() -> str: x = "a"; return x */
+        let shadowing = def(
+            TString,
+            vec![],
+            sequence(assign_to("x", s("a")), ret(Var(String::from("x")))),
+        );
+        let new_env = check_stmt(shadowing, &env).unwrap();
+        assert_eq!(new_env.get("x"), Some(&TInteger));
+
+        /* > def f() -> str: y = x; x = "a"; return y */
+        let unbound = def(
+            TString,
+            vec![],
+            sequence(
+                assign_to("y", Var(String::from("x"))),
+                sequence(assign_to("x", s("a")), ret(Var(String::from("y")))),
+            ),
+        );
+        assert_eq!(
+            check_stmt(unbound, &env),
+            Err(String::from("[Name Error] 'x' is not defined."))
+        );
+
+        /* > def f() -> None: global x; x = "a" */
+        let global = def(
+            TNone,
+            vec![],
+            sequence(Statement::Global(names(&["x"])), assign_to("x", s("a"))),
+        );
+        assert_eq!(
+            check_stmt(global, &env),
+            Err(String::from(
+                "[Type Error] 'x' has type TInteger but is assigned a value of type TString."
+            ))
+        );
+    }
+
+    #[test]
+    fn check_nonlocal_declarations() {
+        /*
+         * > def f() -> int:
+         * >   x = "s"            # shadows the module's x
+         * >   count = 0
+         * >   def g() -> int:
+         * >     nonlocal count
+         * >     global x
+         * >     count += x
+         * >     return count
+         * >   return g()
+         */
+        let nested = |body: Statement| {
+            Statement::FuncDef(Box::new(Function {
+                name: String::from("g"),
+                kind: TInteger,
+                params: vec![],
+                defaults: vec![],
+                varargs: None,
+                kwargs: None,
+                body: Box::new(body),
+            }))
+        };
+        let g = nested(sequence(
+            Statement::Nonlocal(names(&["count"])),
+            sequence(
+                Statement::Global(names(&["x"])),
+                sequence(
+                    Statement::AugmentedAssignment(
+                        Box::new(Var(String::from("count"))),
+                        ArithmeticOp::Add,
+                        Box::new(Var(String::from("x"))),
+                    ),
+                    ret(Var(String::from("count"))),
+                ),
+            ),
+        ));
+        let outer = |g: Statement| {
+            def(
+                TInteger,
+                vec![],
+                sequence(
+                    sequence(
+                        assign_to("x", CString(String::from("s"))),
+                        assign_to("count", CInt(0)),
+                    ),
+                    sequence(g, ret(FuncCall(String::from("g"), vec![]))),
+                ),
+            )
+        };
+        let env = Environment::from([(String::from("x"), TInteger)]);
+        assert!(check_stmt(outer(g), &env).is_ok());
+
+        let missing = nested(sequence(
+            Statement::Nonlocal(names(&["total"])),
+            ret(CInt(0)),
+        ));
+        assert_eq!(
+            check_stmt(outer(missing), &env),
+            Err(String::from(
+                "[Type Error] no binding for nonlocal 'total' found."
+            ))
+        );
+        assert_eq!(
+            check_stmt(Statement::Nonlocal(names(&["x"])), &env),
+            Err(String::from(
+                "[Type Error] nonlocal declaration not allowed at module level."
+            ))
+        );
+    }
+
+    #[test]
+    fn check_nested_functions_see_enclosing_locals() {
+        /*
+         * > def f() -> str:
+         * >   x = "s"            # shadows the module's x
+         * >   def g() -> str:
+         * >     return x
+         * >   return g()
+         */
+        let g = Statement::FuncDef(Box::new(Function {
+            name: String::from("g"),
+            kind: TString,
+            params: vec![],
+            defaults: vec![],
+            varargs: None,
+            kwargs: None,
+            body: Box::new(ret(Var(String::from("x")))),
+        }));
+        let f = def(
+            TString,
+            vec![],
+            sequence(
+                assign_to("x", CString(String::from("s"))),
+                sequence(g, ret(FuncCall(String::from("g"), vec![]))),
+            ),
+        );
+        let env = Environment::from([(String::from("x"), TInteger)]);
+        let new_env = check_stmt(f, &env).unwrap();
+        assert_eq!(new_env.get("x"), Some(&TInteger));
+    }
+
+    #[test]
+    fn check_block_declarations_shadow() {
+        /*
+         * > if c:
+         * >   var x
+         * >   x = "s"
+         * >   y = x
+         * > else:
+         * >   y = "t"
+         * > z = x + 1
+         */
+        let env = Environment::from([(String::from("x"), TInteger), (String::from("c"), TBool)]);
+        let block = sequence(
+            sequence(
+                Statement::VarDeclaration(Box::new(String::from("x"))),
+                assign_to("x", CString(String::from("s"))),
+            ),
+            assign_to("y", Var(String::from("x"))),
+        );
+        let program = sequence(
+            Statement::IfThenElse(
+                Box::new(Var(String::from("c"))),
+                Box::new(block),
+                Box::new(assign_to("y", CString(String::from("t")))),
+            ),
+            assign_to(
+                "z",
+                Add(Box::new(Var(String::from("x"))), Box::new(CInt(1))),
+            ),
+        );
+        let new_env = check_stmt(program, &env).unwrap();
+        assert_eq!(new_env.get("x"), Some(&TInteger));
+        assert_eq!(new_env.get("y"), Some(&TString));
+        assert_eq!(new_env.get("z"), Some(&TInteger));
+    }
+
+    #[test]
+    fn check_imports() {
+        /* what checking the program of a module "geometry" leaves */
+        let shape = TClass(String::from("Shape"), None, vec![], vec![]);
+        let mut geometry =
+            Environment::from([(String::from("origin"), TTuple(vec![TInteger, TInteger]))]);
+        geometry.declare_class(String::from("Shape"), shape);
+        let module = module_type("geometry", &geometry);
+        let shape = TClass(String::from("geometry.Shape"), None, vec![], vec![]);
+        assert_eq!(
+            module,
+            TModule(
+                String::from("geometry"),
+                vec![
+                    (String::from("Shape"), shape.clone()),
+                    (String::from("origin"), TTuple(vec![TInteger, TInteger])),
+                ]
+            )
+        );
+        let mut env = Environment::new();
+        declare_module("geometry", module.clone(), &mut env);
+
+        let imported = check_stmt(Statement::Import(String::from("geometry")), &env).unwrap();
+        assert_eq!(imported.get("geometry"), Some(&module));
+        assert_eq!(imported.get_class("Shape"), None);
+        assert_eq!(imported.get_class("geometry.Shape"), Some(&shape));
+        let origin = FieldAccess(
+            Box::new(Var(String::from("geometry"))),
+            String::from("origin"),
+        );
+        assert_eq!(
+            check(origin, &imported),
+            Ok(TTuple(vec![TInteger, TInteger]))
+        );
+        assert_eq!(
+            check(
+                MethodCall(
+                    Box::new(Var(String::from("geometry"))),
+                    String::from("Shape"),
+                    vec![]
+                ),
+                &imported
+            ),
+            Ok(TObject(String::from("geometry.Shape")))
+        );
+
+        let names = vec![String::from("origin"), String::from("Shape")];
+        let from = Statement::FromImport(String::from("geometry"), names);
+        let imported = check_stmt(from, &env).unwrap();
+        assert_eq!(
+            imported.get("origin"),
+            Some(&TTuple(vec![TInteger, TInteger]))
+        );
+        assert_eq!(imported.get("Shape"), None);
+        assert_eq!(imported.get_class("Shape"), Some(&shape));
+        assert_eq!(
+            check(FuncCall(String::from("Shape"), vec![]), &imported),
+            Ok(TObject(String::from("geometry.Shape")))
+        );
+        assert_eq!(
+            check_stmt(Statement::Import(String::from("physics")), &env),
+            Err(String::from("[Name Error] no module named 'physics'."))
+        );
     }
-}
 
-fn check_bin_arithmetic_expression(
-    left: Expression,
-    right: Expression,
-    env: &Environment,
-) -> Result<Type, ErrorMessage> {
-    let left_type = check(left, env)?;
-    let right_type = check(right, env)?;
+    fn builtins_env() -> Environment {
+        Environment::from([
+            (String::from("xs"), TList(Box::new(TReal))),
+            (String::from("name"), TString),
+        ])
+    }
 
-    match (left_type, right_type) {
-        (Type::TInteger, Type::TInteger) => Ok(Type::TInteger),
-        (Type::TInteger, Type::TReal) => Ok(Type::TReal),
-        (Type::TReal, Type::TInteger) => Ok(Type::TReal),
-        (Type::TReal, Type::TReal) => Ok(Type::TReal),
-        _ => Err(String::from("[Type Error] expecting numeric type values.")),
+    fn builtin_call(name: &str, args: Vec<Expression>) -> Expression {
+        FuncCall(String::from(name), args)
     }
-}
 
-fn check_bin_boolean_expression(
-    left: Expression,
-    right: Expression,
-    env: &Environment,
-) -> Result<Type, ErrorMessage> {
-    let left_type = check(left, env)?;
-    let right_type = check(right, env)?;
+    #[test]
+    fn check_print_and_input() {
+        let env = builtins_env();
+        let end = KeywordArgument(String::from("end"), Box::new(Var(String::from("name"))));
+        assert_eq!(
+            check(
+                builtin_call("print", vec![Var(String::from("xs")), end]),
+                &env
+            ),
+            Ok(TNone)
+        );
+        assert_eq!(check(builtin_call("input", vec![]), &env), Ok(TString));
+        let file = KeywordArgument(String::from("file"), Box::new(CNone));
+        assert_eq!(
+            check(builtin_call("print", vec![file]), &env),
+            Err(String::from(
+                "[Type Error] print() got an unexpected keyword argument 'file'."
+            ))
+        );
+    }
 
-    match (left_type, right_type) {
-        (Type::TBool, Type::TBool) => Ok(Type::TBool),
-        _ => Err(String::from("[Type Error] expecting boolean type values.")),
+    #[test]
+    fn check_len_abs_min_max_and_sum() {
+        let env = builtins_env();
+        let xs = || Var(String::from("xs"));
+        let name = || Var(String::from("name"));
+        assert_eq!(check(builtin_call("len", vec![xs()]), &env), Ok(TInteger));
+        assert_eq!(
+            check(builtin_call("abs", vec![CInt(-1)]), &env),
+            Ok(TInteger)
+        );
+        assert_eq!(
+            check(builtin_call("min", vec![CInt(1), CReal(0.5)]), &env),
+            Ok(TReal)
+        );
+        assert_eq!(check(builtin_call("max", vec![xs()]), &env), Ok(TReal));
+        assert_eq!(check(builtin_call("max", vec![name()]), &env), Ok(TString));
+        assert_eq!(check(builtin_call("sum", vec![xs()]), &env), Ok(TReal));
+        assert_eq!(
+            check(builtin_call("sum", vec![ListValue(vec![CInt(1)])]), &env),
+            Ok(TInteger)
+        );
     }
-}
 
-fn check_not_expression(exp: Expression, env: &Environment) -> Result<Type, ErrorMessage> {
-    let exp_type = check(exp, env)?;
+    #[test]
+    fn check_len_abs_min_max_and_sum_errors() {
+        let env = builtins_env();
+        assert_eq!(
+            check(builtin_call("len", vec![CInt(1)]), &env),
+            Err(String::from(
+                "[Type Error] expecting a string, list, tuple, set, dictionary or range value."
+            ))
+        );
+        assert_eq!(
+            check(
+                builtin_call("max", vec![CInt(1), Var(String::from("name"))]),
+                &env
+            ),
+            Err(String::from(
+                "[Type Error] arguments of max() have incompatible types TInteger and TString."
+            ))
+        );
+        assert_eq!(
+            check(builtin_call("min", vec![ListValue(vec![CNone])]), &env),
+            Err(String::from(
+                "[Type Error] min() expects orderable values, not TNone."
+            ))
+        );
+        assert_eq!(
+            check(builtin_call("sum", vec![Var(String::from("name"))]), &env),
+            Err(String::from(
+                "[Type Error] sum() expects numbers, not TString."
+            ))
+        );
+    }
 
-    match exp_type {
-        Type::TBool => Ok(Type::TBool),
-        _ => Err(String::from("[Type Error] expecting a boolean type value.")),
+    #[test]
+    fn check_conversions() {
+        let env = builtins_env();
+        let xs = || Var(String::from("xs"));
+        assert_eq!(
+            check(builtin_call("int", vec![Var(String::from("name"))]), &env),
+            Ok(TInteger)
+        );
+        assert_eq!(check(builtin_call("float", vec![CInt(1)]), &env), Ok(TReal));
+        assert_eq!(check(builtin_call("str", vec![xs()]), &env), Ok(TString));
+        assert_eq!(check(builtin_call("bool", vec![xs()]), &env), Ok(TBool));
+        assert_eq!(
+            check(builtin_call("int", vec![xs()]), &env),
+            Err(String::from(
+                "[Type Error] int() argument must be a string or a number, not TList(TReal)."
+            ))
+        );
     }
-}
 
-fn check_bin_relational_expression(
-    left: Expression,
-    right: Expression,
-    env: &Environment,
-) -> Result<Type, ErrorMessage> {
-    let left_type = check(left, env)?;
-    let right_type = check(right, env)?;
+    #[test]
+    fn check_range_gives_a_range() {
+        let env = builtins_env();
+        assert_eq!(
+            check(builtin_call("range", vec![CInt(1), CInt(5)]), &env),
+            Ok(TRange)
+        );
+        assert_eq!(
+            check(builtin_call("range", vec![CReal(1.5)]), &env),
+            Err(String::from(
+                "[Type Error] argument 1 of range() has type TInteger but is given a value of type TReal."
+            ))
+        );
+    }
 
-    match (left_type, right_type) {
-        (Type::TInteger, Type::TInteger) => Ok(Type::TBool),
-        (Type::TInteger, Type::TReal) => Ok(Type::TBool),
-        (Type::TReal, Type::TInteger) => Ok(Type::TBool),
-        (Type::TReal, Type::TReal) => Ok(Type::TBool),
-        _ => Err(String::from("[Type Error] expecting numeric type values.")),
+    #[test]
+    fn check_ranges_have_a_length_membership_and_indexing() {
+        let env = builtins_env();
+        let range = || Box::new(builtin_call("range", vec![CInt(10)]));
+        assert_eq!(check(Len(range()), &env), Ok(TInteger));
+        assert_eq!(check(In(Box::new(CInt(3)), range()), &env), Ok(TBool));
+        assert_eq!(
+            check(Index(range(), Box::new(CInt(-1))), &env),
+            Ok(TInteger)
+        );
+        assert_eq!(
+            check(In(Box::new(CString(String::from("3"))), range()), &env),
+            Err(String::from("[Type Error] expecting a number in a range."))
+        );
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn check_ranges_are_iterators_over_integers() {
+        let env = builtins_env();
+        let squares = ListComprehension(
+            Box::new(Mul(
+                Box::new(Var(String::from("i"))),
+                Box::new(Var(String::from("i"))),
+            )),
+            vec![Clause::For(
+                String::from("i"),
+                builtin_call("range", vec![CInt(3)]),
+            )],
+        );
+        assert_eq!(check(squares, &env), Ok(TList(Box::new(TInteger))));
+        assert!(assignable(&TIterator(Box::new(TReal)), &TRange, &env));
+        assert!(!assignable(&TIterator(Box::new(TString)), &TRange, &env));
+    }
 
-    use crate::ir::ast::Expression::*;
-    use crate::ir::ast::Type::*;
+    #[test]
+    fn check_range_takes_no_keyword_arguments() {
+        let stop = KeywordArgument(String::from("stop"), Box::new(CInt(5)));
+        assert_eq!(
+            check(builtin_call("range", vec![stop]), &builtins_env()),
+            Err(String::from(
+                "[Type Error] range() takes no keyword arguments."
+            ))
+        );
+    }
 
     #[test]
-    fn check_tlist_comparison() {
-        let t_list1 = TList(Box::new(TInteger));
-        let t_list2 = TList(Box::new(TInteger));
+    fn check_type_gives_a_class() {
+        let env = builtins_env();
+        assert_eq!(check(builtin_call("type", vec![CNone]), &env), Ok(TType));
+        assert_eq!(check(ClassValue(String::from("float")), &env), Ok(TType));
+    }
 
-        assert_eq!(t_list1 == t_list2, true);
+    #[test]
+    fn check_functions_hide_builtins_of_the_same_name() {
+        let mut env = builtins_env();
+        let signature = builtin_signature("len").unwrap();
+        env.insert(String::from("len"), TFunction(Box::new(TString), signature));
+        assert_eq!(check(builtin_call("len", vec![CInt(1)]), &env), Ok(TString));
     }
 
     #[test]
-    fn check_tlist_comparison_different_types() {
-        let t_list1 = TList(Box::new(TInteger));
-        let t_list2 = TList(Box::new(TBool));
+    fn check_math_module() {
+        let env = check_stmt(Statement::Import(String::from("math")), &Environment::new()).unwrap();
+        let math = || Box::new(Var(String::from("math")));
+        let method = |name: &str, args| MethodCall(math(), String::from(name), args);
+        assert_eq!(
+            check(FieldAccess(math(), String::from("pi")), &env),
+            Ok(TReal)
+        );
+        assert_eq!(check(method("floor", vec![CReal(2.5)]), &env), Ok(TInteger));
+        assert_eq!(
+            check(method("gcd", vec![CInt(4), CInt(6)]), &env),
+            Ok(TInteger)
+        );
+        assert_eq!(
+            check(method("log", vec![CInt(8), CInt(2)]), &env),
+            Ok(TReal)
+        );
+        assert_eq!(
+            check(method("isclose", vec![CInt(1), CReal(1.0)]), &env),
+            Ok(TBool)
+        );
+        assert_eq!(
+            check(method("gcd", vec![CReal(4.0), CInt(6)]), &env),
+            Err(String::from(
+                "[Type Error] argument 1 of gcd() has type TInteger but is given a value of type TReal."
+            ))
+        );
+        assert_eq!(
+            check(method("sqrt", vec![]), &env),
+            Err(String::from("[Type Error] sqrt() missing argument 'x'."))
+        );
+    }
 
-        assert_eq!(t_list1 == t_list2, false);
+    fn higher_order_env() -> Environment {
+        let function = |params: &[&str], result: Type| {
+            let params = params.iter().map(|p| (p.to_string(), TInteger)).collect();
+            let signature = Signature {
+                params,
+                defaults: 0,
+                varargs: None,
+                kwargs: None,
+            };
+            TFunction(Box::new(result), signature)
+        };
+        Environment::from([
+            (String::from("double"), function(&["x"], TInteger)),
+            (String::from("add"), function(&["a", "b"], TInteger)),
+            (String::from("xs"), TList(Box::new(TInteger))),
+            (String::from("names"), TList(Box::new(TString))),
+            (String::from("flags"), TSet(Box::new(TBool))),
+        ])
     }
 
     #[test]
-    fn check_ttuple_comparison() {
-        let t_tuple1 = TTuple(vec![TInteger, TBool]);
-        let t_tuple2 = TTuple(vec![TInteger, TBool]);
+    fn check_map_gives_an_iterator() {
+        let env = higher_order_env();
+        let var = |name: &str| Var(String::from(name));
+        assert_eq!(
+            check(builtin_call("map", vec![var("double"), var("xs")]), &env),
+            Ok(TIterator(Box::new(TInteger)))
+        );
+        assert_eq!(
+            check(
+                builtin_call("map", vec![var("add"), var("xs"), var("xs")]),
+                &env
+            ),
+            Ok(TIterator(Box::new(TInteger)))
+        );
+    }
 
-        assert_eq!(t_tuple1 == t_tuple2, true);
+    #[test]
+    fn check_map_of_a_builtin_follows_its_rule() {
+        let env = higher_order_env();
+        let var = |name: &str| Var(String::from(name));
+        assert_eq!(
+            check(builtin_call("map", vec![var("len"), var("names")]), &env),
+            Ok(TIterator(Box::new(TInteger)))
+        );
+        assert_eq!(
+            check(builtin_call("map", vec![var("str"), var("xs")]), &env),
+            Ok(TIterator(Box::new(TString)))
+        );
+        assert_eq!(
+            check(builtin_call("map", vec![var("len"), var("xs")]), &env),
+            Err(String::from(
+                "[Type Error] expecting a string, list, tuple, set, dictionary or range value."
+            ))
+        );
     }
 
     #[test]
-    fn check_ttuple_comparison_different_types() {
-        let t_tuple1 = TTuple(vec![TInteger, TBool]);
-        let t_tuple2 = TTuple(vec![TBool, TInteger]);
+    fn check_filter_gives_an_iterator() {
+        let env = higher_order_env();
+        let var = |name: &str| Var(String::from(name));
+        assert_eq!(
+            check(builtin_call("filter", vec![CNone, var("xs")]), &env),
+            Ok(TIterator(Box::new(TInteger)))
+        );
+        assert_eq!(
+            check(builtin_call("filter", vec![var("double"), var("xs")]), &env),
+            Ok(TIterator(Box::new(TInteger)))
+        );
+    }
 
-        assert_eq!(t_tuple1 == t_tuple2, false);
+    #[test]
+    fn check_reduce() {
+        let env = higher_order_env();
+        let var = |name: &str| Var(String::from(name));
+        assert_eq!(
+            check(builtin_call("reduce", vec![var("add"), var("xs")]), &env),
+            Ok(TInteger)
+        );
+        assert_eq!(
+            check(
+                builtin_call("reduce", vec![var("add"), var("xs"), CReal(0.5)]),
+                &env
+            ),
+            Err(String::from(
+                "[Type Error] reduce() passes values of type TReal to a function that expects TInteger."
+            ))
+        );
     }
 
     #[test]
-    fn check_constant() {
-        let env = HashMap::new();
-        let c10 = CInt(10);
-        assert_eq!(check(c10, &env), Ok(TInteger));
+    fn check_zip_and_enumerate_give_iterators() {
+        let env = higher_order_env();
+        let var = |name: &str| Var(String::from(name));
+        assert_eq!(
+            check(builtin_call("zip", vec![var("xs"), var("names")]), &env),
+            Ok(TIterator(Box::new(TTuple(vec![TInteger, TString]))))
+        );
+        assert_eq!(
+            check(builtin_call("enumerate", vec![var("names")]), &env),
+            Ok(TIterator(Box::new(TTuple(vec![TInteger, TString]))))
+        );
     }
 
     #[test]
-    fn check_add_integers() {
-        let env = HashMap::new();
-        let c10 = CInt(10);
-        let c20 = CInt(20);
-        let add = Add(Box::new(c10), Box::new(c20));
+    fn check_reversed() {
+        let env = higher_order_env();
+        assert_eq!(
+            check(
+                builtin_call("reversed", vec![Var(String::from("names"))]),
+                &env
+            ),
+            Ok(TList(Box::new(TString)))
+        );
+        assert_eq!(
+            check(
+                builtin_call("reversed", vec![Var(String::from("flags"))]),
+                &env
+            ),
+            Err(String::from(
+                "[Type Error] reversed() expects a sequence, not TSet(TBool)."
+            ))
+        );
+    }
 
-        assert_eq!(check(add, &env), Ok(TInteger));
+    #[test]
+    fn check_sorted() {
+        let env = higher_order_env();
+        let var = |name: &str| Var(String::from(name));
+        let sorted = builtin_call(
+            "sorted",
+            vec![
+                var("names"),
+                KeywordArgument(String::from("key"), Box::new(var("len"))),
+                KeywordArgument(String::from("reverse"), Box::new(CTrue)),
+            ],
+        );
+        assert_eq!(check(sorted, &env), Ok(TList(Box::new(TString))));
+        assert_eq!(
+            check(builtin_call("sorted", vec![var("flags")]), &env),
+            Ok(TList(Box::new(TBool)))
+        );
     }
 
     #[test]
-    fn check_add_reals() {
-        let env = HashMap::new();
-        let c10 = CReal(10.5);
-        let c20 = CReal(20.3);
-        let add = Add(Box::new(c10), Box::new(c20));
+    fn check_any() {
+        let env = higher_order_env();
+        assert_eq!(
+            check(builtin_call("any", vec![Var(String::from("flags"))]), &env),
+            Ok(TBool)
+        );
+    }
 
-        assert_eq!(check(add, &env), Ok(TReal));
+    #[test]
+    fn check_callback_errors() {
+        let env = higher_order_env();
+        let var = |name: &str| Var(String::from(name));
+        assert_eq!(
+            check(builtin_call("map", vec![var("add"), var("xs")]), &env),
+            Err(String::from(
+                "[Type Error] map() expects a function of 1 argument(s), but is given one that takes 2."
+            ))
+        );
+        assert_eq!(
+            check(builtin_call("map", vec![var("double"), var("names")]), &env),
+            Err(String::from(
+                "[Type Error] map() passes values of type TString to a function that expects TInteger."
+            ))
+        );
+        assert_eq!(
+            check(builtin_call("map", vec![CInt(1), var("xs")]), &env),
+            Err(String::from(
+                "[Type Error] map() expects a function, not TInteger."
+            ))
+        );
     }
 
     #[test]
-    fn check_add_real_and_integer() {
-        let env = HashMap::new();
-        let c10 = CInt(10);
-        let c20 = CReal(20.3);
-        let add = Add(Box::new(c10), Box::new(c20));
+    fn check_random_module() {
+        let env = Environment::from([(String::from("names"), TList(Box::new(TString)))]);
+        let env = check_stmt(Statement::Import(String::from("random")), &env).unwrap();
+        let method = |name: &str, args| {
+            MethodCall(
+                Box::new(Var(String::from("random"))),
+                String::from(name),
+                args,
+            )
+        };
+        let names = || Var(String::from("names"));
+        assert_eq!(check(method("seed", vec![CInt(7)]), &env), Ok(TNone));
+        assert_eq!(check(method("random", vec![]), &env), Ok(TReal));
+        assert_eq!(
+            check(method("randint", vec![CInt(1), CInt(6)]), &env),
+            Ok(TInteger)
+        );
+        assert_eq!(check(method("choice", vec![names()]), &env), Ok(TString));
+        assert_eq!(
+            check(method("shuffle", vec![names()]), &env),
+            Ok(TList(Box::new(TString)))
+        );
+        assert_eq!(
+            check(method("sample", vec![names(), CInt(2)]), &env),
+            Ok(TList(Box::new(TString)))
+        );
+    }
 
-        assert_eq!(check(add, &env), Ok(TReal));
+    #[test]
+    fn check_random_module_errors() {
+        let env = Environment::from([(String::from("names"), TList(Box::new(TString)))]);
+        let env = check_stmt(Statement::Import(String::from("random")), &env).unwrap();
+        let method = |name: &str, args| {
+            MethodCall(
+                Box::new(Var(String::from("random"))),
+                String::from(name),
+                args,
+            )
+        };
+        let names = || Var(String::from("names"));
+        assert_eq!(
+            check(method("sample", vec![names(), CReal(2.0)]), &env),
+            Err(String::from(
+                "[Type Error] argument 2 of random.sample() has type TInteger but is given a value of type TReal."
+            ))
+        );
+        assert_eq!(
+            check(method("choice", vec![CInt(3)]), &env),
+            Err(String::from(
+                "[Type Error] expecting an iterable value, found TInteger."
+            ))
+        );
     }
 
     #[test]
-    fn check_add_integer_and_real() {
-        let env = HashMap::new();
-        let c10 = CReal(10.5);
-        let c20 = CInt(20);
-        let add = Add(Box::new(c10), Box::new(c20));
+    fn check_json_module() {
+        let env = check_stmt(Statement::Import(String::from("json")), &Environment::new()).unwrap();
+        let method = |name: &str, args| {
+            MethodCall(
+                Box::new(Var(String::from("json"))),
+                String::from(name),
+                args,
+            )
+        };
+        let text = || CString(String::from("[1]"));
+        assert_eq!(check(method("loads", vec![text()]), &env), Ok(TAny));
+        let indented = KeywordArgument(String::from("indent"), Box::new(CInt(2)));
+        assert_eq!(
+            check(method("dumps", vec![ListValue(vec![]), indented]), &env),
+            Ok(TString)
+        );
+        assert_eq!(
+            check(method("loads", vec![CInt(1)]), &env),
+            Err(String::from(
+                "[Type Error] argument 1 of loads() has type TString but is given a value of type TInteger."
+            ))
+        );
+    }
 
-        assert_eq!(check(add, &env), Ok(TReal));
+    fn file_method(name: &str, args: Vec<Expression>) -> Expression {
+        MethodCall(Box::new(Var(String::from("f"))), String::from(name), args)
+    }
+
+    fn with_f(manager: Expression, body: Statement) -> Statement {
+        Statement::With(Box::new(manager), Some(String::from("f")), Box::new(body))
+    }
+
+    fn file_env() -> Environment {
+        Environment::from([(String::from("f"), TFile)])
     }
 
     #[test]
-    fn check_type_error_arithmetic_expression() {
-        let env = HashMap::new();
-        let c10 = CInt(10);
-        let bool = CFalse;
-        let add = Add(Box::new(c10), Box::new(bool));
+    fn check_open_gives_a_file() {
+        let open = FuncCall(
+            String::from("open"),
+            vec![CString(String::from("a.txt")), CString(String::from("w"))],
+        );
+        assert_eq!(check(open, &Environment::new()), Ok(TFile));
+    }
 
+    #[test]
+    fn check_file_methods() {
+        let env = file_env();
+        assert_eq!(check(file_method("read", vec![]), &env), Ok(TString));
         assert_eq!(
-            check(add, &env),
-            Err(String::from("[Type Error] expecting numeric type values."))
+            check(file_method("write", vec![CString(String::from("x"))]), &env),
+            Ok(TInteger)
         );
     }
 
     #[test]
-    fn check_type_error_not_expression() {
-        let env = HashMap::new();
-        let c10 = CInt(10);
-        let not = Not(Box::new(c10));
+    fn check_with_keeps_the_bindings_of_its_body() {
+        let open = FuncCall(String::from("open"), vec![CString(String::from("a.txt"))]);
+        let program = with_f(open, assign_to("lines", file_method("readlines", vec![])));
+        let env = check_stmt(program, &Environment::new()).unwrap();
+        assert_eq!(env.get("lines"), Some(&TList(Box::new(TString))));
+        assert_eq!(env.get("f"), Some(&TFile));
+    }
 
+    #[test]
+    fn check_open_of_a_non_string() {
+        let open = FuncCall(String::from("open"), vec![CInt(1)]);
         assert_eq!(
-            check(not, &env),
-            Err(String::from("[Type Error] expecting a boolean type value."))
+            check(open, &Environment::new()),
+            Err(String::from(
+                "[Type Error] argument 1 of open() has type TString but is given a value of type TInteger."
+            ))
         );
     }
 
     #[test]
-    fn check_type_error_and_expression() {
-        let env = HashMap::new();
-        let c10 = CInt(10);
-        let bool = CTrue;
-        let and = And(Box::new(c10), Box::new(bool));
+    fn check_write_of_a_non_string() {
+        assert_eq!(
+            check(file_method("write", vec![CInt(1)]), &file_env()),
+            Err(String::from(
+                "[Type Error] argument 1 of write() has type TString but is given a value of type TInteger."
+            ))
+        );
+    }
 
+    #[test]
+    fn check_unknown_file_method() {
         assert_eq!(
-            check(and, &env),
-            Err(String::from("[Type Error] expecting boolean type values."))
+            check(file_method("seek", vec![CInt(0)]), &file_env()),
+            Err(String::from("[Type Error] 'seek' is not a file method."))
         );
     }
 
     #[test]
-    fn check_type_error_or_expression() {
-        let env = HashMap::new();
-        let c10 = CInt(10);
-        let bool = CTrue;
-        let or = Or(Box::new(c10), Box::new(bool));
+    fn check_with_of_a_non_manager() {
+        assert_eq!(
+            check_stmt(with_f(CInt(1), Statement::Global(vec![])), &Environment::new()),
+            Err(String::from(
+                "[Type Error] 'with' expects a file or an object with '__enter__' and '__exit__', found TInteger."
+            ))
+        );
+    }
+
+    /*
+     * > class Session:
+     * >   def __enter__(self) -> Session: return self
+     * >   def __exit__(self, type, value, traceback) -> bool: return False
+     * > with Session() as f: pass
+     */
+    #[test]
+    fn check_with_objects() {
+        let session = class(
+            "Session",
+            None,
+            vec![],
+            vec![
+                method(
+                    "__enter__",
+                    TObject(String::from("Session")),
+                    vec![],
+                    ret(Var(String::from("self"))),
+                ),
+                method(
+                    "__exit__",
+                    TBool,
+                    vec![("type", TAny), ("value", TAny), ("traceback", TAny)],
+                    ret(CFalse),
+                ),
+            ],
+        );
+        let env = check_stmt(session, &Environment::new()).unwrap();
+        let program = with_f(
+            FuncCall(String::from("Session"), vec![]),
+            Statement::Global(vec![]),
+        );
+        let env = check_stmt(program, &env).unwrap();
+        assert_eq!(env.get("f"), Some(&TObject(String::from("Session"))));
+    }
 
+    #[test]
+    fn check_exit_takes_the_exception() {
+        let session = class(
+            "Session",
+            None,
+            vec![],
+            vec![
+                method("__enter__", TNone, vec![], ret(CNone)),
+                method("__exit__", TNone, vec![], ret(CNone)),
+            ],
+        );
+        let env = check_stmt(session, &Environment::new()).unwrap();
+        let program = with_f(
+            FuncCall(String::from("Session"), vec![]),
+            Statement::Global(vec![]),
+        );
         assert_eq!(
-            check(or, &env),
-            Err(String::from("[Type Error] expecting boolean type values."))
+            check_stmt(program, &env),
+            Err(String::from(
+                "[Type Error] '__exit__' of class 'Session' does not accept operands of type [TAny, TAny, TAny]."
+            ))
         );
     }
 }